@@ -0,0 +1,129 @@
+use crate::error::{Result, StreamProcessorError};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+/// Durable position of a stream consumer, tracked per shard.
+///
+/// Processors save the sequence number of the last record they handled and resume after it on restart, so
+/// records are neither skipped nor processed twice across runs.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync + 'static {
+    /// Returns the sequence number of the last record processed from `shard_id`, if any.
+    async fn load_checkpoint(&self, shard_id: &str) -> Result<Option<String>>;
+
+    /// Records `sequence_number` as the last record processed from `shard_id`.
+    async fn save_checkpoint(&self, shard_id: &str, sequence_number: &str) -> Result<()>;
+}
+
+/// Checkpoints stored in a DynamoDB table keyed by `pkey` (consumer name) and `skey` (shard ID).
+///
+/// The consumer name lets several processors of the same stream share one table without overwriting each
+/// other's positions.
+#[derive(Debug, Clone)]
+pub struct DynamoDBCheckpointStore {
+    client: Client,
+    table_name: String,
+    consumer: String,
+}
+
+impl DynamoDBCheckpointStore {
+    pub fn new(client: Client, table_name: impl Into<String>, consumer: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            consumer: consumer.into(),
+        }
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for DynamoDBCheckpointStore {
+    async fn load_checkpoint(&self, shard_id: &str) -> Result<Option<String>> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("pkey", AttributeValue::S(self.consumer.clone()))
+            .key("skey", AttributeValue::S(shard_id.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| StreamProcessorError::Checkpoint(format!("Failed to load checkpoint: {e}")))?;
+
+        Ok(output
+            .item()
+            .and_then(|item| item.get("sequence_number"))
+            .and_then(|value| value.as_s().ok())
+            .cloned())
+    }
+
+    async fn save_checkpoint(&self, shard_id: &str, sequence_number: &str) -> Result<()> {
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("pkey", AttributeValue::S(self.consumer.clone()))
+            .item("skey", AttributeValue::S(shard_id.to_string()))
+            .item("sequence_number", AttributeValue::S(sequence_number.to_string()))
+            .send()
+            .await
+            .map_err(|e| StreamProcessorError::Checkpoint(format!("Failed to save checkpoint: {e}")))?;
+        Ok(())
+    }
+}
+
+/// Memory-based checkpoint store for testing and local debugging
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCheckpointStore {
+    checkpoints: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl MemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for MemoryCheckpointStore {
+    async fn load_checkpoint(&self, shard_id: &str) -> Result<Option<String>> {
+        Ok(self.checkpoints.read().unwrap().get(shard_id).cloned())
+    }
+
+    async fn save_checkpoint(&self, shard_id: &str, sequence_number: &str) -> Result<()> {
+        self.checkpoints
+            .write()
+            .unwrap()
+            .insert(shard_id.to_string(), sequence_number.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_checkpoint_store_save_and_load() {
+        let store = MemoryCheckpointStore::new();
+        assert_eq!(store.load_checkpoint("shard-0").await.unwrap(), None);
+
+        store.save_checkpoint("shard-0", "100").await.unwrap();
+        store.save_checkpoint("shard-0", "200").await.unwrap();
+        store.save_checkpoint("shard-1", "150").await.unwrap();
+
+        assert_eq!(store.load_checkpoint("shard-0").await.unwrap().as_deref(), Some("200"));
+        assert_eq!(store.load_checkpoint("shard-1").await.unwrap().as_deref(), Some("150"));
+    }
+}
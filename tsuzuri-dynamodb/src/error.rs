@@ -5,6 +5,12 @@ pub enum StreamProcessorError {
     #[error("Kinesis Data Streams error: {0}")]
     KinesisDataStreams(String),
 
+    #[error("EventBridge error: {0}")]
+    EventBridge(String),
+
+    #[error("SQS error: {0}")]
+    Sqs(String),
+
     #[error("Tsuzuri projection error: {0}")]
     Projection(#[from] ProjectionError),
 
@@ -13,6 +19,9 @@ pub enum StreamProcessorError {
 
     #[error("Invalid data: {0}")]
     InvalidData(String),
+
+    #[error("Checkpoint store error: {0}")]
+    Checkpoint(String),
 }
 
 pub type Result<T> = std::result::Result<T, StreamProcessorError>;
@@ -0,0 +1,175 @@
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    operation::{delete_item::DeleteItemError, put_item::PutItemError},
+    primitives::Blob,
+    types::{AttributeValue, ReturnValuesOnConditionCheckFailure},
+    Client,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tsuzuri::{
+    idempotency_store::{IdempotencyStore, Reservation},
+    persist::PersistenceError,
+};
+
+/// Idempotency keys stored in a DynamoDB table keyed by `pkey` (scope, e.g. the service or aggregate type) and
+/// `skey` (idempotency key).
+///
+/// The events of a key are kept in an `events` list of binary payloads. Every item carries a `ttl` attribute with
+/// its expiry in epoch seconds; enable time to live on that attribute to have DynamoDB remove old keys. Expired
+/// items that DynamoDB has not removed yet are treated as absent.
+///
+/// A reserved key is an item without `events`, written with a condition that no unexpired item exists, so only
+/// one of concurrent reservations succeeds.
+#[derive(Debug, Clone)]
+pub struct DynamoDBIdempotencyStore {
+    client: Client,
+    table_name: String,
+    scope: String,
+}
+
+impl DynamoDBIdempotencyStore {
+    pub fn new(client: Client, table_name: impl Into<String>, scope: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            scope: scope.into(),
+        }
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for DynamoDBIdempotencyStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<Vec<u8>>>, PersistenceError> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("pkey", AttributeValue::S(self.scope.clone()))
+            .key("skey", AttributeValue::S(key.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| PersistenceError::ConnectionError(Box::new(e)))?;
+
+        let Some(item) = output.item() else {
+            return Ok(None);
+        };
+        match reservation_of(key, item)? {
+            Some(Reservation::Completed(events)) => Ok(Some(events)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn reserve(&self, key: &str, ttl: Duration) -> Result<Reservation, PersistenceError> {
+        let now = SystemTime::now();
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("pkey", AttributeValue::S(self.scope.clone()))
+            .item("skey", AttributeValue::S(key.to_string()))
+            .item("ttl", AttributeValue::N(epoch_seconds(now + ttl).to_string()))
+            .condition_expression("attribute_not_exists(pkey) OR #ttl <= :now")
+            .expression_attribute_names("#ttl", "ttl")
+            .expression_attribute_values(":now", AttributeValue::N(epoch_seconds(now).to_string()))
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld)
+            .send()
+            .await;
+        match result {
+            Ok(_) => Ok(Reservation::Reserved),
+            Err(err) => match err.as_service_error() {
+                Some(PutItemError::ConditionalCheckFailedException(conflict)) => Ok(conflict
+                    .item()
+                    .map(|item| reservation_of(key, item))
+                    .transpose()?
+                    .flatten()
+                    .unwrap_or(Reservation::InProgress)),
+                _ => Err(PersistenceError::ConnectionError(Box::new(err))),
+            },
+        }
+    }
+
+    async fn put(&self, key: &str, events: Vec<Vec<u8>>, ttl: Duration) -> Result<(), PersistenceError> {
+        let expires_at = epoch_seconds(SystemTime::now() + ttl);
+        let events = events
+            .into_iter()
+            .map(|payload| AttributeValue::B(Blob::new(payload)))
+            .collect();
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("pkey", AttributeValue::S(self.scope.clone()))
+            .item("skey", AttributeValue::S(key.to_string()))
+            .item("events", AttributeValue::L(events))
+            .item("ttl", AttributeValue::N(expires_at.to_string()))
+            .send()
+            .await
+            .map_err(|e| PersistenceError::ConnectionError(Box::new(e)))?;
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> Result<(), PersistenceError> {
+        let result = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("pkey", AttributeValue::S(self.scope.clone()))
+            .key("skey", AttributeValue::S(key.to_string()))
+            .condition_expression("attribute_not_exists(events)")
+            .send()
+            .await;
+        match result {
+            Ok(_) => Ok(()),
+            Err(err)
+                if matches!(
+                    err.as_service_error(),
+                    Some(DeleteItemError::ConditionalCheckFailedException(_))
+                ) =>
+            {
+                Ok(())
+            }
+            Err(err) => Err(PersistenceError::ConnectionError(Box::new(err))),
+        }
+    }
+}
+
+/// Reads the item of `key`: recorded events, or a reservation when it has none. `None` once it expired.
+fn reservation_of(key: &str, item: &HashMap<String, AttributeValue>) -> Result<Option<Reservation>, PersistenceError> {
+    let expires_at = item
+        .get("ttl")
+        .and_then(|value| value.as_n().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if expires_at.is_none_or(|expires_at| expires_at <= epoch_seconds(SystemTime::now())) {
+        return Ok(None);
+    }
+    let Some(events) = item.get("events") else {
+        return Ok(Some(Reservation::InProgress));
+    };
+    let events = events.as_l().map_err(|_| {
+        PersistenceError::DeserializationError(format!("idempotency key {key} has no event list").into())
+    })?;
+    events
+        .iter()
+        .map(|event| {
+            event.as_b().map(|payload| payload.as_ref().to_vec()).map_err(|_| {
+                PersistenceError::DeserializationError(format!("idempotency key {key} has a non-binary event").into())
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(|events| Some(Reservation::Completed(events)))
+}
+
+fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
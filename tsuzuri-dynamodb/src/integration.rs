@@ -1,6 +1,10 @@
 pub mod event_type_router;
+pub mod eventbridge;
 pub mod helpers;
 pub mod kinesis;
+pub mod sqs;
 
 pub use event_type_router::ProcessorBasedEventRouter;
+pub use eventbridge::EventBridgePublisher;
 pub use kinesis::process_kinesis_lambda_event;
+pub use sqs::SqsEventConsumer;
@@ -4,7 +4,7 @@ use tsuzuri::{
     event::Envelope,
     integration::{
         adapter::{Adapter, Executer},
-        error::Result,
+        error::{IntegrationError, Result},
         processor::Processor,
     },
     integration_event::IntegrationEvent,
@@ -68,17 +68,34 @@ where
 /// This router can handle multiple different event types
 pub struct ProcessorBasedEventRouter {
     pub(crate) routes: HashMap<String, Box<dyn ProcessorTrait>>,
+    pub(crate) strict: bool,
 }
 
 /// Trait to abstract over different processor types
 #[async_trait]
 pub trait ProcessorTrait: Send + Sync {
     async fn process_bytes(&mut self, payload: &[u8]) -> Result<()>;
+
+    /// Process the payload along with the metadata stored next to it; an empty slice stands for no metadata
+    ///
+    /// Processors that do not read metadata ignore it.
+    async fn process_bytes_with_metadata(&mut self, payload: &[u8], _metadata: &[u8]) -> Result<()> {
+        self.process_bytes(payload).await
+    }
 }
 
 impl ProcessorBasedEventRouter {
     pub fn new() -> Self {
-        Self { routes: HashMap::new() }
+        Self {
+            routes: HashMap::new(),
+            strict: false,
+        }
+    }
+
+    /// Return a `RouteNotFound` error for events without a route instead of silently skipping them
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
     }
 
     /// Register a processor for an event type prefix
@@ -97,19 +114,53 @@ impl ProcessorBasedEventRouter {
     /// Process bytes through appropriate processor
     /// Each processor will handle its own deserialization using its own Serde implementation
     /// Uses prefix matching: "ProjectIntegrationEvent" matches "ProjectIntegrationEventBodyChanged"
+    /// When several prefixes match, the longest one handles the event
     pub async fn process_bytes(&mut self, event_name: &str, payload: &[u8]) -> Result<()> {
-        // First try exact match
-        if let Some(processor) = self.routes.get_mut(event_name) {
-            return processor.process_bytes(payload).await;
+        self.process_bytes_with_metadata(event_name, payload, &[]).await
+    }
+
+    /// Process bytes like [`ProcessorBasedEventRouter::process_bytes`], passing the metadata stored next to the
+    /// event on to the processor, which attaches it to the decoded envelope
+    pub async fn process_bytes_with_metadata(
+        &mut self,
+        event_name: &str,
+        payload: &[u8],
+        metadata: &[u8],
+    ) -> Result<()> {
+        let route = self.find_route(event_name).map(str::to_string);
+        match route.and_then(|route| self.routes.get_mut(&route)) {
+            Some(processor) => processor.process_bytes_with_metadata(payload, metadata).await,
+            None => self.no_route(event_name),
         }
+    }
 
-        // Then try prefix match
-        for (registered_prefix, processor) in &mut self.routes {
-            if event_name.starts_with(registered_prefix) {
-                return processor.process_bytes(payload).await;
-            }
+    /// Returns the event names that no registered route would handle
+    pub fn validate_coverage(&self, event_names: &[&str]) -> Vec<String> {
+        event_names
+            .iter()
+            .filter(|event_name| self.find_route(event_name).is_none())
+            .map(|event_name| event_name.to_string())
+            .collect()
+    }
+
+    /// Finds the route for an event name: an exact match first, then the longest registered prefix
+    ///
+    /// Two matching prefixes of the same length are the same string, so the longest match is unique.
+    fn find_route(&self, event_name: &str) -> Option<&str> {
+        if let Some((registered_name, _)) = self.routes.get_key_value(event_name) {
+            return Some(registered_name);
         }
+        self.routes
+            .keys()
+            .filter(|registered_prefix| event_name.starts_with(registered_prefix.as_str()))
+            .max_by_key(|registered_prefix| registered_prefix.len())
+            .map(String::as_str)
+    }
 
+    fn no_route(&self, event_name: &str) -> Result<()> {
+        if self.strict {
+            return Err(IntegrationError::RouteNotFound(event_name.to_string()));
+        }
         Ok(())
     }
 }
@@ -135,6 +186,10 @@ where
     async fn process_bytes(&mut self, payload: &[u8]) -> Result<()> {
         self.processor.process_bytes(payload).await
     }
+
+    async fn process_bytes_with_metadata(&mut self, payload: &[u8], metadata: &[u8]) -> Result<()> {
+        self.processor.process_bytes_with_metadata(payload, metadata).await
+    }
 }
 
 #[cfg(test)]
@@ -142,10 +197,14 @@ mod tests {
     use super::*;
     use std::sync::{Arc, Mutex};
     use tsuzuri::{
-        event::Metadata, integration::error::IntegrationError, integration_event::IntegrationEvent, message::Message,
+        event::Metadata,
+        integration::{envelope::decode_envelope, error::IntegrationError},
+        integration_event::IntegrationEvent,
+        message::Message,
+        serde::Json,
     };
 
-    #[derive(Debug, Clone, PartialEq)]
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
     struct TestIntegrationEvent {
         pub id: String,
         pub data: String,
@@ -285,6 +344,28 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_typed_event_router_executes_decoded_envelope() {
+        let executer = MockExecuter::<TestIntegrationEvent>::new(false);
+        let calls = executer.calls.clone();
+        let mut router = TypedEventRouter::new().route("TestIntegrationEvent", Box::new(executer));
+
+        let event = TestIntegrationEvent {
+            id: "test-id".to_string(),
+            data: "test data".to_string(),
+        };
+        let payload = serde_json::to_vec(&event).unwrap();
+        let metadata = br#"{"correlation_id":"corr-1"}"#;
+        let envelope = decode_envelope(&payload, metadata, &Json::<TestIntegrationEvent>::default()).unwrap();
+
+        router.execute(envelope).await.unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].message, event);
+        assert_eq!(calls[0].correlation_id(), Some("corr-1"));
+    }
+
     #[tokio::test]
     async fn test_typed_event_router_execute_unregistered_event() {
         let mut router: TypedEventRouter<TestIntegrationEvent> = TypedEventRouter::new();
@@ -344,7 +425,10 @@ mod tests {
             Box::new(mock_processor.clone()) as Box<dyn ProcessorTrait>,
         );
 
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let payload = b"test payload";
         let result = router.process_bytes("TestEvent", payload).await;
@@ -368,7 +452,10 @@ mod tests {
             Box::new(mock_processor.clone()) as Box<dyn ProcessorTrait>,
         );
 
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let payload = b"test payload";
         let result = router
@@ -404,7 +491,10 @@ mod tests {
             Box::new(Arc::new(mock_processor)) as Box<dyn ProcessorTrait>,
         );
 
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let payload = b"test payload";
         let result = router.process_bytes("TestEvent", payload).await;
@@ -434,7 +524,10 @@ mod tests {
             Box::new(prefix_processor.clone()) as Box<dyn ProcessorTrait>,
         );
 
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let payload = b"test payload";
         let result = router.process_bytes("TestEvent", payload).await;
@@ -445,4 +538,99 @@ mod tests {
         // Prefix match should not be called
         assert_eq!(prefix_processor.calls.lock().unwrap().len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_processor_based_event_router_longest_prefix_wins() {
+        let short_prefix_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: false,
+        });
+        let long_prefix_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: false,
+        });
+
+        let mut routes: HashMap<String, Box<dyn ProcessorTrait>> = HashMap::new();
+        routes.insert(
+            "Order".to_string(),
+            Box::new(short_prefix_processor.clone()) as Box<dyn ProcessorTrait>,
+        );
+        routes.insert(
+            "OrderShipped".to_string(),
+            Box::new(long_prefix_processor.clone()) as Box<dyn ProcessorTrait>,
+        );
+
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
+
+        for _ in 0..10 {
+            router.process_bytes("OrderShippedExpress", b"shipped").await.unwrap();
+        }
+        router.process_bytes("OrderPlaced", b"placed").await.unwrap();
+
+        assert_eq!(long_prefix_processor.calls.lock().unwrap().len(), 10);
+        let short_prefix_calls = short_prefix_processor.calls.lock().unwrap();
+        assert_eq!(short_prefix_calls.len(), 1);
+        assert_eq!(short_prefix_calls[0].1, b"placed".to_vec());
+    }
+
+    fn create_router_with_routes(event_names: &[&str]) -> ProcessorBasedEventRouter {
+        let mut routes: HashMap<String, Box<dyn ProcessorTrait>> = HashMap::new();
+        for event_name in event_names {
+            let processor = Arc::new(MockProcessor {
+                calls: Arc::new(Mutex::new(Vec::new())),
+                should_fail: false,
+            });
+            routes.insert(event_name.to_string(), Box::new(processor) as Box<dyn ProcessorTrait>);
+        }
+        ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_coverage_reports_unmatched_events() {
+        let router = create_router_with_routes(&["TestEvent", "ProjectIntegrationEvent"]);
+
+        let uncovered = router.validate_coverage(&[
+            "TestEvent",
+            "ProjectIntegrationEventBodyChanged",
+            "UnknownEvent",
+            "Test",
+        ]);
+        assert_eq!(uncovered, vec!["UnknownEvent".to_string(), "Test".to_string()]);
+    }
+
+    #[test]
+    fn test_validate_coverage_all_covered() {
+        let router = create_router_with_routes(&["TestEvent", "ProjectIntegrationEvent"]);
+
+        let uncovered = router.validate_coverage(&["TestEvent", "ProjectIntegrationEventBodyChanged"]);
+        assert!(uncovered.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_strict_router_rejects_unmatched_event() {
+        let mut router = create_router_with_routes(&["TestEvent"]).strict(true);
+
+        let result = router.process_bytes("UnknownEvent", b"test payload").await;
+        match result.unwrap_err() {
+            IntegrationError::RouteNotFound(event_name) => assert_eq!(event_name, "UnknownEvent"),
+            _ => panic!("Expected RouteNotFound error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strict_router_processes_matched_events() {
+        let mut router = create_router_with_routes(&["TestEvent", "ProjectIntegrationEvent"]).strict(true);
+
+        assert!(router.process_bytes("TestEvent", b"test payload").await.is_ok());
+        assert!(router
+            .process_bytes("ProjectIntegrationEventBodyChanged", b"test payload")
+            .await
+            .is_ok());
+    }
 }
@@ -0,0 +1,240 @@
+use crate::error::{Result, StreamProcessorError};
+use async_trait::async_trait;
+use aws_sdk_eventbridge::types::PutEventsRequestEntry;
+use tracing::error;
+use tsuzuri::integration_event::SerializedIntegrationEvent;
+
+/// Maximum number of entries a single EventBridge `PutEvents` call accepts
+pub const MAX_EVENTBRIDGE_BATCH_SIZE: usize = 10;
+
+/// An event to put onto an EventBridge bus
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventBridgeEntry {
+    pub event_bus_name: String,
+    pub source: String,
+    pub detail_type: String,
+    pub detail: String,
+}
+
+/// The EventBridge operations used by [`EventBridgePublisher`], implemented for `aws_sdk_eventbridge::Client`
+#[async_trait]
+pub trait EventBridgeClient: Send + Sync {
+    /// Put the entries in one call and return the outcome of each, in the order of `entries`
+    async fn put_events(&self, entries: Vec<EventBridgeEntry>) -> Result<Vec<std::result::Result<(), String>>>;
+}
+
+#[async_trait]
+impl EventBridgeClient for aws_sdk_eventbridge::Client {
+    async fn put_events(&self, entries: Vec<EventBridgeEntry>) -> Result<Vec<std::result::Result<(), String>>> {
+        let count = entries.len();
+        let request_entries = entries
+            .into_iter()
+            .map(|entry| {
+                PutEventsRequestEntry::builder()
+                    .event_bus_name(entry.event_bus_name)
+                    .source(entry.source)
+                    .detail_type(entry.detail_type)
+                    .detail(entry.detail)
+                    .build()
+            })
+            .collect();
+        let output = self
+            .put_events()
+            .set_entries(Some(request_entries))
+            .send()
+            .await
+            .map_err(|e| StreamProcessorError::EventBridge(format!("Failed to put events: {e}")))?;
+
+        let mut results: Vec<_> = output
+            .entries()
+            .iter()
+            .map(|entry| match entry.error_code() {
+                Some(code) => Err(format!("{code}: {}", entry.error_message().unwrap_or_default())),
+                None => Ok(()),
+            })
+            .collect();
+        results.resize(count, Err("Missing result entry".to_string()));
+        Ok(results)
+    }
+}
+
+/// An integration event EventBridge did not accept
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublishFailure {
+    pub event_id: String,
+    pub error: String,
+}
+
+/// Outcome of publishing a set of integration events
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PublishReport {
+    pub published: usize,
+    pub failed: Vec<PublishFailure>,
+}
+
+/// Publishes integration events onto an EventBridge bus
+///
+/// Each event becomes an entry with the configured `source`, its `event_type` as `DetailType` and its payload,
+/// which must be a UTF-8 JSON document, as `Detail`. Events are sent in batches of up to 10 entries and failures
+/// are reported per event, so an outbox relay can mark the published events dispatched and retry the others.
+pub struct EventBridgePublisher<C> {
+    client: C,
+    event_bus_name: String,
+    source: String,
+}
+
+impl<C: EventBridgeClient> EventBridgePublisher<C> {
+    pub fn new(client: C, event_bus_name: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            client,
+            event_bus_name: event_bus_name.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn event_bus_name(&self) -> &str {
+        &self.event_bus_name
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Publish the events, stopping at the first batch the EventBridge call itself fails for
+    pub async fn publish(&self, events: &[SerializedIntegrationEvent]) -> Result<PublishReport> {
+        let mut report = PublishReport::default();
+        for batch in events.chunks(MAX_EVENTBRIDGE_BATCH_SIZE) {
+            let mut entries = Vec::with_capacity(batch.len());
+            let mut sent = Vec::with_capacity(batch.len());
+            for event in batch {
+                match std::str::from_utf8(&event.payload) {
+                    Ok(detail) => {
+                        entries.push(EventBridgeEntry {
+                            event_bus_name: self.event_bus_name.clone(),
+                            source: self.source.clone(),
+                            detail_type: event.event_type.clone(),
+                            detail: detail.to_string(),
+                        });
+                        sent.push(event);
+                    }
+                    Err(e) => report.failed.push(PublishFailure {
+                        event_id: event.id.clone(),
+                        error: format!("Payload is not UTF-8: {e}"),
+                    }),
+                }
+            }
+            if entries.is_empty() {
+                continue;
+            }
+
+            let results = self.client.put_events(entries).await?;
+            for (event, result) in sent.into_iter().zip(results) {
+                match result {
+                    Ok(()) => report.published += 1,
+                    Err(e) => {
+                        error!("Failed to publish integration event {} to EventBridge: {}", event.id, e);
+                        report.failed.push(PublishFailure {
+                            event_id: event.id.clone(),
+                            error: e,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct MockEventBridgeClient {
+        batches: Mutex<Vec<Vec<EventBridgeEntry>>>,
+        rejected_detail_type: Option<String>,
+    }
+
+    #[async_trait]
+    impl EventBridgeClient for Arc<MockEventBridgeClient> {
+        async fn put_events(&self, entries: Vec<EventBridgeEntry>) -> Result<Vec<std::result::Result<(), String>>> {
+            let results = entries
+                .iter()
+                .map(|entry| match &self.rejected_detail_type {
+                    Some(rejected) if *rejected == entry.detail_type => {
+                        Err("InternalFailure: Mock failure".to_string())
+                    }
+                    _ => Ok(()),
+                })
+                .collect();
+            self.batches.lock().unwrap().push(entries);
+            Ok(results)
+        }
+    }
+
+    fn integration_event(id: usize, event_type: &str, payload: &[u8]) -> SerializedIntegrationEvent {
+        SerializedIntegrationEvent::new(
+            format!("evt-{id}"),
+            "ord-1".to_string(),
+            "Order".to_string(),
+            event_type.to_string(),
+            payload.to_vec(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_publish_batches_entries() {
+        let client = Arc::new(MockEventBridgeClient::default());
+        let publisher = EventBridgePublisher::new(client.clone(), "orders-bus", "tsuzuri.orders");
+        let events: Vec<_> = (0..23)
+            .map(|i| integration_event(i, "OrderShipped", br#"{"order_id":"ord-1"}"#))
+            .collect();
+
+        let report = publisher.publish(&events).await.unwrap();
+
+        assert_eq!(
+            report,
+            PublishReport {
+                published: 23,
+                failed: vec![]
+            }
+        );
+        let batches = client.batches.lock().unwrap();
+        let sizes: Vec<_> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![10, 10, 3]);
+        assert_eq!(
+            batches[0][0],
+            EventBridgeEntry {
+                event_bus_name: "orders-bus".to_string(),
+                source: "tsuzuri.orders".to_string(),
+                detail_type: "OrderShipped".to_string(),
+                detail: r#"{"order_id":"ord-1"}"#.to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_reports_failed_entries() {
+        let client = Arc::new(MockEventBridgeClient {
+            rejected_detail_type: Some("OrderCancelled".to_string()),
+            ..Default::default()
+        });
+        let publisher = EventBridgePublisher::new(client.clone(), "orders-bus", "tsuzuri.orders");
+        let events = vec![
+            integration_event(1, "OrderShipped", b"{}"),
+            integration_event(2, "OrderCancelled", b"{}"),
+            integration_event(3, "OrderShipped", &[0xff, 0xfe]),
+            integration_event(4, "OrderShipped", b"{}"),
+        ];
+
+        let report = publisher.publish(&events).await.unwrap();
+
+        assert_eq!(report.published, 2);
+        let failed: Vec<_> = report.failed.iter().map(|failure| failure.event_id.as_str()).collect();
+        assert_eq!(failed, vec!["evt-3", "evt-2"]);
+        assert_eq!(report.failed[1].error, "InternalFailure: Mock failure");
+        // The non-UTF-8 payload is never sent
+        assert_eq!(client.batches.lock().unwrap()[0].len(), 3);
+    }
+}
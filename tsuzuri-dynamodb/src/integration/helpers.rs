@@ -58,6 +58,18 @@ pub fn extract_binary_attribute(attributes: &HashMap<String, AttributeValue>, fi
     }
 }
 
+/// Like [`extract_binary_attribute`], but returns an empty vector when the field is absent
+pub fn extract_optional_binary_attribute(
+    attributes: &HashMap<String, AttributeValue>,
+    field_name: &str,
+) -> Result<Vec<u8>> {
+    if attributes.contains_key(field_name) {
+        extract_binary_attribute(attributes, field_name)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
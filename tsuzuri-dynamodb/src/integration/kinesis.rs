@@ -1,4 +1,7 @@
 pub mod lambda;
 pub mod local;
 
-pub use lambda::process_kinesis_lambda_event;
+pub use lambda::{
+    process_kinesis_lambda_event, process_kinesis_lambda_event_with_config, KinesisBatchItemFailure,
+    KinesisBatchResponse, KinesisLambdaConfig,
+};
@@ -1,18 +1,68 @@
 use crate::error::{Result, StreamProcessorError};
 use crate::integration::event_type_router::ProcessorBasedEventRouter;
-use crate::integration::helpers::{extract_binary_attribute, extract_string_attribute};
+use crate::integration::helpers::{
+    extract_binary_attribute, extract_optional_binary_attribute, extract_string_attribute,
+};
 use aws_lambda_events::dynamodb::StreamRecord;
 use aws_lambda_events::kinesis::KinesisEvent;
 use lambda_runtime::LambdaEvent;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+/// Configuration for processing Kinesis Lambda events
+#[derive(Clone, Debug, Default)]
+pub struct KinesisLambdaConfig {
+    /// Stop at the first failing record and fail the whole batch instead of reporting item failures
+    pub fail_fast: bool,
+}
+
+/// Partial batch response for a Kinesis event source mapping with `ReportBatchItemFailures` enabled
+///
+/// Lambda checkpoints before the lowest reported sequence number and retries the batch from there, so records
+/// that are not reported are acknowledged.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KinesisBatchResponse {
+    pub batch_item_failures: Vec<KinesisBatchItemFailure>,
+}
 
+/// A record that failed to process, identified by its sequence number
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KinesisBatchItemFailure {
+    pub item_identifier: String,
+}
+
+/// Process every record of the event and report the ones that failed
 pub async fn process_kinesis_lambda_event(
     router: &mut ProcessorBasedEventRouter,
     event: LambdaEvent<KinesisEvent>,
-) -> Result<()> {
+) -> Result<KinesisBatchResponse> {
+    process_kinesis_lambda_event_with_config(router, event, &KinesisLambdaConfig::default()).await
+}
+
+/// Process the records of the event, reporting failures or failing fast as configured
+pub async fn process_kinesis_lambda_event_with_config(
+    router: &mut ProcessorBasedEventRouter,
+    event: LambdaEvent<KinesisEvent>,
+    config: &KinesisLambdaConfig,
+) -> Result<KinesisBatchResponse> {
+    let mut response = KinesisBatchResponse::default();
     for record in event.payload.records {
-        process_single_record(router, &record.kinesis.data).await?;
+        if let Err(e) = process_single_record(router, &record.kinesis.data).await {
+            if config.fail_fast {
+                return Err(e);
+            }
+            error!(
+                "Failed to process record with sequence number {}: {}",
+                record.kinesis.sequence_number, e
+            );
+            response.batch_item_failures.push(KinesisBatchItemFailure {
+                item_identifier: record.kinesis.sequence_number,
+            });
+        }
     }
-    Ok(())
+    Ok(response)
 }
 
 async fn process_single_record(router: &mut ProcessorBasedEventRouter, data: &[u8]) -> Result<()> {
@@ -21,9 +71,10 @@ async fn process_single_record(router: &mut ProcessorBasedEventRouter, data: &[u
 
     let event_type = extract_string_attribute(&attribute_values, "event_type")?;
     let payload_bytes = extract_binary_attribute(&attribute_values, "payload")?;
+    let metadata_bytes = extract_optional_binary_attribute(&attribute_values, "metadata")?;
 
     router
-        .process_bytes(event_type, &payload_bytes)
+        .process_bytes_with_metadata(event_type, &payload_bytes, &metadata_bytes)
         .await
         .map_err(|e| StreamProcessorError::InvalidData(format!("Failed to process event: {e}")))
 }
@@ -57,7 +108,7 @@ mod tests {
     use std::sync::{Arc, Mutex};
     use tsuzuri::integration::error::Result as IntegrationResult;
 
-    // Mock ProcessorTrait implementation for testing
+    // Mock ProcessorTrait implementation for testing, recording the metadata and payload of every call
     type MockProcessorCalls = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
 
     struct MockProcessor {
@@ -75,7 +126,13 @@ mod tests {
             }
             // Store the call for verification
             let mut calls = self.calls.lock().unwrap();
-            calls.push(("event_type".to_string(), payload.to_vec()));
+            calls.push((String::new(), payload.to_vec()));
+            Ok(())
+        }
+
+        async fn process_bytes_with_metadata(&mut self, payload: &[u8], metadata: &[u8]) -> IntegrationResult<()> {
+            self.process_bytes(payload).await?;
+            self.calls.lock().unwrap().last_mut().unwrap().0 = String::from_utf8(metadata.to_vec()).unwrap();
             Ok(())
         }
     }
@@ -87,6 +144,10 @@ mod tests {
     }
 
     fn create_kinesis_record(data: Vec<u8>) -> KinesisEventRecord {
+        create_kinesis_record_with_sequence_number(data, "12345")
+    }
+
+    fn create_kinesis_record_with_sequence_number(data: Vec<u8>, sequence_number: &str) -> KinesisEventRecord {
         KinesisEventRecord {
             aws_region: Some("us-east-1".to_string()),
             event_id: Some("test-event-id".to_string()),
@@ -100,19 +161,29 @@ mod tests {
                 data: Base64Data(data),
                 encryption_type: aws_lambda_events::kinesis::KinesisEncryptionType::None,
                 partition_key: "test-partition".to_string(),
-                sequence_number: "12345".to_string(),
+                sequence_number: sequence_number.to_string(),
                 kinesis_schema_version: Some("1.0".to_string()),
             },
         }
     }
 
     fn create_dynamodb_stream_data(event_type: &str, payload: &[u8]) -> Vec<u8> {
+        create_dynamodb_stream_data_with_metadata(event_type, payload, None)
+    }
+
+    fn create_dynamodb_stream_data_with_metadata(event_type: &str, payload: &[u8], metadata: Option<&[u8]>) -> Vec<u8> {
         let mut new_image = HashMap::new();
         new_image.insert("event_type".to_string(), AttributeValue::S(event_type.to_string()));
         new_image.insert(
             "payload".to_string(),
             AttributeValue::B(base64::engine::general_purpose::STANDARD.encode(payload).into_bytes()),
         );
+        if let Some(metadata) = metadata {
+            new_image.insert(
+                "metadata".to_string(),
+                AttributeValue::B(base64::engine::general_purpose::STANDARD.encode(metadata).into_bytes()),
+            );
+        }
 
         let stream_record = StreamRecord {
             approximate_creation_date_time: Utc::now(),
@@ -189,7 +260,10 @@ mod tests {
             Box::new(mock_processor.clone()) as Box<dyn crate::integration::event_type_router::ProcessorTrait>,
         );
 
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let stream_data = create_dynamodb_stream_data("TestEvent", b"test payload");
 
@@ -200,6 +274,32 @@ mod tests {
         let calls = mock_processor.calls.lock().unwrap();
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].1, b"test payload");
+        assert_eq!(calls[0].0, "");
+    }
+
+    #[tokio::test]
+    async fn test_process_single_record_passes_metadata() {
+        let mock_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: false,
+        });
+        let mut routes: HashMap<String, Box<dyn crate::integration::event_type_router::ProcessorTrait>> =
+            HashMap::new();
+        routes.insert("TestEvent".to_string(), Box::new(mock_processor.clone()));
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
+        let metadata = br#"{"correlation_id":"req-1"}"#;
+
+        let stream_data = create_dynamodb_stream_data_with_metadata("TestEvent", b"test payload", Some(metadata));
+        process_single_record(&mut router, &stream_data).await.unwrap();
+
+        let calls = mock_processor.calls.lock().unwrap();
+        assert_eq!(
+            calls[0],
+            (r#"{"correlation_id":"req-1"}"#.to_string(), b"test payload".to_vec())
+        );
     }
 
     #[tokio::test]
@@ -216,7 +316,10 @@ mod tests {
             Box::new(mock_processor.clone()) as Box<dyn crate::integration::event_type_router::ProcessorTrait>,
         );
 
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         // Create test data
         let stream_data1 = create_dynamodb_stream_data("TestEvent", b"payload1");
@@ -226,8 +329,8 @@ mod tests {
 
         let lambda_event = create_test_lambda_event(records);
 
-        let result = process_kinesis_lambda_event(&mut router, lambda_event).await;
-        assert!(result.is_ok());
+        let response = process_kinesis_lambda_event(&mut router, lambda_event).await.unwrap();
+        assert!(response.batch_item_failures.is_empty());
 
         // Verify both records were processed
         let calls = mock_processor.calls.lock().unwrap();
@@ -250,16 +353,112 @@ mod tests {
             Box::new(mock_processor) as Box<dyn crate::integration::event_type_router::ProcessorTrait>,
         );
 
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let stream_data = create_dynamodb_stream_data("TestEvent", b"payload");
         let records = vec![create_kinesis_record(stream_data)];
         let lambda_event = create_test_lambda_event(records);
 
-        let result = process_kinesis_lambda_event(&mut router, lambda_event).await;
+        let result = process_kinesis_lambda_event_with_config(
+            &mut router,
+            lambda_event,
+            &KinesisLambdaConfig { fail_fast: true },
+        )
+        .await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_process_kinesis_lambda_event_reports_only_failed_records() {
+        let succeeding_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: false,
+        });
+        let failing_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: true,
+        });
+
+        let mut routes: HashMap<String, Box<dyn crate::integration::event_type_router::ProcessorTrait>> =
+            HashMap::new();
+        routes.insert(
+            "TestEvent".to_string(),
+            Box::new(succeeding_processor.clone()) as Box<dyn crate::integration::event_type_router::ProcessorTrait>,
+        );
+        routes.insert(
+            "FailingEvent".to_string(),
+            Box::new(failing_processor) as Box<dyn crate::integration::event_type_router::ProcessorTrait>,
+        );
+
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
+
+        let records = vec![
+            create_kinesis_record_with_sequence_number(create_dynamodb_stream_data("TestEvent", b"payload1"), "1"),
+            create_kinesis_record_with_sequence_number(create_dynamodb_stream_data("FailingEvent", b"payload2"), "2"),
+            create_kinesis_record_with_sequence_number(create_dynamodb_stream_data("TestEvent", b"payload3"), "3"),
+            create_kinesis_record_with_sequence_number(b"not json".to_vec(), "4"),
+        ];
+        let lambda_event = create_test_lambda_event(records);
+
+        let response = process_kinesis_lambda_event(&mut router, lambda_event).await.unwrap();
+        assert_eq!(
+            response.batch_item_failures,
+            vec![
+                KinesisBatchItemFailure {
+                    item_identifier: "2".to_string()
+                },
+                KinesisBatchItemFailure {
+                    item_identifier: "4".to_string()
+                },
+            ]
+        );
+
+        // Records after a failure are still processed
+        let calls = succeeding_processor.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_process_kinesis_lambda_event_fail_fast_stops_at_first_failure() {
+        let succeeding_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: false,
+        });
+
+        let mut routes: HashMap<String, Box<dyn crate::integration::event_type_router::ProcessorTrait>> =
+            HashMap::new();
+        routes.insert(
+            "TestEvent".to_string(),
+            Box::new(succeeding_processor.clone()) as Box<dyn crate::integration::event_type_router::ProcessorTrait>,
+        );
+
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
+
+        let records = vec![
+            create_kinesis_record_with_sequence_number(b"not json".to_vec(), "1"),
+            create_kinesis_record_with_sequence_number(create_dynamodb_stream_data("TestEvent", b"payload2"), "2"),
+        ];
+        let lambda_event = create_test_lambda_event(records);
+
+        let result = process_kinesis_lambda_event_with_config(
+            &mut router,
+            lambda_event,
+            &KinesisLambdaConfig { fail_fast: true },
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(succeeding_processor.calls.lock().unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_process_single_record_missing_event_type() {
         let _mock_processor = Arc::new(MockProcessor {
@@ -268,7 +467,10 @@ mod tests {
         });
 
         let routes: HashMap<String, Box<dyn crate::integration::event_type_router::ProcessorTrait>> = HashMap::new();
-        let mut router = ProcessorBasedEventRouter { routes };
+        let mut router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         // Create stream data without event_type field
         let mut new_image = HashMap::new();
@@ -1,4 +1,5 @@
 use crate::{
+    checkpoint::CheckpointStore,
     error::{Result, StreamProcessorError},
     integration::{
         event_type_router::ProcessorBasedEventRouter,
@@ -22,6 +23,7 @@ pub struct LocalKinesisDebugger {
     stream_name: String,
     metrics: Arc<Mutex<DebugMetrics>>,
     config: DebugConfig,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
 }
 
 /// Configuration for the local debugger
@@ -37,6 +39,22 @@ pub struct DebugConfig {
     pub pause_between_records: bool,
     /// Pause duration in milliseconds
     pub pause_duration_ms: u64,
+    /// Position in each shard to start reading from when no checkpoint is saved
+    pub iterator_type: ShardIteratorConfig,
+}
+
+/// Starting position of a shard iterator
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ShardIteratorConfig {
+    /// Only records written after the debugger starts
+    #[default]
+    Latest,
+    /// The oldest record still retained in the shard
+    TrimHorizon,
+    /// The first record written at or after the given time
+    AtTimestamp(DateTime<Utc>),
+    /// The record with the given sequence number
+    AtSequenceNumber(String),
 }
 
 impl Default for DebugConfig {
@@ -47,6 +65,7 @@ impl Default for DebugConfig {
             pretty_print: true,
             pause_between_records: false,
             pause_duration_ms: 1000,
+            iterator_type: ShardIteratorConfig::Latest,
         }
     }
 }
@@ -76,9 +95,16 @@ impl LocalKinesisDebugger {
             stream_name,
             metrics: Arc::new(Mutex::new(DebugMetrics::default())),
             config,
+            checkpoint_store: None,
         }
     }
 
+    /// Resume every shard after its saved checkpoint and record progress as records are processed
+    pub fn with_checkpoint_store(mut self, checkpoint_store: impl CheckpointStore) -> Self {
+        self.checkpoint_store = Some(Arc::new(checkpoint_store));
+        self
+    }
+
     /// Start polling and processing Kinesis stream
     pub async fn run(&self) -> Result<()> {
         info!("Starting local Kinesis debugger for stream: {}", self.stream_name);
@@ -148,6 +174,7 @@ impl LocalKinesisDebugger {
 
         let mut current_iterator = Some(shard_iterator);
         let mut processed_count = 0;
+        let mut last_sequence_number = None;
 
         while let Some(iterator) = current_iterator {
             if processed_count >= max_items {
@@ -171,9 +198,16 @@ impl LocalKinesisDebugger {
                 if processed_count >= max_items {
                     break;
                 }
-                self.process_record(record).await?;
+                if let Err(e) = self.process_record(record).await {
+                    self.save_checkpoint(shard_id, last_sequence_number.as_deref()).await?;
+                    return Err(e);
+                }
+                last_sequence_number = Some(record.sequence_number.clone());
                 processed_count += 1;
             }
+            if !records.is_empty() {
+                self.save_checkpoint(shard_id, last_sequence_number.as_deref()).await?;
+            }
 
             current_iterator = records_output.next_shard_iterator().map(String::from);
 
@@ -186,14 +220,30 @@ impl LocalKinesisDebugger {
         Ok(processed_count)
     }
 
-    /// Get shard iterator
+    /// Save the position of the last processed record, if a checkpoint store is configured
+    async fn save_checkpoint(&self, shard_id: &str, sequence_number: Option<&str>) -> Result<()> {
+        match (&self.checkpoint_store, sequence_number) {
+            (Some(store), Some(sequence_number)) => store.save_checkpoint(shard_id, sequence_number).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Get shard iterator, resuming after the saved checkpoint when there is one and starting at the
+    /// configured iterator type otherwise
     async fn get_shard_iterator(&self, stream_arn: &str, shard_id: &str) -> Result<String> {
+        let checkpoint = match &self.checkpoint_store {
+            Some(store) => store.load_checkpoint(shard_id).await?,
+            None => None,
+        };
+        let start = shard_iterator_start(checkpoint, &self.config.iterator_type);
         let output = self
             .kinesis_client
             .get_shard_iterator()
             .stream_arn(stream_arn)
             .shard_id(shard_id)
-            .shard_iterator_type(ShardIteratorType::Latest)
+            .shard_iterator_type(start.iterator_type)
+            .set_starting_sequence_number(start.starting_sequence_number)
+            .set_timestamp(start.timestamp)
             .send()
             .await
             .map_err(|e| StreamProcessorError::KinesisDataStreams(format!("Failed to get shard iterator: {e}")))?;
@@ -237,6 +287,36 @@ impl LocalKinesisDebugger {
     }
 }
 
+/// Parameters of a `GetShardIterator` request
+#[derive(Debug, PartialEq)]
+pub(crate) struct ShardIteratorStart {
+    pub(crate) iterator_type: ShardIteratorType,
+    pub(crate) starting_sequence_number: Option<String>,
+    pub(crate) timestamp: Option<aws_smithy_types::DateTime>,
+}
+
+/// Where to start reading a shard; a saved checkpoint takes precedence over the configured iterator type
+pub(crate) fn shard_iterator_start(checkpoint: Option<String>, config: &ShardIteratorConfig) -> ShardIteratorStart {
+    let (iterator_type, starting_sequence_number, timestamp) = match (checkpoint, config) {
+        (Some(sequence_number), _) => (ShardIteratorType::AfterSequenceNumber, Some(sequence_number), None),
+        (None, ShardIteratorConfig::Latest) => (ShardIteratorType::Latest, None, None),
+        (None, ShardIteratorConfig::TrimHorizon) => (ShardIteratorType::TrimHorizon, None, None),
+        (None, ShardIteratorConfig::AtTimestamp(timestamp)) => (
+            ShardIteratorType::AtTimestamp,
+            None,
+            Some(aws_smithy_types::DateTime::from_millis(timestamp.timestamp_millis())),
+        ),
+        (None, ShardIteratorConfig::AtSequenceNumber(sequence_number)) => {
+            (ShardIteratorType::AtSequenceNumber, Some(sequence_number.clone()), None)
+        }
+    };
+    ShardIteratorStart {
+        iterator_type,
+        starting_sequence_number,
+        timestamp,
+    }
+}
+
 /// Processor wrapper for local debugging
 struct LocalDebugProcessor {
     router: Arc<Mutex<ProcessorBasedEventRouter>>,
@@ -369,6 +449,20 @@ impl LocalDebugProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::checkpoint::MemoryCheckpointStore;
+
+    fn create_debugger() -> LocalKinesisDebugger {
+        let config = aws_sdk_kinesis::Config::builder()
+            .behavior_version(aws_sdk_kinesis::config::BehaviorVersion::latest())
+            .region(aws_sdk_kinesis::config::Region::new("us-east-1"))
+            .build();
+        LocalKinesisDebugger::new(
+            KinesisClient::from_conf(config),
+            ProcessorBasedEventRouter::new(),
+            "test-stream".to_string(),
+            DebugConfig::default(),
+        )
+    }
 
     #[test]
     fn test_debug_config_default() {
@@ -378,6 +472,81 @@ mod tests {
         assert!(config.pretty_print);
         assert!(!config.pause_between_records);
         assert_eq!(config.pause_duration_ms, 1000);
+        assert_eq!(config.iterator_type, ShardIteratorConfig::Latest);
+    }
+
+    #[test]
+    fn test_shard_iterator_start_without_checkpoint() {
+        assert_eq!(
+            shard_iterator_start(None, &ShardIteratorConfig::Latest),
+            ShardIteratorStart {
+                iterator_type: ShardIteratorType::Latest,
+                starting_sequence_number: None,
+                timestamp: None,
+            }
+        );
+        assert_eq!(
+            shard_iterator_start(None, &ShardIteratorConfig::TrimHorizon),
+            ShardIteratorStart {
+                iterator_type: ShardIteratorType::TrimHorizon,
+                starting_sequence_number: None,
+                timestamp: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_shard_iterator_start_at_timestamp() {
+        let timestamp = DateTime::parse_from_rfc3339("2025-01-15T09:30:00.250Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let start = shard_iterator_start(None, &ShardIteratorConfig::AtTimestamp(timestamp));
+
+        assert_eq!(start.iterator_type, ShardIteratorType::AtTimestamp);
+        assert_eq!(start.starting_sequence_number, None);
+        let sdk_timestamp = start.timestamp.unwrap();
+        assert_eq!(sdk_timestamp.secs(), timestamp.timestamp());
+        assert_eq!(sdk_timestamp.subsec_nanos(), 250_000_000);
+    }
+
+    #[test]
+    fn test_shard_iterator_start_at_sequence_number() {
+        assert_eq!(
+            shard_iterator_start(None, &ShardIteratorConfig::AtSequenceNumber("42".to_string())),
+            ShardIteratorStart {
+                iterator_type: ShardIteratorType::AtSequenceNumber,
+                starting_sequence_number: Some("42".to_string()),
+                timestamp: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_shard_iterator_start_resumes_after_checkpoint() {
+        let checkpoint = "49590338271490256608559692538361571095921575989136588898".to_string();
+        assert_eq!(
+            shard_iterator_start(Some(checkpoint.clone()), &ShardIteratorConfig::TrimHorizon),
+            ShardIteratorStart {
+                iterator_type: ShardIteratorType::AfterSequenceNumber,
+                starting_sequence_number: Some(checkpoint),
+                timestamp: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_checkpoint_records_last_sequence_number() {
+        let checkpoint_store = MemoryCheckpointStore::new();
+        let debugger = create_debugger().with_checkpoint_store(checkpoint_store.clone());
+
+        debugger.save_checkpoint("shard-0", None).await.unwrap();
+        assert_eq!(checkpoint_store.load_checkpoint("shard-0").await.unwrap(), None);
+
+        debugger.save_checkpoint("shard-0", Some("42")).await.unwrap();
+        assert_eq!(
+            checkpoint_store.load_checkpoint("shard-0").await.unwrap().as_deref(),
+            Some("42")
+        );
     }
 
     #[test]
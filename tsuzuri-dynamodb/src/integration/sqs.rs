@@ -0,0 +1,477 @@
+use crate::error::{Result, StreamProcessorError};
+use crate::integration::event_type_router::ProcessorBasedEventRouter;
+use crate::integration::helpers::{
+    extract_binary_attribute, extract_optional_binary_attribute, extract_string_attribute,
+};
+use async_trait::async_trait;
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// Maximum number of messages a single SQS `ReceiveMessage` call returns
+pub const MAX_SQS_BATCH_SIZE: i32 = 10;
+
+/// A message received from an SQS queue
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqsMessage {
+    pub message_id: String,
+    pub receipt_handle: String,
+    pub body: String,
+}
+
+/// The SQS operations used by [`SqsEventConsumer`], implemented for `aws_sdk_sqs::Client`
+#[async_trait]
+pub trait SqsClient: Send + Sync {
+    async fn receive_messages(
+        &self,
+        queue_url: &str,
+        max_messages: i32,
+        wait_time: Duration,
+    ) -> Result<Vec<SqsMessage>>;
+
+    async fn delete_message(&self, queue_url: &str, receipt_handle: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl SqsClient for aws_sdk_sqs::Client {
+    async fn receive_messages(
+        &self,
+        queue_url: &str,
+        max_messages: i32,
+        wait_time: Duration,
+    ) -> Result<Vec<SqsMessage>> {
+        let output = self
+            .receive_message()
+            .queue_url(queue_url)
+            .max_number_of_messages(max_messages)
+            .wait_time_seconds(i32::try_from(wait_time.as_secs()).unwrap_or(i32::MAX))
+            .send()
+            .await
+            .map_err(|e| StreamProcessorError::Sqs(format!("Failed to receive messages: {e}")))?;
+        Ok(output
+            .messages()
+            .iter()
+            .filter_map(|message| {
+                Some(SqsMessage {
+                    message_id: message.message_id()?.to_string(),
+                    receipt_handle: message.receipt_handle()?.to_string(),
+                    body: message.body()?.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    async fn delete_message(&self, queue_url: &str, receipt_handle: &str) -> Result<()> {
+        self.delete_message()
+            .queue_url(queue_url)
+            .receipt_handle(receipt_handle)
+            .send()
+            .await
+            .map_err(|e| StreamProcessorError::Sqs(format!("Failed to delete message: {e}")))?;
+        Ok(())
+    }
+}
+
+/// Outcome of one batch of messages
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SqsBatchSummary {
+    /// Messages processed and deleted from the queue
+    pub processed: usize,
+    /// IDs of the messages that failed and were left on the queue for redelivery
+    pub failed: Vec<String>,
+}
+
+/// Consumes integration events from an SQS queue, the polling counterpart of the Kinesis Lambda handler
+///
+/// Each message body is either a DynamoDB stream record (`{"dynamodb": {"NewImage": ...}}`, as forwarded by
+/// EventBridge Pipes) or an outbox item in DynamoDB JSON. Its `event_type` and `payload` attributes are routed
+/// through a [`ProcessorBasedEventRouter`]. Processed messages are deleted; failed ones stay on the queue and are
+/// redelivered once their visibility timeout expires, ending up in the dead-letter queue if one is configured.
+pub struct SqsEventConsumer<C> {
+    client: C,
+    queue_url: String,
+    max_messages: i32,
+    wait_time: Duration,
+}
+
+impl<C: SqsClient> SqsEventConsumer<C> {
+    pub fn new(client: C, queue_url: impl Into<String>) -> Self {
+        Self {
+            client,
+            queue_url: queue_url.into(),
+            max_messages: MAX_SQS_BATCH_SIZE,
+            wait_time: Duration::from_secs(20),
+        }
+    }
+
+    /// Number of messages received per batch, clamped to 1..=10
+    pub fn with_max_messages(mut self, max_messages: i32) -> Self {
+        self.max_messages = max_messages.clamp(1, MAX_SQS_BATCH_SIZE);
+        self
+    }
+
+    /// Long polling wait time of each receive, 20 seconds by default
+    pub fn with_wait_time(mut self, wait_time: Duration) -> Self {
+        self.wait_time = wait_time;
+        self
+    }
+
+    pub fn queue_url(&self) -> &str {
+        &self.queue_url
+    }
+
+    /// Receive one batch of messages and process it
+    pub async fn poll_once(&self, router: &mut ProcessorBasedEventRouter) -> Result<SqsBatchSummary> {
+        let messages = self
+            .client
+            .receive_messages(&self.queue_url, self.max_messages, self.wait_time)
+            .await?;
+        self.process_messages(router, messages).await
+    }
+
+    /// Poll the queue until receiving or deleting messages fails
+    pub async fn run(&self, router: &mut ProcessorBasedEventRouter) -> Result<()> {
+        loop {
+            let summary = self.poll_once(router).await?;
+            debug!(
+                "Processed {} SQS messages, {} failed",
+                summary.processed,
+                summary.failed.len()
+            );
+        }
+    }
+
+    /// Process every message, deleting the ones that succeeded and leaving failures for redelivery
+    pub async fn process_messages(
+        &self,
+        router: &mut ProcessorBasedEventRouter,
+        messages: Vec<SqsMessage>,
+    ) -> Result<SqsBatchSummary> {
+        let mut summary = SqsBatchSummary::default();
+        for message in messages {
+            match process_single_message(router, &message.body).await {
+                Ok(()) => {
+                    self.client
+                        .delete_message(&self.queue_url, &message.receipt_handle)
+                        .await?;
+                    summary.processed += 1;
+                }
+                Err(e) => {
+                    error!("Failed to process SQS message {}: {}", message.message_id, e);
+                    summary.failed.push(message.message_id);
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
+
+async fn process_single_message(router: &mut ProcessorBasedEventRouter, body: &str) -> Result<()> {
+    let attribute_values = extract_item(body)?;
+
+    let event_type = extract_string_attribute(&attribute_values, "event_type")?;
+    let payload_bytes = extract_binary_attribute(&attribute_values, "payload")?;
+    let metadata_bytes = extract_optional_binary_attribute(&attribute_values, "metadata")?;
+
+    router
+        .process_bytes_with_metadata(event_type, &payload_bytes, &metadata_bytes)
+        .await
+        .map_err(|e| StreamProcessorError::InvalidData(format!("Failed to process event: {e}")))
+}
+
+/// Returns the item a message carries: the new image of a stream record, or the outbox item itself
+fn extract_item(body: &str) -> Result<std::collections::HashMap<String, serde_dynamo::AttributeValue>> {
+    let mut json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| StreamProcessorError::InvalidData(format!("Failed to deserialize SQS message: {e}")))?;
+
+    let item = match json.get_mut("dynamodb") {
+        Some(stream_record) => stream_record
+            .get_mut("NewImage")
+            .map(serde_json::Value::take)
+            .ok_or_else(|| {
+                StreamProcessorError::InvalidData("Missing 'NewImage' in DynamoDB stream record".to_string())
+            })?,
+        None => json,
+    };
+    let item: serde_dynamo::Item = serde_json::from_value(item)
+        .map_err(|e| StreamProcessorError::InvalidData(format!("Failed to parse DynamoDB item: {e}")))?;
+    Ok(item.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::event_type_router::ProcessorTrait;
+    use base64::Engine;
+    use serde_dynamo::AttributeValue;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tsuzuri::{
+        event::Envelope,
+        integration::{
+            adapter::Executer,
+            error::{IntegrationError, Result as IntegrationResult},
+            processor::Processor,
+        },
+        integration_event::{IntegrationEvent, SerializedIntegrationEvent},
+        message::{Message, Metadata, CAUSATION_ID, CORRELATION_ID},
+        serde::Json,
+    };
+
+    type MockProcessorCalls = Arc<Mutex<Vec<Vec<u8>>>>;
+
+    struct MockProcessor {
+        calls: MockProcessorCalls,
+        should_fail: bool,
+    }
+
+    #[async_trait]
+    impl ProcessorTrait for Arc<MockProcessor> {
+        async fn process_bytes(&mut self, payload: &[u8]) -> IntegrationResult<()> {
+            if self.should_fail {
+                return Err(IntegrationError::Database("Mock error".to_string()));
+            }
+            self.calls.lock().unwrap().push(payload.to_vec());
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct MockSqsClient {
+        messages: Mutex<Vec<SqsMessage>>,
+        deleted: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl SqsClient for Arc<MockSqsClient> {
+        async fn receive_messages(
+            &self,
+            _queue_url: &str,
+            max_messages: i32,
+            _wait_time: Duration,
+        ) -> Result<Vec<SqsMessage>> {
+            let mut messages = self.messages.lock().unwrap();
+            let count = messages.len().min(max_messages as usize);
+            Ok(messages.drain(..count).collect())
+        }
+
+        async fn delete_message(&self, _queue_url: &str, receipt_handle: &str) -> Result<()> {
+            self.deleted.lock().unwrap().push(receipt_handle.to_string());
+            Ok(())
+        }
+    }
+
+    fn create_router(succeeding: &Arc<MockProcessor>, failing: &Arc<MockProcessor>) -> ProcessorBasedEventRouter {
+        let mut routes: HashMap<String, Box<dyn ProcessorTrait>> = HashMap::new();
+        routes.insert("TestEvent".to_string(), Box::new(succeeding.clone()));
+        routes.insert("FailingEvent".to_string(), Box::new(failing.clone()));
+        ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        }
+    }
+
+    fn mock_processor(should_fail: bool) -> Arc<MockProcessor> {
+        Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail,
+        })
+    }
+
+    fn outbox_item(event_type: &str, payload: &[u8]) -> serde_json::Value {
+        let mut item = HashMap::new();
+        item.insert("event_type".to_string(), AttributeValue::S(event_type.to_string()));
+        item.insert(
+            "payload".to_string(),
+            AttributeValue::B(base64::engine::general_purpose::STANDARD.encode(payload).into_bytes()),
+        );
+        serde_json::to_value(serde_dynamo::Item::from(item)).unwrap()
+    }
+
+    /// DynamoDB JSON of an item the store writes, as a pipe from the outbox table forwards it
+    fn outbox_body(item: &HashMap<String, aws_sdk_dynamodb::types::AttributeValue>) -> serde_json::Value {
+        let item: HashMap<_, _> = item
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    aws_sdk_dynamodb::types::AttributeValue::S(s) => AttributeValue::S(s.clone()),
+                    aws_sdk_dynamodb::types::AttributeValue::N(n) => AttributeValue::N(n.clone()),
+                    aws_sdk_dynamodb::types::AttributeValue::B(b) => AttributeValue::B(
+                        base64::engine::general_purpose::STANDARD
+                            .encode(b.as_ref())
+                            .into_bytes(),
+                    ),
+                    other => panic!("unexpected outbox attribute {other:?}"),
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        serde_json::to_value(serde_dynamo::Item::from(item)).unwrap()
+    }
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct OrderShipped {
+        id: String,
+    }
+
+    impl Message for OrderShipped {
+        fn name(&self) -> &'static str {
+            "OrderShipped"
+        }
+    }
+
+    impl IntegrationEvent for OrderShipped {
+        fn id(&self) -> String {
+            self.id.clone()
+        }
+
+        fn event_type(&self) -> &'static str {
+            "OrderShipped"
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingAdapter {
+        envelopes: Arc<Mutex<Vec<Envelope<OrderShipped>>>>,
+    }
+
+    #[async_trait]
+    impl Executer<OrderShipped> for RecordingAdapter {
+        async fn execute(&mut self, event: Envelope<OrderShipped>) -> IntegrationResult<()> {
+            self.envelopes.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    fn message(id: &str, body: serde_json::Value) -> SqsMessage {
+        SqsMessage {
+            message_id: id.to_string(),
+            receipt_handle: format!("receipt-{id}"),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_extract_item_from_stream_record_and_outbox_item() {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(b"payload");
+        let stream_record = serde_json::json!({
+            "dynamodb": {
+                "NewImage": {
+                    "event_type": { "S": "TestEvent" },
+                    "payload": { "B": encoded },
+                }
+            }
+        });
+
+        for body in [stream_record, outbox_item("TestEvent", b"payload")] {
+            let item = extract_item(&body.to_string()).unwrap();
+            assert_eq!(extract_string_attribute(&item, "event_type").unwrap(), "TestEvent");
+            assert_eq!(extract_binary_attribute(&item, "payload").unwrap(), b"payload");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_deletes_processed_messages() {
+        let client = Arc::new(MockSqsClient::default());
+        *client.messages.lock().unwrap() = vec![
+            message("1", outbox_item("TestEvent", b"payload1")),
+            message("2", outbox_item("TestEvent", b"payload2")),
+        ];
+        let consumer = SqsEventConsumer::new(client.clone(), "queue");
+        let succeeding = mock_processor(false);
+        let mut router = create_router(&succeeding, &mock_processor(true));
+
+        let summary = consumer.poll_once(&mut router).await.unwrap();
+
+        assert_eq!(
+            summary,
+            SqsBatchSummary {
+                processed: 2,
+                failed: vec![]
+            }
+        );
+        assert_eq!(*client.deleted.lock().unwrap(), vec!["receipt-1", "receipt-2"]);
+        assert_eq!(
+            *succeeding.calls.lock().unwrap(),
+            vec![b"payload1".to_vec(), b"payload2".to_vec()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_leaves_failed_messages_for_redelivery() {
+        let client = Arc::new(MockSqsClient::default());
+        *client.messages.lock().unwrap() = vec![
+            message("1", outbox_item("TestEvent", b"payload1")),
+            message("2", outbox_item("FailingEvent", b"payload2")),
+            message("3", serde_json::json!("not an item")),
+            message("4", outbox_item("TestEvent", b"payload4")),
+        ];
+        let consumer = SqsEventConsumer::new(client.clone(), "queue");
+        let succeeding = mock_processor(false);
+        let mut router = create_router(&succeeding, &mock_processor(true));
+
+        let summary = consumer.poll_once(&mut router).await.unwrap();
+
+        assert_eq!(summary.processed, 2);
+        assert_eq!(summary.failed, vec!["2", "3"]);
+        assert_eq!(*client.deleted.lock().unwrap(), vec!["receipt-1", "receipt-4"]);
+    }
+
+    #[tokio::test]
+    async fn test_outbox_metadata_reaches_consumed_envelope() {
+        let metadata = Metadata::from([(CORRELATION_ID, "req-1"), (CAUSATION_ID, "cmd-1")]);
+        let event = SerializedIntegrationEvent::new(
+            "evt-1".to_string(),
+            "ord-1".to_string(),
+            "Order".to_string(),
+            "OrderShipped".to_string(),
+            serde_json::to_vec(&OrderShipped {
+                id: "evt-1".to_string(),
+            })
+            .unwrap(),
+        )
+        .with_metadata(serde_json::to_value(&metadata).unwrap());
+        let transactions = crate::store::DynamoDB::build_integration_event_put_transactions(
+            "outbox",
+            1,
+            &crate::store::key::ModuloHash,
+            None,
+            &[event],
+        )
+        .unwrap();
+        let client = Arc::new(MockSqsClient::default());
+        *client.messages.lock().unwrap() = vec![
+            message("1", outbox_body(transactions[0].put().unwrap().item())),
+            message("2", outbox_item("OrderShipped", br#"{"id":"evt-2"}"#)),
+        ];
+        let adapter = RecordingAdapter::default();
+        let mut router = ProcessorBasedEventRouter::new().route_processor(
+            "OrderShipped",
+            Processor::new(adapter.clone(), Json::<OrderShipped>::default()),
+        );
+
+        let summary = SqsEventConsumer::new(client, "queue")
+            .poll_once(&mut router)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.processed, 2);
+        let envelopes = adapter.envelopes.lock().unwrap();
+        assert_eq!(envelopes[0].message.id, "evt-1");
+        assert_eq!(envelopes[0].correlation_id(), Some("req-1"));
+        assert_eq!(envelopes[0].causation_id(), Some("cmd-1"));
+        assert!(envelopes[1].metadata.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_with_max_messages_limits_batch_size() {
+        let client = Arc::new(MockSqsClient::default());
+        *client.messages.lock().unwrap() = (0..3)
+            .map(|i| message(&i.to_string(), outbox_item("TestEvent", b"payload")))
+            .collect();
+        let consumer = SqsEventConsumer::new(client.clone(), "queue").with_max_messages(2);
+        let mut router = create_router(&mock_processor(false), &mock_processor(true));
+
+        assert_eq!(consumer.poll_once(&mut router).await.unwrap().processed, 2);
+        assert_eq!(consumer.poll_once(&mut router).await.unwrap().processed, 1);
+    }
+}
@@ -1,4 +1,12 @@
+pub mod checkpoint;
 pub mod error;
+pub mod idempotency_store;
 pub mod integration;
+pub mod processed_event_store;
 pub mod projection;
 pub mod store;
+
+pub use store::key::{
+    resolve_partition_key, resolve_partition_key_with, resolve_shard, resolve_sort_key, resolve_sort_key_prefix,
+    resolve_tenant_scoped_key, ModuloHash, ShardStrategy,
+};
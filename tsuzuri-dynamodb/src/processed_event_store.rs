@@ -0,0 +1,85 @@
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tsuzuri::{persist::PersistenceError, processed_event_store::ProcessedEventStore};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Processed event IDs stored in a DynamoDB table keyed by `pkey` (consumer name) and `skey` (event ID).
+///
+/// Every item carries a `ttl` attribute with its expiry in epoch seconds; enable time to live on that attribute
+/// to have DynamoDB remove old IDs. The retention must cover the longest redelivery window of the source stream.
+/// Expired items that DynamoDB has not removed yet are treated as absent.
+#[derive(Debug, Clone)]
+pub struct DynamoDBProcessedEventStore {
+    client: Client,
+    table_name: String,
+    consumer: String,
+    ttl: Duration,
+}
+
+impl DynamoDBProcessedEventStore {
+    pub fn new(client: Client, table_name: impl Into<String>, consumer: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            consumer: consumer.into(),
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// How long an event ID is remembered after it was marked; 7 days by default
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+}
+
+#[async_trait]
+impl ProcessedEventStore for DynamoDBProcessedEventStore {
+    async fn has_processed(&self, event_id: &str) -> Result<bool, PersistenceError> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("pkey", AttributeValue::S(self.consumer.clone()))
+            .key("skey", AttributeValue::S(event_id.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| PersistenceError::ConnectionError(Box::new(e)))?;
+
+        let expires_at = output
+            .item()
+            .and_then(|item| item.get("ttl"))
+            .and_then(|value| value.as_n().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        Ok(expires_at.is_some_and(|expires_at| expires_at > epoch_seconds(SystemTime::now())))
+    }
+
+    async fn mark_processed(&self, event_id: &str) -> Result<(), PersistenceError> {
+        let expires_at = epoch_seconds(SystemTime::now() + self.ttl);
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("pkey", AttributeValue::S(self.consumer.clone()))
+            .item("skey", AttributeValue::S(event_id.to_string()))
+            .item("ttl", AttributeValue::N(expires_at.to_string()))
+            .send()
+            .await
+            .map_err(|e| PersistenceError::ConnectionError(Box::new(e)))?;
+        Ok(())
+    }
+}
+
+fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
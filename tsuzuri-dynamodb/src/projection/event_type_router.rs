@@ -5,7 +5,7 @@ use tsuzuri::{
     event::Envelope,
     projection::{
         adapter::{Adapter, Projector},
-        error::Result,
+        error::{ProjectionError, Result},
         processor::Processor,
     },
     serde::Serde,
@@ -66,6 +66,7 @@ where
 /// This router can handle multiple different event types
 pub struct ProcessorBasedEventRouter {
     pub(crate) routes: HashMap<String, Box<dyn ProcessorTrait>>,
+    pub(crate) strict: bool,
 }
 
 /// Trait to abstract over different processor types
@@ -76,7 +77,16 @@ pub trait ProcessorTrait: Send + Sync {
 
 impl ProcessorBasedEventRouter {
     pub fn new() -> Self {
-        Self { routes: HashMap::new() }
+        Self {
+            routes: HashMap::new(),
+            strict: false,
+        }
+    }
+
+    /// Return a `RouteNotFound` error for events without a route instead of silently skipping them
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
     }
 
     /// Register a processor for an event type prefix
@@ -95,19 +105,41 @@ impl ProcessorBasedEventRouter {
     /// Process bytes through appropriate processor
     /// Each processor will handle its own deserialization using its own Serde implementation
     /// Uses prefix matching: "ProjectDomainEvent" matches "ProjectDomainEventBodyChanged"
+    /// When several prefixes match, the longest one handles the event
     pub async fn process_bytes(&self, event_name: &str, payload: &[u8], metadata: &[u8]) -> Result<()> {
-        // First try exact match
-        if let Some(processor) = self.routes.get(event_name) {
-            return processor.process_bytes(payload, metadata).await;
+        match self.find_route(event_name).and_then(|route| self.routes.get(route)) {
+            Some(processor) => processor.process_bytes(payload, metadata).await,
+            None => self.no_route(event_name),
         }
+    }
+
+    /// Returns the event names that no registered route would handle
+    pub fn validate_coverage(&self, event_names: &[&str]) -> Vec<String> {
+        event_names
+            .iter()
+            .filter(|event_name| self.find_route(event_name).is_none())
+            .map(|event_name| event_name.to_string())
+            .collect()
+    }
 
-        // Then try prefix match
-        for (registered_prefix, processor) in &self.routes {
-            if event_name.starts_with(registered_prefix) {
-                return processor.process_bytes(payload, metadata).await;
-            }
+    /// Finds the route for an event name: an exact match first, then the longest registered prefix
+    ///
+    /// Two matching prefixes of the same length are the same string, so the longest match is unique.
+    fn find_route(&self, event_name: &str) -> Option<&str> {
+        if let Some((registered_name, _)) = self.routes.get_key_value(event_name) {
+            return Some(registered_name);
         }
+        self.routes
+            .keys()
+            .filter(|registered_prefix| event_name.starts_with(registered_prefix.as_str()))
+            .max_by_key(|registered_prefix| registered_prefix.len())
+            .map(String::as_str)
+    }
 
+    fn no_route(&self, event_name: &str) -> Result<()> {
+        if self.strict {
+            return Err(ProjectionError::RouteNotFound(event_name.to_string()));
+        }
         Ok(())
     }
 }
@@ -3,15 +3,40 @@ use crate::projection::event_type_router::ProcessorBasedEventRouter;
 use crate::projection::helpers::{extract_binary_attribute, extract_string_attribute};
 use aws_lambda_events::kinesis::KinesisEvent;
 use lambda_runtime::LambdaEvent;
+use tracing::error;
 
+pub use crate::integration::kinesis::lambda::{KinesisBatchItemFailure, KinesisBatchResponse, KinesisLambdaConfig};
+
+/// Process every record of the event and report the ones that failed
 pub async fn process_kinesis_lambda_event(
     router: &ProcessorBasedEventRouter,
     event: LambdaEvent<KinesisEvent>,
-) -> Result<()> {
+) -> Result<KinesisBatchResponse> {
+    process_kinesis_lambda_event_with_config(router, event, &KinesisLambdaConfig::default()).await
+}
+
+/// Process the records of the event, reporting failures or failing fast as configured
+pub async fn process_kinesis_lambda_event_with_config(
+    router: &ProcessorBasedEventRouter,
+    event: LambdaEvent<KinesisEvent>,
+    config: &KinesisLambdaConfig,
+) -> Result<KinesisBatchResponse> {
+    let mut response = KinesisBatchResponse::default();
     for record in event.payload.records {
-        process_single_record(router, &record.kinesis.data).await?;
+        if let Err(e) = process_single_record(router, &record.kinesis.data).await {
+            if config.fail_fast {
+                return Err(e);
+            }
+            error!(
+                "Failed to process record with sequence number {}: {}",
+                record.kinesis.sequence_number, e
+            );
+            response.batch_item_failures.push(KinesisBatchItemFailure {
+                item_identifier: record.kinesis.sequence_number,
+            });
+        }
     }
-    Ok(())
+    Ok(response)
 }
 
 async fn process_single_record(router: &ProcessorBasedEventRouter, data: &[u8]) -> Result<()> {
@@ -87,6 +112,10 @@ mod tests {
     }
 
     fn create_kinesis_record(data: Vec<u8>) -> KinesisEventRecord {
+        create_kinesis_record_with_sequence_number(data, "12345")
+    }
+
+    fn create_kinesis_record_with_sequence_number(data: Vec<u8>, sequence_number: &str) -> KinesisEventRecord {
         KinesisEventRecord {
             aws_region: Some("us-east-1".to_string()),
             event_id: Some("test-event-id".to_string()),
@@ -100,7 +129,7 @@ mod tests {
                 data: Base64Data(data),
                 encryption_type: aws_lambda_events::kinesis::KinesisEncryptionType::None,
                 partition_key: "test-partition".to_string(),
-                sequence_number: "12345".to_string(),
+                sequence_number: sequence_number.to_string(),
                 kinesis_schema_version: Some("1.0".to_string()),
             },
         }
@@ -192,7 +221,10 @@ mod tests {
             Box::new(mock_processor.clone()) as Box<dyn crate::projection::event_type_router::ProcessorTrait>,
         );
 
-        let router = ProcessorBasedEventRouter { routes };
+        let router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let stream_data = create_dynamodb_stream_data("TestEvent", b"test payload", b"test metadata");
 
@@ -217,7 +249,10 @@ mod tests {
             Box::new(mock_processor.clone()) as Box<dyn crate::projection::event_type_router::ProcessorTrait>,
         );
 
-        let router = ProcessorBasedEventRouter { routes };
+        let router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         // Create test data
         let stream_data1 = create_dynamodb_stream_data("TestEvent", b"payload1", b"metadata1");
@@ -227,8 +262,8 @@ mod tests {
 
         let lambda_event = create_test_lambda_event(records);
 
-        let result = process_kinesis_lambda_event(&router, lambda_event).await;
-        assert!(result.is_ok());
+        let response = process_kinesis_lambda_event(&router, lambda_event).await.unwrap();
+        assert!(response.batch_item_failures.is_empty());
 
         // Verify both records were processed
         let calls = mock_processor.calls.lock().unwrap();
@@ -248,13 +283,113 @@ mod tests {
             Box::new(mock_processor) as Box<dyn crate::projection::event_type_router::ProcessorTrait>,
         );
 
-        let router = ProcessorBasedEventRouter { routes };
+        let router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
 
         let stream_data = create_dynamodb_stream_data("TestEvent", b"payload", b"metadata");
         let records = vec![create_kinesis_record(stream_data)];
         let lambda_event = create_test_lambda_event(records);
 
-        let result = process_kinesis_lambda_event(&router, lambda_event).await;
+        let result =
+            process_kinesis_lambda_event_with_config(&router, lambda_event, &KinesisLambdaConfig { fail_fast: true })
+                .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_process_kinesis_lambda_event_reports_only_failed_records() {
+        let succeeding_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: false,
+        });
+        let failing_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: true,
+        });
+
+        let mut routes: HashMap<String, Box<dyn crate::projection::event_type_router::ProcessorTrait>> = HashMap::new();
+        routes.insert(
+            "TestEvent".to_string(),
+            Box::new(succeeding_processor.clone()) as Box<dyn crate::projection::event_type_router::ProcessorTrait>,
+        );
+        routes.insert(
+            "FailingEvent".to_string(),
+            Box::new(failing_processor) as Box<dyn crate::projection::event_type_router::ProcessorTrait>,
+        );
+
+        let router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
+
+        let records = vec![
+            create_kinesis_record_with_sequence_number(
+                create_dynamodb_stream_data("TestEvent", b"payload1", b"metadata"),
+                "1",
+            ),
+            create_kinesis_record_with_sequence_number(
+                create_dynamodb_stream_data("FailingEvent", b"payload2", b"metadata"),
+                "2",
+            ),
+            create_kinesis_record_with_sequence_number(
+                create_dynamodb_stream_data("TestEvent", b"payload3", b"metadata"),
+                "3",
+            ),
+            create_kinesis_record_with_sequence_number(b"not json".to_vec(), "4"),
+        ];
+        let lambda_event = create_test_lambda_event(records);
+
+        let response = process_kinesis_lambda_event(&router, lambda_event).await.unwrap();
+        assert_eq!(
+            response.batch_item_failures,
+            vec![
+                KinesisBatchItemFailure {
+                    item_identifier: "2".to_string()
+                },
+                KinesisBatchItemFailure {
+                    item_identifier: "4".to_string()
+                },
+            ]
+        );
+
+        // Records after a failure are still processed
+        let calls = succeeding_processor.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_process_kinesis_lambda_event_fail_fast_stops_at_first_failure() {
+        let succeeding_processor = Arc::new(MockProcessor {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail: false,
+        });
+
+        let mut routes: HashMap<String, Box<dyn crate::projection::event_type_router::ProcessorTrait>> = HashMap::new();
+        routes.insert(
+            "TestEvent".to_string(),
+            Box::new(succeeding_processor.clone()) as Box<dyn crate::projection::event_type_router::ProcessorTrait>,
+        );
+
+        let router = ProcessorBasedEventRouter {
+            routes,
+            ..Default::default()
+        };
+
+        let records = vec![
+            create_kinesis_record_with_sequence_number(b"not json".to_vec(), "1"),
+            create_kinesis_record_with_sequence_number(
+                create_dynamodb_stream_data("TestEvent", b"payload2", b"metadata"),
+                "2",
+            ),
+        ];
+        let lambda_event = create_test_lambda_event(records);
+
+        let result =
+            process_kinesis_lambda_event_with_config(&router, lambda_event, &KinesisLambdaConfig { fail_fast: true })
+                .await;
         assert!(result.is_err());
+        assert!(succeeding_processor.calls.lock().unwrap().is_empty());
     }
 }
@@ -1,14 +1,13 @@
+pub use crate::integration::kinesis::local::ShardIteratorConfig;
 use crate::{
     error::{Result, StreamProcessorError},
+    integration::kinesis::local::shard_iterator_start,
     projection::{
         event_type_router::ProcessorBasedEventRouter,
         helpers::{extract_binary_attribute, extract_string_attribute},
     },
 };
-use aws_sdk_kinesis::{
-    types::{Record, ShardIteratorType},
-    Client as KinesisClient,
-};
+use aws_sdk_kinesis::{types::Record, Client as KinesisClient};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -36,6 +35,8 @@ pub struct DebugConfig {
     pub pause_between_records: bool,
     /// Pause duration in milliseconds
     pub pause_duration_ms: u64,
+    /// Position in each shard to start reading from
+    pub iterator_type: ShardIteratorConfig,
 }
 
 impl Default for DebugConfig {
@@ -46,6 +47,7 @@ impl Default for DebugConfig {
             pretty_print: true,
             pause_between_records: false,
             pause_duration_ms: 1000,
+            iterator_type: ShardIteratorConfig::Latest,
         }
     }
 }
@@ -185,14 +187,17 @@ impl LocalKinesisDebugger {
         Ok(processed_count)
     }
 
-    /// Get shard iterator
+    /// Get shard iterator at the configured iterator type
     async fn get_shard_iterator(&self, stream_arn: &str, shard_id: &str) -> Result<String> {
+        let start = shard_iterator_start(None, &self.config.iterator_type);
         let output = self
             .kinesis_client
             .get_shard_iterator()
             .stream_arn(stream_arn)
             .shard_id(shard_id)
-            .shard_iterator_type(ShardIteratorType::Latest)
+            .shard_iterator_type(start.iterator_type)
+            .set_starting_sequence_number(start.starting_sequence_number)
+            .set_timestamp(start.timestamp)
             .send()
             .await
             .map_err(|e| StreamProcessorError::KinesisDataStreams(format!("Failed to get shard iterator: {e}")))?;
@@ -386,6 +391,7 @@ mod tests {
         assert!(config.pretty_print);
         assert!(!config.pause_between_records);
         assert_eq!(config.pause_duration_ms, 1000);
+        assert_eq!(config.iterator_type, ShardIteratorConfig::Latest);
     }
 
     #[test]
@@ -3,40 +3,70 @@
 #![deny(clippy::all)]
 #![warn(rust_2018_idioms)]
 
+pub mod archive;
+pub mod codec;
 pub mod error;
 pub mod helper;
 pub mod key;
+pub mod metrics;
+pub mod outbox;
+mod query;
+pub mod scan;
+pub mod stats;
+pub mod throttle;
+pub mod verify;
 
 use crate::store::{
+    codec::SnapshotCodec,
     error::DynamoAggregateError,
-    helper::{att_as_number, att_as_vec, commit_transactions, serialized_event},
-    key::{resolve_partition_key, resolve_sort_key},
+    helper::{
+        att_as_number, att_as_string, att_as_vec, commit_transactions, commit_transactions_locating_conflict,
+        item_size, serialized_event, MAX_ITEM_SIZE_BYTES, MAX_TRANSACTION_ITEMS,
+    },
+    key::{
+        resolve_partition_key_with, resolve_sort_key, resolve_sort_key_prefix, resolve_tenant_scoped_key, ModuloHash,
+        ShardStrategy,
+    },
+    metrics::{Metrics, NoopMetrics},
+    outbox::{OutboxMode, OutboxStatus},
+    query::KeyConditionBuilder,
+    throttle::ThrottleRetry,
 };
 use async_trait::async_trait;
 use aws_sdk_dynamodb::{
     operation::query::{builders::QueryFluentBuilder, QueryOutput},
     primitives::Blob,
-    types::{AttributeValue, Delete, Put, TransactWriteItem},
+    types::{AttributeValue, ConditionCheck, Delete, Put, Select, TransactWriteItem},
     Client,
 };
-use aws_smithy_types_convert::stream::PaginationStreamExt;
 use futures::{Stream, StreamExt, TryStreamExt};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::warn;
 use tsuzuri::{
     domain_event::SerializedDomainEvent,
     event::{SequenceSelect, Stream as EventStream},
-    event_store::{AggregateEventStreamer, Persister, SnapshotGetter, SnapshotIntervalProvider},
+    event_store::{
+        AggregateEventStreamer, ExpectedState, PersistBatch, Persister, SnapshotGetter, SnapshotIntervalProvider,
+    },
+    helper::to_rfc3339,
     integration_event::SerializedIntegrationEvent,
-    inverted_index_store::{AggregateIdsLoader, InvertedIndexCommiter, InvertedIndexRemover},
+    inverted_index_store::{AggregateIdsLoader, IndexKeyword, IndexOp, InvertedIndexCommiter, InvertedIndexRemover},
     persist::PersistenceError,
     sequence_number::SequenceNumber,
     snapshot::PersistedSnapshot,
     AggregateRoot,
 };
 
-const OUTBOX_STATUS_PENDING: &str = "PENDING";
 const OUTBOX_INITIAL_ATTEMPTS: &str = "0";
 
+/// Number of snapshot queries `get_snapshots` keeps in flight.
+const MAX_CONCURRENT_SNAPSHOT_READS: usize = 100;
+
 /// DynamoDB table names configuration
 #[derive(Debug, Clone)]
 pub struct TableNames {
@@ -65,12 +95,67 @@ impl Default for TableNames {
     }
 }
 
+/// Tables that [`DynamoDB::health_check`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HealthCheckScope {
+    /// Only the journal table, which every command touches.
+    #[default]
+    Journal,
+    /// The journal, snapshot, outbox and inverted index tables.
+    AllTables,
+}
+
+/// How a snapshot write treats a snapshot item that another writer stored in the meantime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotWritePolicy {
+    /// The snapshot is only written when the stored one is missing or one version behind it; otherwise the whole
+    /// transaction fails with a concurrency error.
+    #[default]
+    Conditional,
+    /// The snapshot is written unconditionally and the last write wins. Snapshots are derived from the journal and
+    /// can be rebuilt, so concurrent snapshot writes no longer fail the commit; the events written with them are
+    /// still checked as usual.
+    Overwrite,
+}
+
 /// DynamoDB configuration
 #[derive(Debug, Clone)]
 pub struct DynamoDBConfig {
     pub table_names: TableNames,
     pub shard_count: usize,
     pub snapshot_interval: usize,
+    /// Per aggregate type overrides of `snapshot_interval`, keyed by `AggregateRoot::TYPE`.
+    pub snapshot_intervals: HashMap<&'static str, usize>,
+    /// Number of failed delivery attempts after which an outbox record is moved to `DEAD`.
+    pub dead_letter_after: usize,
+    /// Shard counts used before `shard_count` was changed. Snapshot lookups also query the partitions these
+    /// produce so data written under an earlier shard count stays readable. Journal reads go through the
+    /// aggregate ID index and do not depend on the shard count, unless `consistent_stream_reads` is enabled.
+    pub legacy_shard_counts: Vec<usize>,
+    /// Reads event streams with strongly consistent reads so a load right after a commit sees its events.
+    /// Global secondary indexes only support eventually consistent reads, so this queries the journal's
+    /// `pkey`/`skey` instead of `journal_aid_index`, which costs twice the read capacity.
+    pub consistent_stream_reads: bool,
+    /// Compression applied to snapshot payloads. Snapshots are decoded with the codec they were written with.
+    pub snapshot_codec: SnapshotCodec,
+    /// Tenant whose items this store reads and writes. Partition keys of every table are prefixed with
+    /// `"{tenant}#"`, and reads through `journal_aid_index` are filtered to that prefix, so stores of different
+    /// tenants can share tables without seeing each other's items.
+    pub tenant_scope: Option<String>,
+    /// Tables whose existence `health_check` confirms.
+    pub health_check_scope: HealthCheckScope,
+    /// How long outbox records are kept once dispatched. When set, `mark_dispatched` writes an `expires_at`
+    /// attribute with the expiry in epoch seconds; the outbox table must have time to live enabled on
+    /// `expires_at` for DynamoDB to delete them. Pending and dead records never expire.
+    pub outbox_ttl: Option<Duration>,
+    /// Whether outbox records share the transaction of the domain events or are written after it.
+    pub outbox_mode: OutboxMode,
+    /// How queries, scans and transactions DynamoDB throttles are retried before `Throttled` is returned.
+    pub throttle_retry: ThrottleRetry,
+    /// Assigns aggregates to shards, for writes and reads alike; [`ModuloHash`] by default.
+    pub shard_strategy: Arc<dyn ShardStrategy>,
+    /// Whether snapshot writes are conditional on the stored snapshot's version.
+    pub snapshot_write_policy: SnapshotWritePolicy,
 }
 
 impl Default for DynamoDBConfig {
@@ -79,6 +164,18 @@ impl Default for DynamoDBConfig {
             table_names: TableNames::default(),
             shard_count: 4,
             snapshot_interval: 100,
+            snapshot_intervals: HashMap::new(),
+            dead_letter_after: 5,
+            legacy_shard_counts: Vec::new(),
+            consistent_stream_reads: false,
+            snapshot_codec: SnapshotCodec::None,
+            tenant_scope: None,
+            health_check_scope: HealthCheckScope::default(),
+            outbox_ttl: None,
+            outbox_mode: OutboxMode::default(),
+            throttle_retry: ThrottleRetry::default(),
+            shard_strategy: Arc::new(ModuloHash),
+            snapshot_write_policy: SnapshotWritePolicy::default(),
         }
     }
 }
@@ -89,6 +186,18 @@ pub struct DynamoDBConfigBuilder {
     table_names: Option<TableNames>,
     shard_count: Option<usize>,
     snapshot_interval: Option<usize>,
+    snapshot_intervals: HashMap<&'static str, usize>,
+    dead_letter_after: Option<usize>,
+    legacy_shard_counts: Option<Vec<usize>>,
+    consistent_stream_reads: Option<bool>,
+    snapshot_codec: Option<SnapshotCodec>,
+    tenant_scope: Option<String>,
+    health_check_scope: Option<HealthCheckScope>,
+    outbox_ttl: Option<Duration>,
+    outbox_mode: Option<OutboxMode>,
+    throttle_retry: Option<ThrottleRetry>,
+    shard_strategy: Option<Arc<dyn ShardStrategy>>,
+    snapshot_write_policy: Option<SnapshotWritePolicy>,
 }
 
 impl DynamoDBConfigBuilder {
@@ -111,32 +220,115 @@ impl DynamoDBConfigBuilder {
         self
     }
 
+    pub fn snapshot_interval_for(mut self, aggregate_type: &'static str, interval: usize) -> Self {
+        self.snapshot_intervals.insert(aggregate_type, interval);
+        self
+    }
+
+    pub fn dead_letter_after(mut self, attempts: usize) -> Self {
+        self.dead_letter_after = Some(attempts);
+        self
+    }
+
+    pub fn legacy_shard_counts(mut self, counts: Vec<usize>) -> Self {
+        self.legacy_shard_counts = Some(counts);
+        self
+    }
+
+    pub fn consistent_stream_reads(mut self, enabled: bool) -> Self {
+        self.consistent_stream_reads = Some(enabled);
+        self
+    }
+
+    pub fn snapshot_codec(mut self, codec: SnapshotCodec) -> Self {
+        self.snapshot_codec = Some(codec);
+        self
+    }
+
+    pub fn tenant_scope(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant_scope = Some(tenant.into());
+        self
+    }
+
+    pub fn health_check_scope(mut self, scope: HealthCheckScope) -> Self {
+        self.health_check_scope = Some(scope);
+        self
+    }
+
+    pub fn outbox_ttl(mut self, ttl: Duration) -> Self {
+        self.outbox_ttl = Some(ttl);
+        self
+    }
+
+    pub fn outbox_mode(mut self, mode: OutboxMode) -> Self {
+        self.outbox_mode = Some(mode);
+        self
+    }
+
+    pub fn throttle_retry(mut self, retry: ThrottleRetry) -> Self {
+        self.throttle_retry = Some(retry);
+        self
+    }
+
+    pub fn shard_strategy(mut self, strategy: impl ShardStrategy) -> Self {
+        self.shard_strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    pub fn snapshot_write_policy(mut self, policy: SnapshotWritePolicy) -> Self {
+        self.snapshot_write_policy = Some(policy);
+        self
+    }
+
     pub fn build(self) -> DynamoDBConfig {
         DynamoDBConfig {
             table_names: self.table_names.unwrap_or_default(),
             shard_count: self.shard_count.unwrap_or(4),
             snapshot_interval: self.snapshot_interval.unwrap_or(100),
+            snapshot_intervals: self.snapshot_intervals,
+            dead_letter_after: self.dead_letter_after.unwrap_or(5),
+            legacy_shard_counts: self.legacy_shard_counts.unwrap_or_default(),
+            consistent_stream_reads: self.consistent_stream_reads.unwrap_or(false),
+            snapshot_codec: self.snapshot_codec.unwrap_or_default(),
+            tenant_scope: self.tenant_scope,
+            health_check_scope: self.health_check_scope.unwrap_or_default(),
+            outbox_ttl: self.outbox_ttl,
+            outbox_mode: self.outbox_mode.unwrap_or_default(),
+            throttle_retry: self.throttle_retry.unwrap_or_default(),
+            shard_strategy: self.shard_strategy.unwrap_or_else(|| Arc::new(ModuloHash)),
+            snapshot_write_policy: self.snapshot_write_policy.unwrap_or_default(),
         }
     }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct DynamoDB {
     client: Client,
     config: DynamoDBConfig,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl fmt::Debug for DynamoDB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamoDB")
+            .field("client", &self.client)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
 }
 
 impl DynamoDB {
     pub fn new(client: Client) -> Self {
-        Self {
-            client,
-            config: DynamoDBConfig::default(),
-        }
+        Self::with_config(client, DynamoDBConfig::default())
     }
 
     pub fn with_config(client: Client, config: DynamoDBConfig) -> Self {
-        Self { client, config }
+        Self {
+            client,
+            config,
+            metrics: Arc::new(NoopMetrics),
+        }
     }
 
     pub fn builder(client: Client) -> DynamoDBBuilder {
@@ -151,23 +343,161 @@ impl DynamoDB {
         self.config.shard_count
     }
 
+    pub fn shard_strategy(&self) -> &dyn ShardStrategy {
+        self.config.shard_strategy.as_ref()
+    }
+
     pub fn snapshot_interval(&self) -> usize {
         self.config.snapshot_interval
     }
 
+    pub fn snapshot_intervals(&self) -> &HashMap<&'static str, usize> {
+        &self.config.snapshot_intervals
+    }
+
+    pub fn dead_letter_after(&self) -> usize {
+        self.config.dead_letter_after
+    }
+
+    pub fn legacy_shard_counts(&self) -> &[usize] {
+        &self.config.legacy_shard_counts
+    }
+
+    pub fn consistent_stream_reads(&self) -> bool {
+        self.config.consistent_stream_reads
+    }
+
+    pub fn snapshot_codec(&self) -> SnapshotCodec {
+        self.config.snapshot_codec
+    }
+
+    pub fn tenant_scope(&self) -> Option<&str> {
+        self.config.tenant_scope.as_deref()
+    }
+
+    pub fn outbox_ttl(&self) -> Option<Duration> {
+        self.config.outbox_ttl
+    }
+
+    pub fn outbox_mode(&self) -> OutboxMode {
+        self.config.outbox_mode
+    }
+
+    pub fn snapshot_write_policy(&self) -> SnapshotWritePolicy {
+        self.config.snapshot_write_policy
+    }
+
+    pub fn throttle_retry(&self) -> ThrottleRetry {
+        self.config.throttle_retry
+    }
+
+    pub fn health_check_scope(&self) -> HealthCheckScope {
+        self.config.health_check_scope
+    }
+
+    /// Confirms that DynamoDB is reachable and the tables selected by `health_check_scope` exist, for readiness
+    /// probes.
+    ///
+    /// Issues one `DescribeTable` per table, which reads no items and consumes no read capacity.
+    pub async fn health_check(&self) -> Result<(), DynamoAggregateError> {
+        let table_names = &self.config.table_names;
+        let tables = match self.config.health_check_scope {
+            HealthCheckScope::Journal => vec![&table_names.journal],
+            HealthCheckScope::AllTables => vec![
+                &table_names.journal,
+                &table_names.snapshot,
+                &table_names.outbox,
+                &table_names.inverted_index,
+            ],
+        };
+        let describes = tables
+            .into_iter()
+            .map(|table| self.client.describe_table().table_name(table).send());
+        futures::future::try_join_all(describes).await?;
+        Ok(())
+    }
+
+    fn scoped_key(&self, key: String) -> String {
+        resolve_tenant_scoped_key(self.tenant_scope(), key)
+    }
+
+    /// `pkey` and `skey` of the journal item holding event `seq_nr` of an aggregate, as written by this store.
+    ///
+    /// Meant for looking items up by hand, e.g. in the AWS console; the partition key uses the current shard
+    /// count and tenant scope.
+    pub fn debug_keys(&self, aggregate_type: &str, aggregate_id: &str, seq_nr: SequenceNumber) -> (String, String) {
+        let pkey = self.scoped_key(resolve_partition_key_with(
+            self.shard_strategy(),
+            aggregate_id.to_string(),
+            aggregate_type.to_string(),
+            self.config.shard_count,
+        ));
+        let skey = resolve_sort_key(aggregate_type.to_string(), aggregate_id.to_string(), seq_nr);
+        (pkey, skey)
+    }
+
+    /// Index of the shard the aggregate's items are written to under the current shard count.
+    pub fn debug_shard_for(&self, aggregate_id: &str) -> usize {
+        self.config.shard_strategy.shard(aggregate_id, self.config.shard_count)
+    }
+
+    /// Filter that keeps only this tenant's items when reading through an aggregate ID index, whose key is not
+    /// tenant scoped.
+    fn tenant_filter(&self) -> Option<(String, AttributeValue)> {
+        self.tenant_scope().map(|tenant| {
+            (
+                "begins_with(#pkey, :tenant)".to_string(),
+                AttributeValue::S(resolve_tenant_scoped_key(Some(tenant), String::new())),
+            )
+        })
+    }
+
+    /// Current shard count followed by the legacy ones, skipping any that resolve to an already listed partition.
+    fn read_shard_counts(&self, aggregate_type: &str, aggregate_id: &str) -> Vec<usize> {
+        let mut partition_keys = Vec::new();
+        let mut shard_counts = Vec::new();
+        for shard_count in
+            std::iter::once(self.config.shard_count).chain(self.config.legacy_shard_counts.iter().copied())
+        {
+            let partition_key = resolve_partition_key_with(
+                self.shard_strategy(),
+                aggregate_id.to_string(),
+                aggregate_type.to_string(),
+                shard_count,
+            );
+            if !partition_keys.contains(&partition_key) {
+                partition_keys.push(partition_key);
+                shard_counts.push(shard_count);
+            }
+        }
+        shard_counts
+    }
+
     fn build_all_event_transactions(
         journal_table_name: &str,
         outbox_table_name: &str,
         shard_count: usize,
+        shard_strategy: &dyn ShardStrategy,
+        tenant_scope: Option<&str>,
         domain_events: &[SerializedDomainEvent],
         integration_events: &[SerializedIntegrationEvent],
     ) -> Result<(Vec<TransactWriteItem>, usize), DynamoAggregateError> {
-        let (mut transactions, current_seq_nr) =
-            Self::build_domain_event_put_transactions(journal_table_name, shard_count, domain_events)?;
+        let (mut transactions, current_seq_nr) = Self::build_domain_event_put_transactions(
+            journal_table_name,
+            shard_count,
+            shard_strategy,
+            tenant_scope,
+            domain_events,
+        )?;
 
         if !integration_events.is_empty() {
-            let integration_transactions =
-                Self::build_integration_event_put_transactions(outbox_table_name, shard_count, integration_events)?;
+            let integration_transactions = Self::build_integration_event_put_transactions(
+                outbox_table_name,
+                shard_count,
+                shard_strategy,
+                tenant_scope,
+                integration_events,
+            )?;
             transactions.extend(integration_transactions);
         }
 
@@ -177,16 +507,22 @@ impl DynamoDB {
     fn build_domain_event_put_transactions(
         journal_table_name: &str,
         shard_count: usize,
+        shard_strategy: &dyn ShardStrategy,
+        tenant_scope: Option<&str>,
         domain_events: &[SerializedDomainEvent],
     ) -> Result<(Vec<TransactWriteItem>, usize), DynamoAggregateError> {
         let mut current_seq_nr: usize = 0;
         let mut transactions: Vec<TransactWriteItem> = Vec::default();
         for event in domain_events {
             current_seq_nr = event.seq_nr;
-            let pkey = AttributeValue::S(resolve_partition_key(
-                event.aggregate_id.clone(),
-                event.aggregate_type.clone(),
-                shard_count,
+            let pkey = AttributeValue::S(resolve_tenant_scoped_key(
+                tenant_scope,
+                resolve_partition_key_with(
+                    shard_strategy,
+                    event.aggregate_id.clone(),
+                    event.aggregate_type.clone(),
+                    shard_count,
+                ),
             ));
             let skey = AttributeValue::S(resolve_sort_key(
                 event.aggregate_type.clone(),
@@ -201,6 +537,10 @@ impl DynamoDB {
             let payload = AttributeValue::B(Blob::new(&*event.payload));
             let metadata_blob = serde_json::to_vec(&event.metadata)?;
             let metadata = AttributeValue::B(Blob::new(metadata_blob));
+            let schema_version = AttributeValue::N(event.schema_version.to_string());
+            let occurred_at = AttributeValue::S(
+                to_rfc3339(&event.occurred_at).map_err(|e| DynamoAggregateError::UnknownError(Box::new(e)))?,
+            );
 
             let put_event_store = Put::builder()
                 .table_name(journal_table_name)
@@ -213,6 +553,8 @@ impl DynamoDB {
                 .item("event_type", event_type.clone())
                 .item("payload", payload.clone())
                 .item("metadata", metadata.clone())
+                .item("schema_version", schema_version)
+                .item("occurred_at", occurred_at)
                 .condition_expression("attribute_not_exists(#seq)")
                 .expression_attribute_names("#seq", "seq_nr")
                 .build()
@@ -224,17 +566,23 @@ impl DynamoDB {
         Ok((transactions, current_seq_nr))
     }
 
-    fn build_integration_event_put_transactions(
+    pub(crate) fn build_integration_event_put_transactions(
         outbox_table_name: &str,
         shard_count: usize,
+        shard_strategy: &dyn ShardStrategy,
+        tenant_scope: Option<&str>,
         integration_events: &[SerializedIntegrationEvent],
     ) -> Result<Vec<TransactWriteItem>, DynamoAggregateError> {
         let mut transactions: Vec<TransactWriteItem> = Vec::default();
         for event in integration_events {
-            let pkey = AttributeValue::S(resolve_partition_key(
-                event.aggregate_id.clone(),
-                event.aggregate_type.clone(),
-                shard_count,
+            let pkey = AttributeValue::S(resolve_tenant_scoped_key(
+                tenant_scope,
+                resolve_partition_key_with(
+                    shard_strategy,
+                    event.aggregate_id.clone(),
+                    event.aggregate_type.clone(),
+                    shard_count,
+                ),
             ));
             let skey = AttributeValue::S(event.id.clone());
             let event_type = AttributeValue::S(String::from(&event.event_type));
@@ -242,7 +590,7 @@ impl DynamoDB {
             let aggregate_id = AttributeValue::S(event.aggregate_id.clone());
             let aggregate_type = AttributeValue::S(event.aggregate_type.clone());
 
-            let put_outbox = Put::builder()
+            let mut put_outbox = Put::builder()
                 .table_name(outbox_table_name)
                 .item("pkey", pkey)
                 .item("skey", skey)
@@ -250,8 +598,13 @@ impl DynamoDB {
                 .item("aggregate_type", aggregate_type)
                 .item("event_type", event_type)
                 .item("payload", payload)
-                .item("status", AttributeValue::S(OUTBOX_STATUS_PENDING.to_string()))
-                .item("attempts", AttributeValue::N(OUTBOX_INITIAL_ATTEMPTS.to_string()))
+                .item("status", AttributeValue::S(OutboxStatus::Pending.to_string()))
+                .item("attempts", AttributeValue::N(OUTBOX_INITIAL_ATTEMPTS.to_string()));
+            if !event.metadata.is_null() {
+                let metadata_blob = serde_json::to_vec(&event.metadata)?;
+                put_outbox = put_outbox.item("metadata", AttributeValue::B(Blob::new(metadata_blob)));
+            }
+            let put_outbox = put_outbox
                 .build()
                 .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
             let outbox_item = TransactWriteItem::builder().put(put_outbox).build();
@@ -260,22 +613,142 @@ impl DynamoDB {
         Ok(transactions)
     }
 
+    /// Condition on the journal that enforces `expected_state` for the aggregate the events belong to.
+    ///
+    /// Every journal put already requires its own row to be absent, so writing event `n + 1` proves the journal
+    /// ends at or before `n`. The returned check covers the other bound: row `n` must exist for
+    /// [`ExpectedState::ExpectedVersion`], and row 1 must be absent for [`ExpectedState::MustNotExist`] when the
+    /// events do not start at 1 themselves.
+    fn build_expected_state_check(
+        journal_table_name: &str,
+        shard_count: usize,
+        shard_strategy: &dyn ShardStrategy,
+        tenant_scope: Option<&str>,
+        domain_events: &[SerializedDomainEvent],
+        expected_state: ExpectedState,
+    ) -> Result<Option<TransactWriteItem>, DynamoAggregateError> {
+        let Some(first) = domain_events.first() else {
+            return Ok(None);
+        };
+        let (seq_nr, condition) = match expected_state {
+            ExpectedState::Any => return Ok(None),
+            ExpectedState::MustNotExist if first.seq_nr == 1 => return Ok(None),
+            ExpectedState::MustNotExist => (1, "attribute_not_exists(#seq)"),
+            ExpectedState::ExpectedVersion(expected_seq) if first.seq_nr != expected_seq + 1 => {
+                return Err(DynamoAggregateError::OptimisticConcurrency {
+                    aggregate_id: first.aggregate_id.clone(),
+                    expected_seq,
+                });
+            }
+            ExpectedState::ExpectedVersion(0) => return Ok(None),
+            ExpectedState::ExpectedVersion(expected_seq) => (expected_seq, "attribute_exists(#seq)"),
+        };
+        let check = ConditionCheck::builder()
+            .table_name(journal_table_name)
+            .key(
+                "pkey",
+                AttributeValue::S(resolve_tenant_scoped_key(
+                    tenant_scope,
+                    resolve_partition_key_with(
+                        shard_strategy,
+                        first.aggregate_id.clone(),
+                        first.aggregate_type.clone(),
+                        shard_count,
+                    ),
+                )),
+            )
+            .key(
+                "skey",
+                AttributeValue::S(resolve_sort_key(
+                    first.aggregate_type.clone(),
+                    first.aggregate_id.clone(),
+                    seq_nr,
+                )),
+            )
+            .condition_expression(condition)
+            .expression_attribute_names("#seq", "seq_nr")
+            .build()
+            .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
+        Ok(Some(TransactWriteItem::builder().condition_check(check).build()))
+    }
+
+    /// Index puts are unconditional so that re-indexing an aggregate under a keyword it already has is a no-op.
+    fn build_index_transactions(
+        inverted_index_table_name: &str,
+        tenant_scope: Option<&str>,
+        index_ops: &[IndexOp],
+    ) -> Result<Vec<TransactWriteItem>, DynamoAggregateError> {
+        let mut transactions: Vec<TransactWriteItem> = Vec::with_capacity(index_ops.len());
+        for op in index_ops {
+            let write_item = match op {
+                IndexOp::Put { keyword, aggregate_id } => {
+                    let put = Put::builder()
+                        .table_name(inverted_index_table_name)
+                        .item(
+                            "pkey",
+                            AttributeValue::S(resolve_tenant_scoped_key(tenant_scope, keyword.to_string())),
+                        )
+                        .item("skey", AttributeValue::S(aggregate_id.clone()))
+                        .build()
+                        .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
+                    TransactWriteItem::builder().put(put).build()
+                }
+                IndexOp::Delete { keyword, aggregate_id } => {
+                    let delete = Delete::builder()
+                        .table_name(inverted_index_table_name)
+                        .key(
+                            "pkey",
+                            AttributeValue::S(resolve_tenant_scoped_key(tenant_scope, keyword.to_string())),
+                        )
+                        .key("skey", AttributeValue::S(aggregate_id.clone()))
+                        .build()
+                        .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
+                    TransactWriteItem::builder().delete(delete).build()
+                }
+            };
+            transactions.push(write_item);
+        }
+        Ok(transactions)
+    }
+
     async fn insert_events(
         &self,
         domain_events: &[SerializedDomainEvent],
         integration_events: &[SerializedIntegrationEvent],
+        index_ops: &[IndexOp],
+        expected_state: ExpectedState,
     ) -> Result<(), DynamoAggregateError> {
-        if domain_events.is_empty() {
+        if domain_events.is_empty() && index_ops.is_empty() {
             return Ok(());
         }
-        let (transactions, _) = Self::build_all_event_transactions(
+        let (mut transactions, _) = Self::build_all_event_transactions(
             &self.config.table_names.journal,
             &self.config.table_names.outbox,
             self.config.shard_count,
+            self.shard_strategy(),
+            self.tenant_scope(),
             domain_events,
             integration_events,
         )?;
-        commit_transactions(&self.client, transactions).await?;
+        transactions.extend(Self::build_expected_state_check(
+            &self.config.table_names.journal,
+            self.config.shard_count,
+            self.shard_strategy(),
+            self.tenant_scope(),
+            domain_events,
+            expected_state,
+        )?);
+        transactions.extend(Self::build_index_transactions(
+            &self.config.table_names.inverted_index,
+            self.tenant_scope(),
+            index_ops,
+        )?);
+        commit_transactions(&self.client, self.config.throttle_retry, transactions)
+            .await
+            .map_err(|e| match domain_events.first() {
+                Some(event) => e.into_concurrency_error(&event.aggregate_id, event.seq_nr.saturating_sub(1)),
+                None => e,
+            })?;
         Ok(())
     }
 
@@ -287,10 +760,9 @@ impl DynamoDB {
         shard_count: usize,
         seq_nr: SequenceNumber,
     ) -> Result<QueryOutput, DynamoAggregateError> {
-        let output = self
-            .create_query(table, aggregate_type, aggregate_id, shard_count, seq_nr)
-            .send()
-            .await?;
+        let query = self.create_query(table, aggregate_type, aggregate_id, shard_count, seq_nr);
+        let output = self.config.throttle_retry.send(|| query.clone().send()).await?;
+        self.metrics.record_query_items(output.items().len());
         Ok(output)
     }
 
@@ -302,70 +774,280 @@ impl DynamoDB {
         shard_count: usize,
         seq_nr: SequenceNumber,
     ) -> QueryFluentBuilder {
-        let pkey = resolve_partition_key(aggregate_id.to_string(), aggregate_type.to_string(), shard_count);
+        let pkey = self.scoped_key(resolve_partition_key_with(
+            self.shard_strategy(),
+            aggregate_id.to_string(),
+            aggregate_type.to_string(),
+            shard_count,
+        ));
         let skey = resolve_sort_key(aggregate_type.to_string(), aggregate_id.to_string(), seq_nr);
-        self.client
-            .query()
-            .table_name(table)
+        KeyConditionBuilder::new(table)
             .consistent_read(true)
-            .key_condition_expression("#pkey = :pkey AND #skey >= :skey")
-            .expression_attribute_names("#pkey", "pkey")
-            .expression_attribute_names("#skey", "skey")
-            .expression_attribute_values(":pkey", AttributeValue::S(pkey))
-            .expression_attribute_values(":skey", AttributeValue::S(skey))
+            .key_eq("pkey", AttributeValue::S(pkey))
+            .key_ge("skey", AttributeValue::S(skey))
+            .build(&self.client)
     }
 
-    async fn update_snapshot(
+    /// Pages of the results of `query`, each retried according to `throttle_retry` while it is throttled.
+    pub(crate) fn query_pages(
         &self,
-        snapshot: &PersistedSnapshot,
-        domain_events: &[SerializedDomainEvent],
-        integration_events: &[SerializedIntegrationEvent],
-    ) -> Result<(), DynamoAggregateError> {
-        let expected_snapshot = snapshot.version.saturating_sub(1);
-        let (mut transactions, current_seq_nr) = Self::build_all_event_transactions(
-            &self.config.table_names.journal,
-            &self.config.table_names.outbox,
-            self.config.shard_count,
-            domain_events,
-            integration_events,
-        )?;
+        query: QueryFluentBuilder,
+    ) -> impl Stream<Item = Result<QueryOutput, DynamoAggregateError>> {
+        let retry = self.config.throttle_retry;
+        // `None` once the last page was read, otherwise the key to start the next page from.
+        futures::stream::try_unfold(Some(None), move |start_key| {
+            let query = query.clone();
+            async move {
+                let Some(start_key) = start_key else {
+                    return Ok(None);
+                };
+                let page = retry
+                    .send(|| query.clone().set_exclusive_start_key(start_key.clone()).send())
+                    .await?;
+                let next_key = page.last_evaluated_key().map(|key| Some(key.clone()));
+                Ok(Some((page, next_key)))
+            }
+        })
+    }
 
-        let pkey = AttributeValue::S(resolve_partition_key(
+    fn build_snapshot_put_transaction(
+        &self,
+        snapshot: &PersistedSnapshot,
+    ) -> Result<TransactWriteItem, DynamoAggregateError> {
+        let pkey = AttributeValue::S(self.scoped_key(resolve_partition_key_with(
+            self.shard_strategy(),
             snapshot.aggregate_id.clone(),
             snapshot.aggregate_type.clone(),
             self.config.shard_count,
-        ));
+        )));
         let skey = AttributeValue::S(resolve_sort_key(
             snapshot.aggregate_type.clone(),
             snapshot.aggregate_id.clone(),
-            current_seq_nr,
+            snapshot.seq_nr,
         ));
         let aid = AttributeValue::S(String::from(&snapshot.aggregate_id));
-        let current_seq_nr = AttributeValue::N(current_seq_nr.to_string());
+        let seq_nr = AttributeValue::N(snapshot.seq_nr.to_string());
         let version = AttributeValue::N(snapshot.version.to_string());
-        let payload = AttributeValue::B(Blob::new(&*snapshot.aggregate));
-        let expected_snapshot = AttributeValue::N(expected_snapshot.to_string());
+        let codec = self.config.snapshot_codec;
+        let payload = AttributeValue::B(Blob::new(codec.encode(&snapshot.aggregate)?));
+        let expected_snapshot = AttributeValue::N(snapshot.version.saturating_sub(1).to_string());
+
+        let item = HashMap::from([
+            ("pkey".to_string(), pkey),
+            ("skey".to_string(), skey),
+            ("aid".to_string(), aid),
+            ("seq_nr".to_string(), seq_nr),
+            ("version".to_string(), version),
+            (
+                "aggregate_type".to_string(),
+                AttributeValue::S(snapshot.aggregate_type.clone()),
+            ),
+            ("payload".to_string(), payload),
+            ("codec".to_string(), AttributeValue::S(codec.as_str().to_string())),
+        ]);
+        // DynamoDB rejects oversized items with a generic validation error; fail early with the actual size instead.
+        let bytes = item_size(&item);
+        if bytes > MAX_ITEM_SIZE_BYTES {
+            return Err(DynamoAggregateError::ItemTooLarge {
+                bytes,
+                limit: MAX_ITEM_SIZE_BYTES,
+            });
+        }
 
         let put = Put::builder()
             .table_name(&self.config.table_names.snapshot)
-            .item("pkey", pkey)
-            .item("skey", skey)
-            .item("aid", aid)
-            .item("seq_nr", current_seq_nr)
-            .item("version", version)
-            .item("aggregate_type", AttributeValue::S(snapshot.aggregate_type.clone()))
-            .item("payload", payload)
-            .condition_expression("attribute_not_exists(version) OR (version  = :version)")
-            .expression_attribute_values(":version", expected_snapshot)
-            .build()
-            .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
+            .set_item(Some(item));
+        let put = match self.config.snapshot_write_policy {
+            SnapshotWritePolicy::Conditional => put
+                .condition_expression("attribute_not_exists(version) OR (version  = :version)")
+                .expression_attribute_values(":version", expected_snapshot),
+            SnapshotWritePolicy::Overwrite => put,
+        }
+        .build()
+        .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
 
-        let write_item = TransactWriteItem::builder().put(put).build();
-        transactions.push(write_item);
-        commit_transactions(&self.client, transactions).await?;
+        Ok(TransactWriteItem::builder().put(put).build())
+    }
+
+    async fn update_snapshot(
+        &self,
+        snapshot: &PersistedSnapshot,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        index_ops: &[IndexOp],
+        expected_state: ExpectedState,
+    ) -> Result<(), DynamoAggregateError> {
+        let (mut transactions, _) = Self::build_all_event_transactions(
+            &self.config.table_names.journal,
+            &self.config.table_names.outbox,
+            self.config.shard_count,
+            self.shard_strategy(),
+            self.tenant_scope(),
+            domain_events,
+            integration_events,
+        )?;
+        transactions.extend(Self::build_expected_state_check(
+            &self.config.table_names.journal,
+            self.config.shard_count,
+            self.shard_strategy(),
+            self.tenant_scope(),
+            domain_events,
+            expected_state,
+        )?);
+
+        transactions.push(self.build_snapshot_put_transaction(snapshot)?);
+        transactions.extend(Self::build_index_transactions(
+            &self.config.table_names.inverted_index,
+            self.tenant_scope(),
+            index_ops,
+        )?);
+        let expected_seq = domain_events
+            .first()
+            .map_or(snapshot.seq_nr, |event| event.seq_nr)
+            .saturating_sub(1);
+        commit_transactions(&self.client, self.config.throttle_retry, transactions)
+            .await
+            .map_err(|e| e.into_concurrency_error(&snapshot.aggregate_id, expected_seq))?;
+        Ok(())
+    }
+
+    /// Item count of `batch` once written, which decides how many batches share a transaction.
+    fn batch_item_count(batch: &PersistBatch) -> usize {
+        batch.domain_events.len()
+            + batch.integration_events.len()
+            + usize::from(batch.snapshot_update.is_some())
+            + batch.index_ops.len()
+    }
+
+    /// Splits `batches` into runs of consecutive batches that each fit into one transaction.
+    ///
+    /// A transaction holds at most [`MAX_TRANSACTION_ITEMS`] items and may not touch an item twice, so a run
+    /// also ends before a second batch of the same aggregate. A single batch that exceeds the limit is rejected.
+    fn group_batches(batches: &[PersistBatch]) -> Result<Vec<&[PersistBatch]>, DynamoAggregateError> {
+        let mut groups = Vec::new();
+        let mut start = 0;
+        let mut items = 0;
+        let mut aggregate_ids: Vec<&str> = Vec::new();
+        for (index, batch) in batches.iter().enumerate() {
+            let count = Self::batch_item_count(batch);
+            if count > MAX_TRANSACTION_ITEMS {
+                return Err(DynamoAggregateError::TransactionTooLarge {
+                    items: count,
+                    limit: MAX_TRANSACTION_ITEMS,
+                });
+            }
+            let repeats_aggregate = batch.aggregate_id().is_some_and(|id| aggregate_ids.contains(&id));
+            if items + count > MAX_TRANSACTION_ITEMS || repeats_aggregate {
+                groups.push(&batches[start..index]);
+                start = index;
+                items = 0;
+                aggregate_ids.clear();
+            }
+            items += count;
+            aggregate_ids.extend(batch.aggregate_id());
+        }
+        if start < batches.len() {
+            groups.push(&batches[start..]);
+        }
+        Ok(groups)
+    }
+
+    fn build_batch_transactions(&self, batch: &PersistBatch) -> Result<Vec<TransactWriteItem>, DynamoAggregateError> {
+        let (mut transactions, _) = Self::build_all_event_transactions(
+            &self.config.table_names.journal,
+            &self.config.table_names.outbox,
+            self.config.shard_count,
+            self.shard_strategy(),
+            self.tenant_scope(),
+            &batch.domain_events,
+            self.transactional_outbox(&batch.integration_events),
+        )?;
+        if let Some(snapshot) = &batch.snapshot_update {
+            transactions.push(self.build_snapshot_put_transaction(snapshot)?);
+        }
+        transactions.extend(Self::build_index_transactions(
+            &self.config.table_names.inverted_index,
+            self.tenant_scope(),
+            &batch.index_ops,
+        )?);
+        Ok(transactions)
+    }
+
+    async fn write_batches(&self, batches: &[PersistBatch]) -> Result<(), DynamoAggregateError> {
+        for group in Self::group_batches(batches)? {
+            let mut transactions = Vec::new();
+            // Batch that wrote each transaction item, to attribute a failed condition to its aggregate.
+            let mut owners = Vec::new();
+            for batch in group {
+                let batch_transactions = self.build_batch_transactions(batch)?;
+                owners.extend(std::iter::repeat_n(batch, batch_transactions.len()));
+                transactions.extend(batch_transactions);
+            }
+            if transactions.is_empty() {
+                continue;
+            }
+            commit_transactions_locating_conflict(&self.client, self.config.throttle_retry, transactions)
+                .await
+                .map_err(|(error, index)| match index.and_then(|index| owners.get(index)) {
+                    Some(batch) => {
+                        let expected_seq = batch
+                            .domain_events
+                            .first()
+                            .map(|event| event.seq_nr)
+                            .or_else(|| batch.snapshot_update.as_ref().map(|snapshot| snapshot.seq_nr))
+                            .unwrap_or_default()
+                            .saturating_sub(1);
+                        error.into_concurrency_error(batch.aggregate_id().unwrap_or_default(), expected_seq)
+                    }
+                    None => error,
+                })?;
+            for batch in group {
+                self.write_outbox_best_effort(&batch.integration_events).await;
+            }
+        }
         Ok(())
     }
 
+    /// Integration events to write in the transaction of the domain events, which is none of them in
+    /// [`OutboxMode::BestEffort`].
+    fn transactional_outbox<'a>(
+        &self,
+        integration_events: &'a [SerializedIntegrationEvent],
+    ) -> &'a [SerializedIntegrationEvent] {
+        match self.config.outbox_mode {
+            OutboxMode::Transactional => integration_events,
+            OutboxMode::BestEffort => &[],
+        }
+    }
+
+    /// Writes the outbox records of committed domain events in [`OutboxMode::BestEffort`], logging failures
+    /// instead of returning them.
+    async fn write_outbox_best_effort(&self, integration_events: &[SerializedIntegrationEvent]) {
+        if self.config.outbox_mode != OutboxMode::BestEffort || integration_events.is_empty() {
+            return;
+        }
+        for chunk in integration_events.chunks(MAX_TRANSACTION_ITEMS) {
+            let result = match Self::build_integration_event_put_transactions(
+                &self.config.table_names.outbox,
+                self.config.shard_count,
+                self.shard_strategy(),
+                self.tenant_scope(),
+                chunk,
+            ) {
+                Ok(transactions) => commit_transactions(&self.client, self.config.throttle_retry, transactions).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!(
+                    aggregate_id = %chunk[0].aggregate_id,
+                    integration_events = chunk.len(),
+                    error = %e,
+                    "Failed to write outbox records after the journal commit"
+                );
+            }
+        }
+    }
+
     fn get_stream(
         &self,
         table_name: &str,
@@ -373,27 +1055,89 @@ impl DynamoDB {
         aggregate_id: &str,
         seq_nr: usize,
     ) -> impl Stream<Item = Result<HashMap<String, AttributeValue>, PersistenceError>> {
-        self.client
-            .query()
-            .table_name(table_name)
-            .index_name(table_index_name)
-            .key_condition_expression("#aid = :aid AND #seq >= :seq")
-            .expression_attribute_names("#aid", "aid")
-            .expression_attribute_names("#seq", "seq_nr")
-            .expression_attribute_values(":aid", AttributeValue::S(aggregate_id.to_string()))
-            .expression_attribute_values(":seq", AttributeValue::N(seq_nr.to_string()))
+        let metrics = Arc::clone(&self.metrics);
+        let query = KeyConditionBuilder::new(table_name)
+            .index(table_index_name)
             .consistent_read(false)
-            .into_paginator()
-            .items()
-            .send()
-            .into_stream_03x()
-            .map_err(DynamoAggregateError::from)
+            .key_eq("aid", AttributeValue::S(aggregate_id.to_string()))
+            .key_ge("seq_nr", AttributeValue::N(seq_nr.to_string()))
+            .tenant(self.tenant_scope())
+            .build(&self.client);
+        self.query_pages(query)
+            .map_ok(move |page| {
+                metrics.record_query_items(page.items().len());
+                futures::stream::iter(page.items.unwrap_or_default().into_iter().map(Ok))
+            })
             .map_err(PersistenceError::from)
+            .try_flatten()
+    }
+
+    /// Reads the journal from the base table, which unlike `journal_aid_index` supports strongly consistent reads.
+    ///
+    /// Sort keys embed the sequence number as text and so come back in lexicographic order; the items of every
+    /// partition the aggregate may live in are collected and sorted by `seq_nr` before they are yielded.
+    fn get_consistent_stream(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        seq_nr: usize,
+    ) -> impl Stream<Item = Result<HashMap<String, AttributeValue>, PersistenceError>> {
+        let metrics = Arc::clone(&self.metrics);
+        let prefix = resolve_sort_key_prefix(aggregate_type.to_string(), aggregate_id.to_string());
+        let pages: Vec<_> = self
+            .read_shard_counts(aggregate_type, aggregate_id)
+            .into_iter()
+            .map(|shard_count| {
+                let pkey = self.scoped_key(resolve_partition_key_with(
+                    self.shard_strategy(),
+                    aggregate_id.to_string(),
+                    aggregate_type.to_string(),
+                    shard_count,
+                ));
+                let query = KeyConditionBuilder::new(&self.config.table_names.journal)
+                    .consistent_read(true)
+                    .key_eq("pkey", AttributeValue::S(pkey))
+                    .key_begins_with("skey", AttributeValue::S(prefix.clone()))
+                    .filter_ge("seq_nr", AttributeValue::N(seq_nr.to_string()))
+                    .build(&self.client);
+                self.query_pages(query)
+            })
+            .collect();
+
+        let items = async move {
+            let mut items = Vec::new();
+            for pages in pages {
+                let mut pages = std::pin::pin!(pages);
+                while let Some(page) = pages.try_next().await? {
+                    metrics.record_query_items(page.items().len());
+                    for item in page.items.unwrap_or_default() {
+                        items.push((att_as_number(&item, "seq_nr")?, item));
+                    }
+                }
+            }
+            items.sort_by_key(|(seq_nr, _)| *seq_nr);
+            Ok::<_, PersistenceError>(futures::stream::iter(items.into_iter().map(|(_, item)| Ok(item))))
+        };
+        futures::stream::once(items).try_flatten()
+    }
+
+    async fn count_journal_items(&self, aggregate_id: &str) -> Result<usize, DynamoAggregateError> {
+        let query = KeyConditionBuilder::new(&self.config.table_names.journal)
+            .index(&self.config.table_names.journal_aid_index)
+            .key_eq("aid", AttributeValue::S(aggregate_id.to_string()))
+            .tenant(self.tenant_scope())
+            .build(&self.client)
+            .select(Select::Count);
+        self.query_pages(query)
+            .try_fold(0, |count, page| async move {
+                Ok(count + usize::try_from(page.count).unwrap_or_default())
+            })
+            .await
     }
 
     async fn insert_inverted_index(&self, aggregate_id: &str, keyword: &str) -> Result<(), DynamoAggregateError> {
         let mut transactions: Vec<TransactWriteItem> = Vec::default();
-        let pkey = AttributeValue::S(keyword.to_string());
+        let pkey = AttributeValue::S(self.scoped_key(keyword.to_string()));
         let skey = AttributeValue::S(aggregate_id.to_string());
         let put = Put::builder()
             .table_name(&self.config.table_names.inverted_index)
@@ -404,22 +1148,19 @@ impl DynamoDB {
             .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
         let write_item = TransactWriteItem::builder().put(put).build();
         transactions.push(write_item);
-        commit_transactions(&self.client, transactions).await?;
+        commit_transactions(&self.client, self.config.throttle_retry, transactions).await?;
         Ok(())
     }
 
+    /// Aggregate IDs are the sort key, so they come back in ascending order.
     async fn query_inverted_index(&self, keyword: &str) -> Result<Vec<String>, DynamoAggregateError> {
-        let response = self
-            .client
-            .query()
-            .table_name(&self.config.table_names.inverted_index)
-            .key_condition_expression("pkey = :keyword")
-            .expression_attribute_values(":keyword", AttributeValue::S(keyword.to_string()))
-            .send()
-            .await?;
-        let items = response.items.unwrap_or_default();
-        let targets: Vec<String> = items
+        let query = KeyConditionBuilder::new(&self.config.table_names.inverted_index)
+            .key_eq("pkey", AttributeValue::S(self.scoped_key(keyword.to_string())))
+            .build(&self.client);
+        let pages: Vec<QueryOutput> = self.query_pages(query).try_collect().await?;
+        let targets: Vec<String> = pages
             .iter()
+            .flat_map(|page| page.items())
             .filter_map(|item| item.get("skey")?.as_s().ok().cloned())
             .collect();
         Ok(targets)
@@ -427,7 +1168,7 @@ impl DynamoDB {
 
     async fn remove_inverted_index(&self, aggregate_id: &str, keyword: &str) -> Result<(), DynamoAggregateError> {
         let mut transactions: Vec<TransactWriteItem> = Vec::default();
-        let pkey = AttributeValue::S(keyword.to_string());
+        let pkey = AttributeValue::S(self.scoped_key(keyword.to_string()));
         let skey = AttributeValue::S(aggregate_id.to_string());
         let delete = Delete::builder()
             .table_name(&self.config.table_names.inverted_index)
@@ -437,37 +1178,47 @@ impl DynamoDB {
             .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
         let write_item = TransactWriteItem::builder().delete(delete).build();
         transactions.push(write_item);
-        commit_transactions(&self.client, transactions).await?;
+        commit_transactions(&self.client, self.config.throttle_retry, transactions).await?;
         Ok(())
     }
 
-    async fn get_snapshot<T: AggregateRoot>(
+    async fn get_snapshot_by_type(
         &self,
+        aggregate_type: &str,
         id: &str,
     ) -> Result<Option<PersistedSnapshot>, DynamoAggregateError> {
-        let query_output = self
-            .query_table(
-                &self.config.table_names.snapshot,
-                T::TYPE,
-                id,
-                self.config.shard_count,
-                0,
-            )
-            .await?;
-        let Some(query_items_vec) = query_output.items else {
+        let queries = self
+            .read_shard_counts(aggregate_type, id)
+            .into_iter()
+            .map(|shard_count| self.query_table(&self.config.table_names.snapshot, aggregate_type, id, shard_count, 0));
+        let query_outputs = futures::future::try_join_all(queries).await?;
+
+        // Snapshots may be spread over the partitions of several shard counts; the newest one wins.
+        let mut latest: Option<(SequenceNumber, usize, HashMap<String, AttributeValue>)> = None;
+        for query_item in query_outputs
+            .into_iter()
+            .flat_map(|output| output.items.unwrap_or_default())
+        {
+            let seq_nr = att_as_number(&query_item, "seq_nr")?;
+            let version = att_as_number(&query_item, "version")?;
+            if latest
+                .as_ref()
+                .is_none_or(|(latest_seq_nr, latest_version, _)| (seq_nr, version) > (*latest_seq_nr, *latest_version))
+            {
+                latest = Some((seq_nr, version, query_item));
+            }
+        }
+        let Some((seq_nr, version, query_item)) = latest else {
             return Ok(None);
         };
-        if query_items_vec.is_empty() {
-            return Ok(None);
-        }
-        let query_item = query_items_vec
-            .last()
-            .ok_or_else(|| DynamoAggregateError::MissingAttribute("No items in query result".to_string()))?;
-        let aggregate = att_as_vec(query_item, "payload")?;
-        let seq_nr = att_as_number(query_item, "seq_nr")?;
-        let version = att_as_number(query_item, "version")?;
+        // Snapshots written before compression was supported have no codec attribute.
+        let codec = match query_item.get("codec") {
+            Some(_) => SnapshotCodec::from_marker(&att_as_string(&query_item, "codec")?)?,
+            None => SnapshotCodec::None,
+        };
+        let aggregate = codec.decode(&att_as_vec(&query_item, "payload")?)?;
         let persisted_aggregate = PersistedSnapshot {
-            aggregate_type: T::TYPE.to_string(),
+            aggregate_type: aggregate_type.to_string(),
             aggregate_id: id.to_string(),
             aggregate,
             seq_nr,
@@ -477,10 +1228,19 @@ impl DynamoDB {
     }
 }
 
-#[derive(Debug)]
 pub struct DynamoDBBuilder {
     client: Client,
     config_builder: DynamoDBConfigBuilder,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl fmt::Debug for DynamoDBBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamoDBBuilder")
+            .field("client", &self.client)
+            .field("config_builder", &self.config_builder)
+            .finish_non_exhaustive()
+    }
 }
 
 impl DynamoDBBuilder {
@@ -488,6 +1248,7 @@ impl DynamoDBBuilder {
         Self {
             client,
             config_builder: DynamoDBConfigBuilder::new(),
+            metrics: Arc::new(NoopMetrics),
         }
     }
 
@@ -506,38 +1267,139 @@ impl DynamoDBBuilder {
         self
     }
 
+    pub fn snapshot_interval_for(mut self, aggregate_type: &'static str, interval: usize) -> Self {
+        self.config_builder = self.config_builder.snapshot_interval_for(aggregate_type, interval);
+        self
+    }
+
+    pub fn dead_letter_after(mut self, attempts: usize) -> Self {
+        self.config_builder = self.config_builder.dead_letter_after(attempts);
+        self
+    }
+
+    pub fn legacy_shard_counts(mut self, counts: Vec<usize>) -> Self {
+        self.config_builder = self.config_builder.legacy_shard_counts(counts);
+        self
+    }
+
+    pub fn consistent_stream_reads(mut self, enabled: bool) -> Self {
+        self.config_builder = self.config_builder.consistent_stream_reads(enabled);
+        self
+    }
+
+    pub fn snapshot_codec(mut self, codec: SnapshotCodec) -> Self {
+        self.config_builder = self.config_builder.snapshot_codec(codec);
+        self
+    }
+
+    pub fn tenant_scope(mut self, tenant: impl Into<String>) -> Self {
+        self.config_builder = self.config_builder.tenant_scope(tenant);
+        self
+    }
+
+    /// Tables [`DynamoDB::health_check`] runs `DescribeTable` against; only the journal by default.
+    pub fn health_check_scope(mut self, scope: HealthCheckScope) -> Self {
+        self.config_builder = self.config_builder.health_check_scope(scope);
+        self
+    }
+
+    pub fn outbox_ttl(mut self, ttl: Duration) -> Self {
+        self.config_builder = self.config_builder.outbox_ttl(ttl);
+        self
+    }
+
+    pub fn outbox_mode(mut self, mode: OutboxMode) -> Self {
+        self.config_builder = self.config_builder.outbox_mode(mode);
+        self
+    }
+
+    pub fn throttle_retry(mut self, retry: ThrottleRetry) -> Self {
+        self.config_builder = self.config_builder.throttle_retry(retry);
+        self
+    }
+
+    pub fn shard_strategy(mut self, strategy: impl ShardStrategy) -> Self {
+        self.config_builder = self.config_builder.shard_strategy(strategy);
+        self
+    }
+
+    pub fn snapshot_write_policy(mut self, policy: SnapshotWritePolicy) -> Self {
+        self.config_builder = self.config_builder.snapshot_write_policy(policy);
+        self
+    }
+
+    /// Installs the hooks notified about persist latency, query sizes and write conflicts.
+    pub fn metrics(mut self, metrics: impl Metrics) -> Self {
+        self.metrics = Arc::new(metrics);
+        self
+    }
+
     pub fn build(self) -> DynamoDB {
         DynamoDB {
             client: self.client,
             config: self.config_builder.build(),
+            metrics: self.metrics,
         }
     }
 }
 
+#[async_trait]
 impl AggregateEventStreamer for DynamoDB {
     fn stream_events<T: AggregateRoot>(
         &self,
         id: &str,
         select: SequenceSelect,
     ) -> EventStream<'_, SerializedDomainEvent, PersistenceError> {
-        self.get_stream(
-            &self.config.table_names.journal,
-            &self.config.table_names.journal_aid_index,
-            id,
-            match select {
-                SequenceSelect::All => 1,
-                SequenceSelect::From(seq) => seq,
-            },
-        )
-        .map(|item| item.and_then(|entry| serialized_event(entry).map_err(PersistenceError::from)))
-        .boxed()
+        let seq_nr = match select {
+            SequenceSelect::All => 1,
+            SequenceSelect::From(seq) => seq,
+        };
+        let items = if self.config.consistent_stream_reads {
+            self.get_consistent_stream(T::TYPE, id, seq_nr).boxed()
+        } else {
+            self.get_stream(
+                &self.config.table_names.journal,
+                &self.config.table_names.journal_aid_index,
+                id,
+                seq_nr,
+            )
+            .boxed()
+        };
+        items
+            .map(|item| item.and_then(|entry| serialized_event(entry).map_err(PersistenceError::from)))
+            .boxed()
+    }
+
+    async fn count_events<T: AggregateRoot>(&self, id: &str) -> Result<usize, PersistenceError> {
+        Ok(self.count_journal_items(id).await?)
     }
 }
 
 #[async_trait]
 impl SnapshotGetter for DynamoDB {
     async fn get_snapshot<T: AggregateRoot>(&self, id: &str) -> Result<Option<PersistedSnapshot>, PersistenceError> {
-        self.get_snapshot::<T>(id).await.map_err(PersistenceError::from)
+        self.get_snapshot_by_type(T::TYPE, id)
+            .await
+            .map_err(PersistenceError::from)
+    }
+
+    /// Snapshot items are keyed by their sequence number, which is unknown before reading them, so they cannot be
+    /// fetched with `BatchGetItem`. Instead the per-aggregate queries run concurrently, at most 100 at a time.
+    async fn get_snapshots<T: AggregateRoot>(
+        &self,
+        ids: &[&str],
+    ) -> Result<HashMap<String, PersistedSnapshot>, PersistenceError> {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        let snapshots: Vec<_> = futures::stream::iter(ids)
+            .map(|id| async move { self.get_snapshot_by_type(T::TYPE, &id).await })
+            .buffer_unordered(MAX_CONCURRENT_SNAPSHOT_READS)
+            .try_collect()
+            .await?;
+        Ok(snapshots
+            .into_iter()
+            .flatten()
+            .map(|snapshot| (snapshot.aggregate_id.clone(), snapshot))
+            .collect())
     }
 }
 
@@ -548,14 +1410,70 @@ impl Persister for DynamoDB {
         domain_events: &[SerializedDomainEvent],
         integration_events: &[SerializedIntegrationEvent],
         snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
     ) -> Result<(), PersistenceError> {
-        match snapshot_update {
-            None => self.insert_events(domain_events, integration_events).await?,
+        self.persist_expecting(
+            domain_events,
+            integration_events,
+            snapshot_update,
+            index_ops,
+            ExpectedState::Any,
+        )
+        .await
+    }
+
+    /// Enforces `expected_state` with a condition check in the same transaction as the events, so the check and
+    /// the write cannot interleave with another writer.
+    async fn persist_expecting(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+        expected_state: ExpectedState,
+    ) -> Result<(), PersistenceError> {
+        let started = Instant::now();
+        let transactional_outbox = self.transactional_outbox(integration_events);
+        let result = match snapshot_update {
+            None => {
+                self.insert_events(domain_events, transactional_outbox, index_ops, expected_state)
+                    .await
+            }
             Some(snapshot) => {
-                self.update_snapshot(snapshot, domain_events, integration_events)
-                    .await?
+                self.update_snapshot(snapshot, domain_events, transactional_outbox, index_ops, expected_state)
+                    .await
             }
         };
+        // Like the transactional outbox, records are only written when something was committed with them.
+        if result.is_ok() && !(domain_events.is_empty() && snapshot_update.is_none() && index_ops.is_empty()) {
+            self.write_outbox_best_effort(integration_events).await;
+        }
+        self.metrics.record_persist_latency(started.elapsed());
+        if let Err(DynamoAggregateError::OptimisticLock | DynamoAggregateError::OptimisticConcurrency { .. }) = &result
+        {
+            self.metrics.incr_conflict();
+        }
+        let result = match (result, expected_state) {
+            (Err(DynamoAggregateError::OptimisticConcurrency { aggregate_id, .. }), ExpectedState::MustNotExist) => {
+                Err(DynamoAggregateError::AlreadyExists { aggregate_id })
+            }
+            (result, _) => result,
+        };
+        result?;
+        Ok(())
+    }
+
+    /// Packs consecutive batches into shared `TransactWriteItems` calls. Each call is atomic, so a conflict rolls
+    /// back every batch sharing its transaction, while batches committed by earlier calls stay written.
+    async fn persist_batch(&self, batches: &[PersistBatch]) -> Result<(), PersistenceError> {
+        let started = Instant::now();
+        let result = self.write_batches(batches).await;
+        self.metrics.record_persist_latency(started.elapsed());
+        if let Err(DynamoAggregateError::OptimisticLock | DynamoAggregateError::OptimisticConcurrency { .. }) = &result
+        {
+            self.metrics.incr_conflict();
+        }
+        result?;
         Ok(())
     }
 }
@@ -564,28 +1482,49 @@ impl SnapshotIntervalProvider for DynamoDB {
     fn snapshot_interval(&self) -> usize {
         self.config.snapshot_interval
     }
+
+    fn snapshot_interval_for<T: AggregateRoot>(&self) -> usize {
+        self.config
+            .snapshot_intervals
+            .get(T::TYPE)
+            .copied()
+            .unwrap_or(self.config.snapshot_interval)
+    }
 }
 
 #[async_trait]
 impl AggregateIdsLoader for DynamoDB {
-    async fn get_aggregate_ids(&self, keyword: &str) -> Result<Vec<String>, PersistenceError> {
-        let targets = self.query_inverted_index(keyword).await?;
+    async fn get_aggregate_ids(
+        &self,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<Vec<String>, PersistenceError> {
+        let targets = self.query_inverted_index(keyword.into().as_str()).await?;
         Ok(targets)
     }
 }
 
 #[async_trait]
 impl InvertedIndexCommiter for DynamoDB {
-    async fn commit(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
-        self.insert_inverted_index(aggregate_id, keyword).await?;
+    async fn commit(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        self.insert_inverted_index(aggregate_id, keyword.into().as_str())
+            .await?;
         Ok(())
     }
 }
 
 #[async_trait]
 impl InvertedIndexRemover for DynamoDB {
-    async fn remove(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
-        self.remove_inverted_index(aggregate_id, keyword).await?;
+    async fn remove(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        self.remove_inverted_index(aggregate_id, keyword.into().as_str())
+            .await?;
         Ok(())
     }
 }
@@ -593,6 +1532,7 @@ impl InvertedIndexRemover for DynamoDB {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::key::resolve_partition_key;
 
     #[test]
     fn test_table_names_default() {
@@ -612,6 +1552,60 @@ mod tests {
         let config = DynamoDBConfig::default();
         assert_eq!(config.shard_count, 4);
         assert_eq!(config.snapshot_interval, 100);
+        assert_eq!(config.dead_letter_after, 5);
+    }
+
+    #[test]
+    fn test_dynamodb_config_builder_dead_letter_after() {
+        let config = DynamoDBConfigBuilder::new().dead_letter_after(3).build();
+        assert_eq!(config.dead_letter_after, 3);
+        assert_eq!(DynamoDBConfigBuilder::new().build().dead_letter_after, 5);
+    }
+
+    fn create_mock_client() -> Client {
+        let config = aws_sdk_dynamodb::Config::builder()
+            .behavior_version(aws_sdk_dynamodb::config::BehaviorVersion::latest())
+            .region(aws_sdk_dynamodb::config::Region::new("us-east-1"))
+            .build();
+        Client::from_conf(config)
+    }
+
+    #[test]
+    fn test_read_shard_counts_skips_duplicate_partitions() {
+        let db = DynamoDB::builder(create_mock_client())
+            .shard_count(8)
+            .legacy_shard_counts(vec![4, 8, 1, 1])
+            .build();
+
+        let shard_counts = db.read_shard_counts("TestAggregate", "test-1");
+        let partition_keys: Vec<_> = shard_counts
+            .iter()
+            .map(|shard_count| resolve_partition_key("test-1".to_string(), "TestAggregate".to_string(), *shard_count))
+            .collect();
+
+        assert_eq!(shard_counts[0], 8);
+        assert!(!shard_counts[1..].contains(&8));
+        for (i, partition_key) in partition_keys.iter().enumerate() {
+            assert!(!partition_keys[i + 1..].contains(partition_key));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_interval_for_aggregate_type() {
+        let db = DynamoDB::builder(create_mock_client())
+            .snapshot_interval(500)
+            .snapshot_interval_for("Hot", 20)
+            .build();
+
+        assert_eq!(db.snapshot_intervals().get("Hot"), Some(&20));
+        assert_eq!(db.snapshot_intervals().get("Cold"), None);
+    }
+
+    #[test]
+    fn test_read_shard_counts_without_legacy_counts() {
+        let db = DynamoDB::builder(create_mock_client()).shard_count(4).build();
+
+        assert_eq!(db.read_shard_counts("TestAggregate", "test-1"), vec![4]);
     }
 
     #[test]
@@ -628,6 +1622,8 @@ mod tests {
                 event_type: "Created".to_string(),
                 payload: vec![1, 2, 3],
                 metadata: Default::default(),
+                schema_version: 1,
+                occurred_at: Default::default(),
             },
             SerializedDomainEvent {
                 id: "event-2".to_string(),
@@ -637,10 +1633,13 @@ mod tests {
                 event_type: "Updated".to_string(),
                 payload: vec![4, 5, 6],
                 metadata: Default::default(),
+                schema_version: 1,
+                occurred_at: Default::default(),
             },
         ];
 
-        let result = DynamoDB::build_domain_event_put_transactions(journal_table, shard_count, &events);
+        let result =
+            DynamoDB::build_domain_event_put_transactions(journal_table, shard_count, &ModuloHash, None, &events);
 
         assert!(result.is_ok());
         let (transactions, current_seq_nr) = result.unwrap();
@@ -648,24 +1647,121 @@ mod tests {
         assert_eq!(current_seq_nr, 2);
     }
 
+    #[test]
+    fn test_debug_keys_match_journal_items() {
+        let event = SerializedDomainEvent {
+            id: "event-3".to_string(),
+            aggregate_id: "agg-1".to_string(),
+            aggregate_type: "TestAggregate".to_string(),
+            seq_nr: 3,
+            event_type: "Updated".to_string(),
+            payload: vec![],
+            metadata: Default::default(),
+            schema_version: 1,
+            occurred_at: Default::default(),
+        };
+        for tenant_scope in [None, Some("tenant-a")] {
+            let mut builder = DynamoDB::builder(create_mock_client()).shard_count(4);
+            if let Some(tenant) = tenant_scope {
+                builder = builder.tenant_scope(tenant);
+            }
+            let db = builder.build();
+
+            let (transactions, _) = DynamoDB::build_domain_event_put_transactions(
+                "test-journal",
+                db.shard_count(),
+                db.shard_strategy(),
+                db.tenant_scope(),
+                std::slice::from_ref(&event),
+            )
+            .unwrap();
+            let item = transactions[0].put.as_ref().unwrap().item();
+
+            let (pkey, skey) = db.debug_keys("TestAggregate", "agg-1", 3);
+            assert_eq!(item.get("pkey"), Some(&AttributeValue::S(pkey.clone())));
+            assert_eq!(item.get("skey"), Some(&AttributeValue::S(skey)));
+            assert!(pkey.ends_with(&format!("TestAggregate-{}", db.debug_shard_for("agg-1"))));
+        }
+    }
+
+    #[test]
+    fn test_build_expected_state_check() {
+        let event = |seq_nr| SerializedDomainEvent {
+            id: format!("event-{seq_nr}"),
+            aggregate_id: "agg-1".to_string(),
+            aggregate_type: "TestAggregate".to_string(),
+            seq_nr,
+            event_type: "Updated".to_string(),
+            payload: vec![],
+            metadata: Default::default(),
+            schema_version: 1,
+            occurred_at: Default::default(),
+        };
+        let check = |events: &[SerializedDomainEvent], expected_state| {
+            DynamoDB::build_expected_state_check("test-journal", 4, &ModuloHash, None, events, expected_state)
+        };
+
+        assert!(check(&[event(3)], ExpectedState::Any).unwrap().is_none());
+        assert!(check(&[event(1)], ExpectedState::MustNotExist).unwrap().is_none());
+        assert!(check(&[event(1)], ExpectedState::ExpectedVersion(0)).unwrap().is_none());
+        assert!(check(&[], ExpectedState::ExpectedVersion(5)).unwrap().is_none());
+
+        let condition = check(&[event(3)], ExpectedState::ExpectedVersion(2))
+            .unwrap()
+            .and_then(|item| item.condition_check)
+            .unwrap();
+        assert_eq!(condition.condition_expression(), "attribute_exists(#seq)");
+        assert_eq!(
+            condition.key().get("skey"),
+            Some(&AttributeValue::S(resolve_sort_key(
+                "TestAggregate".to_string(),
+                "agg-1".to_string(),
+                2
+            )))
+        );
+
+        assert!(matches!(
+            check(&[event(3)], ExpectedState::ExpectedVersion(1)),
+            Err(DynamoAggregateError::OptimisticConcurrency { expected_seq: 1, .. })
+        ));
+    }
+
     #[test]
     fn test_build_integration_event_put_transactions() {
         let outbox_table = "test-outbox";
         let shard_count = 4;
 
-        let events = vec![SerializedIntegrationEvent {
-            id: "int-event-1".to_string(),
-            aggregate_id: "agg-1".to_string(),
-            aggregate_type: "TestAggregate".to_string(),
-            event_type: "Published".to_string(),
-            payload: vec![7, 8, 9],
-        }];
+        let metadata = tsuzuri::message::Metadata::from([(tsuzuri::message::CORRELATION_ID, "req-1")]);
+        let events = vec![
+            SerializedIntegrationEvent {
+                id: "int-event-1".to_string(),
+                aggregate_id: "agg-1".to_string(),
+                aggregate_type: "TestAggregate".to_string(),
+                event_type: "Published".to_string(),
+                payload: vec![7, 8, 9],
+                metadata: serde_json::to_value(&metadata).unwrap(),
+            },
+            SerializedIntegrationEvent {
+                id: "int-event-2".to_string(),
+                aggregate_id: "agg-1".to_string(),
+                aggregate_type: "TestAggregate".to_string(),
+                event_type: "Published".to_string(),
+                payload: vec![7, 8, 9],
+                metadata: Default::default(),
+            },
+        ];
 
-        let result = DynamoDB::build_integration_event_put_transactions(outbox_table, shard_count, &events);
+        let result =
+            DynamoDB::build_integration_event_put_transactions(outbox_table, shard_count, &ModuloHash, None, &events);
 
         assert!(result.is_ok());
         let transactions = result.unwrap();
-        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions.len(), 2);
+        let item = transactions[0].put().unwrap().item();
+        let blob = item["metadata"].as_b().unwrap();
+        let written: tsuzuri::message::Metadata = serde_json::from_slice(blob.as_ref()).unwrap();
+        assert_eq!(written.get_str(tsuzuri::message::CORRELATION_ID), Some("req-1"));
+        assert!(!transactions[1].put().unwrap().item().contains_key("metadata"));
     }
 
     #[test]
@@ -682,6 +1778,8 @@ mod tests {
             event_type: "Created".to_string(),
             payload: vec![1, 2, 3],
             metadata: Default::default(),
+            schema_version: 1,
+            occurred_at: Default::default(),
         }];
 
         let integration_events = vec![SerializedIntegrationEvent {
@@ -690,12 +1788,15 @@ mod tests {
             aggregate_type: "TestAggregate".to_string(),
             event_type: "Published".to_string(),
             payload: vec![7, 8, 9],
+            metadata: Default::default(),
         }];
 
         let result = DynamoDB::build_all_event_transactions(
             journal_table,
             outbox_table,
             shard_count,
+            &ModuloHash,
+            None,
             &domain_events,
             &integration_events,
         );
@@ -720,6 +1821,8 @@ mod tests {
             event_type: "Created".to_string(),
             payload: vec![1, 2, 3],
             metadata: Default::default(),
+            schema_version: 1,
+            occurred_at: Default::default(),
         }];
 
         let integration_events = vec![];
@@ -728,6 +1831,8 @@ mod tests {
             journal_table,
             outbox_table,
             shard_count,
+            &ModuloHash,
+            None,
             &domain_events,
             &integration_events,
         );
@@ -737,4 +1842,262 @@ mod tests {
         assert_eq!(transactions.len(), 1); // Only domain event
         assert_eq!(current_seq_nr, 1);
     }
+
+    fn journal_item(schema_version: Option<&str>) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::from([
+            ("event_id".to_string(), AttributeValue::S("event-1".to_string())),
+            ("aid".to_string(), AttributeValue::S("agg-1".to_string())),
+            ("seq_nr".to_string(), AttributeValue::N("1".to_string())),
+            (
+                "aggregate_type".to_string(),
+                AttributeValue::S("TestAggregate".to_string()),
+            ),
+            ("event_type".to_string(), AttributeValue::S("Created".to_string())),
+            ("payload".to_string(), AttributeValue::B(Blob::new(vec![1, 2, 3]))),
+            ("metadata".to_string(), AttributeValue::B(Blob::new(b"{}".to_vec()))),
+        ]);
+        if let Some(version) = schema_version {
+            item.insert("schema_version".to_string(), AttributeValue::N(version.to_string()));
+        }
+        item
+    }
+
+    #[test]
+    fn test_serialized_event_reads_schema_version() {
+        let event = serialized_event(journal_item(Some("3"))).unwrap();
+        assert_eq!(event.schema_version, 3);
+    }
+
+    #[test]
+    fn test_serialized_event_defaults_schema_version_for_legacy_rows() {
+        let event = serialized_event(journal_item(None)).unwrap();
+        assert_eq!(event.schema_version, 1);
+        assert_eq!(event.occurred_at, Default::default());
+    }
+
+    #[test]
+    fn test_journal_item_round_trips_occurred_at() {
+        let occurred_at = tsuzuri::helper::from_rfc3339("2021-01-01T00:00:00.123456789Z").unwrap();
+        let event = SerializedDomainEvent::new(
+            "event-1".to_string(),
+            "agg-1".to_string(),
+            1,
+            "TestAggregate".to_string(),
+            "Created".to_string(),
+            vec![1, 2, 3],
+            serde_json::Value::Null,
+        )
+        .with_occurred_at(occurred_at);
+
+        let (transactions, _) =
+            DynamoDB::build_domain_event_put_transactions("test-journal", 4, &ModuloHash, None, &[event]).unwrap();
+        let item = transactions[0].put().unwrap().item().clone();
+        assert_eq!(
+            item["occurred_at"].as_s().unwrap(),
+            "2021-01-01T00:00:00.123456789+00:00"
+        );
+        assert_eq!(serialized_event(item).unwrap().occurred_at, occurred_at);
+    }
+
+    #[test]
+    fn test_journal_item_round_trips_correlation_and_causation_ids() {
+        let metadata = tsuzuri::message::Metadata::from([
+            (tsuzuri::message::CORRELATION_ID.to_string(), "req-1".to_string()),
+            (tsuzuri::message::CAUSATION_ID.to_string(), "cmd-1".to_string()),
+        ]);
+        let event = SerializedDomainEvent::new(
+            "event-1".to_string(),
+            "agg-1".to_string(),
+            1,
+            "TestAggregate".to_string(),
+            "Created".to_string(),
+            vec![1, 2, 3],
+            serde_json::to_value(&metadata).unwrap(),
+        );
+
+        let (transactions, _) =
+            DynamoDB::build_domain_event_put_transactions("test-journal", 4, &ModuloHash, None, &[event]).unwrap();
+        let item = transactions[0].put().unwrap().item().clone();
+        let read_back = serialized_event(item).unwrap();
+
+        let read_metadata: tsuzuri::message::Metadata = serde_json::from_value(read_back.metadata).unwrap();
+        assert_eq!(read_metadata, metadata);
+    }
+
+    #[test]
+    fn test_tenant_scope_prefixes_partition_keys() {
+        let db = DynamoDB::builder(create_mock_client()).tenant_scope("tenant-a").build();
+        assert_eq!(db.tenant_scope(), Some("tenant-a"));
+
+        let event = SerializedDomainEvent::new(
+            "event-1".to_string(),
+            "agg-1".to_string(),
+            1,
+            "TestAggregate".to_string(),
+            "Created".to_string(),
+            vec![1, 2, 3],
+            serde_json::Value::Null,
+        );
+        let (transactions, _) =
+            DynamoDB::build_domain_event_put_transactions("test-journal", 4, &ModuloHash, db.tenant_scope(), &[event])
+                .unwrap();
+        let item = transactions[0].put().unwrap().item();
+        let expected_pkey = format!(
+            "tenant-a#{}",
+            resolve_partition_key("agg-1".to_string(), "TestAggregate".to_string(), 4)
+        );
+        assert_eq!(item["pkey"].as_s().unwrap(), &expected_pkey);
+        assert_eq!(item["aid"].as_s().unwrap(), "agg-1");
+
+        let index_ops = [IndexOp::put("keyword", "agg-1")];
+        let transactions =
+            DynamoDB::build_index_transactions("test-inverted-index", db.tenant_scope(), &index_ops).unwrap();
+        let item = transactions[0].put().unwrap().item();
+        assert_eq!(item["pkey"].as_s().unwrap(), "tenant-a#keyword");
+        assert_eq!(item["skey"].as_s().unwrap(), "agg-1");
+    }
+
+    #[tokio::test]
+    async fn test_update_snapshot_rejects_oversized_item() {
+        let db = DynamoDB::builder(create_mock_client()).build();
+        let snapshot = PersistedSnapshot {
+            aggregate_type: "TestAggregate".to_string(),
+            aggregate_id: "agg-1".to_string(),
+            aggregate: vec![0; MAX_ITEM_SIZE_BYTES],
+            seq_nr: 1,
+            version: 1,
+        };
+
+        // The size check runs before the transaction is sent, so the unreachable client is never used
+        let result = db.update_snapshot(&snapshot, &[], &[], &[], ExpectedState::Any).await;
+
+        match result {
+            Err(DynamoAggregateError::ItemTooLarge { bytes, limit }) => {
+                assert!(bytes > MAX_ITEM_SIZE_BYTES);
+                assert_eq!(limit, MAX_ITEM_SIZE_BYTES);
+            }
+            other => panic!("Expected ItemTooLarge, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_write_policy_controls_condition() {
+        let snapshot = PersistedSnapshot {
+            aggregate_type: "TestAggregate".to_string(),
+            aggregate_id: "agg-1".to_string(),
+            aggregate: b"{}".to_vec(),
+            seq_nr: 5,
+            version: 2,
+        };
+
+        let db = DynamoDB::builder(create_mock_client()).build();
+        assert_eq!(db.snapshot_write_policy(), SnapshotWritePolicy::Conditional);
+        let transaction = db.build_snapshot_put_transaction(&snapshot).unwrap();
+        let put = transaction.put().unwrap();
+        assert!(put.condition_expression().is_some());
+        assert_eq!(
+            put.expression_attribute_values().unwrap().get(":version"),
+            Some(&AttributeValue::N("1".to_string()))
+        );
+
+        let db = DynamoDB::builder(create_mock_client())
+            .snapshot_write_policy(SnapshotWritePolicy::Overwrite)
+            .build();
+        let transaction = db.build_snapshot_put_transaction(&snapshot).unwrap();
+        let put = transaction.put().unwrap();
+        assert_eq!(put.condition_expression(), None);
+        assert_eq!(put.expression_attribute_values(), None);
+        assert_eq!(put.item().get("version"), Some(&AttributeValue::N("2".to_string())));
+    }
+
+    #[test]
+    fn test_item_size_counts_names_and_values() {
+        let item = HashMap::from([
+            ("pkey".to_string(), AttributeValue::S("abc".to_string())),
+            ("seq_nr".to_string(), AttributeValue::N("12".to_string())),
+            ("payload".to_string(), AttributeValue::B(Blob::new(vec![0; 10]))),
+        ]);
+
+        assert_eq!(item_size(&item), (4 + 3) + (6 + 2) + (7 + 10));
+    }
+
+    fn batch_of(aggregate_id: &str, events: usize) -> PersistBatch {
+        PersistBatch::new(
+            (1..=events)
+                .map(|seq_nr| SerializedDomainEvent {
+                    id: format!("{aggregate_id}-{seq_nr}"),
+                    aggregate_id: aggregate_id.to_string(),
+                    aggregate_type: "TestAggregate".to_string(),
+                    seq_nr,
+                    event_type: "Created".to_string(),
+                    payload: vec![],
+                    metadata: Default::default(),
+                    schema_version: 1,
+                    occurred_at: Default::default(),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_group_batches_packs_up_to_item_limit() {
+        let batches = vec![batch_of("agg-1", 40), batch_of("agg-2", 40), batch_of("agg-3", 40)];
+
+        let groups = DynamoDB::group_batches(&batches).unwrap();
+
+        assert_eq!(groups.iter().map(|group| group.len()).collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_group_batches_splits_repeated_aggregates() {
+        let batches = vec![batch_of("agg-1", 1), batch_of("agg-2", 1), batch_of("agg-1", 1)];
+
+        let groups = DynamoDB::group_batches(&batches).unwrap();
+
+        assert_eq!(groups.iter().map(|group| group.len()).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(groups[1][0].aggregate_id(), Some("agg-1"));
+    }
+
+    #[test]
+    fn test_group_batches_rejects_oversized_batch() {
+        let batches = vec![batch_of("agg-1", MAX_TRANSACTION_ITEMS + 1)];
+
+        let result = DynamoDB::group_batches(&batches);
+
+        assert!(matches!(
+            result,
+            Err(DynamoAggregateError::TransactionTooLarge { items, limit })
+                if items == MAX_TRANSACTION_ITEMS + 1 && limit == MAX_TRANSACTION_ITEMS
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_commit_transactions_rejects_over_limit_transaction() {
+        let events: Vec<_> = (1..=MAX_TRANSACTION_ITEMS + 1)
+            .map(|seq_nr| SerializedDomainEvent {
+                id: format!("event-{seq_nr}"),
+                aggregate_id: "agg-1".to_string(),
+                aggregate_type: "TestAggregate".to_string(),
+                seq_nr,
+                event_type: "TestEvent".to_string(),
+                payload: vec![],
+                metadata: Default::default(),
+                schema_version: 1,
+                occurred_at: Default::default(),
+            })
+            .collect();
+        let (transactions, _) =
+            DynamoDB::build_domain_event_put_transactions("journal", 4, &ModuloHash, None, &events).unwrap();
+
+        // The count is checked before the transaction is sent, so the unreachable client is never used
+        let result = commit_transactions(&create_mock_client(), ThrottleRetry::disabled(), transactions).await;
+
+        match result {
+            Err(DynamoAggregateError::TransactionTooLarge { items, limit }) => {
+                assert_eq!(items, MAX_TRANSACTION_ITEMS + 1);
+                assert_eq!(limit, MAX_TRANSACTION_ITEMS);
+            }
+            other => panic!("Expected TransactionTooLarge, got {other:?}"),
+        }
+    }
 }
@@ -0,0 +1,312 @@
+use crate::store::{
+    error::DynamoAggregateError,
+    helper::{commit_transactions, require_attribute, serialized_event, MAX_TRANSACTION_ITEMS},
+    query::KeyConditionBuilder,
+    DynamoDB,
+};
+use aws_sdk_dynamodb::types::{AttributeValue, Delete, TransactWriteItem};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_smithy_types_convert::stream::PaginationStreamExt;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tsuzuri::{
+    aggregate_id::AggregateId,
+    domain_event::SerializedDomainEvent,
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, TOMBSTONE_EVENT_TYPE},
+    helper::{from_rfc3339, to_rfc3339},
+    persist::PersistenceError,
+    sequence_number::SequenceNumber,
+    serde::Deserializer,
+    AggregateRoot, VersionedAggregate,
+};
+
+/// Journal event as stored in an archive object, which holds one JSON document per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedEvent {
+    pub id: String,
+    pub aggregate_id: String,
+    pub seq_nr: SequenceNumber,
+    pub aggregate_type: String,
+    pub event_type: String,
+    /// Base64 of the event payload.
+    pub payload: String,
+    pub metadata: serde_json::Value,
+    pub schema_version: u32,
+    /// RFC 3339 string.
+    pub occurred_at: String,
+}
+
+impl ArchivedEvent {
+    pub fn from_event(event: &SerializedDomainEvent) -> Result<Self, DynamoAggregateError> {
+        Ok(Self {
+            id: event.id.clone(),
+            aggregate_id: event.aggregate_id.clone(),
+            seq_nr: event.seq_nr,
+            aggregate_type: event.aggregate_type.clone(),
+            event_type: event.event_type.clone(),
+            payload: STANDARD.encode(&event.payload),
+            metadata: event.metadata.clone(),
+            schema_version: event.schema_version,
+            occurred_at: to_rfc3339(&event.occurred_at).map_err(|e| DynamoAggregateError::UnknownError(Box::new(e)))?,
+        })
+    }
+
+    pub fn into_event(self) -> Result<SerializedDomainEvent, DynamoAggregateError> {
+        let payload = STANDARD
+            .decode(&self.payload)
+            .map_err(|e| DynamoAggregateError::UnknownError(Box::new(e)))?;
+        let occurred_at = from_rfc3339(&self.occurred_at)
+            .map_err(|_| DynamoAggregateError::MissingAttribute(format!("occurred_at: {}", self.occurred_at)))?;
+        Ok(SerializedDomainEvent {
+            id: self.id,
+            aggregate_id: self.aggregate_id,
+            seq_nr: self.seq_nr,
+            aggregate_type: self.aggregate_type,
+            event_type: self.event_type,
+            payload,
+            metadata: self.metadata,
+            schema_version: self.schema_version,
+            occurred_at,
+        })
+    }
+}
+
+impl DynamoDB {
+    /// Moves the events of an aggregate with a sequence number below `seq_nr` from the journal to an S3 object
+    /// in `bucket`, returning how many were archived.
+    ///
+    /// The events are written as newline-delimited [`ArchivedEvent`]s under [`DynamoDB::archive_prefix`] before
+    /// they are deleted, and only when the aggregate's snapshot starts replay after `seq_nr`, so loading the
+    /// aggregate never needs them and the last event the snapshot covers stays in the journal for
+    /// [`AggregateLoader::load_aggregate_as_of`](tsuzuri::AggregateLoader::load_aggregate_as_of) to date the snapshot
+    /// by. The aggregate's latest event always stays in the journal to keep guarding the sequence numbers of later
+    /// commits.
+    ///
+    /// Once events are archived the journal can no longer be replayed from the initial state, so
+    /// [`EventSourced::rebuild_snapshot`](tsuzuri::EventSourced::rebuild_snapshot) fails for the aggregate with
+    /// [`PersistenceError::CorruptStream`]; use [`DynamoDB::load_aggregate_with_archive`] for its full history.
+    pub async fn archive_events_before(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        seq_nr: SequenceNumber,
+        s3_client: &aws_sdk_s3::Client,
+        bucket: &str,
+    ) -> Result<usize, DynamoAggregateError> {
+        let covered = self
+            .get_snapshot_by_type(aggregate_type, aggregate_id)
+            .await?
+            .is_some_and(|snapshot| snapshot.seq_nr > seq_nr);
+        if !covered {
+            return Err(DynamoAggregateError::SnapshotRequired {
+                aggregate_id: aggregate_id.to_string(),
+                seq_nr,
+            });
+        }
+
+        // The last item is either the event at `seq_nr` or, when there is none, the aggregate's latest event;
+        // neither is archived.
+        let mut items = self.query_journal_through(aggregate_id, seq_nr).await?;
+        items.pop();
+        let events = items
+            .iter()
+            .map(|item| serialized_event(item.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Ok(0);
+        };
+
+        let mut body = Vec::new();
+        for event in &events {
+            serde_json::to_writer(&mut body, &ArchivedEvent::from_event(event)?)?;
+            body.push(b'\n');
+        }
+        let key = format!(
+            "{}{:020}-{:020}.ndjson",
+            self.archive_prefix(aggregate_type, aggregate_id),
+            first.seq_nr,
+            last.seq_nr
+        );
+        s3_client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .content_type("application/x-ndjson")
+            .body(ByteStream::from(body))
+            .send()
+            .await?;
+
+        let deletes = items
+            .iter()
+            .map(|item| {
+                let delete = Delete::builder()
+                    .table_name(&self.config.table_names.journal)
+                    .key("pkey", require_attribute(item, "pkey")?.clone())
+                    .key("skey", require_attribute(item, "skey")?.clone())
+                    .build()
+                    .map_err(|e| DynamoAggregateError::BuilderError(e.to_string()))?;
+                Ok(TransactWriteItem::builder().delete(delete).build())
+            })
+            .collect::<Result<Vec<_>, DynamoAggregateError>>()?;
+        for chunk in deletes.chunks(MAX_TRANSACTION_ITEMS) {
+            commit_transactions(&self.client, self.config.throttle_retry, chunk.to_vec()).await?;
+        }
+        Ok(events.len())
+    }
+
+    /// Reads back the events of an aggregate archived by [`DynamoDB::archive_events_before`], in sequence number
+    /// order.
+    pub async fn archived_events(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        s3_client: &aws_sdk_s3::Client,
+        bucket: &str,
+    ) -> Result<Vec<SerializedDomainEvent>, DynamoAggregateError> {
+        let pages: Vec<_> = s3_client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(self.archive_prefix(aggregate_type, aggregate_id))
+            .into_paginator()
+            .send()
+            .into_stream_03x()
+            .try_collect()
+            .await?;
+        let mut events = Vec::new();
+        for key in pages
+            .iter()
+            .flat_map(|page| page.contents())
+            .filter_map(|object| object.key())
+        {
+            let object = s3_client.get_object().bucket(bucket).key(key).send().await?;
+            let body = object
+                .body
+                .collect()
+                .await
+                .map_err(|e| DynamoAggregateError::UnknownError(Box::new(e)))?
+                .into_bytes();
+            for line in body.split(|byte| *byte == b'\n').filter(|line| !line.is_empty()) {
+                events.push(serde_json::from_slice::<ArchivedEvent>(line)?.into_event()?);
+            }
+        }
+        Ok(merge_events(events, Vec::new()))
+    }
+
+    /// Loads an aggregate by replaying its full history, archived events included, from its initial state.
+    ///
+    /// Archived and live events are merged by sequence number, so events still left in the journal by an
+    /// interrupted archival are applied once. Snapshots are not used, and the returned aggregate has version 0;
+    /// it is meant for audits and temporal queries, not for handling commands.
+    pub async fn load_aggregate_with_archive<T: AggregateRoot>(
+        &self,
+        id: &AggregateId<T::ID>,
+        event_serde: &impl Deserializer<T::DomainEvent>,
+        s3_client: &aws_sdk_s3::Client,
+        bucket: &str,
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        let aggregate_id = id.to_string();
+        let archived = self.archived_events(T::TYPE, &aggregate_id, s3_client, bucket).await?;
+        let live: Vec<_> = self
+            .stream_events::<T>(&aggregate_id, SequenceSelect::All)
+            .try_collect()
+            .await?;
+
+        let mut versioned_aggregate = VersionedAggregate::new(T::init(id.clone()), 0, 0);
+        for event in merge_events(archived, live) {
+            if event.event_type == TOMBSTONE_EVENT_TYPE {
+                return Err(PersistenceError::Tombstoned { aggregate_id });
+            }
+            versioned_aggregate.apply(event_serde.deserialize(&event.payload)?);
+            versioned_aggregate.set_seq_nr(event.seq_nr);
+        }
+        Ok(versioned_aggregate)
+    }
+
+    /// S3 key prefix of the archive objects of an aggregate, scoped to the tenant like the table keys.
+    pub fn archive_prefix(&self, aggregate_type: &str, aggregate_id: &str) -> String {
+        self.scoped_key(format!("{aggregate_type}/{aggregate_id}/"))
+    }
+
+    /// Journal items of the aggregate up to and including `seq_nr`, in sequence number order.
+    async fn query_journal_through(
+        &self,
+        aggregate_id: &str,
+        seq_nr: SequenceNumber,
+    ) -> Result<Vec<HashMap<String, AttributeValue>>, DynamoAggregateError> {
+        let query = KeyConditionBuilder::new(&self.config.table_names.journal)
+            .index(&self.config.table_names.journal_aid_index)
+            .key_eq("aid", AttributeValue::S(aggregate_id.to_string()))
+            .key_le("seq_nr", AttributeValue::N(seq_nr.to_string()))
+            .tenant(self.tenant_scope())
+            .build(&self.client);
+        let pages: Vec<_> = self.query_pages(query).try_collect().await?;
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.items.unwrap_or_default())
+            .collect())
+    }
+}
+
+/// Orders `archived` and `live` events by sequence number, keeping the live one of any duplicate.
+fn merge_events(archived: Vec<SerializedDomainEvent>, live: Vec<SerializedDomainEvent>) -> Vec<SerializedDomainEvent> {
+    let mut events = BTreeMap::new();
+    for event in archived.into_iter().chain(live) {
+        events.insert(event.seq_nr, event);
+    }
+    events.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archived_event_round_trips() {
+        let event = SerializedDomainEvent::new(
+            "event-1".to_string(),
+            "agg-1".to_string(),
+            3,
+            "TestAggregate".to_string(),
+            "Updated".to_string(),
+            vec![0, 159, 146, 150],
+            serde_json::json!({ "correlation_id": "req-1" }),
+        )
+        .with_schema_version(2)
+        .with_occurred_at(from_rfc3339("2021-01-01T00:00:00.5Z").unwrap());
+
+        let archived = ArchivedEvent::from_event(&event).unwrap();
+        assert_eq!(archived.payload, "AJ+Slg==");
+        assert_eq!(archived.occurred_at, "2021-01-01T00:00:00.500+00:00");
+
+        let line = serde_json::to_string(&archived).unwrap();
+        let read_back: ArchivedEvent = serde_json::from_str(&line).unwrap();
+        assert_eq!(read_back.into_event().unwrap(), event);
+    }
+
+    fn event(seq_nr: SequenceNumber, event_type: &str) -> SerializedDomainEvent {
+        SerializedDomainEvent::new(
+            format!("event-{seq_nr}"),
+            "agg-1".to_string(),
+            seq_nr,
+            "TestAggregate".to_string(),
+            event_type.to_string(),
+            vec![],
+            serde_json::Value::Null,
+        )
+    }
+
+    #[test]
+    fn test_merge_events_orders_and_dedupes_by_seq_nr() {
+        let archived = vec![event(2, "Archived"), event(1, "Archived"), event(3, "Archived")];
+        let live = vec![event(3, "Live"), event(4, "Live")];
+
+        let merged = merge_events(archived, live);
+
+        let seq_nrs: Vec<_> = merged.iter().map(|event| event.seq_nr).collect();
+        assert_eq!(seq_nrs, vec![1, 2, 3, 4]);
+        assert_eq!(merged[2].event_type, "Live");
+    }
+}
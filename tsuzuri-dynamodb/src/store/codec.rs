@@ -0,0 +1,90 @@
+use crate::store::error::DynamoAggregateError;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::io::{Read, Write};
+
+/// Compression applied to snapshot payloads before they are written to the snapshot table.
+///
+/// The codec is stored in the `codec` attribute of every snapshot item, so snapshots written with another codec,
+/// or before compression existed, stay readable after the configured codec changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotCodec {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl SnapshotCodec {
+    /// Value stored in the `codec` attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotCodec::None => "none",
+            SnapshotCodec::Gzip => "gzip",
+            SnapshotCodec::Zstd => "zstd",
+        }
+    }
+
+    /// Resolves the codec from the value of a stored `codec` attribute.
+    pub fn from_marker(marker: &str) -> Result<Self, DynamoAggregateError> {
+        match marker {
+            "none" => Ok(SnapshotCodec::None),
+            "gzip" => Ok(SnapshotCodec::Gzip),
+            "zstd" => Ok(SnapshotCodec::Zstd),
+            other => Err(DynamoAggregateError::UnknownSnapshotCodec(other.to_string())),
+        }
+    }
+
+    pub fn encode(&self, payload: &[u8]) -> Result<Vec<u8>, DynamoAggregateError> {
+        match self {
+            SnapshotCodec::None => Ok(payload.to_vec()),
+            SnapshotCodec::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(payload)?;
+                Ok(encoder.finish()?)
+            }
+            SnapshotCodec::Zstd => Ok(zstd::encode_all(payload, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+        }
+    }
+
+    pub fn decode(&self, payload: &[u8]) -> Result<Vec<u8>, DynamoAggregateError> {
+        match self {
+            SnapshotCodec::None => Ok(payload.to_vec()),
+            SnapshotCodec::Gzip => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(payload).read_to_end(&mut decoded)?;
+                Ok(decoded)
+            }
+            SnapshotCodec::Zstd => Ok(zstd::decode_all(payload)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codecs_roundtrip() {
+        let payload = b"snapshot payload ".repeat(1_000);
+        for codec in [SnapshotCodec::None, SnapshotCodec::Gzip, SnapshotCodec::Zstd] {
+            let encoded = codec.encode(&payload).unwrap();
+            assert_eq!(codec.decode(&encoded).unwrap(), payload);
+            assert_eq!(SnapshotCodec::from_marker(codec.as_str()).unwrap(), codec);
+        }
+    }
+
+    #[test]
+    fn test_compressing_codecs_shrink_repetitive_payloads() {
+        let payload = b"snapshot payload ".repeat(1_000);
+        assert!(SnapshotCodec::Gzip.encode(&payload).unwrap().len() < payload.len());
+        assert!(SnapshotCodec::Zstd.encode(&payload).unwrap().len() < payload.len());
+    }
+
+    #[test]
+    fn test_unknown_marker_is_rejected() {
+        assert!(matches!(
+            SnapshotCodec::from_marker("lz4"),
+            Err(DynamoAggregateError::UnknownSnapshotCodec(marker)) if marker == "lz4"
+        ));
+    }
+}
@@ -1,7 +1,14 @@
+use crate::store::throttle::{is_throttled, Throttling};
 use ::serde::de::StdError;
 use aws_sdk_dynamodb::{
     error::SdkError,
-    operation::{query::QueryError, scan::ScanError, transact_write_items::TransactWriteItemsError},
+    operation::{
+        describe_table::DescribeTableError, query::QueryError, scan::ScanError,
+        transact_write_items::TransactWriteItemsError, update_item::UpdateItemError,
+    },
+};
+use aws_sdk_s3::operation::{
+    get_object::GetObjectError, list_objects_v2::ListObjectsV2Error, put_object::PutObjectError,
 };
 use tsuzuri::{error::AggregateError, persist::PersistenceError};
 
@@ -9,12 +16,44 @@ use tsuzuri::{error::AggregateError, persist::PersistenceError};
 pub enum DynamoAggregateError {
     #[error("optimistic lock error")]
     OptimisticLock,
-    #[error("Too many operations: {0}, DynamoDb supports only up to 25 operations per transactions")]
-    TransactionListTooLong(usize),
+    #[error("optimistic concurrency conflict on aggregate {aggregate_id}: expected sequence number {expected_seq}")]
+    OptimisticConcurrency { aggregate_id: String, expected_seq: usize },
+    #[error("aggregate {aggregate_id} already exists")]
+    AlreadyExists { aggregate_id: String },
+    #[error("transaction of {items} items exceeds the DynamoDB limit of {limit} items per transaction")]
+    TransactionTooLarge { items: usize, limit: usize },
     #[error("missing attribute: {0}")]
     MissingAttribute(String),
     #[error("builder error: {0}")]
     BuilderError(String),
+    #[error("unknown snapshot codec: {0}")]
+    UnknownSnapshotCodec(String),
+    #[error("item of {bytes} bytes exceeds the DynamoDB item size limit of {limit} bytes")]
+    ItemTooLarge { bytes: usize, limit: usize },
+    #[error("no snapshot of aggregate {aggregate_id} covers the events through sequence number {seq_nr}")]
+    SnapshotRequired { aggregate_id: String, seq_nr: usize },
+    #[error("table {table} configured as table_names.{field} does not exist")]
+    TableMissing { field: &'static str, table: String },
+    #[error("index {index} configured as table_names.{field} does not exist on table {table}")]
+    IndexMissing {
+        field: &'static str,
+        table: String,
+        index: String,
+    },
+    #[error("table {table} has key schema {found}, expected {expected}")]
+    TableSchemaMismatch {
+        table: String,
+        expected: String,
+        found: String,
+    },
+    #[error("index {index} has key schema {found}, expected {expected}")]
+    IndexSchemaMismatch {
+        index: String,
+        expected: String,
+        found: String,
+    },
+    #[error("request throttled by DynamoDB: {0}")]
+    Throttled(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error(transparent)]
     UnknownError(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
@@ -23,9 +62,19 @@ impl<T: std::error::Error> From<DynamoAggregateError> for AggregateError<T> {
     fn from(error: DynamoAggregateError) -> Self {
         match error {
             DynamoAggregateError::OptimisticLock => Self::AggregateConflict,
+            DynamoAggregateError::OptimisticConcurrency { .. } => Self::AggregateConflict,
+            DynamoAggregateError::AlreadyExists { aggregate_id } => Self::AlreadyExists { aggregate_id },
             // DynamoAggregateError::ConnectionError(err) => Self::DatabaseConnectionError(err),
             // DynamoAggregateError::DeserializationError(err) => Self::DeserializationError(err),
-            DynamoAggregateError::TransactionListTooLong(_) => Self::UnexpectedError(Box::new(error)),
+            DynamoAggregateError::TransactionTooLarge { .. } => Self::UnexpectedError(Box::new(error)),
+            DynamoAggregateError::UnknownSnapshotCodec(_) => Self::UnexpectedError(Box::new(error)),
+            DynamoAggregateError::ItemTooLarge { .. } => Self::UnexpectedError(Box::new(error)),
+            DynamoAggregateError::SnapshotRequired { .. } => Self::UnexpectedError(Box::new(error)),
+            DynamoAggregateError::TableMissing { .. }
+            | DynamoAggregateError::IndexMissing { .. }
+            | DynamoAggregateError::TableSchemaMismatch { .. }
+            | DynamoAggregateError::IndexSchemaMismatch { .. } => Self::UnexpectedError(Box::new(error)),
+            DynamoAggregateError::Throttled(_) => Self::DatabaseConnectionError(Box::new(error)),
             DynamoAggregateError::MissingAttribute(err) => {
                 Self::UnexpectedError(Box::new(DynamoAggregateError::MissingAttribute(err)))
             }
@@ -37,35 +86,92 @@ impl<T: std::error::Error> From<DynamoAggregateError> for AggregateError<T> {
     }
 }
 
+impl DynamoAggregateError {
+    /// Turns a failed write condition into an [`DynamoAggregateError::OptimisticConcurrency`] for the given aggregate.
+    pub fn into_concurrency_error(self, aggregate_id: &str, expected_seq: usize) -> Self {
+        match self {
+            Self::OptimisticLock => Self::OptimisticConcurrency {
+                aggregate_id: aggregate_id.to_string(),
+                expected_seq,
+            },
+            error => error,
+        }
+    }
+}
+
 impl From<serde_json::Error> for DynamoAggregateError {
     fn from(err: serde_json::Error) -> Self {
         Self::UnknownError(Box::new(err))
     }
 }
 
+impl From<std::io::Error> for DynamoAggregateError {
+    fn from(err: std::io::Error) -> Self {
+        Self::UnknownError(Box::new(err))
+    }
+}
+
 impl From<SdkError<TransactWriteItemsError>> for DynamoAggregateError {
     fn from(error: SdkError<TransactWriteItemsError>) -> Self {
-        if let SdkError::ServiceError(err) = &error {
-            if let TransactWriteItemsError::TransactionCanceledException(cancellation) = err.err() {
-                for reason in cancellation.cancellation_reasons() {
-                    if reason.code() == Some("ConditionalCheckFailed") {
-                        return Self::OptimisticLock;
-                    }
-                }
-            }
+        if failed_condition_index(&error).is_some() {
+            return Self::OptimisticLock;
         }
-        Self::UnknownError(Box::new(error))
+        throttled_or_unknown_error(error)
     }
 }
 
+/// Position in the transaction of the first item whose write condition failed, if any.
+pub(crate) fn failed_condition_index(error: &SdkError<TransactWriteItemsError>) -> Option<usize> {
+    let SdkError::ServiceError(err) = error else {
+        return None;
+    };
+    let TransactWriteItemsError::TransactionCanceledException(cancellation) = err.err() else {
+        return None;
+    };
+    cancellation
+        .cancellation_reasons()
+        .iter()
+        .position(|reason| reason.code() == Some("ConditionalCheckFailed"))
+}
+
 impl From<SdkError<QueryError>> for DynamoAggregateError {
     fn from(error: SdkError<QueryError>) -> Self {
+        throttled_or_unknown_error(error)
+    }
+}
+
+impl From<SdkError<UpdateItemError>> for DynamoAggregateError {
+    fn from(error: SdkError<UpdateItemError>) -> Self {
+        unknown_error(error)
+    }
+}
+
+impl From<SdkError<DescribeTableError>> for DynamoAggregateError {
+    fn from(error: SdkError<DescribeTableError>) -> Self {
         unknown_error(error)
     }
 }
 
 impl From<SdkError<ScanError>> for DynamoAggregateError {
     fn from(error: SdkError<ScanError>) -> Self {
+        throttled_or_unknown_error(error)
+    }
+}
+
+impl From<SdkError<PutObjectError>> for DynamoAggregateError {
+    fn from(error: SdkError<PutObjectError>) -> Self {
+        unknown_error(error)
+    }
+}
+
+impl From<SdkError<GetObjectError>> for DynamoAggregateError {
+    fn from(error: SdkError<GetObjectError>) -> Self {
+        unknown_error(error)
+    }
+}
+
+impl From<SdkError<ListObjectsV2Error>> for DynamoAggregateError {
+    fn from(error: SdkError<ListObjectsV2Error>) -> Self {
         unknown_error(error)
     }
 }
@@ -74,13 +180,38 @@ fn unknown_error<T: StdError + Send + Sync + 'static>(error: SdkError<T>) -> Dyn
     DynamoAggregateError::UnknownError(Box::new(error))
 }
 
+fn throttled_or_unknown_error<T: StdError + Throttling + Send + Sync + 'static>(
+    error: SdkError<T>,
+) -> DynamoAggregateError {
+    if is_throttled(&error) {
+        return DynamoAggregateError::Throttled(Box::new(error));
+    }
+    unknown_error(error)
+}
+
 impl From<DynamoAggregateError> for PersistenceError {
     fn from(error: DynamoAggregateError) -> Self {
         match error {
             DynamoAggregateError::OptimisticLock => Self::OptimisticLockError,
+            DynamoAggregateError::OptimisticConcurrency {
+                aggregate_id,
+                expected_seq,
+            } => Self::OptimisticConcurrency {
+                aggregate_id,
+                expected_seq,
+            },
+            DynamoAggregateError::AlreadyExists { aggregate_id } => Self::AlreadyExists { aggregate_id },
             // DynamoAggregateError::ConnectionError(err) => Self::ConnectionError(err),
             // DynamoAggregateError::DeserializationError(err) => Self::DeserializationError(err),
-            DynamoAggregateError::TransactionListTooLong(_) => Self::UnknownError(Box::new(error)),
+            DynamoAggregateError::TransactionTooLarge { .. } => Self::UnknownError(Box::new(error)),
+            DynamoAggregateError::UnknownSnapshotCodec(_) => Self::UnknownError(Box::new(error)),
+            DynamoAggregateError::ItemTooLarge { .. } => Self::UnknownError(Box::new(error)),
+            DynamoAggregateError::SnapshotRequired { .. } => Self::UnknownError(Box::new(error)),
+            DynamoAggregateError::TableMissing { .. }
+            | DynamoAggregateError::IndexMissing { .. }
+            | DynamoAggregateError::TableSchemaMismatch { .. }
+            | DynamoAggregateError::IndexSchemaMismatch { .. } => Self::ConnectionError(Box::new(error)),
+            DynamoAggregateError::Throttled(_) => Self::ConnectionError(Box::new(error)),
             DynamoAggregateError::MissingAttribute(err) => {
                 Self::UnknownError(Box::new(DynamoAggregateError::MissingAttribute(err)))
             }
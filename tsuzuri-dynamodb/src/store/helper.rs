@@ -1,11 +1,18 @@
-use crate::store::error::DynamoAggregateError;
+use crate::store::{
+    error::{failed_condition_index, DynamoAggregateError},
+    throttle::ThrottleRetry,
+};
 use aws_sdk_dynamodb::{
     types::{AttributeValue, TransactWriteItem},
     Client,
 };
+use prost_types::Timestamp;
 use serde_json::Value;
 use std::collections::HashMap;
-use tsuzuri::domain_event::SerializedDomainEvent;
+use tsuzuri::{
+    domain_event::{SerializedDomainEvent, DEFAULT_SCHEMA_VERSION},
+    helper::from_rfc3339,
+};
 
 pub fn att_as_vec(
     values: &HashMap<String, AttributeValue>,
@@ -42,6 +49,16 @@ pub fn att_as_number(
     }
 }
 
+pub fn att_as_u32(values: &HashMap<String, AttributeValue>, attribute_name: &str) -> Result<u32, DynamoAggregateError> {
+    let attribute = require_attribute(values, attribute_name)?;
+    match attribute.as_n() {
+        Ok(attribute_as_n) => attribute_as_n
+            .parse::<u32>()
+            .map_err(|_| DynamoAggregateError::MissingAttribute(attribute_name.to_string())),
+        Err(_) => Err(DynamoAggregateError::MissingAttribute(attribute_name.to_string())),
+    }
+}
+
 pub fn att_as_string(
     values: &HashMap<String, AttributeValue>,
     attribute_name: &str,
@@ -53,6 +70,15 @@ pub fn att_as_string(
     }
 }
 
+/// Reads an RFC 3339 string attribute as a timestamp.
+pub fn att_as_timestamp(
+    values: &HashMap<String, AttributeValue>,
+    attribute_name: &str,
+) -> Result<Timestamp, DynamoAggregateError> {
+    let attribute = att_as_string(values, attribute_name)?;
+    from_rfc3339(&attribute).map_err(|_| DynamoAggregateError::MissingAttribute(attribute_name.to_string()))
+}
+
 pub fn require_attribute<'a>(
     values: &'a HashMap<String, AttributeValue>,
     attribute_name: &str,
@@ -70,6 +96,16 @@ pub fn serialized_event(entry: HashMap<String, AttributeValue>) -> Result<Serial
     let event_type = att_as_string(&entry, "event_type")?;
     let payload = att_as_vec(&entry, "payload")?;
     let metadata = att_as_value(&entry, "metadata")?;
+    // Rows written before schema versions were recorded have no attribute.
+    let schema_version = match entry.get("schema_version") {
+        Some(_) => att_as_u32(&entry, "schema_version")?,
+        None => DEFAULT_SCHEMA_VERSION,
+    };
+    // Likewise for rows written before occurred-at timestamps were recorded.
+    let occurred_at = match entry.get("occurred_at") {
+        Some(_) => att_as_timestamp(&entry, "occurred_at")?,
+        None => Timestamp::default(),
+    };
 
     Ok(SerializedDomainEvent {
         id,
@@ -79,21 +115,77 @@ pub fn serialized_event(entry: HashMap<String, AttributeValue>) -> Result<Serial
         event_type,
         payload,
         metadata,
+        schema_version,
+        occurred_at,
     })
 }
 
+/// Maximum size of a single DynamoDB item, attribute names included.
+pub const MAX_ITEM_SIZE_BYTES: usize = 400 * 1024;
+
+/// Approximates the stored size of `item` the way DynamoDB counts it: attribute names plus values.
+///
+/// Numbers are counted by their textual length, which never underestimates their stored size.
+pub fn item_size(item: &HashMap<String, AttributeValue>) -> usize {
+    item.iter()
+        .map(|(name, value)| name.len() + attribute_size(value))
+        .sum()
+}
+
+fn attribute_size(value: &AttributeValue) -> usize {
+    match value {
+        AttributeValue::S(s) | AttributeValue::N(s) => s.len(),
+        AttributeValue::B(blob) => blob.as_ref().len(),
+        AttributeValue::Bool(_) | AttributeValue::Null(_) => 1,
+        AttributeValue::Ss(values) | AttributeValue::Ns(values) => values.iter().map(String::len).sum(),
+        AttributeValue::Bs(values) => values.iter().map(|blob| blob.as_ref().len()).sum(),
+        AttributeValue::L(values) => 3 + values.iter().map(|value| 1 + attribute_size(value)).sum::<usize>(),
+        AttributeValue::M(values) => 3 + item_size(values) + values.len(),
+        _ => 0,
+    }
+}
+
+/// Maximum number of items DynamoDB accepts in a single `TransactWriteItems` call.
+pub const MAX_TRANSACTION_ITEMS: usize = 100;
+
+/// Commits the items in one transaction, retrying it according to `retry` while it is throttled.
 pub async fn commit_transactions(
     client: &Client,
+    retry: ThrottleRetry,
     transactions: Vec<TransactWriteItem>,
 ) -> Result<(), DynamoAggregateError> {
+    commit_transactions_locating_conflict(client, retry, transactions)
+        .await
+        .map_err(|(error, _)| error)
+}
+
+/// Same as [`commit_transactions`], but a failed write condition also reports the position of the failing item.
+pub async fn commit_transactions_locating_conflict(
+    client: &Client,
+    retry: ThrottleRetry,
+    transactions: Vec<TransactWriteItem>,
+) -> Result<(), (DynamoAggregateError, Option<usize>)> {
     let transaction_len = transactions.len();
-    if transaction_len > 25 {
-        return Err(DynamoAggregateError::TransactionListTooLong(transaction_len));
+    if transaction_len > MAX_TRANSACTION_ITEMS {
+        return Err((
+            DynamoAggregateError::TransactionTooLarge {
+                items: transaction_len,
+                limit: MAX_TRANSACTION_ITEMS,
+            },
+            None,
+        ));
     }
-    client
-        .transact_write_items()
-        .set_transact_items(Some(transactions))
-        .send()
-        .await?;
+    retry
+        .send(|| {
+            client
+                .transact_write_items()
+                .set_transact_items(Some(transactions.clone()))
+                .send()
+        })
+        .await
+        .map_err(|error| {
+            let index = failed_condition_index(&error);
+            (DynamoAggregateError::from(error), index)
+        })?;
     Ok(())
 }
@@ -1,22 +1,130 @@
+//! Partition and sort keys of the items the store writes.
+//!
+//! The strings these functions produce are what existing tables are keyed by, so their format is stable: backfill
+//! and migration scripts, or external tooling querying the tables directly, can rely on them. They are re-exported
+//! from the crate root.
+
 use std::collections::hash_map::DefaultHasher;
+use std::fmt;
 use std::hash::{Hash, Hasher};
 use tsuzuri::sequence_number::SequenceNumber;
 
+/// Assigns aggregates to the shards their partition keys end with.
+///
+/// Every item of an aggregate is read and written under the shard this returns, so a strategy must be
+/// deterministic and must not change while data written with it is still read, apart from the shard count changes
+/// that `legacy_shard_counts` covers.
+pub trait ShardStrategy: fmt::Debug + Send + Sync + 'static {
+    /// Index of the shard, in `0..shard_count`, that the aggregate `id` is assigned to.
+    fn shard(&self, id: &str, shard_count: usize) -> usize;
+}
+
+/// Hashes the aggregate ID and takes it modulo the shard count; the default strategy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuloHash;
+
+impl ShardStrategy for ModuloHash {
+    fn shard(&self, id: &str, shard_count: usize) -> usize {
+        resolve_shard(id, shard_count)
+    }
+}
+
+/// Partition key of the aggregate `id` of type `name` under the default [`ModuloHash`] strategy, formatted as
+/// `"{name}-{shard}"`.
+///
+/// # Examples
+///
+/// ```
+/// use tsuzuri_dynamodb::{resolve_partition_key, resolve_shard};
+///
+/// let pkey = resolve_partition_key("agg-1".to_string(), "Order".to_string(), 4);
+/// assert_eq!(pkey, format!("Order-{}", resolve_shard("agg-1", 4)));
+/// ```
 pub fn resolve_partition_key(id: String, name: String, shard_count: usize) -> String {
+    resolve_partition_key_with(&ModuloHash, id, name, shard_count)
+}
+
+/// Partition key of the aggregate `id` of type `name` on the shard `strategy` assigns it to, formatted as
+/// `"{name}-{shard}"`.
+pub fn resolve_partition_key_with(
+    strategy: &dyn ShardStrategy,
+    id: String,
+    name: String,
+    shard_count: usize,
+) -> String {
+    let remainder = strategy.shard(&id, shard_count);
+    format!("{name}-{remainder}")
+}
+
+/// Index of the shard, in `0..shard_count`, that [`ModuloHash`] assigns the aggregate `id` to.
+///
+/// The ID is hashed with the standard library's `DefaultHasher` created through `DefaultHasher::new`, whose output
+/// the tests of this module pin; a toolchain that changed it would move aggregates to other shards.
+///
+/// # Examples
+///
+/// ```
+/// use tsuzuri_dynamodb::resolve_shard;
+///
+/// assert!(resolve_shard("agg-1", 8) < 8);
+/// assert_eq!(resolve_shard("agg-1", 1), 0);
+/// ```
+pub fn resolve_shard(id: &str, shard_count: usize) -> usize {
     let mut hasher = DefaultHasher::new();
     id.hash(&mut hasher);
     let hash_value = hasher.finish();
-    let remainder = hash_value % shard_count as u64;
-    format!("{name}-{remainder}")
+    (hash_value % shard_count as u64) as usize
+}
+
+/// Prefixes `key` with `"{tenant}#"` when a tenant scope is configured, so tenants sharing a table never share a
+/// partition.
+pub fn resolve_tenant_scoped_key(tenant_scope: Option<&str>, key: String) -> String {
+    match tenant_scope {
+        Some(tenant) => format!("{tenant}#{key}"),
+        None => key,
+    }
 }
 
+/// Sort key of the item at `seq_nr` of the aggregate `id` of type `name`, formatted as `"{name}-{id}-{seq_nr}"`.
+///
+/// # Examples
+///
+/// ```
+/// use tsuzuri_dynamodb::resolve_sort_key;
+///
+/// assert_eq!(resolve_sort_key("Order".to_string(), "agg-1".to_string(), 3), "Order-agg-1-3");
+/// ```
 pub fn resolve_sort_key(name: String, id: String, seq_nr: SequenceNumber) -> String {
-    format!("{name}-{id}-{seq_nr}")
+    format!("{}{seq_nr}", resolve_sort_key_prefix(name, id))
+}
+
+/// Prefix shared by the sort keys of every item of one aggregate.
+pub fn resolve_sort_key_prefix(name: String, id: String) -> String {
+    format!("{name}-{id}-")
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{resolve_partition_key, resolve_sort_key};
+    use super::{
+        resolve_partition_key, resolve_partition_key_with, resolve_shard, resolve_sort_key, resolve_sort_key_prefix,
+        resolve_tenant_scoped_key, ShardStrategy,
+    };
+
+    /// Pins `hot` to the last shard and spreads every other aggregate over the remaining ones.
+    #[derive(Debug)]
+    struct PinnedShard {
+        hot: &'static str,
+    }
+
+    impl ShardStrategy for PinnedShard {
+        fn shard(&self, id: &str, shard_count: usize) -> usize {
+            if id == self.hot {
+                shard_count - 1
+            } else {
+                resolve_shard(id, shard_count - 1)
+            }
+        }
+    }
 
     #[test]
     fn test_partition_key() {
@@ -25,10 +133,95 @@ mod tests {
         assert_eq!(partition_key, "TestAggregate-0");
     }
 
+    /// Pins the shards of existing data; a failure here means aggregates would be looked up on other shards.
+    #[test]
+    fn test_shard_is_stable() {
+        for (id, shards) in [
+            ("test", [0, 4, 12]),
+            ("agg-1", [2, 2, 10]),
+            ("agg-2", [1, 5, 13]),
+            ("agg-3", [3, 3, 3]),
+            ("order-42", [1, 5, 13]),
+        ] {
+            assert_eq!(
+                [resolve_shard(id, 4), resolve_shard(id, 8), resolve_shard(id, 16)],
+                shards,
+                "{id}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_key_formats_are_stable() {
+        assert_eq!(
+            resolve_partition_key("agg-2".to_string(), "Order".to_string(), 8),
+            "Order-5"
+        );
+        assert_eq!(
+            resolve_sort_key("Order".to_string(), "agg-2".to_string(), 12),
+            "Order-agg-2-12"
+        );
+        assert_eq!(
+            resolve_sort_key_prefix("Order".to_string(), "agg-2".to_string()),
+            "Order-agg-2-"
+        );
+        assert_eq!(
+            resolve_tenant_scoped_key(
+                Some("tenant-a"),
+                resolve_partition_key("agg-2".to_string(), "Order".to_string(), 8)
+            ),
+            "tenant-a#Order-5"
+        );
+    }
+
+    #[test]
+    fn test_partition_key_ends_with_shard() {
+        for id in ["test", "agg-1", "agg-2", "agg-3"] {
+            let shard = resolve_shard(id, 8);
+            assert!(shard < 8);
+            assert_eq!(
+                resolve_partition_key(id.to_string(), "TestAggregate".to_string(), 8),
+                format!("TestAggregate-{shard}")
+            );
+        }
+    }
+
+    #[test]
+    fn test_partition_key_with_custom_strategy() {
+        let strategy = PinnedShard { hot: "agg-hot" };
+
+        assert_eq!(
+            resolve_partition_key_with(&strategy, "agg-hot".to_string(), "TestAggregate".to_string(), 8),
+            "TestAggregate-7"
+        );
+        for id in ["test", "agg-1", "agg-2", "agg-3"] {
+            assert!(strategy.shard(id, 8) < 7);
+        }
+    }
+
     #[test]
     fn test_sort_key() {
         let seq_nr = 1;
         let sort_key = resolve_sort_key("TestAggregate".to_string(), "test".to_string(), seq_nr);
         assert_eq!(sort_key, "TestAggregate-test-1");
     }
+
+    #[test]
+    fn test_sort_key_prefix() {
+        let prefix = resolve_sort_key_prefix("TestAggregate".to_string(), "test".to_string());
+        assert_eq!(prefix, "TestAggregate-test-");
+        assert!(resolve_sort_key("TestAggregate".to_string(), "test".to_string(), 10).starts_with(&prefix));
+    }
+
+    #[test]
+    fn test_tenant_scoped_key() {
+        assert_eq!(
+            resolve_tenant_scoped_key(Some("tenant-a"), "TestAggregate-0".to_string()),
+            "tenant-a#TestAggregate-0"
+        );
+        assert_eq!(
+            resolve_tenant_scoped_key(None, "TestAggregate-0".to_string()),
+            "TestAggregate-0"
+        );
+    }
 }
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+/// Hooks invoked by [`DynamoDB`](crate::store::DynamoDB) around its DynamoDB calls.
+///
+/// Implement this to bridge the store to a metrics library such as `metrics` or `prometheus`.
+pub trait Metrics: Send + Sync + 'static {
+    /// Called after every `persist`, whether or not the write succeeded.
+    fn record_persist_latency(&self, latency: Duration);
+
+    /// Called with the number of items returned by each query page.
+    fn record_query_items(&self, items: usize);
+
+    /// Called when a write is rejected because another writer appended first.
+    fn incr_conflict(&self);
+}
+
+/// Metrics implementation that discards every measurement.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {
+    fn record_persist_latency(&self, _latency: Duration) {}
+
+    fn record_query_items(&self, _items: usize) {}
+
+    fn incr_conflict(&self) {}
+}
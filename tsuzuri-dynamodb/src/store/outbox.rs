@@ -0,0 +1,294 @@
+use crate::store::{
+    error::DynamoAggregateError,
+    helper::{att_as_number, att_as_string, att_as_value, att_as_vec},
+    query::KeyConditionBuilder,
+    DynamoDB,
+};
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Delivery state of an outbox record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutboxStatus {
+    Pending,
+    Dispatched,
+    /// Delivery failed `dead_letter_after` times; the record is no longer polled.
+    Dead,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "PENDING",
+            Self::Dispatched => "DISPATCHED",
+            Self::Dead => "DEAD",
+        }
+    }
+
+    /// Status a record should have once it has failed `attempts` times.
+    fn after_failures(attempts: usize, dead_letter_after: usize) -> Self {
+        if attempts >= dead_letter_after {
+            Self::Dead
+        } else {
+            Self::Pending
+        }
+    }
+}
+
+impl fmt::Display for OutboxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OutboxStatus {
+    type Err = DynamoAggregateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PENDING" => Ok(Self::Pending),
+            "DISPATCHED" => Ok(Self::Dispatched),
+            "DEAD" => Ok(Self::Dead),
+            other => Err(DynamoAggregateError::MissingAttribute(format!(
+                "status: unknown outbox status {other}"
+            ))),
+        }
+    }
+}
+
+/// How integration events are written to the outbox relative to the journal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OutboxMode {
+    /// Outbox records are written in the same transaction as the domain events, so either both are stored or
+    /// neither is.
+    #[default]
+    Transactional,
+    /// The journal is written transactionally and the outbox records separately afterwards. A failed outbox
+    /// write, e.g. a throttled outbox table, is logged and does not fail the persist, so those integration events
+    /// are lost; the journal stays the source of truth to rebuild them from.
+    BestEffort,
+}
+
+/// Integration event read back from the outbox table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxRecord {
+    pub pkey: String,
+    pub skey: String,
+    pub aggregate_id: String,
+    pub aggregate_type: String,
+    pub event_type: String,
+    pub payload: Vec<u8>,
+    /// Metadata of the originating domain event; `Null` for records written without any.
+    pub metadata: Value,
+    pub status: OutboxStatus,
+    pub attempts: usize,
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for OutboxRecord {
+    type Error = DynamoAggregateError;
+
+    fn try_from(item: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pkey: att_as_string(&item, "pkey")?,
+            skey: att_as_string(&item, "skey")?,
+            aggregate_id: att_as_string(&item, "aid")?,
+            aggregate_type: att_as_string(&item, "aggregate_type")?,
+            event_type: att_as_string(&item, "event_type")?,
+            payload: att_as_vec(&item, "payload")?,
+            metadata: if item.contains_key("metadata") {
+                att_as_value(&item, "metadata")?
+            } else {
+                Value::Null
+            },
+            status: att_as_string(&item, "status")?.parse()?,
+            attempts: att_as_number(&item, "attempts")?,
+        })
+    }
+}
+
+impl DynamoDB {
+    /// Returns up to `limit` pending outbox records, oldest first.
+    ///
+    /// Records stay `PENDING` until they are passed to [`DynamoDB::mark_dispatched`].
+    pub async fn poll_outbox(&self, limit: usize) -> Result<Vec<OutboxRecord>, DynamoAggregateError> {
+        self.query_outbox_by_status(OutboxStatus::Pending, limit).await
+    }
+
+    /// Marks an outbox record as delivered so it is no longer returned by [`DynamoDB::poll_outbox`].
+    ///
+    /// With `outbox_ttl` configured the record also gets its `expires_at`.
+    pub async fn mark_dispatched(&self, pkey: &str, skey: &str) -> Result<(), DynamoAggregateError> {
+        let mut update = self
+            .client
+            .update_item()
+            .table_name(&self.config.table_names.outbox)
+            .key("pkey", AttributeValue::S(pkey.to_string()))
+            .key("skey", AttributeValue::S(skey.to_string()))
+            .update_expression("SET #status = :status")
+            .condition_expression("attribute_exists(pkey)")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":status", AttributeValue::S(OutboxStatus::Dispatched.to_string()));
+        if let Some(ttl) = self.config.outbox_ttl {
+            update = update
+                .update_expression("SET #status = :status, #expires_at = :expires_at")
+                .expression_attribute_names("#expires_at", "expires_at")
+                .expression_attribute_values(
+                    ":expires_at",
+                    AttributeValue::N(outbox_expires_at(SystemTime::now(), ttl).to_string()),
+                );
+        }
+        update.send().await?;
+        Ok(())
+    }
+
+    /// Records a failed delivery attempt and returns the resulting status.
+    ///
+    /// The record stays `PENDING` until it has failed `dead_letter_after` times, at which point it is moved to
+    /// `DEAD` and only returned by [`DynamoDB::poll_dead_letters`].
+    pub async fn mark_failed(&self, pkey: &str, skey: &str) -> Result<OutboxStatus, DynamoAggregateError> {
+        let response = self
+            .client
+            .update_item()
+            .table_name(&self.config.table_names.outbox)
+            .key("pkey", AttributeValue::S(pkey.to_string()))
+            .key("skey", AttributeValue::S(skey.to_string()))
+            .update_expression("SET #attempts = #attempts + :one")
+            .condition_expression("attribute_exists(pkey)")
+            .expression_attribute_names("#attempts", "attempts")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await?;
+        let attributes = response.attributes.unwrap_or_default();
+        let attempts = att_as_number(&attributes, "attempts")?;
+
+        let status = OutboxStatus::after_failures(attempts, self.config.dead_letter_after);
+        if status == OutboxStatus::Dead {
+            self.client
+                .update_item()
+                .table_name(&self.config.table_names.outbox)
+                .key("pkey", AttributeValue::S(pkey.to_string()))
+                .key("skey", AttributeValue::S(skey.to_string()))
+                .update_expression("SET #status = :dead")
+                .condition_expression("#status = :pending")
+                .expression_attribute_names("#status", "status")
+                .expression_attribute_values(":dead", AttributeValue::S(OutboxStatus::Dead.to_string()))
+                .expression_attribute_values(":pending", AttributeValue::S(OutboxStatus::Pending.to_string()))
+                .send()
+                .await?;
+        }
+        Ok(status)
+    }
+
+    /// Returns up to `limit` records that exceeded the dead-letter threshold, oldest first.
+    pub async fn poll_dead_letters(&self, limit: usize) -> Result<Vec<OutboxRecord>, DynamoAggregateError> {
+        self.query_outbox_by_status(OutboxStatus::Dead, limit).await
+    }
+
+    async fn query_outbox_by_status(
+        &self,
+        status: OutboxStatus,
+        limit: usize,
+    ) -> Result<Vec<OutboxRecord>, DynamoAggregateError> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let query = KeyConditionBuilder::new(&self.config.table_names.outbox)
+            .index(&self.config.table_names.outbox_status_index)
+            .key_eq("status", AttributeValue::S(status.to_string()))
+            .scan_index_forward(true)
+            .limit(limit)
+            .build(&self.client);
+        let response = self.config.throttle_retry.send(|| query.clone().send()).await?;
+        response
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(OutboxRecord::try_from)
+            .collect()
+    }
+}
+
+/// Epoch seconds at which a record dispatched at `dispatched_at` expires.
+fn outbox_expires_at(dispatched_at: SystemTime, ttl: Duration) -> u64 {
+    (dispatched_at + ttl)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::primitives::Blob;
+
+    #[test]
+    fn test_outbox_status_round_trip() {
+        for status in [OutboxStatus::Pending, OutboxStatus::Dispatched, OutboxStatus::Dead] {
+            assert_eq!(status.as_str().parse::<OutboxStatus>().unwrap(), status);
+        }
+        assert!("UNKNOWN".parse::<OutboxStatus>().is_err());
+    }
+
+    #[test]
+    fn test_outbox_status_after_failures_boundary() {
+        assert_eq!(OutboxStatus::after_failures(4, 5), OutboxStatus::Pending);
+        assert_eq!(OutboxStatus::after_failures(5, 5), OutboxStatus::Dead);
+        assert_eq!(OutboxStatus::after_failures(6, 5), OutboxStatus::Dead);
+        assert_eq!(OutboxStatus::after_failures(1, 1), OutboxStatus::Dead);
+    }
+
+    #[test]
+    fn test_outbox_record_from_item() {
+        let item = HashMap::from([
+            ("pkey".to_string(), AttributeValue::S("TestAggregate-1".to_string())),
+            ("skey".to_string(), AttributeValue::S("evt-1".to_string())),
+            ("aid".to_string(), AttributeValue::S("test-1".to_string())),
+            (
+                "aggregate_type".to_string(),
+                AttributeValue::S("TestAggregate".to_string()),
+            ),
+            ("event_type".to_string(), AttributeValue::S("TestEvent".to_string())),
+            ("payload".to_string(), AttributeValue::B(Blob::new(vec![1, 2, 3]))),
+            ("status".to_string(), AttributeValue::S("PENDING".to_string())),
+            ("attempts".to_string(), AttributeValue::N("2".to_string())),
+        ]);
+
+        let record = OutboxRecord::try_from(item).unwrap();
+
+        assert_eq!(record.pkey, "TestAggregate-1");
+        assert_eq!(record.skey, "evt-1");
+        assert_eq!(record.aggregate_id, "test-1");
+        assert_eq!(record.payload, vec![1, 2, 3]);
+        assert_eq!(record.metadata, Value::Null);
+        assert_eq!(record.status, OutboxStatus::Pending);
+        assert_eq!(record.attempts, 2);
+    }
+
+    #[test]
+    fn test_outbox_expires_at_adds_ttl_to_dispatch_time() {
+        let dispatched_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_900);
+
+        assert_eq!(
+            outbox_expires_at(dispatched_at, Duration::from_secs(86_400)),
+            1_700_086_400
+        );
+        assert_eq!(outbox_expires_at(dispatched_at, Duration::ZERO), 1_700_000_000);
+    }
+
+    #[test]
+    fn test_outbox_record_missing_attribute() {
+        let item = HashMap::from([("pkey".to_string(), AttributeValue::S("TestAggregate-1".to_string()))]);
+
+        assert!(matches!(
+            OutboxRecord::try_from(item),
+            Err(DynamoAggregateError::MissingAttribute(_))
+        ));
+    }
+}
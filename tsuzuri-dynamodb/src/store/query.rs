@@ -0,0 +1,249 @@
+use crate::store::key::resolve_tenant_scoped_key;
+use aws_sdk_dynamodb::{operation::query::builders::QueryFluentBuilder, types::AttributeValue, Client};
+use std::collections::HashMap;
+
+/// Builds the key condition, filter and paging options of a query.
+///
+/// Every condition names its attribute once; the `#attribute` name placeholder and a `:vN` value placeholder are
+/// derived from it, so an expression can never reference a placeholder that was not bound.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyConditionBuilder {
+    table: String,
+    index: Option<String>,
+    consistent_read: Option<bool>,
+    scan_index_forward: Option<bool>,
+    limit: Option<i32>,
+    key_conditions: Vec<String>,
+    filters: Vec<String>,
+    names: HashMap<String, String>,
+    values: HashMap<String, AttributeValue>,
+}
+
+impl KeyConditionBuilder {
+    pub(crate) fn new(table: &str) -> Self {
+        Self {
+            table: table.to_string(),
+            index: None,
+            consistent_read: None,
+            scan_index_forward: None,
+            limit: None,
+            key_conditions: Vec::new(),
+            filters: Vec::new(),
+            names: HashMap::new(),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Queries the global secondary index `index` of the table instead of the table itself.
+    pub(crate) fn index(mut self, index: &str) -> Self {
+        self.index = Some(index.to_string());
+        self
+    }
+
+    pub(crate) fn consistent_read(mut self, enabled: bool) -> Self {
+        self.consistent_read = Some(enabled);
+        self
+    }
+
+    pub(crate) fn scan_index_forward(mut self, forward: bool) -> Self {
+        self.scan_index_forward = Some(forward);
+        self
+    }
+
+    /// Caps the number of items read per page.
+    pub(crate) fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(i32::try_from(limit).unwrap_or(i32::MAX));
+        self
+    }
+
+    pub(crate) fn key_eq(mut self, attribute: &str, value: AttributeValue) -> Self {
+        let condition = self.condition(attribute, value, |name, value| format!("{name} = {value}"));
+        self.key_conditions.push(condition);
+        self
+    }
+
+    pub(crate) fn key_ge(mut self, attribute: &str, value: AttributeValue) -> Self {
+        let condition = self.condition(attribute, value, |name, value| format!("{name} >= {value}"));
+        self.key_conditions.push(condition);
+        self
+    }
+
+    pub(crate) fn key_le(mut self, attribute: &str, value: AttributeValue) -> Self {
+        let condition = self.condition(attribute, value, |name, value| format!("{name} <= {value}"));
+        self.key_conditions.push(condition);
+        self
+    }
+
+    pub(crate) fn key_begins_with(mut self, attribute: &str, value: AttributeValue) -> Self {
+        let condition = self.condition(attribute, value, |name, value| format!("begins_with({name}, {value})"));
+        self.key_conditions.push(condition);
+        self
+    }
+
+    pub(crate) fn filter_ge(mut self, attribute: &str, value: AttributeValue) -> Self {
+        let condition = self.condition(attribute, value, |name, value| format!("{name} >= {value}"));
+        self.filters.push(condition);
+        self
+    }
+
+    /// Keeps only the items of `tenant_scope`, for reads through an aggregate ID index whose key is not tenant
+    /// scoped. Does nothing without a tenant scope.
+    pub(crate) fn tenant(mut self, tenant_scope: Option<&str>) -> Self {
+        if let Some(tenant) = tenant_scope {
+            let prefix = AttributeValue::S(resolve_tenant_scoped_key(Some(tenant), String::new()));
+            let condition = self.condition("pkey", prefix, |name, value| format!("begins_with({name}, {value})"));
+            self.filters.push(condition);
+        }
+        self
+    }
+
+    /// Query with the conditions and options set so far.
+    pub(crate) fn build(self, client: &Client) -> QueryFluentBuilder {
+        let mut query = client
+            .query()
+            .table_name(self.table)
+            .set_index_name(self.index)
+            .set_consistent_read(self.consistent_read)
+            .set_scan_index_forward(self.scan_index_forward)
+            .set_limit(self.limit)
+            .key_condition_expression(self.key_conditions.join(" AND "))
+            .set_expression_attribute_names(Some(self.names))
+            .set_expression_attribute_values(Some(self.values));
+        if !self.filters.is_empty() {
+            query = query.filter_expression(self.filters.join(" AND "));
+        }
+        query
+    }
+
+    /// Binds `attribute` and `value` to their placeholders and renders the condition `format` makes of them.
+    fn condition(
+        &mut self,
+        attribute: &str,
+        value: AttributeValue,
+        format: impl FnOnce(&str, &str) -> String,
+    ) -> String {
+        let name = format!("#{attribute}");
+        let placeholder = format!(":v{}", self.values.len());
+        self.names.insert(name.clone(), attribute.to_string());
+        self.values.insert(placeholder.clone(), value);
+        format(&name, &placeholder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyConditionBuilder;
+    use aws_sdk_dynamodb::{types::AttributeValue, Client};
+    use std::collections::HashMap;
+
+    fn create_mock_client() -> Client {
+        let config = aws_sdk_dynamodb::Config::builder()
+            .behavior_version(aws_sdk_dynamodb::config::BehaviorVersion::latest())
+            .region(aws_sdk_dynamodb::config::Region::new("us-east-1"))
+            .build();
+        Client::from_conf(config)
+    }
+
+    #[test]
+    fn test_partition_and_sort_key_conditions() {
+        let query = KeyConditionBuilder::new("journal")
+            .consistent_read(true)
+            .key_eq("pkey", AttributeValue::S("TestAggregate-0".to_string()))
+            .key_ge("skey", AttributeValue::S("TestAggregate-agg-1-3".to_string()))
+            .build(&create_mock_client());
+        let input = query.as_input();
+
+        assert_eq!(input.get_table_name().as_deref(), Some("journal"));
+        assert_eq!(input.get_index_name(), &None);
+        assert_eq!(input.get_consistent_read(), &Some(true));
+        assert_eq!(
+            input.get_key_condition_expression().as_deref(),
+            Some("#pkey = :v0 AND #skey >= :v1")
+        );
+        assert_eq!(input.get_filter_expression(), &None);
+        assert_eq!(
+            input.get_expression_attribute_names(),
+            &Some(HashMap::from([
+                ("#pkey".to_string(), "pkey".to_string()),
+                ("#skey".to_string(), "skey".to_string()),
+            ]))
+        );
+        assert_eq!(
+            input.get_expression_attribute_values(),
+            &Some(HashMap::from([
+                (":v0".to_string(), AttributeValue::S("TestAggregate-0".to_string())),
+                (
+                    ":v1".to_string(),
+                    AttributeValue::S("TestAggregate-agg-1-3".to_string())
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_index_query_with_tenant_filter() {
+        let query = KeyConditionBuilder::new("journal")
+            .index("journal-aid-index")
+            .key_eq("aid", AttributeValue::S("agg-1".to_string()))
+            .key_le("seq_nr", AttributeValue::N("7".to_string()))
+            .tenant(Some("tenant-a"))
+            .build(&create_mock_client());
+        let input = query.as_input();
+
+        assert_eq!(input.get_index_name().as_deref(), Some("journal-aid-index"));
+        assert_eq!(input.get_consistent_read(), &None);
+        assert_eq!(
+            input.get_key_condition_expression().as_deref(),
+            Some("#aid = :v0 AND #seq_nr <= :v1")
+        );
+        assert_eq!(
+            input.get_filter_expression().as_deref(),
+            Some("begins_with(#pkey, :v2)")
+        );
+        let names = input.get_expression_attribute_names().as_ref().unwrap();
+        assert_eq!(names.get("#pkey").map(String::as_str), Some("pkey"));
+        assert_eq!(names.get("#seq_nr").map(String::as_str), Some("seq_nr"));
+        let values = input.get_expression_attribute_values().as_ref().unwrap();
+        assert_eq!(values.get(":v2"), Some(&AttributeValue::S("tenant-a#".to_string())));
+    }
+
+    #[test]
+    fn test_tenant_filter_without_scope() {
+        let query = KeyConditionBuilder::new("journal")
+            .key_eq("aid", AttributeValue::S("agg-1".to_string()))
+            .tenant(None)
+            .build(&create_mock_client());
+        let input = query.as_input();
+
+        assert_eq!(input.get_filter_expression(), &None);
+        assert!(!input
+            .get_expression_attribute_names()
+            .as_ref()
+            .unwrap()
+            .contains_key("#pkey"));
+    }
+
+    #[test]
+    fn test_begins_with_filter_and_paging_options() {
+        let query = KeyConditionBuilder::new("journal")
+            .key_eq("pkey", AttributeValue::S("TestAggregate-0".to_string()))
+            .key_begins_with("skey", AttributeValue::S("TestAggregate-agg-1-".to_string()))
+            .filter_ge("seq_nr", AttributeValue::N("3".to_string()))
+            .scan_index_forward(true)
+            .limit(25)
+            .build(&create_mock_client());
+        let input = query.as_input();
+
+        assert_eq!(
+            input.get_key_condition_expression().as_deref(),
+            Some("#pkey = :v0 AND begins_with(#skey, :v1)")
+        );
+        assert_eq!(input.get_filter_expression().as_deref(), Some("#seq_nr >= :v2"));
+        assert_eq!(input.get_scan_index_forward(), &Some(true));
+        assert_eq!(input.get_limit(), &Some(25));
+        assert_eq!(
+            input.get_expression_attribute_values().as_ref().unwrap().get(":v2"),
+            Some(&AttributeValue::N("3".to_string()))
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::store::{error::DynamoAggregateError, helper::att_as_string, DynamoDB};
+use aws_sdk_dynamodb::{operation::scan::ScanOutput, types::AttributeValue};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures::{stream, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Items read per `Scan` page of the `journal_aid_index`.
+const JOURNAL_SCAN_PAGE_SIZE: i32 = 500;
+
+/// Opaque position in a [`DynamoDB::scan_aggregate_ids`] scan, to resume it with
+/// [`DynamoDB::scan_aggregate_ids_from`], e.g. after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScanCursor(String);
+
+impl ScanCursor {
+    /// Restores a cursor from the string returned by [`ScanCursor::as_str`].
+    pub fn new(cursor: impl Into<String>) -> Self {
+        Self(cursor.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn encode(state: &CursorState) -> Result<Self, DynamoAggregateError> {
+        Ok(Self(URL_SAFE_NO_PAD.encode(serde_json::to_vec(state)?)))
+    }
+
+    fn decode(&self) -> Result<CursorState, DynamoAggregateError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(&self.0)
+            .map_err(|e| DynamoAggregateError::UnknownError(Box::new(e)))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// Aggregate IDs of one page of a scan, and the cursor of the next page unless the scan is complete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateIdPage {
+    pub aggregate_ids: Vec<String>,
+    pub cursor: Option<ScanCursor>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorState {
+    /// `LastEvaluatedKey` of the page the cursor follows.
+    key: BTreeMap<String, CursorKeyValue>,
+    /// Last aggregate ID yielded, so an aggregate whose items span two pages is yielded once.
+    last_aggregate_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum CursorKeyValue {
+    S(String),
+    N(String),
+}
+
+impl DynamoDB {
+    /// Streams the ID of every aggregate of `aggregate_type` in the journal, each exactly once.
+    ///
+    /// Meant for bootstrapping read models: feed the IDs to `load_aggregate`. This scans the whole
+    /// `journal_aid_index` page by page, reading only the aggregate ID of each item, and retries throttled pages
+    /// with an exponential backoff.
+    pub fn scan_aggregate_ids<'a>(
+        &'a self,
+        aggregate_type: &'a str,
+    ) -> impl Stream<Item = Result<String, DynamoAggregateError>> + 'a {
+        self.scan_aggregate_ids_from(aggregate_type, None)
+    }
+
+    /// Like [`DynamoDB::scan_aggregate_ids`], starting after the page `cursor` was returned with, or from the start
+    /// when `cursor` is `None`.
+    pub fn scan_aggregate_ids_from<'a>(
+        &'a self,
+        aggregate_type: &'a str,
+        cursor: Option<ScanCursor>,
+    ) -> impl Stream<Item = Result<String, DynamoAggregateError>> + 'a {
+        // `None` once the last page was read, `Some(None)` before the first page of a fresh scan.
+        stream::try_unfold(Some(cursor), move |next| async move {
+            let Some(cursor) = next else {
+                return Ok::<_, DynamoAggregateError>(None);
+            };
+            let page = self.scan_aggregate_ids_page(aggregate_type, cursor.as_ref()).await?;
+            let aggregate_ids = stream::iter(page.aggregate_ids.into_iter().map(Ok));
+            Ok(Some((aggregate_ids, page.cursor.map(Some))))
+        })
+        .try_flatten()
+    }
+
+    /// Reads one page of the aggregate IDs of `aggregate_type`, starting after `cursor`.
+    ///
+    /// A page may hold no IDs while the scan goes on; the scan is complete when the returned cursor is `None`.
+    pub async fn scan_aggregate_ids_page(
+        &self,
+        aggregate_type: &str,
+        cursor: Option<&ScanCursor>,
+    ) -> Result<AggregateIdPage, DynamoAggregateError> {
+        let (exclusive_start_key, mut last_aggregate_id) = match cursor {
+            Some(cursor) => {
+                let state = cursor.decode()?;
+                (Some(from_cursor_key(state.key)), state.last_aggregate_id)
+            }
+            None => (None, None),
+        };
+        let page = self
+            .scan_journal_aid_index_page(aggregate_type, exclusive_start_key)
+            .await?;
+        self.metrics.record_query_items(page.items().len());
+
+        // The items of an aggregate are contiguous in a scan of the index, which is keyed by the aggregate ID.
+        let mut aggregate_ids = Vec::new();
+        for item in page.items() {
+            let aggregate_id = att_as_string(item, "aid")?;
+            if last_aggregate_id.as_ref() != Some(&aggregate_id) {
+                aggregate_ids.push(aggregate_id.clone());
+                last_aggregate_id = Some(aggregate_id);
+            }
+        }
+        let cursor = match page.last_evaluated_key {
+            Some(key) if !key.is_empty() => Some(ScanCursor::encode(&CursorState {
+                key: to_cursor_key(key)?,
+                last_aggregate_id,
+            })?),
+            _ => None,
+        };
+        Ok(AggregateIdPage { aggregate_ids, cursor })
+    }
+
+    /// Scans one page of the `journal_aid_index` items of `aggregate_type`, reading their aggregate ID and
+    /// sequence number, and retries the page according to `throttle_retry` while it is throttled.
+    pub(crate) async fn scan_journal_aid_index_page(
+        &self,
+        aggregate_type: &str,
+        exclusive_start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<ScanOutput, DynamoAggregateError> {
+        let mut filter = "#type = :type".to_string();
+        let mut scan = self
+            .client
+            .scan()
+            .table_name(&self.config.table_names.journal)
+            .index_name(&self.config.table_names.journal_aid_index)
+            .projection_expression("#aid, #seq")
+            .expression_attribute_names("#aid", "aid")
+            .expression_attribute_names("#seq", "seq_nr")
+            .expression_attribute_names("#type", "aggregate_type")
+            .expression_attribute_values(":type", AttributeValue::S(aggregate_type.to_string()))
+            .set_exclusive_start_key(exclusive_start_key)
+            .limit(JOURNAL_SCAN_PAGE_SIZE);
+        if let Some((tenant_filter, tenant)) = self.tenant_filter() {
+            filter = format!("{filter} AND {tenant_filter}");
+            scan = scan
+                .expression_attribute_names("#pkey", "pkey")
+                .expression_attribute_values(":tenant", tenant);
+        }
+        let scan = scan.filter_expression(filter);
+
+        Ok(self.config.throttle_retry.send(|| scan.clone().send()).await?)
+    }
+}
+
+fn to_cursor_key(
+    key: HashMap<String, AttributeValue>,
+) -> Result<BTreeMap<String, CursorKeyValue>, DynamoAggregateError> {
+    key.into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                AttributeValue::S(value) => CursorKeyValue::S(value),
+                AttributeValue::N(value) => CursorKeyValue::N(value),
+                _ => return Err(DynamoAggregateError::MissingAttribute(format!("scan key {name}"))),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn from_cursor_key(key: BTreeMap<String, CursorKeyValue>) -> HashMap<String, AttributeValue> {
+    key.into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                CursorKeyValue::S(value) => AttributeValue::S(value),
+                CursorKeyValue::N(value) => AttributeValue::N(value),
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_cursor_round_trips_key_and_last_aggregate_id() {
+        let key = HashMap::from([
+            ("pkey".to_string(), AttributeValue::S("TestAggregate-1".to_string())),
+            (
+                "skey".to_string(),
+                AttributeValue::S("TestAggregate-test-1-00000000000000000003".to_string()),
+            ),
+            ("aid".to_string(), AttributeValue::S("test-1".to_string())),
+            ("seq_nr".to_string(), AttributeValue::N("3".to_string())),
+        ]);
+        let cursor = ScanCursor::encode(&CursorState {
+            key: to_cursor_key(key.clone()).unwrap(),
+            last_aggregate_id: Some("test-1".to_string()),
+        })
+        .unwrap();
+
+        let state = ScanCursor::new(cursor.as_str()).decode().unwrap();
+
+        assert_eq!(from_cursor_key(state.key), key);
+        assert_eq!(state.last_aggregate_id.as_deref(), Some("test-1"));
+    }
+
+    #[test]
+    fn test_scan_cursor_rejects_garbage() {
+        assert!(ScanCursor::new("not a cursor").decode().is_err());
+    }
+}
@@ -0,0 +1,50 @@
+use crate::store::{
+    error::DynamoAggregateError,
+    helper::{att_as_number, att_as_string},
+    DynamoDB,
+};
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::collections::{BTreeMap, HashMap};
+
+/// Journal size of a single aggregate, as reported by [`DynamoDB::journal_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateStat {
+    pub aggregate_id: String,
+    /// Journal items of the aggregate, tombstone included.
+    pub event_count: usize,
+    pub last_seq_nr: usize,
+}
+
+impl DynamoDB {
+    /// Reports the journal size of every aggregate of `aggregate_type`, ordered by aggregate ID.
+    ///
+    /// Meant for finding aggregates that would benefit from snapshotting or archival. This scans the whole
+    /// `journal_aid_index`, reading only the aggregate ID and sequence number of each item, one page at a time;
+    /// pages that are throttled are retried with an exponential backoff instead of failing the report.
+    pub async fn journal_stats(&self, aggregate_type: &str) -> Result<Vec<AggregateStat>, DynamoAggregateError> {
+        let mut stats: BTreeMap<String, AggregateStat> = BTreeMap::new();
+        let mut exclusive_start_key: Option<HashMap<String, AttributeValue>> = None;
+        loop {
+            let page = self
+                .scan_journal_aid_index_page(aggregate_type, exclusive_start_key.take())
+                .await?;
+            self.metrics.record_query_items(page.items().len());
+            for item in page.items() {
+                let aggregate_id = att_as_string(item, "aid")?;
+                let seq_nr = att_as_number(item, "seq_nr")?;
+                let stat = stats.entry(aggregate_id.clone()).or_insert(AggregateStat {
+                    aggregate_id,
+                    event_count: 0,
+                    last_seq_nr: 0,
+                });
+                stat.event_count += 1;
+                stat.last_seq_nr = stat.last_seq_nr.max(seq_nr);
+            }
+            match page.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+        Ok(stats.into_values().collect())
+    }
+}
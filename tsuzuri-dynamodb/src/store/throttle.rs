@@ -0,0 +1,208 @@
+use aws_sdk_dynamodb::{
+    error::SdkError,
+    operation::{query::QueryError, scan::ScanError, transact_write_items::TransactWriteItemsError},
+};
+use std::{future::Future, time::Duration};
+
+/// How requests DynamoDB throttles are retried.
+///
+/// A query, scan or transaction rejected with `ProvisionedThroughputExceededException` or `RequestLimitExceeded`,
+/// or a transaction cancelled for a `ThrottlingError`, is sent again after `base_delay`, doubling the delay on
+/// every further retry. Once `max_retries` retries are throttled as well, the error is returned as
+/// [`DynamoAggregateError::Throttled`](crate::store::error::DynamoAggregateError::Throttled). These retries come on
+/// top of the ones the SDK performs itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottleRetry {
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+impl ThrottleRetry {
+    pub fn new(max_retries: u32, base_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+        }
+    }
+
+    /// Returns throttling errors right away.
+    pub fn disabled() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    pub fn base_delay(&self) -> Duration {
+        self.base_delay
+    }
+
+    fn delay(&self, retry: u32) -> Duration {
+        self.base_delay.saturating_mul(2u32.saturating_pow(retry))
+    }
+
+    /// Sends the request `send` builds, and sends it again while it is throttled and retries are left.
+    pub(crate) async fn send<T, E, R, F, Fut>(&self, mut send: F) -> Result<T, SdkError<E, R>>
+    where
+        E: Throttling,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, SdkError<E, R>>>,
+    {
+        let mut retries = 0;
+        loop {
+            match send().await {
+                Err(error) if is_throttled(&error) && retries < self.max_retries => {
+                    tokio::time::sleep(self.delay(retries)).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for ThrottleRetry {
+    fn default() -> Self {
+        Self::new(5, Duration::from_millis(100))
+    }
+}
+
+/// Service errors that tell the request was throttled.
+pub(crate) trait Throttling {
+    fn is_throttling(&self) -> bool;
+}
+
+impl Throttling for QueryError {
+    fn is_throttling(&self) -> bool {
+        self.is_provisioned_throughput_exceeded_exception() || self.is_request_limit_exceeded()
+    }
+}
+
+impl Throttling for ScanError {
+    fn is_throttling(&self) -> bool {
+        self.is_provisioned_throughput_exceeded_exception() || self.is_request_limit_exceeded()
+    }
+}
+
+impl Throttling for TransactWriteItemsError {
+    /// A transaction cancelled for a failed condition is a conflict even if other items were throttled, since a
+    /// retry would fail the condition again.
+    fn is_throttling(&self) -> bool {
+        match self {
+            Self::TransactionCanceledException(cancellation) => {
+                let codes: Vec<_> = cancellation
+                    .cancellation_reasons()
+                    .iter()
+                    .filter_map(|reason| reason.code())
+                    .collect();
+                codes.contains(&"ThrottlingError") && !codes.contains(&"ConditionalCheckFailed")
+            }
+            _ => self.is_provisioned_throughput_exceeded_exception() || self.is_request_limit_exceeded(),
+        }
+    }
+}
+
+pub(crate) fn is_throttled<E: Throttling, R>(error: &SdkError<E, R>) -> bool {
+    error.as_service_error().is_some_and(Throttling::is_throttling)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::error::DynamoAggregateError;
+    use aws_sdk_dynamodb::{
+        config::http::HttpResponse,
+        operation::query::QueryOutput,
+        types::{error::ProvisionedThroughputExceededException, CancellationReason},
+    };
+    use aws_smithy_types::body::SdkBody;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Answers queries with a throttling error until `throttled_attempts` queries were sent.
+    struct ThrottlingClient {
+        throttled_attempts: u32,
+        attempts: AtomicU32,
+    }
+
+    impl ThrottlingClient {
+        fn new(throttled_attempts: u32) -> Self {
+            Self {
+                throttled_attempts,
+                attempts: AtomicU32::new(0),
+            }
+        }
+
+        async fn query(&self) -> Result<QueryOutput, SdkError<QueryError, HttpResponse>> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.throttled_attempts {
+                let error = ProvisionedThroughputExceededException::builder()
+                    .message("throughput exceeded")
+                    .build();
+                return Err(SdkError::service_error(
+                    QueryError::ProvisionedThroughputExceededException(error),
+                    HttpResponse::new(400.try_into().unwrap(), SdkBody::empty()),
+                ));
+            }
+            Ok(QueryOutput::builder().count(1).build())
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.load(Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_while_throttled() {
+        let client = ThrottlingClient::new(3);
+        let retry = ThrottleRetry::new(3, Duration::from_millis(1));
+
+        let output = retry.send(|| client.query()).await.unwrap();
+
+        assert_eq!(output.count(), 1);
+        assert_eq!(client.attempts(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_send_returns_throttling_once_retries_are_exhausted() {
+        let client = ThrottlingClient::new(3);
+        let retry = ThrottleRetry::new(2, Duration::from_millis(1));
+
+        let error = retry.send(|| client.query()).await.unwrap_err();
+
+        assert_eq!(client.attempts(), 3);
+        assert!(matches!(
+            DynamoAggregateError::from(error),
+            DynamoAggregateError::Throttled(_)
+        ));
+
+        let client = ThrottlingClient::new(1);
+        assert!(ThrottleRetry::disabled().send(|| client.query()).await.is_err());
+        assert_eq!(client.attempts(), 1);
+    }
+
+    #[test]
+    fn test_delay_doubles_per_retry() {
+        let retry = ThrottleRetry::new(5, Duration::from_millis(100));
+        assert_eq!(retry.delay(0), Duration::from_millis(100));
+        assert_eq!(retry.delay(3), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn test_cancelled_transaction_is_throttling_without_failed_condition() {
+        let cancelled = |codes: &[&str]| {
+            TransactWriteItemsError::TransactionCanceledException(
+                aws_sdk_dynamodb::types::error::TransactionCanceledException::builder()
+                    .set_cancellation_reasons(Some(
+                        codes
+                            .iter()
+                            .map(|code| CancellationReason::builder().code(*code).build())
+                            .collect(),
+                    ))
+                    .build(),
+            )
+        };
+        assert!(cancelled(&["None", "ThrottlingError"]).is_throttling());
+        assert!(!cancelled(&["ConditionalCheckFailed", "ThrottlingError"]).is_throttling());
+        assert!(!cancelled(&["ConditionalCheckFailed", "None"]).is_throttling());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::store::{error::DynamoAggregateError, DynamoDB};
+use aws_sdk_dynamodb::types::{KeySchemaElement, KeyType, TableDescription};
+
+/// Key schema every table of the store is created with.
+const TABLE_KEY_SCHEMA: [(&str, KeyType); 2] = [("pkey", KeyType::Hash), ("skey", KeyType::Range)];
+
+/// Key schema of `journal_aid_index` and `snapshot_aid_index`.
+const AID_INDEX_KEY_SCHEMA: [(&str, KeyType); 2] = [("aid", KeyType::Hash), ("seq_nr", KeyType::Range)];
+
+/// Key schema of `outbox_status_index`.
+const STATUS_INDEX_KEY_SCHEMA: [(&str, KeyType); 2] = [("status", KeyType::Hash), ("skey", KeyType::Range)];
+
+impl DynamoDB {
+    /// Confirms that every configured table exists with the `pkey`/`skey` key schema and carries its configured
+    /// global secondary index with the expected hash and range keys, naming the first table or index that does not.
+    ///
+    /// An index keyed on the wrong attributes is rejected too, since queries through it would silently return
+    /// nothing.
+    ///
+    /// Meant as a startup assertion: a typo in [`TableNames`](crate::store::TableNames) otherwise only surfaces as
+    /// a `ResourceNotFoundException` from the first query that touches the table. Issues one `DescribeTable` per
+    /// table. `inverted_index_keyword_index` is not checked, since the store never queries it.
+    pub async fn verify_tables(&self) -> Result<(), DynamoAggregateError> {
+        let table_names = &self.config.table_names;
+        let tables = [
+            (
+                "journal",
+                &table_names.journal,
+                vec![(
+                    "journal_aid_index",
+                    &table_names.journal_aid_index,
+                    &AID_INDEX_KEY_SCHEMA,
+                )],
+            ),
+            (
+                "snapshot",
+                &table_names.snapshot,
+                vec![(
+                    "snapshot_aid_index",
+                    &table_names.snapshot_aid_index,
+                    &AID_INDEX_KEY_SCHEMA,
+                )],
+            ),
+            (
+                "outbox",
+                &table_names.outbox,
+                vec![(
+                    "outbox_status_index",
+                    &table_names.outbox_status_index,
+                    &STATUS_INDEX_KEY_SCHEMA,
+                )],
+            ),
+            ("inverted_index", &table_names.inverted_index, vec![]),
+        ];
+        for (field, table, indexes) in tables {
+            let description = self.describe_configured_table(field, table).await?;
+            let found = description.key_schema();
+            if !key_schema_matches(found, &TABLE_KEY_SCHEMA) {
+                return Err(DynamoAggregateError::TableSchemaMismatch {
+                    table: table.clone(),
+                    expected: format_expected_key_schema(&TABLE_KEY_SCHEMA),
+                    found: format_key_schema(found),
+                });
+            }
+            for (index_field, index, expected) in indexes {
+                let Some(gsi) = description
+                    .global_secondary_indexes()
+                    .iter()
+                    .find(|gsi| gsi.index_name() == Some(index.as_str()))
+                else {
+                    return Err(DynamoAggregateError::IndexMissing {
+                        field: index_field,
+                        table: table.clone(),
+                        index: index.clone(),
+                    });
+                };
+                if !key_schema_matches(gsi.key_schema(), expected) {
+                    return Err(DynamoAggregateError::IndexSchemaMismatch {
+                        index: index.clone(),
+                        expected: format_expected_key_schema(expected),
+                        found: format_key_schema(gsi.key_schema()),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn describe_configured_table(
+        &self,
+        field: &'static str,
+        table: &str,
+    ) -> Result<TableDescription, DynamoAggregateError> {
+        let output = self
+            .client
+            .describe_table()
+            .table_name(table)
+            .send()
+            .await
+            .map_err(|error| {
+                if error
+                    .as_service_error()
+                    .is_some_and(|error| error.is_resource_not_found_exception())
+                {
+                    DynamoAggregateError::TableMissing {
+                        field,
+                        table: table.to_string(),
+                    }
+                } else {
+                    error.into()
+                }
+            })?;
+        output.table.ok_or_else(|| DynamoAggregateError::TableMissing {
+            field,
+            table: table.to_string(),
+        })
+    }
+}
+
+fn key_schema_matches(found: &[KeySchemaElement], expected: &[(&str, KeyType)]) -> bool {
+    found.len() == expected.len()
+        && expected.iter().all(|(name, key_type)| {
+            found
+                .iter()
+                .any(|element| element.attribute_name() == *name && element.key_type() == key_type)
+        })
+}
+
+fn format_key_schema(key_schema: &[KeySchemaElement]) -> String {
+    key_schema
+        .iter()
+        .map(|element| format!("{} {}", element.attribute_name(), element.key_type().as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_expected_key_schema(key_schema: &[(&str, KeyType)]) -> String {
+    key_schema
+        .iter()
+        .map(|(name, key_type)| format!("{name} {}", key_type.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(name: &str, key_type: KeyType) -> KeySchemaElement {
+        KeySchemaElement::builder()
+            .attribute_name(name)
+            .key_type(key_type)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_key_schema_matches_regardless_of_order() {
+        let found = [element("skey", KeyType::Range), element("pkey", KeyType::Hash)];
+        assert!(key_schema_matches(&found, &TABLE_KEY_SCHEMA));
+
+        let hash_only = [element("pkey", KeyType::Hash)];
+        assert!(!key_schema_matches(&hash_only, &TABLE_KEY_SCHEMA));
+
+        let renamed = [element("pk", KeyType::Hash), element("skey", KeyType::Range)];
+        assert!(!key_schema_matches(&renamed, &TABLE_KEY_SCHEMA));
+        assert_eq!(format_key_schema(&renamed), "pk HASH, skey RANGE");
+        assert_eq!(format_expected_key_schema(&TABLE_KEY_SCHEMA), "pkey HASH, skey RANGE");
+    }
+}
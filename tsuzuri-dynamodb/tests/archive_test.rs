@@ -0,0 +1,210 @@
+mod common;
+
+use common::{fixtures::*, LocalStackSetup};
+use tsuzuri::{
+    aggregate_id::AggregateId,
+    domain_event::SerializedDomainEvent,
+    event_store::{AggregateEventStreamer, Persister, SnapshotGetter},
+    persist::PersistenceError,
+    serde::{Json, Serializer},
+    snapshot::PersistedSnapshot,
+    AggregateLoader, AggregateRoot, EventSourced,
+};
+use tsuzuri_dynamodb::store::{archive::ArchivedEvent, error::DynamoAggregateError, DynamoDB};
+
+fn updated_event(aggregate_id: &str, seq_nr: usize) -> SerializedDomainEvent {
+    let event = TestDomainEvent::Updated(TestAggregateUpdated { value: seq_nr as i32 });
+    SerializedDomainEvent {
+        payload: Json::default().serialize(&event).unwrap(),
+        ..create_test_domain_event(aggregate_id, seq_nr, "TestAggregateUpdated")
+    }
+}
+
+fn created_event(id: &AggregateId<TestId>) -> SerializedDomainEvent {
+    let event = TestDomainEvent::Created(TestAggregateCreated {
+        id: *id,
+        name: "archived".to_string(),
+    });
+    SerializedDomainEvent {
+        payload: Json::default().serialize(&event).unwrap(),
+        ..create_test_domain_event(&id.to_string(), 1, "TestAggregateCreated")
+    }
+}
+
+/// Persists a creation event, four updates and a snapshot that resumes replay at event 4.
+async fn persist_with_snapshot(store: &DynamoDB, id: &AggregateId<TestId>) {
+    let aggregate_id = id.to_string();
+    let events: Vec<_> = std::iter::once(created_event(id))
+        .chain((2..=5).map(|seq_nr| updated_event(&aggregate_id, seq_nr)))
+        .collect();
+    let mut aggregate = TestAggregate::init(*id);
+    aggregate.name = "archived".to_string();
+    aggregate.value = 3;
+    let snapshot = PersistedSnapshot::new(
+        TestAggregate::TYPE.to_string(),
+        aggregate_id,
+        Json::default().serialize(&aggregate).unwrap(),
+        4,
+        1,
+    );
+    store
+        .persist(&events, &[], Some(&snapshot), &[])
+        .await
+        .expect("Failed to persist events");
+}
+
+#[tokio::test]
+async fn test_archive_events_before_moves_events_to_s3() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+    let (s3_client, bucket) = setup.create_s3_bucket().await;
+    let id = AggregateId::<TestId>::new();
+    let aggregate_id = id.to_string();
+    persist_with_snapshot(&store, &id).await;
+
+    let archived = store
+        .archive_events_before(TestAggregate::TYPE, &aggregate_id, 3, &s3_client, &bucket)
+        .await
+        .expect("Failed to archive events");
+    assert_eq!(archived, 2);
+
+    // The archive object holds events 1 and 2, one per line.
+    let objects = s3_client
+        .list_objects_v2()
+        .bucket(&bucket)
+        .prefix(store.archive_prefix(TestAggregate::TYPE, &aggregate_id))
+        .send()
+        .await
+        .expect("Failed to list archive objects");
+    assert_eq!(objects.contents().len(), 1);
+    let object = s3_client
+        .get_object()
+        .bucket(&bucket)
+        .key(objects.contents()[0].key().unwrap())
+        .send()
+        .await
+        .expect("Failed to get archive object");
+    let body = object.body.collect().await.unwrap().into_bytes();
+    let seq_nrs: Vec<_> = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<ArchivedEvent>(line).unwrap().seq_nr)
+        .collect();
+    assert_eq!(seq_nrs, vec![1, 2]);
+
+    // Only the last event the snapshot covers and the events after it are left in the journal, and loading still
+    // reaches the latest state.
+    assert_eq!(store.count_events::<TestAggregate>(&aggregate_id).await.unwrap(), 3);
+    let repository = EventSourced::new(store, Json::default(), Json::default(), Json::default());
+    let loaded: tsuzuri::VersionedAggregate<TestAggregate> = repository.load_aggregate(&id).await.unwrap();
+    assert_eq!(loaded.seq_nr(), 5);
+    assert_eq!(loaded.aggregate().value, 5);
+}
+
+#[tokio::test]
+async fn test_archive_events_before_requires_covering_snapshot() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+    let (s3_client, bucket) = setup.create_s3_bucket().await;
+    let id = AggregateId::<TestId>::new();
+    let aggregate_id = id.to_string();
+    persist_with_snapshot(&store, &id).await;
+
+    let result = store
+        .archive_events_before(TestAggregate::TYPE, &aggregate_id, 4, &s3_client, &bucket)
+        .await;
+    assert!(matches!(
+        result,
+        Err(DynamoAggregateError::SnapshotRequired { seq_nr: 4, .. })
+    ));
+    assert_eq!(store.count_events::<TestAggregate>(&aggregate_id).await.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn test_archive_events_before_keeps_latest_event() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+    let (s3_client, bucket) = setup.create_s3_bucket().await;
+    let aggregate_id = AggregateId::<TestId>::new().to_string();
+
+    let events: Vec<_> = (1..=2).map(|seq_nr| updated_event(&aggregate_id, seq_nr)).collect();
+    let snapshot = PersistedSnapshot::new(TestAggregate::TYPE.to_string(), aggregate_id.clone(), vec![], 4, 1);
+    store
+        .persist(&events, &[], Some(&snapshot), &[])
+        .await
+        .expect("Failed to persist events");
+
+    let archived = store
+        .archive_events_before(TestAggregate::TYPE, &aggregate_id, 3, &s3_client, &bucket)
+        .await
+        .expect("Failed to archive events");
+    assert_eq!(archived, 1);
+    assert_eq!(store.count_events::<TestAggregate>(&aggregate_id).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_load_aggregate_with_archive_replays_full_history() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+    let (s3_client, bucket) = setup.create_s3_bucket().await;
+    let id = AggregateId::<TestId>::new();
+    let aggregate_id = id.to_string();
+    persist_with_snapshot(&store, &id).await;
+    store
+        .archive_events_before(TestAggregate::TYPE, &aggregate_id, 3, &s3_client, &bucket)
+        .await
+        .expect("Failed to archive events");
+
+    let archived = store
+        .archived_events(TestAggregate::TYPE, &aggregate_id, &s3_client, &bucket)
+        .await
+        .expect("Failed to read archived events");
+    let seq_nrs: Vec<_> = archived.iter().map(|event| event.seq_nr).collect();
+    assert_eq!(seq_nrs, vec![1, 2]);
+    assert_eq!(archived[0].event_type, "TestAggregateCreated");
+
+    // The name comes from the archived creation event, the value from the last live event.
+    let loaded = store
+        .load_aggregate_with_archive::<TestAggregate>(&id, &Json::default(), &s3_client, &bucket)
+        .await
+        .expect("Failed to load aggregate with archive");
+    assert_eq!(loaded.seq_nr(), 5);
+    assert_eq!(loaded.aggregate().name, "archived");
+    assert_eq!(loaded.aggregate().value, 5);
+}
+
+#[tokio::test]
+async fn test_archive_events_before_keeps_snapshot_usable() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+    let (s3_client, bucket) = setup.create_s3_bucket().await;
+    let id = AggregateId::<TestId>::new();
+    let aggregate_id = id.to_string();
+    persist_with_snapshot(&store, &id).await;
+    store
+        .archive_events_before(TestAggregate::TYPE, &aggregate_id, 3, &s3_client, &bucket)
+        .await
+        .expect("Failed to archive events");
+    let repository = EventSourced::new(store.clone(), Json::default(), Json::default(), Json::default());
+
+    // The kept event dates the snapshot, so a temporal load resumes from it instead of replaying the partial
+    // journal from the initial state.
+    let loaded: tsuzuri::VersionedAggregate<TestAggregate> = repository
+        .load_aggregate_as_of(&id, tsuzuri::helper::now_timestamp().unwrap())
+        .await
+        .expect("Failed to load aggregate as of now");
+    assert_eq!(loaded.seq_nr(), 5);
+    assert_eq!(loaded.aggregate().name, "archived");
+    assert_eq!(loaded.aggregate().value, 5);
+
+    // Rebuilding from the partial journal is refused and leaves the snapshot as it is.
+    let result = repository.rebuild_snapshot(&id).await;
+    assert!(matches!(result, Err(PersistenceError::CorruptStream { seq_nr: 3, .. })));
+    let snapshot = store
+        .get_snapshot::<TestAggregate>(&aggregate_id)
+        .await
+        .unwrap()
+        .expect("Snapshot should be kept");
+    assert_eq!(snapshot.seq_nr, 4);
+    assert_eq!(snapshot.version, 1);
+}
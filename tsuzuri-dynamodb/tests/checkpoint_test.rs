@@ -0,0 +1,94 @@
+mod common;
+
+use aws_sdk_dynamodb::types::{AttributeDefinition, BillingMode, KeySchemaElement, KeyType, ScalarAttributeType};
+use common::LocalStackSetup;
+use tsuzuri_dynamodb::checkpoint::{CheckpointStore, DynamoDBCheckpointStore};
+
+async fn create_checkpoint_table(setup: &LocalStackSetup) -> String {
+    let table_name = format!("test-checkpoints-{}", uuid::Uuid::new_v4().simple());
+    setup
+        .client
+        .create_table()
+        .table_name(&table_name)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("pkey")
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+                .unwrap(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("skey")
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+                .unwrap(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("pkey")
+                .key_type(KeyType::Hash)
+                .build()
+                .unwrap(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("skey")
+                .key_type(KeyType::Range)
+                .build()
+                .unwrap(),
+        )
+        .send()
+        .await
+        .expect("Failed to create checkpoint table");
+    table_name
+}
+
+#[tokio::test]
+async fn test_checkpoint_save_and_load() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_checkpoint_table(&setup).await;
+    let store = DynamoDBCheckpointStore::new(setup.client.clone(), &table_name, "order-projection");
+
+    assert_eq!(store.load_checkpoint("shardId-000000000000").await.unwrap(), None);
+
+    store
+        .save_checkpoint("shardId-000000000000", "100")
+        .await
+        .expect("Failed to save checkpoint");
+    store
+        .save_checkpoint("shardId-000000000000", "200")
+        .await
+        .expect("Failed to overwrite checkpoint");
+
+    assert_eq!(
+        store.load_checkpoint("shardId-000000000000").await.unwrap().as_deref(),
+        Some("200")
+    );
+    assert_eq!(store.load_checkpoint("shardId-000000000001").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_checkpoints_are_isolated_per_consumer() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_checkpoint_table(&setup).await;
+    let orders = DynamoDBCheckpointStore::new(setup.client.clone(), &table_name, "orders");
+    let billing = DynamoDBCheckpointStore::new(setup.client.clone(), &table_name, "billing");
+
+    orders.save_checkpoint("shardId-000000000000", "100").await.unwrap();
+    billing.save_checkpoint("shardId-000000000000", "300").await.unwrap();
+
+    assert_eq!(
+        orders.load_checkpoint("shardId-000000000000").await.unwrap().as_deref(),
+        Some("100")
+    );
+    assert_eq!(
+        billing
+            .load_checkpoint("shardId-000000000000")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("300")
+    );
+}
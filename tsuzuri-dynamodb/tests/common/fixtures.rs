@@ -209,5 +209,7 @@ pub fn create_test_domain_event(
         event_type: event_type.to_string(),
         payload: vec![],
         metadata: Default::default(),
+        schema_version: 1,
+        occurred_at: tsuzuri::helper::now_timestamp().unwrap(),
     }
 }
@@ -320,6 +320,29 @@ impl LocalStackSetup {
             .await;
     }
 
+    /// S3 client for the same LocalStack endpoint, with a freshly created bucket.
+    pub async fn create_s3_bucket(&self) -> (aws_sdk_s3::Client, String) {
+        let config = aws_config::defaults(BehaviorVersion::latest())
+            .endpoint_url(&self.endpoint_url)
+            .region(aws_config::Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .load()
+            .await;
+        let client = aws_sdk_s3::Client::from_conf(
+            aws_sdk_s3::config::Builder::from(&config)
+                .force_path_style(true)
+                .build(),
+        );
+        let bucket = format!("test-archive-{}", uuid::Uuid::new_v4());
+        client
+            .create_bucket()
+            .bucket(&bucket)
+            .send()
+            .await
+            .expect("Failed to create bucket");
+        (client, bucket)
+    }
+
     pub fn create_dynamodb_store(&self) -> DynamoDB {
         DynamoDB::builder(self.client.clone())
             .table_names(self.table_names.clone())
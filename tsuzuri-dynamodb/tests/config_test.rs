@@ -1,5 +1,12 @@
 use aws_sdk_dynamodb::Client;
-use tsuzuri_dynamodb::store::{DynamoDB, DynamoDBConfig, DynamoDBConfigBuilder, TableNames};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tsuzuri_dynamodb::store::{
+    codec::SnapshotCodec,
+    key::{ModuloHash, ShardStrategy},
+    outbox::OutboxMode,
+    throttle::ThrottleRetry,
+    DynamoDB, DynamoDBConfig, DynamoDBConfigBuilder, HealthCheckScope, SnapshotWritePolicy, TableNames,
+};
 
 fn create_mock_client() -> Client {
     // This creates a client but we won't actually use it for these tests
@@ -34,6 +41,15 @@ fn test_dynamodb_config_default() {
 
     assert_eq!(config.shard_count, 4);
     assert_eq!(config.snapshot_interval, 100);
+    assert_eq!(config.dead_letter_after, 5);
+    assert!(config.legacy_shard_counts.is_empty());
+    assert!(!config.consistent_stream_reads);
+    assert_eq!(config.snapshot_codec, SnapshotCodec::None);
+    assert_eq!(config.tenant_scope, None);
+    assert_eq!(config.health_check_scope, HealthCheckScope::Journal);
+    assert_eq!(config.outbox_ttl, None);
+    assert_eq!(config.outbox_mode, OutboxMode::Transactional);
+    assert_eq!(config.throttle_retry, ThrottleRetry::new(5, Duration::from_millis(100)));
 
     // Table names should also be default
     assert_eq!(config.table_names.journal, "journal");
@@ -56,10 +72,16 @@ fn test_dynamodb_config_builder() {
         .table_names(custom_table_names.clone())
         .shard_count(8)
         .snapshot_interval(50)
+        .snapshot_interval_for("Order", 10)
+        .snapshot_codec(SnapshotCodec::Gzip)
+        .tenant_scope("tenant-a")
         .build();
 
     assert_eq!(config.shard_count, 8);
+    assert_eq!(config.snapshot_codec, SnapshotCodec::Gzip);
+    assert_eq!(config.tenant_scope.as_deref(), Some("tenant-a"));
     assert_eq!(config.snapshot_interval, 50);
+    assert_eq!(config.snapshot_intervals.get("Order"), Some(&10));
     assert_eq!(config.table_names.journal, "custom-journal");
     assert_eq!(config.table_names.snapshot, "custom-snapshot");
 }
@@ -94,12 +116,34 @@ fn test_dynamodb_with_config() {
         },
         shard_count: 10,
         snapshot_interval: 200,
+        snapshot_intervals: HashMap::from([("Order", 20)]),
+        dead_letter_after: 7,
+        legacy_shard_counts: vec![4],
+        consistent_stream_reads: true,
+        snapshot_codec: SnapshotCodec::Zstd,
+        tenant_scope: Some("tenant-a".to_string()),
+        health_check_scope: HealthCheckScope::AllTables,
+        outbox_ttl: Some(Duration::from_secs(3600)),
+        outbox_mode: OutboxMode::BestEffort,
+        throttle_retry: ThrottleRetry::disabled(),
+        shard_strategy: Arc::new(ModuloHash),
+        snapshot_write_policy: SnapshotWritePolicy::default(),
     };
 
     let db = DynamoDB::with_config(client, config);
 
     assert_eq!(db.shard_count(), 10);
     assert_eq!(db.snapshot_interval(), 200);
+    assert_eq!(db.dead_letter_after(), 7);
+    assert_eq!(db.snapshot_intervals().get("Order"), Some(&20));
+    assert_eq!(db.legacy_shard_counts(), &[4]);
+    assert!(db.consistent_stream_reads());
+    assert_eq!(db.snapshot_codec(), SnapshotCodec::Zstd);
+    assert_eq!(db.tenant_scope(), Some("tenant-a"));
+    assert_eq!(db.health_check_scope(), HealthCheckScope::AllTables);
+    assert_eq!(db.outbox_ttl(), Some(Duration::from_secs(3600)));
+    assert_eq!(db.outbox_mode(), OutboxMode::BestEffort);
+    assert_eq!(db.throttle_retry().max_retries(), 0);
     assert_eq!(db.table_names().journal, "test-journal");
 }
 
@@ -121,14 +165,52 @@ fn test_dynamodb_builder() {
         .table_names(custom_tables)
         .shard_count(12)
         .snapshot_interval(150)
+        .dead_letter_after(2)
+        .outbox_ttl(Duration::from_secs(86_400))
+        .outbox_mode(OutboxMode::BestEffort)
+        .throttle_retry(ThrottleRetry::new(8, Duration::from_millis(50)))
+        .snapshot_write_policy(SnapshotWritePolicy::Overwrite)
         .build();
 
     assert_eq!(db.shard_count(), 12);
     assert_eq!(db.snapshot_interval(), 150);
+    assert_eq!(db.dead_letter_after(), 2);
+    assert_eq!(db.outbox_ttl(), Some(Duration::from_secs(86_400)));
+    assert_eq!(db.outbox_mode(), OutboxMode::BestEffort);
+    assert_eq!(db.throttle_retry(), ThrottleRetry::new(8, Duration::from_millis(50)));
+    assert_eq!(db.snapshot_write_policy(), SnapshotWritePolicy::Overwrite);
     assert_eq!(db.table_names().journal, "builder-journal");
     assert_eq!(db.table_names().outbox, "builder-outbox");
 }
 
+/// Routes every aggregate to shard 0 except `agg-hot`, which gets the last shard to itself.
+#[derive(Debug)]
+struct HotAggregateStrategy;
+
+impl ShardStrategy for HotAggregateStrategy {
+    fn shard(&self, id: &str, shard_count: usize) -> usize {
+        if id == "agg-hot" {
+            shard_count - 1
+        } else {
+            0
+        }
+    }
+}
+
+#[test]
+fn test_dynamodb_builder_with_shard_strategy() {
+    let db = DynamoDB::builder(create_mock_client())
+        .shard_count(8)
+        .shard_strategy(HotAggregateStrategy)
+        .build();
+
+    assert_eq!(db.debug_shard_for("agg-hot"), 7);
+    assert_eq!(db.debug_shard_for("agg-1"), 0);
+    assert_eq!(db.shard_strategy().shard("agg-hot", 4), 3);
+    assert_eq!(db.debug_keys("TestAggregate", "agg-hot", 1).0, "TestAggregate-7");
+    assert_eq!(db.debug_keys("TestAggregate", "agg-1", 1).0, "TestAggregate-0");
+}
+
 #[test]
 fn test_dynamodb_builder_chain() {
     let client = create_mock_client();
@@ -162,11 +244,25 @@ fn test_dynamodb_config_clone() {
         },
         shard_count: 6,
         snapshot_interval: 75,
+        snapshot_intervals: HashMap::new(),
+        dead_letter_after: 5,
+        legacy_shard_counts: vec![2, 4],
+        consistent_stream_reads: false,
+        snapshot_codec: SnapshotCodec::Gzip,
+        tenant_scope: None,
+        health_check_scope: HealthCheckScope::Journal,
+        outbox_ttl: None,
+        outbox_mode: OutboxMode::Transactional,
+        throttle_retry: ThrottleRetry::default(),
+        shard_strategy: Arc::new(ModuloHash),
+        snapshot_write_policy: SnapshotWritePolicy::default(),
     };
 
     let cloned = original.clone();
 
     assert_eq!(cloned.shard_count, 6);
     assert_eq!(cloned.snapshot_interval, 75);
+    assert_eq!(cloned.legacy_shard_counts, vec![2, 4]);
+    assert_eq!(cloned.snapshot_codec, SnapshotCodec::Gzip);
     assert_eq!(cloned.table_names.journal, "config-journal");
 }
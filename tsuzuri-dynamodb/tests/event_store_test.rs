@@ -5,11 +5,15 @@ use futures::StreamExt;
 use tsuzuri::{
     domain_event::SerializedDomainEvent,
     event::SequenceSelect,
-    event_store::{AggregateEventStreamer, Persister, SnapshotGetter, SnapshotIntervalProvider},
+    event_store::{
+        AggregateEventStreamer, ExpectedState, PersistBatch, Persister, SnapshotGetter, SnapshotIntervalProvider,
+    },
     integration_event::SerializedIntegrationEvent,
+    persist::PersistenceError,
     snapshot::PersistedSnapshot,
     AggregateRoot,
 };
+use tsuzuri_dynamodb::store::{codec::SnapshotCodec, DynamoDB, SnapshotWritePolicy};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -37,6 +41,8 @@ async fn test_persist_and_stream_domain_events() {
             event_type: "TestAggregateCreated".to_string(),
             payload: serde_json::to_vec(&event1).unwrap(),
             metadata: Default::default(),
+            schema_version: 1,
+            occurred_at: Default::default(),
         },
         SerializedDomainEvent {
             id: Uuid::new_v4().to_string(),
@@ -46,12 +52,14 @@ async fn test_persist_and_stream_domain_events() {
             event_type: "TestAggregateUpdated".to_string(),
             payload: serde_json::to_vec(&event2).unwrap(),
             metadata: Default::default(),
+            schema_version: 1,
+            occurred_at: Default::default(),
         },
     ];
 
     // Persist events
     store
-        .persist(&domain_events, &[], None)
+        .persist(&domain_events, &[], None, &[])
         .await
         .expect("Failed to persist events");
 
@@ -97,6 +105,8 @@ async fn test_persist_with_integration_events() {
         event_type: "TestAggregateCreated".to_string(),
         payload: vec![],
         metadata: Default::default(),
+        schema_version: 1,
+        occurred_at: Default::default(),
     };
 
     let integration_event = TestIntegrationEvent {
@@ -110,11 +120,12 @@ async fn test_persist_with_integration_events() {
         aggregate_type: aggregate_type.to_string(),
         event_type: "TestIntegrationEvent".to_string(),
         payload: serde_json::to_vec(&integration_event).unwrap(),
+        metadata: Default::default(),
     };
 
     // Persist both domain and integration events
     store
-        .persist(&[domain_event], &[serialized_integration], None)
+        .persist(&[domain_event], &[serialized_integration], None, &[])
         .await
         .expect("Failed to persist events");
 
@@ -130,6 +141,63 @@ async fn test_persist_with_integration_events() {
     assert_eq!(count, 1);
 }
 
+#[tokio::test]
+async fn test_persist_preserves_schema_version() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNV";
+    let events = vec![
+        create_test_domain_event(aggregate_id, 1, "TestAggregateCreated"),
+        create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated").with_schema_version(3),
+    ];
+
+    store
+        .persist(&events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let mut stream = store.stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All);
+    let mut schema_versions = Vec::new();
+    while let Some(event_result) = stream.next().await {
+        schema_versions.push(event_result.expect("Failed to stream event").schema_version);
+    }
+
+    assert_eq!(schema_versions, vec![1, 3]);
+}
+
+#[tokio::test]
+async fn test_count_events() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNW";
+    assert_eq!(store.count_events::<TestAggregate>(aggregate_id).await.unwrap(), 0);
+
+    let events: Vec<_> = (1..=3)
+        .map(|seq_nr| create_test_domain_event(aggregate_id, seq_nr, "TestAggregateUpdated"))
+        .collect();
+    store
+        .persist(&events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+    store
+        .persist(
+            &[create_test_domain_event(
+                "test-01J1234567890ABCDEFGHJKMNX",
+                1,
+                "TestAggregateCreated",
+            )],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist events");
+
+    assert_eq!(store.count_events::<TestAggregate>(aggregate_id).await.unwrap(), 3);
+}
+
 #[tokio::test]
 async fn test_snapshot_create_and_retrieve() {
     let setup = LocalStackSetup::new().await;
@@ -159,11 +227,13 @@ async fn test_snapshot_create_and_retrieve() {
         event_type: "TestAggregateUpdated".to_string(),
         payload: vec![],
         metadata: Default::default(),
+        schema_version: 1,
+        occurred_at: Default::default(),
     };
 
     // Persist event with snapshot
     store
-        .persist(&[domain_event], &[], Some(&snapshot))
+        .persist(&[domain_event], &[], Some(&snapshot), &[])
         .await
         .expect("Failed to persist with snapshot");
 
@@ -185,6 +255,41 @@ async fn test_snapshot_create_and_retrieve() {
     assert_eq!(deserialized.value, 100);
 }
 
+#[tokio::test]
+async fn test_get_snapshots_reads_every_aggregate() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_ids = [
+        "test-01J1234567890ABCDEFGHJKMS0",
+        "test-01J1234567890ABCDEFGHJKMS1",
+        "test-01J1234567890ABCDEFGHJKMS2",
+    ];
+    for (seq_nr, aggregate_id) in aggregate_ids.iter().take(2).enumerate() {
+        let snapshot = PersistedSnapshot {
+            aggregate_type: TestAggregate::TYPE.to_string(),
+            aggregate_id: aggregate_id.to_string(),
+            aggregate: vec![],
+            seq_nr: seq_nr + 1,
+            version: 1,
+        };
+        store
+            .persist(&[], &[], Some(&snapshot), &[])
+            .await
+            .expect("Failed to persist snapshot");
+    }
+
+    let snapshots = store
+        .get_snapshots::<TestAggregate>(&aggregate_ids)
+        .await
+        .expect("Failed to retrieve snapshots");
+
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(snapshots[aggregate_ids[0]].seq_nr, 1);
+    assert_eq!(snapshots[aggregate_ids[1]].seq_nr, 2);
+    assert!(!snapshots.contains_key(aggregate_ids[2]));
+}
+
 #[tokio::test]
 async fn test_snapshot_interval_provider() {
     let setup = LocalStackSetup::new().await;
@@ -210,20 +315,116 @@ async fn test_concurrent_event_persistence() {
         event_type: "TestAggregateCreated".to_string(),
         payload: vec![],
         metadata: Default::default(),
+        schema_version: 1,
+        occurred_at: Default::default(),
     };
 
     // Persist first event
     store
-        .persist(&[event1.clone()], &[], None)
+        .persist(&[event1.clone()], &[], None, &[])
         .await
         .expect("Failed to persist first event");
 
     // Try to persist same sequence number again (should fail)
-    let result = store.persist(&[event1], &[], None).await;
+    let result = store.persist(&[event1], &[], None, &[]).await;
 
     assert!(result.is_err(), "Should fail when persisting duplicate sequence number");
 }
 
+#[tokio::test]
+async fn test_duplicate_seq_nr_reports_optimistic_concurrency() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNW";
+
+    // Two writers that both loaded the aggregate before any event and append seq_nr 1
+    let first_writer = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    let second_writer = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+
+    store
+        .persist(&[first_writer], &[], None, &[])
+        .await
+        .expect("Failed to persist first writer's event");
+
+    let result = store.persist(&[second_writer], &[], None, &[]).await;
+
+    match result {
+        Err(PersistenceError::OptimisticConcurrency {
+            aggregate_id: conflicted_id,
+            expected_seq,
+        }) => {
+            assert_eq!(conflicted_id, aggregate_id);
+            assert_eq!(expected_seq, 0);
+        }
+        other => panic!("Expected OptimisticConcurrency, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_must_not_exist_rejects_second_creation() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNX";
+
+    let created = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    store
+        .persist_expecting(&[created], &[], None, &[], ExpectedState::MustNotExist)
+        .await
+        .expect("Failed to create aggregate");
+
+    let created_again = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    let result = store
+        .persist_expecting(&[created_again], &[], None, &[], ExpectedState::MustNotExist)
+        .await;
+
+    match result {
+        Err(PersistenceError::AlreadyExists {
+            aggregate_id: existing_id,
+        }) => assert_eq!(existing_id, aggregate_id),
+        other => panic!("Expected AlreadyExists, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_expected_version_mismatch_reports_optimistic_concurrency() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNY";
+
+    let created = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    store
+        .persist_expecting(&[created], &[], None, &[], ExpectedState::ExpectedVersion(0))
+        .await
+        .expect("Failed to create aggregate");
+
+    // The journal ends at 1, so a writer that expects version 2 must not be able to append event 3
+    let stale = create_test_domain_event(aggregate_id, 3, "TestAggregateUpdated");
+    let result = store
+        .persist_expecting(&[stale], &[], None, &[], ExpectedState::ExpectedVersion(2))
+        .await;
+
+    match result {
+        Err(PersistenceError::OptimisticConcurrency {
+            aggregate_id: conflicted_id,
+            expected_seq,
+        }) => {
+            assert_eq!(conflicted_id, aggregate_id);
+            assert_eq!(expected_seq, 2);
+        }
+        other => panic!("Expected OptimisticConcurrency, got {other:?}"),
+    }
+
+    let updated = create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated");
+    store
+        .persist_expecting(&[updated], &[], None, &[], ExpectedState::ExpectedVersion(1))
+        .await
+        .expect("Failed to append at the expected version");
+    assert_eq!(store.count_events::<TestAggregate>(aggregate_id).await.unwrap(), 2);
+}
+
 #[tokio::test]
 async fn test_empty_event_stream() {
     let setup = LocalStackSetup::new().await;
@@ -271,11 +472,13 @@ async fn test_snapshot_update() {
         event_type: "TestAggregateUpdated".to_string(),
         payload: vec![],
         metadata: Default::default(),
+        schema_version: 1,
+        occurred_at: Default::default(),
     };
 
     // Persist first snapshot
     store
-        .persist(&[event1], &[], Some(&snapshot1))
+        .persist(&[event1], &[], Some(&snapshot1), &[])
         .await
         .expect("Failed to persist first snapshot");
 
@@ -303,11 +506,13 @@ async fn test_snapshot_update() {
         event_type: "TestAggregateUpdated".to_string(),
         payload: vec![],
         metadata: Default::default(),
+        schema_version: 1,
+        occurred_at: Default::default(),
     };
 
     // Persist updated snapshot
     store
-        .persist(&[event2], &[], Some(&snapshot2))
+        .persist(&[event2], &[], Some(&snapshot2), &[])
         .await
         .expect("Failed to persist updated snapshot");
 
@@ -325,3 +530,365 @@ async fn test_snapshot_update() {
     assert_eq!(deserialized.name, "Updated");
     assert_eq!(deserialized.value, 2);
 }
+
+fn versioned_snapshot(aggregate_id: &str, value: i32) -> PersistedSnapshot {
+    let aggregate = TestAggregate {
+        id: aggregate_id.parse().expect("Failed to parse aggregate_id"),
+        name: format!("Writer {value}"),
+        value,
+    };
+    PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: serde_json::to_vec(&aggregate).unwrap(),
+        seq_nr: 10,
+        version: 1,
+    }
+}
+
+/// Several writers store the same snapshot version at once, as concurrent snapshot rebuilds would.
+async fn write_snapshots_concurrently(
+    setup: &LocalStackSetup,
+    policy: SnapshotWritePolicy,
+    aggregate_id: &str,
+) -> Vec<Result<(), PersistenceError>> {
+    let stores: Vec<_> = (0..4)
+        .map(|_| {
+            DynamoDB::builder(setup.client.clone())
+                .table_names(setup.table_names.clone())
+                .snapshot_write_policy(policy)
+                .build()
+        })
+        .collect();
+    let snapshots: Vec<_> = (1..=4).map(|value| versioned_snapshot(aggregate_id, value)).collect();
+    futures::future::join_all(
+        stores
+            .iter()
+            .zip(&snapshots)
+            .map(|(store, snapshot)| store.persist(&[], &[], Some(snapshot), &[])),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_conditional_snapshot_writes_reject_concurrent_updates() {
+    let setup = LocalStackSetup::new().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMRA";
+
+    let results = write_snapshots_concurrently(&setup, SnapshotWritePolicy::Conditional, aggregate_id).await;
+
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+
+    // A stale writer is still rejected once the concurrent writes are done
+    let store = setup.create_dynamodb_store();
+    assert!(store
+        .persist(&[], &[], Some(&versioned_snapshot(aggregate_id, 5)), &[])
+        .await
+        .is_err());
+    let retrieved = store
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .expect("Failed to retrieve snapshot")
+        .expect("Snapshot should exist");
+    let aggregate: TestAggregate = serde_json::from_slice(&retrieved.aggregate).unwrap();
+    assert_ne!(aggregate.value, 5);
+}
+
+#[tokio::test]
+async fn test_overwrite_snapshot_writes_accept_concurrent_updates() {
+    let setup = LocalStackSetup::new().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMRB";
+
+    let results = write_snapshots_concurrently(&setup, SnapshotWritePolicy::Overwrite, aggregate_id).await;
+
+    for result in results {
+        result.expect("Overwriting snapshot write should succeed");
+    }
+
+    // The last write wins, even when it carries a version that is already stored
+    let store = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .snapshot_write_policy(SnapshotWritePolicy::Overwrite)
+        .build();
+    store
+        .persist(&[], &[], Some(&versioned_snapshot(aggregate_id, 5)), &[])
+        .await
+        .expect("Failed to overwrite snapshot");
+    let retrieved = store
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .expect("Failed to retrieve snapshot")
+        .expect("Snapshot should exist");
+    assert_eq!(retrieved.version, 1);
+    let aggregate: TestAggregate = serde_json::from_slice(&retrieved.aggregate).unwrap();
+    assert_eq!(aggregate.value, 5);
+}
+
+#[tokio::test]
+async fn test_consistent_stream_reads_query_base_table() {
+    let setup = LocalStackSetup::new().await;
+    let store = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .consistent_stream_reads(true)
+        .build();
+
+    // Sort keys order "-10" before "-2", so the fallback has to restore numeric order itself.
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNZ";
+    let other_id = "test-01J1234567890ABCDEFGHJKMNZ0";
+    let events: Vec<_> = (1..=12)
+        .map(|seq_nr| create_test_domain_event(aggregate_id, seq_nr, "TestAggregateUpdated"))
+        .collect();
+    store
+        .persist(&events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+    store
+        .persist(
+            &[create_test_domain_event(other_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist other aggregate's event");
+
+    let seq_nrs: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .map(|event| event.expect("Failed to read event").seq_nr)
+        .collect()
+        .await;
+    assert_eq!(seq_nrs, (1..=12).collect::<Vec<_>>());
+
+    let seq_nrs: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::From(9))
+        .map(|event| event.expect("Failed to read event").seq_nr)
+        .collect()
+        .await;
+    assert_eq!(seq_nrs, vec![9, 10, 11, 12]);
+}
+
+fn large_snapshot(aggregate_id: &str, seq_nr: usize, version: usize) -> PersistedSnapshot {
+    // Well above DynamoDB's 400KB item limit, but repetitive enough to compress far below it
+    let aggregate = serde_json::to_vec(&TestAggregate {
+        id: aggregate_id.parse().expect("Failed to parse aggregate_id"),
+        name: "large ".repeat(100_000),
+        value: 1,
+    })
+    .unwrap();
+    assert!(aggregate.len() > 400 * 1024);
+    PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate,
+        seq_nr,
+        version,
+    }
+}
+
+#[tokio::test]
+async fn test_compressed_snapshot_roundtrip() {
+    let setup = LocalStackSetup::new().await;
+    for (codec, aggregate_id) in [
+        (SnapshotCodec::Gzip, "test-01J1234567890ABCDEFGHJKMQA"),
+        (SnapshotCodec::Zstd, "test-01J1234567890ABCDEFGHJKMQB"),
+    ] {
+        let store = DynamoDB::builder(setup.client.clone())
+            .table_names(setup.table_names.clone())
+            .snapshot_codec(codec)
+            .build();
+        let snapshot = large_snapshot(aggregate_id, 1, 1);
+        store
+            .persist(
+                &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+                &[],
+                Some(&snapshot),
+                &[],
+            )
+            .await
+            .expect("Failed to persist compressed snapshot");
+
+        let retrieved = store
+            .get_snapshot::<TestAggregate>(aggregate_id)
+            .await
+            .expect("Failed to retrieve snapshot")
+            .expect("Snapshot should exist");
+        assert_eq!(retrieved.aggregate, snapshot.aggregate);
+    }
+}
+
+#[tokio::test]
+async fn test_uncompressed_snapshot_reads_after_enabling_compression() {
+    let setup = LocalStackSetup::new().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMQC";
+    let snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: b"uncompressed".to_vec(),
+        seq_nr: 1,
+        version: 1,
+    };
+    setup
+        .create_dynamodb_store()
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            Some(&snapshot),
+            &[],
+        )
+        .await
+        .expect("Failed to persist snapshot");
+
+    let store = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .snapshot_codec(SnapshotCodec::Zstd)
+        .build();
+    let retrieved = store
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .expect("Failed to retrieve snapshot")
+        .expect("Snapshot should exist");
+    assert_eq!(retrieved.aggregate, b"uncompressed");
+}
+
+#[tokio::test]
+async fn test_persist_batch_writes_every_aggregate() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_ids = ["test-01J1234567890ABCDEFGHJKMRA", "test-01J1234567890ABCDEFGHJKMRB"];
+    let batches: Vec<_> = aggregate_ids
+        .iter()
+        .map(|aggregate_id| {
+            PersistBatch::new(vec![
+                create_test_domain_event(aggregate_id, 1, "TestAggregateCreated"),
+                create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated"),
+            ])
+        })
+        .collect();
+
+    store.persist_batch(&batches).await.expect("Failed to persist batches");
+
+    for aggregate_id in aggregate_ids {
+        let events: Vec<_> = store
+            .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+            .collect()
+            .await;
+        assert_eq!(events.len(), 2);
+    }
+}
+
+#[tokio::test]
+async fn test_persist_batch_reports_conflicting_aggregate() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let fresh_id = "test-01J1234567890ABCDEFGHJKMRC";
+    let conflicting_id = "test-01J1234567890ABCDEFGHJKMRD";
+    store
+        .persist(
+            &[create_test_domain_event(conflicting_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist first writer's event");
+
+    let batches = [
+        PersistBatch::new(vec![create_test_domain_event(fresh_id, 1, "TestAggregateCreated")]),
+        PersistBatch::new(vec![create_test_domain_event(
+            conflicting_id,
+            1,
+            "TestAggregateCreated",
+        )]),
+    ];
+    let result = store.persist_batch(&batches).await;
+
+    match result {
+        Err(PersistenceError::OptimisticConcurrency {
+            aggregate_id,
+            expected_seq,
+        }) => {
+            assert_eq!(aggregate_id, conflicting_id);
+            assert_eq!(expected_seq, 0);
+        }
+        other => panic!("Expected OptimisticConcurrency, got {other:?}"),
+    }
+    // Both batches shared one transaction, so the fresh aggregate was rolled back as well
+    let events: Vec<_> = store
+        .stream_events::<TestAggregate>(fresh_id, SequenceSelect::All)
+        .collect()
+        .await;
+    assert!(events.is_empty());
+}
+
+#[tokio::test]
+async fn test_tenant_scopes_isolate_events_and_snapshots() {
+    let setup = LocalStackSetup::new().await;
+    let tenant_a = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .tenant_scope("tenant-a")
+        .build();
+    let tenant_b = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .tenant_scope("tenant-b")
+        .build();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMT1";
+    let snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: b"{}".to_vec(),
+        seq_nr: 2,
+        version: 1,
+    };
+    tenant_a
+        .persist(
+            &[
+                create_test_domain_event(aggregate_id, 1, "TestAggregateCreated"),
+                create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated"),
+            ],
+            &[],
+            Some(&snapshot),
+            &[],
+        )
+        .await
+        .expect("Failed to persist tenant-a events");
+    // The same aggregate ID and sequence number do not conflict across tenants
+    tenant_b
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist tenant-b event");
+
+    let tenant_a_events: Vec<_> = tenant_a
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .map(|event| event.expect("Failed to read event").seq_nr)
+        .collect()
+        .await;
+    assert_eq!(tenant_a_events, vec![1, 2]);
+    let tenant_b_events: Vec<_> = tenant_b
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .map(|event| event.expect("Failed to read event").seq_nr)
+        .collect()
+        .await;
+    assert_eq!(tenant_b_events, vec![1]);
+
+    assert_eq!(tenant_a.count_events::<TestAggregate>(aggregate_id).await.unwrap(), 2);
+    assert_eq!(tenant_b.count_events::<TestAggregate>(aggregate_id).await.unwrap(), 1);
+
+    assert!(tenant_a
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(tenant_b
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::LocalStackSetup;
+use tsuzuri_dynamodb::store::{DynamoDB, HealthCheckScope, TableNames};
+
+#[tokio::test]
+async fn test_health_check_succeeds_against_created_tables() {
+    let setup = LocalStackSetup::new().await;
+
+    let journal_only = setup.create_dynamodb_store();
+    journal_only.health_check().await.expect("Journal health check failed");
+
+    let all_tables = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .health_check_scope(HealthCheckScope::AllTables)
+        .build();
+    all_tables
+        .health_check()
+        .await
+        .expect("Health check of all tables failed");
+}
+
+#[tokio::test]
+async fn test_health_check_fails_against_missing_table() {
+    let setup = LocalStackSetup::new().await;
+
+    let missing_journal = DynamoDB::builder(setup.client.clone())
+        .table_names(TableNames {
+            journal: "missing-journal".to_string(),
+            ..setup.table_names.clone()
+        })
+        .build();
+    assert!(missing_journal.health_check().await.is_err());
+
+    // The journal exists, but the snapshot table is only described when every table is checked
+    let missing_snapshot = TableNames {
+        snapshot: "missing-snapshot".to_string(),
+        ..setup.table_names.clone()
+    };
+    let journal_only = DynamoDB::builder(setup.client.clone())
+        .table_names(missing_snapshot.clone())
+        .build();
+    journal_only.health_check().await.expect("Journal health check failed");
+
+    let all_tables = DynamoDB::builder(setup.client.clone())
+        .table_names(missing_snapshot)
+        .health_check_scope(HealthCheckScope::AllTables)
+        .build();
+    assert!(all_tables.health_check().await.is_err());
+}
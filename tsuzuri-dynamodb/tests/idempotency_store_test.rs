@@ -0,0 +1,118 @@
+mod common;
+
+use aws_sdk_dynamodb::types::{AttributeDefinition, BillingMode, KeySchemaElement, KeyType, ScalarAttributeType};
+use common::LocalStackSetup;
+use std::time::Duration;
+use tsuzuri::idempotency_store::{IdempotencyStore, Reservation};
+use tsuzuri_dynamodb::idempotency_store::DynamoDBIdempotencyStore;
+
+async fn create_idempotency_table(setup: &LocalStackSetup) -> String {
+    let table_name = format!("test-idempotency-{}", uuid::Uuid::new_v4().simple());
+    setup
+        .client
+        .create_table()
+        .table_name(&table_name)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("pkey")
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+                .unwrap(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("skey")
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+                .unwrap(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("pkey")
+                .key_type(KeyType::Hash)
+                .build()
+                .unwrap(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("skey")
+                .key_type(KeyType::Range)
+                .build()
+                .unwrap(),
+        )
+        .send()
+        .await
+        .expect("Failed to create idempotency table");
+    table_name
+}
+
+#[tokio::test]
+async fn test_put_events_are_returned_for_key() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_idempotency_table(&setup).await;
+    let store = DynamoDBIdempotencyStore::new(setup.client.clone(), &table_name, "orders");
+
+    assert_eq!(store.get("key-1").await.unwrap(), None);
+
+    let events = vec![b"event-1".to_vec(), b"event-2".to_vec()];
+    store
+        .put("key-1", events.clone(), Duration::from_secs(60))
+        .await
+        .expect("Failed to put idempotency key");
+
+    assert_eq!(store.get("key-1").await.unwrap(), Some(events));
+    assert_eq!(store.get("key-2").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_idempotency_keys_are_isolated_per_scope() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_idempotency_table(&setup).await;
+    let orders = DynamoDBIdempotencyStore::new(setup.client.clone(), &table_name, "orders");
+    let billing = DynamoDBIdempotencyStore::new(setup.client.clone(), &table_name, "billing");
+
+    orders.put("key-1", vec![], Duration::from_secs(60)).await.unwrap();
+
+    assert_eq!(orders.get("key-1").await.unwrap(), Some(vec![]));
+    assert_eq!(billing.get("key-1").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_expired_idempotency_keys_are_treated_as_absent() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_idempotency_table(&setup).await;
+    let store = DynamoDBIdempotencyStore::new(setup.client.clone(), &table_name, "orders");
+
+    store
+        .put("key-1", vec![b"event-1".to_vec()], Duration::ZERO)
+        .await
+        .unwrap();
+
+    assert_eq!(store.get("key-1").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_reserve_succeeds_once_per_key() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_idempotency_table(&setup).await;
+    let store = DynamoDBIdempotencyStore::new(setup.client.clone(), &table_name, "orders");
+    let ttl = Duration::from_secs(60);
+
+    let (first, second) = tokio::join!(store.reserve("key-1", ttl), store.reserve("key-1", ttl));
+    let mut reservations = vec![first.unwrap(), second.unwrap()];
+    reservations.sort_by_key(|reservation| reservation != &Reservation::Reserved);
+    assert_eq!(reservations, vec![Reservation::Reserved, Reservation::InProgress]);
+    assert_eq!(store.get("key-1").await.unwrap(), None);
+
+    store.release("key-1").await.unwrap();
+    assert_eq!(store.reserve("key-1", ttl).await.unwrap(), Reservation::Reserved);
+
+    let events = vec![b"event-1".to_vec()];
+    store.put("key-1", events.clone(), ttl).await.unwrap();
+    store.release("key-1").await.unwrap();
+    assert_eq!(
+        store.reserve("key-1", ttl).await.unwrap(),
+        Reservation::Completed(events)
+    );
+}
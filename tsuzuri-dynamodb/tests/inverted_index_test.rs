@@ -1,7 +1,16 @@
 mod common;
 
-use common::LocalStackSetup;
-use tsuzuri::inverted_index_store::{AggregateIdsLoader, InvertedIndexCommiter, InvertedIndexRemover};
+use common::{
+    fixtures::{create_test_domain_event, TestAggregate},
+    LocalStackSetup,
+};
+use futures::TryStreamExt;
+use tsuzuri::{
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, Persister},
+    inverted_index_store::{AggregateIdsLoader, IndexOp, InvertedIndexCommiter, InvertedIndexRemover},
+};
+use tsuzuri_dynamodb::store::DynamoDB;
 
 #[tokio::test]
 async fn test_commit_and_get_aggregate_ids() {
@@ -63,7 +72,7 @@ async fn test_commit_multiple_keywords_single_aggregate() {
     let keywords = ["keyword1", "keyword2", "keyword3"];
 
     // Commit multiple keywords for single aggregate
-    for keyword in &keywords {
+    for keyword in keywords {
         store
             .commit(aggregate_id, keyword)
             .await
@@ -71,7 +80,7 @@ async fn test_commit_multiple_keywords_single_aggregate() {
     }
 
     // Verify each keyword returns the aggregate
-    for keyword in &keywords {
+    for keyword in keywords {
         let ids = store
             .get_aggregate_ids(keyword)
             .await
@@ -200,3 +209,110 @@ async fn test_remove_non_existent_keyword() {
     let result = store.remove("non-existent-agg", "non-existent-keyword").await;
     assert!(result.is_ok(), "Removing non-existent keyword should not error");
 }
+
+#[tokio::test]
+async fn test_persist_applies_index_ops_with_events() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMMD";
+    store.commit(aggregate_id, "email:old").await.unwrap();
+
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[
+                IndexOp::delete("email:old", aggregate_id),
+                IndexOp::put("email:new", aggregate_id),
+            ],
+        )
+        .await
+        .expect("Failed to persist events with index ops");
+
+    assert!(store.get_aggregate_ids("email:old").await.unwrap().is_empty());
+    assert_eq!(store.get_aggregate_ids("email:new").await.unwrap(), vec![aggregate_id]);
+}
+
+#[tokio::test]
+async fn test_conflicting_persist_does_not_write_index_ops() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMME";
+    let event = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    store
+        .persist(std::slice::from_ref(&event), &[], None, &[])
+        .await
+        .expect("Failed to persist first writer's event");
+
+    let result = store
+        .persist(&[event], &[], None, &[IndexOp::put("email:new", aggregate_id)])
+        .await;
+
+    assert!(result.is_err());
+    assert!(store.get_aggregate_ids("email:new").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_persist_rejects_transactions_over_item_limit() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMMF";
+    let index_ops: Vec<IndexOp> = (0..100)
+        .map(|i| IndexOp::put(format!("tag:{i}"), aggregate_id))
+        .collect();
+
+    // One event plus 100 index writes exceeds DynamoDB's 100 item limit, so nothing is written
+    let result = store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &index_ops,
+        )
+        .await;
+
+    assert!(result.is_err());
+    let events: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(events.is_empty());
+    assert!(store.get_aggregate_ids("tag:0").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_tenant_scopes_isolate_inverted_index() {
+    let setup = LocalStackSetup::new().await;
+    let tenant_a = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .tenant_scope("tenant-a")
+        .build();
+    let tenant_b = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .tenant_scope("tenant-b")
+        .build();
+
+    let keyword = "tenant-keyword";
+    tenant_a
+        .commit("agg-a", keyword)
+        .await
+        .expect("Failed to commit tenant-a keyword");
+    tenant_b
+        .persist(&[], &[], None, &[IndexOp::put(keyword, "agg-b")])
+        .await
+        .expect("Failed to persist tenant-b index op");
+
+    assert_eq!(
+        tenant_a.get_aggregate_ids(keyword).await.unwrap(),
+        vec!["agg-a".to_string()]
+    );
+    assert_eq!(
+        tenant_b.get_aggregate_ids(keyword).await.unwrap(),
+        vec!["agg-b".to_string()]
+    );
+}
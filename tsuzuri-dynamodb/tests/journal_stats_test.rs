@@ -0,0 +1,57 @@
+mod common;
+
+use common::{fixtures::*, LocalStackSetup};
+use tsuzuri::{event_store::Persister, AggregateRoot};
+use tsuzuri_dynamodb::store::stats::AggregateStat;
+
+#[tokio::test]
+async fn test_journal_stats_counts_events_per_aggregate() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregates = [
+        ("test-01J1234567890ABCDEFGHJKMS1", 3),
+        ("test-01J1234567890ABCDEFGHJKMS2", 1),
+        ("test-01J1234567890ABCDEFGHJKMS3", 12),
+    ];
+    for (aggregate_id, event_count) in aggregates {
+        let events: Vec<_> = (1..=event_count)
+            .map(|seq_nr| create_test_domain_event(aggregate_id, seq_nr, "TestAggregateUpdated"))
+            .collect();
+        store
+            .persist(&events, &[], None, &[])
+            .await
+            .expect("Failed to persist events");
+    }
+
+    // Aggregates of other types are left out of the report.
+    let other = tsuzuri::domain_event::SerializedDomainEvent {
+        aggregate_type: "OtherAggregate".to_string(),
+        ..create_test_domain_event("other-01J1234567890ABCDEFGHJKMS4", 1, "OtherCreated")
+    };
+    store
+        .persist(&[other], &[], None, &[])
+        .await
+        .expect("Failed to persist event");
+
+    let stats = store
+        .journal_stats(TestAggregate::TYPE)
+        .await
+        .expect("Failed to compute journal stats");
+
+    let expected: Vec<_> = aggregates
+        .iter()
+        .map(|(aggregate_id, event_count)| AggregateStat {
+            aggregate_id: aggregate_id.to_string(),
+            event_count: *event_count,
+            last_seq_nr: *event_count,
+        })
+        .collect();
+    assert_eq!(stats, expected);
+
+    let empty = store
+        .journal_stats("UnknownAggregate")
+        .await
+        .expect("Failed to compute journal stats");
+    assert!(empty.is_empty());
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::{fixtures::*, LocalStackSetup};
+use futures::TryStreamExt;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tsuzuri::{
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, Persister, SnapshotGetter},
+    snapshot::PersistedSnapshot,
+    AggregateRoot,
+};
+use tsuzuri_dynamodb::store::{metrics::Metrics, DynamoDB};
+
+#[derive(Debug, Default, Clone)]
+struct CountingMetrics {
+    persists: Arc<AtomicUsize>,
+    query_items: Arc<AtomicUsize>,
+    conflicts: Arc<AtomicUsize>,
+}
+
+impl Metrics for CountingMetrics {
+    fn record_persist_latency(&self, _latency: Duration) {
+        self.persists.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn record_query_items(&self, items: usize) {
+        self.query_items.fetch_add(items, Ordering::SeqCst);
+    }
+
+    fn incr_conflict(&self) {
+        self.conflicts.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn create_store(setup: &LocalStackSetup, metrics: CountingMetrics) -> DynamoDB {
+    DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .metrics(metrics)
+        .build()
+}
+
+#[tokio::test]
+async fn test_metrics_record_persist_and_stream_items() {
+    let setup = LocalStackSetup::new().await;
+    let metrics = CountingMetrics::default();
+    let store = create_store(&setup, metrics.clone());
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMMA";
+    let events = vec![
+        create_test_domain_event(aggregate_id, 1, "TestAggregateCreated"),
+        create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated"),
+    ];
+    store
+        .persist(&events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let streamed: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .try_collect()
+        .await
+        .expect("Failed to stream events");
+
+    assert_eq!(streamed.len(), 2);
+    assert_eq!(metrics.persists.load(Ordering::SeqCst), 1);
+    assert_eq!(metrics.query_items.load(Ordering::SeqCst), 2);
+    assert_eq!(metrics.conflicts.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_metrics_record_snapshot_query_items() {
+    let setup = LocalStackSetup::new().await;
+    let metrics = CountingMetrics::default();
+    let store = create_store(&setup, metrics.clone());
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMMB";
+    let snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: b"snapshot".to_vec(),
+        seq_nr: 2,
+        version: 1,
+    };
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            Some(&snapshot),
+            &[],
+        )
+        .await
+        .expect("Failed to persist snapshot");
+
+    store
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .expect("Failed to query snapshot")
+        .expect("Snapshot should exist");
+
+    assert_eq!(metrics.persists.load(Ordering::SeqCst), 1);
+    assert_eq!(metrics.query_items.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_metrics_count_conflicts() {
+    let setup = LocalStackSetup::new().await;
+    let metrics = CountingMetrics::default();
+    let store = create_store(&setup, metrics.clone());
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMMC";
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist first writer's event");
+    let result = store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[],
+        )
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(metrics.persists.load(Ordering::SeqCst), 2);
+    assert_eq!(metrics.conflicts.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,264 @@
+mod common;
+
+use common::{fixtures::*, LocalStackSetup};
+use futures::TryStreamExt;
+use tsuzuri::{
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, Persister},
+    integration_event::SerializedIntegrationEvent,
+    AggregateRoot,
+};
+use tsuzuri_dynamodb::store::{
+    outbox::{OutboxMode, OutboxStatus},
+    DynamoDB, TableNames,
+};
+use uuid::Uuid;
+
+fn create_integration_event(aggregate_id: &str, id: &str) -> SerializedIntegrationEvent {
+    SerializedIntegrationEvent {
+        id: id.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        event_type: "TestIntegrationEvent".to_string(),
+        payload: id.as_bytes().to_vec(),
+        metadata: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_poll_outbox_returns_pending_records_in_order() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMO1";
+    let domain_event = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    let integration_events = vec![
+        create_integration_event(aggregate_id, "evt-0001"),
+        create_integration_event(aggregate_id, "evt-0002"),
+    ];
+
+    store
+        .persist(&[domain_event], &integration_events, None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let records = store.poll_outbox(10).await.expect("Failed to poll outbox");
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].skey, "evt-0001");
+    assert_eq!(records[1].skey, "evt-0002");
+    assert_eq!(records[0].aggregate_id, aggregate_id);
+    assert_eq!(records[0].payload, b"evt-0001".to_vec());
+    assert!(records
+        .iter()
+        .all(|record| record.status == OutboxStatus::Pending && record.attempts == 0));
+
+    let limited = store.poll_outbox(1).await.expect("Failed to poll outbox");
+    assert_eq!(limited.len(), 1);
+    assert_eq!(limited[0].skey, "evt-0001");
+}
+
+#[tokio::test]
+async fn test_mark_dispatched_removes_record_from_poll() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMO2";
+    let integration_event = create_integration_event(aggregate_id, &Uuid::new_v4().to_string());
+
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[integration_event],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist events");
+
+    let records = store.poll_outbox(10).await.expect("Failed to poll outbox");
+    assert_eq!(records.len(), 1);
+
+    store
+        .mark_dispatched(&records[0].pkey, &records[0].skey)
+        .await
+        .expect("Failed to mark record as dispatched");
+
+    let records = store.poll_outbox(10).await.expect("Failed to poll outbox");
+    assert!(records.is_empty(), "Dispatched records should not be polled again");
+}
+
+#[tokio::test]
+async fn test_mark_failed_increments_attempts() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMO3";
+    let integration_event = create_integration_event(aggregate_id, &Uuid::new_v4().to_string());
+
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[integration_event],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist events");
+
+    let record = store.poll_outbox(10).await.expect("Failed to poll outbox").remove(0);
+
+    for _ in 0..2 {
+        let status = store
+            .mark_failed(&record.pkey, &record.skey)
+            .await
+            .expect("Failed to mark record as failed");
+        assert_eq!(status, OutboxStatus::Pending);
+    }
+
+    let records = store.poll_outbox(10).await.expect("Failed to poll outbox");
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].status, OutboxStatus::Pending);
+    assert_eq!(records[0].attempts, 2);
+}
+
+#[tokio::test]
+async fn test_mark_unknown_record_fails() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    assert!(store.mark_dispatched("missing", "missing").await.is_err());
+    assert!(store.mark_failed("missing", "missing").await.is_err());
+}
+
+#[tokio::test]
+async fn test_mark_failed_moves_record_to_dead_letters_at_threshold() {
+    let setup = LocalStackSetup::new().await;
+    let store = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .dead_letter_after(3)
+        .build();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMO4";
+    let integration_event = create_integration_event(aggregate_id, &Uuid::new_v4().to_string());
+
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[integration_event],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist events");
+
+    let record = store.poll_outbox(10).await.expect("Failed to poll outbox").remove(0);
+
+    for attempt in 1..3 {
+        let status = store
+            .mark_failed(&record.pkey, &record.skey)
+            .await
+            .expect("Failed to mark record as failed");
+        assert_eq!(status, OutboxStatus::Pending, "attempt {attempt} should stay pending");
+    }
+    assert!(store
+        .poll_dead_letters(10)
+        .await
+        .expect("Failed to poll dead letters")
+        .is_empty());
+
+    let status = store
+        .mark_failed(&record.pkey, &record.skey)
+        .await
+        .expect("Failed to mark record as failed");
+    assert_eq!(status, OutboxStatus::Dead);
+
+    assert!(store.poll_outbox(10).await.expect("Failed to poll outbox").is_empty());
+    let dead_letters = store.poll_dead_letters(10).await.expect("Failed to poll dead letters");
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].skey, record.skey);
+    assert_eq!(dead_letters[0].status, OutboxStatus::Dead);
+    assert_eq!(dead_letters[0].attempts, 3);
+}
+
+/// Store whose outbox table does not exist, so every outbox write fails.
+fn create_store_with_missing_outbox(setup: &LocalStackSetup, mode: OutboxMode) -> DynamoDB {
+    DynamoDB::builder(setup.client.clone())
+        .table_names(TableNames {
+            outbox: format!("missing-outbox-{}", Uuid::new_v4().simple()),
+            ..setup.table_names.clone()
+        })
+        .outbox_mode(mode)
+        .build()
+}
+
+async fn journal_len(store: &DynamoDB, aggregate_id: &str) -> usize {
+    let events: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .try_collect()
+        .await
+        .expect("Failed to stream events");
+    events.len()
+}
+
+#[tokio::test]
+async fn test_transactional_outbox_failure_fails_persist() {
+    let setup = LocalStackSetup::new().await;
+    let store = create_store_with_missing_outbox(&setup, OutboxMode::Transactional);
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMO5";
+    let result = store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[create_integration_event(aggregate_id, "evt-0001")],
+            None,
+            &[],
+        )
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(journal_len(&store, aggregate_id).await, 0);
+}
+
+#[tokio::test]
+async fn test_best_effort_outbox_failure_keeps_journal_write() {
+    let setup = LocalStackSetup::new().await;
+    let store = create_store_with_missing_outbox(&setup, OutboxMode::BestEffort);
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMO6";
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[create_integration_event(aggregate_id, "evt-0001")],
+            None,
+            &[],
+        )
+        .await
+        .expect("Outbox failures should not fail a best-effort persist");
+
+    assert_eq!(journal_len(&store, aggregate_id).await, 1);
+}
+
+#[tokio::test]
+async fn test_best_effort_outbox_writes_records_after_journal() {
+    let setup = LocalStackSetup::new().await;
+    let store = DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .outbox_mode(OutboxMode::BestEffort)
+        .build();
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMO7";
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[create_integration_event(aggregate_id, "evt-0001")],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist events");
+
+    let records = store.poll_outbox(10).await.expect("Failed to poll outbox");
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].skey, "evt-0001");
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use aws_sdk_dynamodb::types::{AttributeDefinition, BillingMode, KeySchemaElement, KeyType, ScalarAttributeType};
+use common::LocalStackSetup;
+use std::time::Duration;
+use tsuzuri::processed_event_store::ProcessedEventStore;
+use tsuzuri_dynamodb::processed_event_store::DynamoDBProcessedEventStore;
+
+async fn create_processed_event_table(setup: &LocalStackSetup) -> String {
+    let table_name = format!("test-processed-events-{}", uuid::Uuid::new_v4().simple());
+    setup
+        .client
+        .create_table()
+        .table_name(&table_name)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("pkey")
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+                .unwrap(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("skey")
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+                .unwrap(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("pkey")
+                .key_type(KeyType::Hash)
+                .build()
+                .unwrap(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("skey")
+                .key_type(KeyType::Range)
+                .build()
+                .unwrap(),
+        )
+        .send()
+        .await
+        .expect("Failed to create processed event table");
+    table_name
+}
+
+#[tokio::test]
+async fn test_mark_processed_is_remembered() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_processed_event_table(&setup).await;
+    let store = DynamoDBProcessedEventStore::new(setup.client.clone(), &table_name, "order-projection");
+
+    assert!(!store.has_processed("evt-1").await.unwrap());
+
+    store.mark_processed("evt-1").await.expect("Failed to mark event");
+    store.mark_processed("evt-1").await.expect("Failed to mark event twice");
+
+    assert!(store.has_processed("evt-1").await.unwrap());
+    assert!(!store.has_processed("evt-2").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_processed_events_are_isolated_per_consumer() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_processed_event_table(&setup).await;
+    let orders = DynamoDBProcessedEventStore::new(setup.client.clone(), &table_name, "orders");
+    let billing = DynamoDBProcessedEventStore::new(setup.client.clone(), &table_name, "billing");
+
+    orders.mark_processed("evt-1").await.unwrap();
+
+    assert!(orders.has_processed("evt-1").await.unwrap());
+    assert!(!billing.has_processed("evt-1").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_expired_event_ids_are_treated_as_absent() {
+    let setup = LocalStackSetup::new().await;
+    let table_name = create_processed_event_table(&setup).await;
+    let store = DynamoDBProcessedEventStore::new(setup.client.clone(), &table_name, "orders").with_ttl(Duration::ZERO);
+
+    store.mark_processed("evt-1").await.unwrap();
+
+    assert!(!store.has_processed("evt-1").await.unwrap());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{fixtures::*, LocalStackSetup};
+use futures::TryStreamExt;
+use std::collections::HashSet;
+use tsuzuri::{event_store::Persister, AggregateRoot};
+
+#[tokio::test]
+async fn test_scan_aggregate_ids_returns_every_aggregate_once() {
+    let setup = LocalStackSetup::new().await;
+    let store = setup.create_dynamodb_store();
+
+    // 600 events overflow the first page of the scan, so aggregates end up split across pages.
+    let aggregate_ids: Vec<_> = (1..=12)
+        .map(|n| format!("test-01J1234567890ABCDEFGHJK{n:03}"))
+        .collect();
+    for aggregate_id in &aggregate_ids {
+        let events: Vec<_> = (1..=50)
+            .map(|seq_nr| create_test_domain_event(aggregate_id, seq_nr, "TestAggregateUpdated"))
+            .collect();
+        store
+            .persist(&events, &[], None, &[])
+            .await
+            .expect("Failed to persist events");
+    }
+
+    // Aggregates of other types are left out of the scan.
+    let other = tsuzuri::domain_event::SerializedDomainEvent {
+        aggregate_type: "OtherAggregate".to_string(),
+        ..create_test_domain_event("other-01J1234567890ABCDEFGHJKMS4", 1, "OtherCreated")
+    };
+    store
+        .persist(&[other], &[], None, &[])
+        .await
+        .expect("Failed to persist event");
+
+    let scanned: Vec<String> = store
+        .scan_aggregate_ids(TestAggregate::TYPE)
+        .try_collect()
+        .await
+        .expect("Failed to scan aggregate IDs");
+
+    let unique: HashSet<_> = scanned.iter().collect();
+    assert_eq!(
+        unique.len(),
+        scanned.len(),
+        "aggregate IDs returned more than once: {scanned:?}"
+    );
+    let mut sorted = scanned.clone();
+    sorted.sort();
+    assert_eq!(sorted, aggregate_ids);
+
+    // Resuming from the cursor of the first page yields the rest of the scan.
+    let first = store
+        .scan_aggregate_ids_page(TestAggregate::TYPE, None)
+        .await
+        .expect("Failed to scan first page");
+    let cursor = first.cursor.expect("Scan should not fit in a single page");
+    let rest: Vec<String> = store
+        .scan_aggregate_ids_from(TestAggregate::TYPE, Some(cursor))
+        .try_collect()
+        .await
+        .expect("Failed to resume scan");
+    let mut resumed = first.aggregate_ids;
+    resumed.extend(rest);
+    assert_eq!(resumed, scanned);
+
+    let empty: Vec<String> = store
+        .scan_aggregate_ids("UnknownAggregate")
+        .try_collect()
+        .await
+        .expect("Failed to scan aggregate IDs");
+    assert!(empty.is_empty());
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::{fixtures::*, LocalStackSetup};
+use futures::StreamExt;
+use tsuzuri::{
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, Persister, SnapshotGetter},
+    snapshot::PersistedSnapshot,
+    AggregateRoot,
+};
+use tsuzuri_dynamodb::store::{key::resolve_partition_key, DynamoDB};
+
+/// Returns an aggregate ID whose partition differs between the two shard counts.
+fn aggregate_id_moved_between(old_shard_count: usize, new_shard_count: usize) -> String {
+    (0..)
+        .map(|i| format!("test-01J1234567890ABCDEFGHJ{i:04}"))
+        .find(|id| {
+            resolve_partition_key(id.clone(), TestAggregate::TYPE.to_string(), old_shard_count)
+                != resolve_partition_key(id.clone(), TestAggregate::TYPE.to_string(), new_shard_count)
+        })
+        .unwrap()
+}
+
+fn create_store(setup: &LocalStackSetup, shard_count: usize, legacy_shard_counts: Vec<usize>) -> DynamoDB {
+    DynamoDB::builder(setup.client.clone())
+        .table_names(setup.table_names.clone())
+        .shard_count(shard_count)
+        .legacy_shard_counts(legacy_shard_counts)
+        .build()
+}
+
+#[tokio::test]
+async fn test_read_after_shard_count_change_with_legacy_shard_counts() {
+    let setup = LocalStackSetup::new().await;
+    let old_store = create_store(&setup, 4, vec![]);
+    let aggregate_id = aggregate_id_moved_between(4, 8);
+
+    let snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.clone(),
+        aggregate: b"snapshot".to_vec(),
+        seq_nr: 2,
+        version: 1,
+    };
+    old_store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            Some(&snapshot),
+            &[],
+        )
+        .await
+        .expect("Failed to persist with shard_count=4");
+
+    let unmigrated_store = create_store(&setup, 8, vec![]);
+    let missing = unmigrated_store
+        .get_snapshot::<TestAggregate>(&aggregate_id)
+        .await
+        .expect("Failed to query snapshot");
+    assert!(missing.is_none(), "Snapshot should live in a shard_count=4 partition");
+
+    let migrated_store = create_store(&setup, 8, vec![4]);
+    let retrieved = migrated_store
+        .get_snapshot::<TestAggregate>(&aggregate_id)
+        .await
+        .expect("Failed to query snapshot")
+        .expect("Snapshot should be found through the legacy shard count");
+    assert_eq!(retrieved, snapshot);
+
+    let events: Vec<_> = migrated_store
+        .stream_events::<TestAggregate>(&aggregate_id, SequenceSelect::All)
+        .collect()
+        .await;
+    assert_eq!(events.len(), 1);
+}
+
+#[tokio::test]
+async fn test_newest_snapshot_wins_across_shard_counts() {
+    let setup = LocalStackSetup::new().await;
+    let old_store = create_store(&setup, 4, vec![]);
+    let new_store = create_store(&setup, 8, vec![4]);
+    let aggregate_id = aggregate_id_moved_between(4, 8);
+
+    let old_snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.clone(),
+        aggregate: b"old".to_vec(),
+        seq_nr: 2,
+        version: 1,
+    };
+    old_store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            Some(&old_snapshot),
+            &[],
+        )
+        .await
+        .expect("Failed to persist with shard_count=4");
+
+    let new_snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.clone(),
+        aggregate: b"new".to_vec(),
+        seq_nr: 3,
+        version: 2,
+    };
+    new_store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 2, "TestAggregateUpdated")],
+            &[],
+            Some(&new_snapshot),
+            &[],
+        )
+        .await
+        .expect("Failed to persist with shard_count=8");
+
+    let retrieved = new_store
+        .get_snapshot::<TestAggregate>(&aggregate_id)
+        .await
+        .expect("Failed to query snapshot")
+        .expect("Snapshot should exist");
+    assert_eq!(retrieved, new_snapshot);
+}
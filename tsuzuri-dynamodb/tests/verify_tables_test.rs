@@ -0,0 +1,166 @@
+mod common;
+
+use aws_sdk_dynamodb::types::{
+    AttributeDefinition, BillingMode, GlobalSecondaryIndex, KeySchemaElement, KeyType, Projection, ProjectionType,
+    ScalarAttributeType,
+};
+use common::LocalStackSetup;
+use tsuzuri_dynamodb::store::{error::DynamoAggregateError, DynamoDB, TableNames};
+
+fn create_store(setup: &LocalStackSetup, table_names: TableNames) -> DynamoDB {
+    DynamoDB::builder(setup.client.clone()).table_names(table_names).build()
+}
+
+#[tokio::test]
+async fn test_verify_tables_passes_for_created_tables() {
+    let setup = LocalStackSetup::new().await;
+
+    setup
+        .create_dynamodb_store()
+        .verify_tables()
+        .await
+        .expect("Created tables should verify");
+}
+
+#[tokio::test]
+async fn test_verify_tables_names_missing_table() {
+    let setup = LocalStackSetup::new().await;
+    let store = create_store(
+        &setup,
+        TableNames {
+            outbox: "missing-outbox".to_string(),
+            ..setup.table_names.clone()
+        },
+    );
+
+    let error = store.verify_tables().await.unwrap_err();
+    assert!(
+        matches!(&error, DynamoAggregateError::TableMissing { field: "outbox", table } if table == "missing-outbox"),
+        "unexpected error: {error}"
+    );
+}
+
+#[tokio::test]
+async fn test_verify_tables_names_missing_index() {
+    let setup = LocalStackSetup::new().await;
+    let store = create_store(
+        &setup,
+        TableNames {
+            journal_aid_index: "missing-journal-aid-index".to_string(),
+            ..setup.table_names.clone()
+        },
+    );
+
+    let error = store.verify_tables().await.unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            DynamoAggregateError::IndexMissing { field: "journal_aid_index", table, index }
+                if *table == setup.table_names.journal && index == "missing-journal-aid-index"
+        ),
+        "unexpected error: {error}"
+    );
+}
+
+#[tokio::test]
+async fn test_verify_tables_rejects_wrong_key_schema() {
+    let setup = LocalStackSetup::new().await;
+    let table = format!("test-hash-only-{}", uuid::Uuid::new_v4().simple());
+    setup
+        .client
+        .create_table()
+        .table_name(&table)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("pkey")
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+                .unwrap(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("pkey")
+                .key_type(KeyType::Hash)
+                .build()
+                .unwrap(),
+        )
+        .send()
+        .await
+        .expect("Failed to create table");
+    let store = create_store(
+        &setup,
+        TableNames {
+            inverted_index: table.clone(),
+            ..setup.table_names.clone()
+        },
+    );
+
+    let error = store.verify_tables().await.unwrap_err();
+    assert!(
+        matches!(&error, DynamoAggregateError::TableSchemaMismatch { table: found_table, found, .. }
+            if *found_table == table && found == "pkey HASH"),
+        "unexpected error: {error}"
+    );
+}
+
+#[tokio::test]
+async fn test_verify_tables_rejects_index_with_wrong_range_key() {
+    let setup = LocalStackSetup::new().await;
+    let table = format!("test-journal-wrong-index-{}", uuid::Uuid::new_v4().simple());
+    let attribute = |name: &str| {
+        AttributeDefinition::builder()
+            .attribute_name(name)
+            .attribute_type(ScalarAttributeType::S)
+            .build()
+            .unwrap()
+    };
+    let key = |name: &str, key_type: KeyType| {
+        KeySchemaElement::builder()
+            .attribute_name(name)
+            .key_type(key_type)
+            .build()
+            .unwrap()
+    };
+    setup
+        .client
+        .create_table()
+        .table_name(&table)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(attribute("pkey"))
+        .attribute_definitions(attribute("skey"))
+        .attribute_definitions(attribute("aid"))
+        .key_schema(key("pkey", KeyType::Hash))
+        .key_schema(key("skey", KeyType::Range))
+        .global_secondary_indexes(
+            GlobalSecondaryIndex::builder()
+                .index_name(&setup.table_names.journal_aid_index)
+                .key_schema(key("aid", KeyType::Hash))
+                .key_schema(key("skey", KeyType::Range))
+                .projection(Projection::builder().projection_type(ProjectionType::All).build())
+                .build()
+                .unwrap(),
+        )
+        .send()
+        .await
+        .expect("Failed to create table");
+    let store = create_store(
+        &setup,
+        TableNames {
+            journal: table,
+            ..setup.table_names.clone()
+        },
+    );
+
+    let error = store.verify_tables().await.unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            DynamoAggregateError::IndexSchemaMismatch { index, expected, found }
+                if *index == setup.table_names.journal_aid_index
+                    && expected == "aid HASH, seq_nr RANGE"
+                    && found == "aid HASH, skey RANGE"
+        ),
+        "unexpected error: {error}"
+    );
+}
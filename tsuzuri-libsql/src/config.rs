@@ -1,9 +1,17 @@
 use crate::read::{ConnectionConfig, EmbeddedReplicaConfig, RemoteConfig};
 use std::time::Duration;
 
+/// Connections the pool of a configured `ConnectionManager` opens at most unless `max_connections` is set.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 4;
+
+/// Shortest sync interval accepted for embedded replicas; shorter intervals keep the replica syncing nonstop.
+pub const MIN_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Clone)]
 pub struct LibSqlConfig {
     pub connection: ConnectionConfig,
+    /// Upper bound on the connections the pool opens; concurrent operations beyond it wait for a free one.
+    pub max_connections: usize,
 }
 
 impl LibSqlConfig {
@@ -17,6 +25,7 @@ impl LibSqlConfig {
                 url: url.into(),
                 auth_token: auth_token.into(),
             }),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
         }
     }
 
@@ -33,12 +42,18 @@ impl LibSqlConfig {
                 sync_interval: None,
                 encryption_key: None,
             }),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
         }
     }
 
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
         use std::env;
 
+        let max_connections = env::var("DATABASE_MAX_CONNECTIONS")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS);
+
         if env::var("DATABASE_USE_EMBEDDED_REPLICA").unwrap_or_default() == "true" {
             let config = EmbeddedReplicaConfig {
                 local_path: env::var("DATABASE_LOCAL_PATH").unwrap_or_else(|_| "local.db".to_string()),
@@ -52,6 +67,7 @@ impl LibSqlConfig {
             };
             Ok(Self {
                 connection: ConnectionConfig::EmbeddedReplica(config),
+                max_connections,
             })
         } else {
             let config = RemoteConfig {
@@ -60,11 +76,17 @@ impl LibSqlConfig {
             };
             Ok(Self {
                 connection: ConnectionConfig::Remote(config),
+                max_connections,
             })
         }
     }
 
     pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_connections == 0 {
+            return Err(ConfigError::InvalidConfiguration(
+                "Max connections must be at least 1".to_string(),
+            ));
+        }
         match &self.connection {
             ConnectionConfig::Remote(config) => {
                 if config.url.is_empty() {
@@ -102,6 +124,11 @@ impl LibSqlConfig {
                         "Sync URL must start with libsql:// or https://".to_string(),
                     ));
                 }
+                if let Some(interval) = config.sync_interval {
+                    if interval < MIN_SYNC_INTERVAL {
+                        return Err(ConfigError::InvalidSyncInterval(interval));
+                    }
+                }
                 if let Some(ref key) = config.encryption_key {
                     let key_len = if key.len() == 64 { 32 } else { key.len() };
                     if key_len != 32 {
@@ -123,6 +150,7 @@ impl Default for LibSqlConfig {
                 url: String::new(),
                 auth_token: String::new(),
             }),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
         }
     }
 }
@@ -135,6 +163,7 @@ pub struct LibSqlConfigBuilder {
     local_path: Option<String>,
     sync_interval: Option<Duration>,
     encryption_key: Option<String>,
+    max_connections: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -183,13 +212,23 @@ impl LibSqlConfigBuilder {
         self
     }
 
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
     pub fn build(self) -> Result<LibSqlConfig, ConfigError> {
         let connection_type = self.connection_type.ok_or(ConfigError::MissingConnectionType)?;
         let url = self.url.ok_or(ConfigError::MissingUrl)?;
         let auth_token = self.auth_token.ok_or(ConfigError::MissingAuthToken)?;
 
         let connection = match connection_type {
-            ConnectionType::Remote => ConnectionConfig::Remote(RemoteConfig { url, auth_token }),
+            ConnectionType::Remote => {
+                if self.sync_interval.is_some() {
+                    return Err(ConfigError::SyncIntervalOnRemote);
+                }
+                ConnectionConfig::Remote(RemoteConfig { url, auth_token })
+            }
             ConnectionType::EmbeddedReplica => {
                 let local_path = self.local_path.ok_or(ConfigError::MissingLocalPath)?;
                 ConnectionConfig::EmbeddedReplica(EmbeddedReplicaConfig {
@@ -202,7 +241,10 @@ impl LibSqlConfigBuilder {
             }
         };
 
-        let config = LibSqlConfig { connection };
+        let config = LibSqlConfig {
+            connection,
+            max_connections: self.max_connections.unwrap_or(DEFAULT_MAX_CONNECTIONS),
+        };
         config.validate()?;
         Ok(config)
     }
@@ -220,4 +262,71 @@ pub enum ConfigError {
     MissingLocalPath,
     #[error("Invalid configuration: {0}")]
     InvalidConfiguration(String),
+    #[error("Sync interval must be at least 1 second, got {0:?}")]
+    InvalidSyncInterval(Duration),
+    #[error("Sync interval only applies to embedded replicas, not remote connections")]
+    SyncIntervalOnRemote,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embedded_replica(sync_interval: Duration) -> Result<LibSqlConfig, ConfigError> {
+        LibSqlConfig::builder()
+            .embedded_replica()
+            .url("libsql://example.turso.io")
+            .auth_token("token")
+            .local_path("local.db")
+            .sync_interval(sync_interval)
+            .build()
+    }
+
+    #[test]
+    fn test_zero_sync_interval_is_rejected() {
+        assert!(matches!(
+            embedded_replica(Duration::ZERO),
+            Err(ConfigError::InvalidSyncInterval(interval)) if interval.is_zero()
+        ));
+        assert!(matches!(
+            embedded_replica(Duration::from_millis(500)),
+            Err(ConfigError::InvalidSyncInterval(_))
+        ));
+    }
+
+    #[test]
+    fn test_valid_sync_interval_is_kept() {
+        let config = embedded_replica(Duration::from_secs(30)).unwrap();
+        match config.connection {
+            ConnectionConfig::EmbeddedReplica(replica) => {
+                assert_eq!(replica.sync_interval, Some(Duration::from_secs(30)));
+            }
+            ConnectionConfig::Remote(_) => panic!("Expected an embedded replica config"),
+        }
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let config = embedded_replica(Duration::from_secs(30)).unwrap();
+        assert_eq!(config.max_connections, DEFAULT_MAX_CONNECTIONS);
+
+        let result = LibSqlConfig::builder()
+            .remote()
+            .url("libsql://example.turso.io")
+            .auth_token("token")
+            .max_connections(0)
+            .build();
+        assert!(matches!(result, Err(ConfigError::InvalidConfiguration(_))));
+    }
+
+    #[test]
+    fn test_sync_interval_on_remote_is_rejected() {
+        let result = LibSqlConfig::builder()
+            .remote()
+            .url("libsql://example.turso.io")
+            .auth_token("token")
+            .sync_interval(Duration::from_secs(30))
+            .build();
+        assert!(matches!(result, Err(ConfigError::SyncIntervalOnRemote)));
+    }
 }
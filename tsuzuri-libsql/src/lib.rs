@@ -1,5 +1,9 @@
 mod config;
+mod pool;
 mod read;
+pub mod store;
 
-pub use config::{ConfigError, LibSqlConfig, LibSqlConfigBuilder};
-pub use read::{ConnectionConfig, ConnectionManager, EmbeddedReplicaConfig, RemoteConfig};
+pub use config::{ConfigError, LibSqlConfig, LibSqlConfigBuilder, DEFAULT_MAX_CONNECTIONS, MIN_SYNC_INTERVAL};
+pub use pool::{PoolMetrics, PooledConnection};
+pub use read::{ConnectionConfig, ConnectionManager, EmbeddedReplicaConfig, HealthReport, RemoteConfig, SyncStatus};
+pub use store::LibSqlEventStore;
@@ -0,0 +1,98 @@
+use libsql::{Connection, Database};
+use std::{ops::Deref, sync::Mutex};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Connections handed out by [`crate::ConnectionManager::acquire`].
+///
+/// Connections are opened on demand up to `max_connections` and kept idle after use, so concurrent commands and
+/// queries each run on a connection of their own instead of sharing one, which also keeps their transactions
+/// apart.
+#[derive(Debug)]
+pub(crate) struct ConnectionPool {
+    idle: Mutex<Vec<Connection>>,
+    permits: Semaphore,
+    max_connections: usize,
+}
+
+/// Point-in-time usage of the connection pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolMetrics {
+    pub max_connections: usize,
+    /// Connections currently handed out.
+    pub in_use: usize,
+    /// Open connections waiting in the pool.
+    pub idle: usize,
+}
+
+impl ConnectionPool {
+    /// Creates a pool whose first connection is `first`. A `max_connections` of 0 is treated as 1.
+    pub(crate) fn new(first: Connection, max_connections: usize) -> Self {
+        let max_connections = max_connections.max(1);
+        Self {
+            idle: Mutex::new(vec![first]),
+            permits: Semaphore::new(max_connections),
+            max_connections,
+        }
+    }
+
+    /// Same pool with room for `max_connections` connections. The idle connections are kept, which matters for
+    /// `":memory:"` databases, whose data only lives as long as their connection.
+    pub(crate) fn with_max_connections(self, max_connections: usize) -> Self {
+        let max_connections = max_connections.max(1);
+        Self {
+            idle: self.idle,
+            permits: Semaphore::new(max_connections),
+            max_connections,
+        }
+    }
+
+    pub(crate) async fn acquire(&self, database: &Database) -> Result<PooledConnection<'_>, libsql::Error> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the pool semaphore is never closed");
+        let idle = self.idle.lock().unwrap().pop();
+        let connection = match idle {
+            Some(connection) => connection,
+            None => database.connect()?,
+        };
+        Ok(PooledConnection {
+            connection: Some(connection),
+            pool: self,
+            _permit: permit,
+        })
+    }
+
+    pub(crate) fn metrics(&self) -> PoolMetrics {
+        PoolMetrics {
+            max_connections: self.max_connections,
+            in_use: self.max_connections - self.permits.available_permits(),
+            idle: self.idle.lock().unwrap().len(),
+        }
+    }
+}
+
+/// Connection taken from the pool; it goes back to the pool when dropped.
+#[derive(Debug)]
+pub struct PooledConnection<'a> {
+    connection: Option<Connection>,
+    pool: &'a ConnectionPool,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection.as_ref().expect("the connection is only taken on drop")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.idle.lock().unwrap().push(connection);
+        }
+    }
+}
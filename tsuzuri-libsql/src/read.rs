@@ -1,7 +1,13 @@
-use crate::config::LibSqlConfig;
+use crate::{
+    config::{LibSqlConfig, DEFAULT_MAX_CONNECTIONS},
+    pool::{ConnectionPool, PoolMetrics, PooledConnection},
+};
 use bytes::Bytes;
-use libsql::{Builder, Cipher, Connection, Database, EncryptionConfig};
-use std::time::Duration;
+use libsql::{Builder, Cipher, Database, EncryptionConfig};
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
 
 #[derive(Debug, Clone)]
 pub struct RemoteConfig {
@@ -24,18 +30,39 @@ pub enum ConnectionConfig {
     EmbeddedReplica(EmbeddedReplicaConfig),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConnectionType {
-    Remote(Connection),
-    EmbeddedReplica {
-        connection: Connection,
-        database: Box<Database>,
-    },
+    Remote,
+    EmbeddedReplica,
+    Local,
+}
+
+/// Outcome of a sync of an embedded replica started through [`ConnectionManager::sync_now`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncStatus {
+    pub synced_at: SystemTime,
+    /// Replication index of the replica after the sync, if it has received any frames.
+    pub frame_no: Option<u64>,
+    pub frames_synced: usize,
+}
+
+/// Result of a successful [`ConnectionManager::health_check`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthReport {
+    /// Replication index of an embedded replica, including frames pulled by periodic syncs. `None` for other
+    /// connections.
+    pub replication_index: Option<u64>,
+    /// Last sync started through [`ConnectionManager::sync_now`] or [`ConnectionManager::sync`]. Periodic syncs
+    /// configured with `sync_interval` are not recorded.
+    pub last_sync: Option<SyncStatus>,
 }
 
 #[derive(Debug)]
 pub struct ConnectionManager {
     connection_type: ConnectionType,
+    database: Database,
+    pool: ConnectionPool,
+    last_sync: Mutex<Option<SyncStatus>>,
 }
 
 impl ConnectionManager {
@@ -47,15 +74,14 @@ impl ConnectionManager {
     }
 
     pub async fn from_config(config: LibSqlConfig) -> Result<Self, libsql::Error> {
-        Self::new(config.connection).await
+        Ok(Self::new(config.connection)
+            .await?
+            .with_max_connections(config.max_connections))
     }
 
     pub async fn new_remote(config: RemoteConfig) -> Result<Self, libsql::Error> {
         let db = Builder::new_remote(config.url, config.auth_token).build().await?;
-        let conn = db.connect()?;
-        Ok(Self {
-            connection_type: ConnectionType::Remote(conn),
-        })
+        Self::with_database(ConnectionType::Remote, db, DEFAULT_MAX_CONNECTIONS)
     }
 
     pub async fn new_embedded_replica(config: EmbeddedReplicaConfig) -> Result<Self, libsql::Error> {
@@ -86,39 +112,104 @@ impl ConnectionManager {
         }
 
         let db = builder.build().await?;
-        let conn = db.connect()?;
+        Self::with_database(ConnectionType::EmbeddedReplica, db, DEFAULT_MAX_CONNECTIONS)
+    }
+
+    /// Opens a local SQLite database file. Pass `":memory:"` for an in-memory database.
+    ///
+    /// The pool holds a single connection, since every connection to `":memory:"` opens a database of its own.
+    /// Raise it with [`ConnectionManager::with_max_connections`] for database files.
+    pub async fn new_local(path: impl AsRef<std::path::Path>) -> Result<Self, libsql::Error> {
+        let db = Builder::new_local(path).build().await?;
+        Self::with_database(ConnectionType::Local, db, 1)
+    }
 
+    fn with_database(
+        connection_type: ConnectionType,
+        database: Database,
+        max_connections: usize,
+    ) -> Result<Self, libsql::Error> {
+        let connection = database.connect()?;
         Ok(Self {
-            connection_type: ConnectionType::EmbeddedReplica {
-                connection: conn,
-                database: Box::new(db),
-            },
+            connection_type,
+            pool: ConnectionPool::new(connection, max_connections),
+            database,
+            last_sync: Mutex::new(None),
         })
     }
 
+    /// Lets the pool open up to `max_connections` connections, keeping the connections it already holds.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.pool = self.pool.with_max_connections(max_connections);
+        self
+    }
+
     pub async fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
         let config = LibSqlConfig::from_env()?;
         Ok(Self::from_config(config).await?)
     }
 
-    pub fn get_connection(&self) -> &Connection {
-        match &self.connection_type {
-            ConnectionType::Remote(conn) => conn,
-            ConnectionType::EmbeddedReplica { connection, .. } => connection,
-        }
+    /// Takes a connection from the pool, like [`ConnectionManager::acquire`].
+    ///
+    /// libSQL connections do not isolate their clones from each other, so there is no shared connection: every
+    /// caller gets one to itself, and statements sent through it cannot interleave with a transaction of the store.
+    pub async fn get_connection(&self) -> Result<PooledConnection<'_>, libsql::Error> {
+        self.acquire().await
+    }
+
+    /// Takes a connection from the pool for exclusive use until the returned guard is dropped, waiting while
+    /// `max_connections` connections are in use.
+    pub async fn acquire(&self) -> Result<PooledConnection<'_>, libsql::Error> {
+        self.pool.acquire(&self.database).await
+    }
+
+    pub fn pool_metrics(&self) -> PoolMetrics {
+        self.pool.metrics()
     }
 
     pub async fn sync(&self) -> Result<(), libsql::Error> {
-        match &self.connection_type {
-            ConnectionType::Remote(_) => Ok(()),
-            ConnectionType::EmbeddedReplica { database, .. } => {
-                database.sync().await?;
-                Ok(())
-            }
+        self.sync_now().await?;
+        Ok(())
+    }
+
+    /// Pulls the latest frames from the primary into an embedded replica right away, independent of the
+    /// configured `sync_interval`. Returns `None` for connections that are not embedded replicas.
+    pub async fn sync_now(&self) -> Result<Option<SyncStatus>, libsql::Error> {
+        if !self.is_embedded_replica() {
+            return Ok(None);
         }
+        let replicated = self.database.sync().await?;
+        let status = SyncStatus {
+            synced_at: SystemTime::now(),
+            frame_no: replicated.frame_no(),
+            frames_synced: replicated.frames_synced(),
+        };
+        *self.last_sync.lock().unwrap() = Some(status.clone());
+        Ok(Some(status))
+    }
+
+    /// Last sync started through [`ConnectionManager::sync_now`] or [`ConnectionManager::sync`].
+    pub fn last_sync(&self) -> Option<SyncStatus> {
+        self.last_sync.lock().unwrap().clone()
+    }
+
+    /// Runs `SELECT 1` to confirm the database is reachable and, for embedded replicas, reports how far the
+    /// replica has synced.
+    pub async fn health_check(&self) -> Result<HealthReport, libsql::Error> {
+        let conn = self.acquire().await?;
+        let mut rows = conn.query("SELECT 1", ()).await?;
+        rows.next().await?;
+        let replication_index = match self.connection_type {
+            ConnectionType::EmbeddedReplica => self.database.replication_index().await?,
+            ConnectionType::Remote | ConnectionType::Local => None,
+        };
+        Ok(HealthReport {
+            replication_index,
+            last_sync: self.last_sync(),
+        })
     }
 
     pub fn is_embedded_replica(&self) -> bool {
-        matches!(self.connection_type, ConnectionType::EmbeddedReplica { .. })
+        self.connection_type == ConnectionType::EmbeddedReplica
     }
 }
@@ -0,0 +1,468 @@
+#![forbid(unsafe_code)]
+#![deny(clippy::all)]
+#![warn(rust_2018_idioms)]
+
+pub mod error;
+
+use crate::{read::ConnectionManager, store::error::LibSqlAggregateError};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use libsql::{params, Connection, Row};
+use prost_types::Timestamp;
+use std::collections::HashMap;
+use tsuzuri::{
+    domain_event::SerializedDomainEvent,
+    event::{SequenceSelect, Stream as EventStream},
+    event_store::{AggregateEventStreamer, Persister, SnapshotGetter, SnapshotIntervalProvider},
+    helper::{from_rfc3339, to_rfc3339},
+    integration_event::SerializedIntegrationEvent,
+    inverted_index_store::{AggregateIdsLoader, IndexKeyword, IndexOp, InvertedIndexCommiter, InvertedIndexRemover},
+    persist::PersistenceError,
+    snapshot::PersistedSnapshot,
+    AggregateRoot,
+};
+
+const OUTBOX_STATUS_PENDING: &str = "PENDING";
+const OUTBOX_INITIAL_ATTEMPTS: i64 = 0;
+const DEFAULT_SNAPSHOT_INTERVAL: usize = 100;
+
+const CREATE_TABLES: &str = r#"
+CREATE TABLE IF NOT EXISTS journal (
+    event_id TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    aggregate_type TEXT NOT NULL,
+    seq_nr INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    payload BLOB NOT NULL,
+    metadata TEXT NOT NULL,
+    schema_version INTEGER NOT NULL DEFAULT 1,
+    occurred_at TEXT NOT NULL DEFAULT '1970-01-01T00:00:00+00:00',
+    UNIQUE (aggregate_id, seq_nr)
+);
+CREATE TABLE IF NOT EXISTS snapshot (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    seq_nr INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    payload BLOB NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id)
+);
+CREATE TABLE IF NOT EXISTS outbox (
+    event_id TEXT NOT NULL PRIMARY KEY,
+    aggregate_id TEXT NOT NULL,
+    aggregate_type TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload BLOB NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS outbox_status_index ON outbox (status);
+CREATE TABLE IF NOT EXISTS inverted_index (
+    keyword TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    PRIMARY KEY (keyword, aggregate_id)
+);
+"#;
+
+/// Event store backed by libSQL / SQLite.
+///
+/// Events are appended to the `journal` table, whose `UNIQUE (aggregate_id, seq_nr)` constraint
+/// rejects a second writer for the same sequence number with [`PersistenceError::OptimisticConcurrency`].
+#[derive(Debug)]
+pub struct LibSqlEventStore {
+    manager: ConnectionManager,
+    snapshot_interval: usize,
+    snapshot_intervals: HashMap<&'static str, usize>,
+}
+
+impl LibSqlEventStore {
+    pub fn new(manager: ConnectionManager) -> Self {
+        Self {
+            manager,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            snapshot_intervals: HashMap::new(),
+        }
+    }
+
+    pub fn with_snapshot_interval(mut self, interval: usize) -> Self {
+        self.snapshot_interval = interval;
+        self
+    }
+
+    /// Overrides the snapshot interval for aggregates whose `AggregateRoot::TYPE` is `aggregate_type`.
+    pub fn with_snapshot_interval_for(mut self, aggregate_type: &'static str, interval: usize) -> Self {
+        self.snapshot_intervals.insert(aggregate_type, interval);
+        self
+    }
+
+    pub fn connection_manager(&self) -> &ConnectionManager {
+        &self.manager
+    }
+
+    /// Creates the `journal`, `snapshot`, `outbox` and `inverted_index` tables if they do not exist yet.
+    pub async fn create_tables(&self) -> Result<(), PersistenceError> {
+        let conn = self.manager.acquire().await.map_err(LibSqlAggregateError::from)?;
+        conn.execute_batch(CREATE_TABLES)
+            .await
+            .map_err(LibSqlAggregateError::from)?;
+        Ok(())
+    }
+
+    async fn insert_events(
+        conn: &Connection,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+    ) -> Result<(), LibSqlAggregateError> {
+        for event in domain_events {
+            let metadata = serde_json::to_string(&event.metadata)?;
+            let occurred_at =
+                to_rfc3339(&event.occurred_at).map_err(|e| LibSqlAggregateError::UnknownError(Box::new(e)))?;
+            conn.execute(
+                "INSERT INTO journal \
+                 (event_id, aggregate_id, aggregate_type, seq_nr, event_type, payload, metadata, schema_version, \
+                 occurred_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    event.id.as_str(),
+                    event.aggregate_id.as_str(),
+                    event.aggregate_type.as_str(),
+                    event.seq_nr as i64,
+                    event.event_type.as_str(),
+                    event.payload.clone(),
+                    metadata,
+                    i64::from(event.schema_version),
+                    occurred_at,
+                ],
+            )
+            .await?;
+        }
+
+        for event in integration_events {
+            conn.execute(
+                "INSERT INTO outbox (event_id, aggregate_id, aggregate_type, event_type, payload, status, attempts) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    event.id.as_str(),
+                    event.aggregate_id.as_str(),
+                    event.aggregate_type.as_str(),
+                    event.event_type.as_str(),
+                    event.payload.clone(),
+                    OUTBOX_STATUS_PENDING,
+                    OUTBOX_INITIAL_ATTEMPTS,
+                ],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn apply_index_ops(conn: &Connection, index_ops: &[IndexOp]) -> Result<(), LibSqlAggregateError> {
+        for op in index_ops {
+            match op {
+                IndexOp::Put { keyword, aggregate_id } => {
+                    conn.execute(
+                        "INSERT OR IGNORE INTO inverted_index (keyword, aggregate_id) VALUES (?1, ?2)",
+                        params![keyword.as_str(), aggregate_id.as_str()],
+                    )
+                    .await?;
+                }
+                IndexOp::Delete { keyword, aggregate_id } => {
+                    conn.execute(
+                        "DELETE FROM inverted_index WHERE keyword = ?1 AND aggregate_id = ?2",
+                        params![keyword.as_str(), aggregate_id.as_str()],
+                    )
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn update_snapshot(conn: &Connection, snapshot: &PersistedSnapshot) -> Result<(), LibSqlAggregateError> {
+        let expected_version = snapshot.version.saturating_sub(1);
+        let updated = conn
+            .execute(
+                "INSERT INTO snapshot (aggregate_type, aggregate_id, seq_nr, version, payload) \
+                 VALUES (?1, ?2, ?3, ?4, ?5) \
+                 ON CONFLICT (aggregate_type, aggregate_id) DO UPDATE SET \
+                 seq_nr = excluded.seq_nr, version = excluded.version, payload = excluded.payload \
+                 WHERE snapshot.version = ?6",
+                params![
+                    snapshot.aggregate_type.as_str(),
+                    snapshot.aggregate_id.as_str(),
+                    snapshot.seq_nr as i64,
+                    snapshot.version as i64,
+                    snapshot.aggregate.clone(),
+                    expected_version as i64,
+                ],
+            )
+            .await?;
+        if updated == 0 {
+            return Err(LibSqlAggregateError::OptimisticLock);
+        }
+        Ok(())
+    }
+
+    async fn write(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+    ) -> Result<(), LibSqlAggregateError> {
+        if domain_events.is_empty()
+            && integration_events.is_empty()
+            && snapshot_update.is_none()
+            && index_ops.is_empty()
+        {
+            return Ok(());
+        }
+        let conn = self.manager.acquire().await?;
+        let tx = conn.transaction().await?;
+        let result = async {
+            Self::insert_events(&tx, domain_events, integration_events)
+                .await
+                .map_err(|e| match domain_events.first() {
+                    Some(event) => e.into_concurrency_error(&event.aggregate_id, event.seq_nr.saturating_sub(1)),
+                    None => e,
+                })?;
+            if let Some(snapshot) = snapshot_update {
+                Self::update_snapshot(&tx, snapshot).await?;
+            }
+            Self::apply_index_ops(&tx, index_ops).await?;
+            Ok::<(), LibSqlAggregateError>(())
+        }
+        .await;
+        match result {
+            Ok(()) => {
+                tx.commit().await?;
+                Ok(())
+            }
+            Err(err) => {
+                tx.rollback().await?;
+                Err(err)
+            }
+        }
+    }
+
+    async fn select_events(
+        &self,
+        aggregate_id: &str,
+        seq_nr: usize,
+    ) -> Result<Vec<SerializedDomainEvent>, LibSqlAggregateError> {
+        let conn = self.manager.acquire().await?;
+        let mut rows = conn
+            .query(
+                "SELECT event_id, aggregate_id, seq_nr, aggregate_type, event_type, payload, metadata, schema_version, \
+                 occurred_at FROM journal WHERE aggregate_id = ?1 AND seq_nr >= ?2 ORDER BY seq_nr ASC",
+                params![aggregate_id, seq_nr as i64],
+            )
+            .await?;
+        let mut events = Vec::new();
+        while let Some(row) = rows.next().await? {
+            events.push(serialized_event(&row)?);
+        }
+        Ok(events)
+    }
+
+    async fn count_journal_rows(&self, aggregate_id: &str) -> Result<usize, LibSqlAggregateError> {
+        let conn = self.manager.acquire().await?;
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM journal WHERE aggregate_id = ?1",
+                params![aggregate_id],
+            )
+            .await?;
+        match rows.next().await? {
+            Some(row) => column_as_usize(&row, 0),
+            None => Ok(0),
+        }
+    }
+
+    async fn select_snapshot<T: AggregateRoot>(
+        &self,
+        id: &str,
+    ) -> Result<Option<PersistedSnapshot>, LibSqlAggregateError> {
+        let conn = self.manager.acquire().await?;
+        let mut rows = conn
+            .query(
+                "SELECT payload, seq_nr, version FROM snapshot WHERE aggregate_type = ?1 AND aggregate_id = ?2",
+                params![T::TYPE, id],
+            )
+            .await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        Ok(Some(PersistedSnapshot {
+            aggregate_type: T::TYPE.to_string(),
+            aggregate_id: id.to_string(),
+            aggregate: row.get::<Vec<u8>>(0)?,
+            seq_nr: column_as_usize(&row, 1)?,
+            version: column_as_usize(&row, 2)?,
+        }))
+    }
+
+    async fn insert_inverted_index(&self, aggregate_id: &str, keyword: &str) -> Result<(), LibSqlAggregateError> {
+        self.manager
+            .acquire()
+            .await?
+            .execute(
+                "INSERT OR IGNORE INTO inverted_index (keyword, aggregate_id) VALUES (?1, ?2)",
+                params![keyword, aggregate_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn query_inverted_index(&self, keyword: &str) -> Result<Vec<String>, LibSqlAggregateError> {
+        let conn = self.manager.acquire().await?;
+        let mut rows = conn
+            .query(
+                "SELECT aggregate_id FROM inverted_index WHERE keyword = ?1 ORDER BY aggregate_id",
+                params![keyword],
+            )
+            .await?;
+        let mut targets = Vec::new();
+        while let Some(row) = rows.next().await? {
+            targets.push(row.get::<String>(0)?);
+        }
+        Ok(targets)
+    }
+
+    async fn remove_inverted_index(&self, aggregate_id: &str, keyword: &str) -> Result<(), LibSqlAggregateError> {
+        self.manager
+            .acquire()
+            .await?
+            .execute(
+                "DELETE FROM inverted_index WHERE keyword = ?1 AND aggregate_id = ?2",
+                params![keyword, aggregate_id],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+fn column_as_usize(row: &Row, idx: i32) -> Result<usize, LibSqlAggregateError> {
+    let value = row.get::<i64>(idx)?;
+    usize::try_from(value).map_err(|_| LibSqlAggregateError::InvalidColumn(format!("column {idx}: {value}")))
+}
+
+fn column_as_u32(row: &Row, idx: i32) -> Result<u32, LibSqlAggregateError> {
+    let value = row.get::<i64>(idx)?;
+    u32::try_from(value).map_err(|_| LibSqlAggregateError::InvalidColumn(format!("column {idx}: {value}")))
+}
+
+fn column_as_timestamp(row: &Row, idx: i32) -> Result<Timestamp, LibSqlAggregateError> {
+    let value = row.get::<String>(idx)?;
+    from_rfc3339(&value).map_err(|_| LibSqlAggregateError::InvalidColumn(format!("column {idx}: {value}")))
+}
+
+fn serialized_event(row: &Row) -> Result<SerializedDomainEvent, LibSqlAggregateError> {
+    let metadata = row.get::<String>(6)?;
+    Ok(SerializedDomainEvent {
+        id: row.get::<String>(0)?,
+        aggregate_id: row.get::<String>(1)?,
+        seq_nr: column_as_usize(row, 2)?,
+        aggregate_type: row.get::<String>(3)?,
+        event_type: row.get::<String>(4)?,
+        payload: row.get::<Vec<u8>>(5)?,
+        metadata: serde_json::from_str(&metadata)?,
+        schema_version: column_as_u32(row, 7)?,
+        occurred_at: column_as_timestamp(row, 8)?,
+    })
+}
+
+#[async_trait]
+impl AggregateEventStreamer for LibSqlEventStore {
+    fn stream_events<T: AggregateRoot>(
+        &self,
+        id: &str,
+        select: SequenceSelect,
+    ) -> EventStream<'_, SerializedDomainEvent, PersistenceError> {
+        let id = id.to_string();
+        let seq_nr = match select {
+            SequenceSelect::All => 1,
+            SequenceSelect::From(seq) => seq,
+        };
+        stream::once(async move { self.select_events(&id, seq_nr).await.map_err(PersistenceError::from) })
+            .map_ok(|events| stream::iter(events.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+
+    async fn count_events<T: AggregateRoot>(&self, id: &str) -> Result<usize, PersistenceError> {
+        Ok(self.count_journal_rows(id).await?)
+    }
+}
+
+#[async_trait]
+impl SnapshotGetter for LibSqlEventStore {
+    async fn get_snapshot<T: AggregateRoot>(&self, id: &str) -> Result<Option<PersistedSnapshot>, PersistenceError> {
+        self.select_snapshot::<T>(id).await.map_err(PersistenceError::from)
+    }
+}
+
+#[async_trait]
+impl Persister for LibSqlEventStore {
+    async fn persist(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+    ) -> Result<(), PersistenceError> {
+        self.write(domain_events, integration_events, snapshot_update, index_ops)
+            .await?;
+        Ok(())
+    }
+}
+
+impl SnapshotIntervalProvider for LibSqlEventStore {
+    fn snapshot_interval(&self) -> usize {
+        self.snapshot_interval
+    }
+
+    fn snapshot_interval_for<T: AggregateRoot>(&self) -> usize {
+        self.snapshot_intervals
+            .get(T::TYPE)
+            .copied()
+            .unwrap_or(self.snapshot_interval)
+    }
+}
+
+#[async_trait]
+impl AggregateIdsLoader for LibSqlEventStore {
+    async fn get_aggregate_ids(
+        &self,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<Vec<String>, PersistenceError> {
+        let targets = self.query_inverted_index(keyword.into().as_str()).await?;
+        Ok(targets)
+    }
+}
+
+#[async_trait]
+impl InvertedIndexCommiter for LibSqlEventStore {
+    async fn commit(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        self.insert_inverted_index(aggregate_id, keyword.into().as_str())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl InvertedIndexRemover for LibSqlEventStore {
+    async fn remove(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        self.remove_inverted_index(aggregate_id, keyword.into().as_str())
+            .await?;
+        Ok(())
+    }
+}
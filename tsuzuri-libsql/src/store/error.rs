@@ -0,0 +1,126 @@
+use tsuzuri::{error::AggregateError, persist::PersistenceError};
+
+/// SQLite primary result code for constraint violations (`SQLITE_CONSTRAINT`).
+const SQLITE_CONSTRAINT: i32 = 19;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LibSqlAggregateError {
+    #[error("optimistic lock error")]
+    OptimisticLock,
+    #[error("optimistic concurrency conflict on aggregate {aggregate_id}: expected sequence number {expected_seq}")]
+    OptimisticConcurrency { aggregate_id: String, expected_seq: usize },
+    #[error("invalid column: {0}")]
+    InvalidColumn(String),
+    #[error(transparent)]
+    ConnectionError(libsql::Error),
+    #[error(transparent)]
+    UnknownError(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl From<libsql::Error> for LibSqlAggregateError {
+    fn from(error: libsql::Error) -> Self {
+        if is_constraint_violation(&error) {
+            return Self::OptimisticLock;
+        }
+        match error {
+            libsql::Error::ConnectionFailed(_) | libsql::Error::Hrana(_) => Self::ConnectionError(error),
+            _ => Self::UnknownError(Box::new(error)),
+        }
+    }
+}
+
+impl From<serde_json::Error> for LibSqlAggregateError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::UnknownError(Box::new(err))
+    }
+}
+
+impl LibSqlAggregateError {
+    /// Turns a constraint violation into an [`LibSqlAggregateError::OptimisticConcurrency`] for the given aggregate.
+    pub fn into_concurrency_error(self, aggregate_id: &str, expected_seq: usize) -> Self {
+        match self {
+            Self::OptimisticLock => Self::OptimisticConcurrency {
+                aggregate_id: aggregate_id.to_string(),
+                expected_seq,
+            },
+            error => error,
+        }
+    }
+}
+
+fn is_constraint_violation(error: &libsql::Error) -> bool {
+    match error {
+        // Local connections report the extended result code, e.g. SQLITE_CONSTRAINT_UNIQUE (2067).
+        libsql::Error::SqliteFailure(code, _) => code & 0xff == SQLITE_CONSTRAINT,
+        libsql::Error::RemoteSqliteFailure(code, extended_code, _) => {
+            *code == SQLITE_CONSTRAINT || extended_code & 0xff == SQLITE_CONSTRAINT
+        }
+        libsql::Error::Hrana(err) => err.to_string().contains("SQLITE_CONSTRAINT"),
+        _ => false,
+    }
+}
+
+impl<T: std::error::Error> From<LibSqlAggregateError> for AggregateError<T> {
+    fn from(error: LibSqlAggregateError) -> Self {
+        match error {
+            LibSqlAggregateError::OptimisticLock => Self::AggregateConflict,
+            LibSqlAggregateError::OptimisticConcurrency { .. } => Self::AggregateConflict,
+            LibSqlAggregateError::InvalidColumn(_) => Self::DeserializationError(Box::new(error)),
+            LibSqlAggregateError::ConnectionError(err) => Self::DatabaseConnectionError(Box::new(err)),
+            LibSqlAggregateError::UnknownError(err) => Self::UnexpectedError(err),
+        }
+    }
+}
+
+impl From<LibSqlAggregateError> for PersistenceError {
+    fn from(error: LibSqlAggregateError) -> Self {
+        match error {
+            LibSqlAggregateError::OptimisticLock => Self::OptimisticLockError,
+            LibSqlAggregateError::OptimisticConcurrency {
+                aggregate_id,
+                expected_seq,
+            } => Self::OptimisticConcurrency {
+                aggregate_id,
+                expected_seq,
+            },
+            LibSqlAggregateError::InvalidColumn(_) => Self::DeserializationError(Box::new(error)),
+            LibSqlAggregateError::ConnectionError(err) => Self::ConnectionError(Box::new(err)),
+            LibSqlAggregateError::UnknownError(err) => Self::UnknownError(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_constraint_maps_to_optimistic_lock() {
+        let error = libsql::Error::SqliteFailure(2067, "UNIQUE constraint failed: journal.aggregate_id".to_string());
+        assert!(matches!(
+            LibSqlAggregateError::from(error),
+            LibSqlAggregateError::OptimisticLock
+        ));
+
+        let error = libsql::Error::RemoteSqliteFailure(19, 2067, "UNIQUE constraint failed".to_string());
+        assert!(matches!(
+            LibSqlAggregateError::from(error),
+            LibSqlAggregateError::OptimisticLock
+        ));
+    }
+
+    #[test]
+    fn test_other_errors_are_not_conflicts() {
+        let error = libsql::Error::SqliteFailure(1, "no such table: journal".to_string());
+        assert!(matches!(
+            LibSqlAggregateError::from(error),
+            LibSqlAggregateError::UnknownError(_)
+        ));
+
+        let error = libsql::Error::ConnectionFailed("refused".to_string());
+        assert!(matches!(
+            PersistenceError::from(LibSqlAggregateError::from(error)),
+            PersistenceError::ConnectionError(_)
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+#![allow(dead_code)]
+
+use tsuzuri::{
+    aggregate_id::{AggregateId, HasIdPrefix},
+    command::Command,
+    domain_event::{DomainEvent, SerializedDomainEvent},
+    integration_event::{IntegrationEvent, IntoIntegrationEvents},
+    message::Message,
+    AggregateRoot, EventIdType,
+};
+use tsuzuri_libsql::{ConnectionManager, LibSqlEventStore};
+
+// Test ID type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TestId;
+
+impl HasIdPrefix for TestId {
+    const PREFIX: &'static str = "test";
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TestError {
+    #[error("Test error: {0}")]
+    TestError(String),
+}
+
+// Test aggregate
+#[derive(Debug, Clone)]
+pub struct TestAggregate {
+    pub id: AggregateId<TestId>,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestCommand {
+    pub id: AggregateId<TestId>,
+    pub value: i32,
+}
+
+impl Message for TestCommand {
+    fn name(&self) -> &'static str {
+        "TestCommand"
+    }
+}
+
+impl Command for TestCommand {
+    type ID = TestId;
+
+    fn id(&self) -> AggregateId<Self::ID> {
+        self.id
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestEvent {
+    pub value: i32,
+}
+
+impl Message for TestEvent {
+    fn name(&self) -> &'static str {
+        "TestEvent"
+    }
+}
+
+impl DomainEvent for TestEvent {
+    fn id(&self) -> EventIdType {
+        EventIdType::new()
+    }
+
+    fn event_type(&self) -> &'static str {
+        self.name()
+    }
+}
+
+impl IntoIntegrationEvents for TestEvent {
+    type IntegrationEvent = TestIntegrationEvent;
+    type IntoIter = std::vec::IntoIter<Self::IntegrationEvent>;
+
+    fn into_integration_events(self) -> Self::IntoIter {
+        vec![].into_iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TestIntegrationEvent;
+
+impl Message for TestIntegrationEvent {
+    fn name(&self) -> &'static str {
+        "TestIntegrationEvent"
+    }
+}
+
+impl IntegrationEvent for TestIntegrationEvent {
+    fn id(&self) -> String {
+        EventIdType::new().to_string()
+    }
+
+    fn event_type(&self) -> &'static str {
+        self.name()
+    }
+}
+
+impl AggregateRoot for TestAggregate {
+    type ID = TestId;
+    type Command = TestCommand;
+    type DomainEvent = TestEvent;
+    type IntegrationEvent = TestIntegrationEvent;
+    type Error = TestError;
+    const TYPE: &'static str = "TestAggregate";
+
+    fn init(id: AggregateId<Self::ID>) -> Self {
+        Self { id, value: 0 }
+    }
+
+    fn id(&self) -> &AggregateId<Self::ID> {
+        &self.id
+    }
+
+    fn handle(&mut self, cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+        Ok(TestEvent { value: cmd.value })
+    }
+
+    fn apply(&mut self, event: Self::DomainEvent) {
+        self.value = event.value;
+    }
+}
+
+/// Creates a store over a fresh in-memory database with all tables in place.
+pub async fn create_store() -> LibSqlEventStore {
+    let manager = ConnectionManager::new_local(":memory:")
+        .await
+        .expect("Failed to open in-memory database");
+    let store = LibSqlEventStore::new(manager).with_snapshot_interval(10);
+    store.create_tables().await.expect("Failed to create tables");
+    store
+}
+
+pub fn create_test_domain_event(aggregate_id: &str, seq_nr: usize, event_type: &str) -> SerializedDomainEvent {
+    SerializedDomainEvent {
+        id: EventIdType::new().to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        seq_nr,
+        event_type: event_type.to_string(),
+        payload: vec![seq_nr as u8],
+        metadata: Default::default(),
+        schema_version: 1,
+        occurred_at: tsuzuri::helper::now_timestamp().unwrap(),
+    }
+}
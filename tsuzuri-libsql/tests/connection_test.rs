@@ -0,0 +1,143 @@
+mod common;
+
+use common::{create_test_domain_event, TestAggregate};
+use futures::{future, TryStreamExt};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use tsuzuri::{
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, Persister},
+    EventIdType,
+};
+use tsuzuri_libsql::{ConnectionManager, HealthReport, LibSqlEventStore};
+
+#[tokio::test]
+async fn test_health_check_on_local_database() {
+    let manager = ConnectionManager::new_local(":memory:")
+        .await
+        .expect("Failed to open in-memory database");
+
+    let report = manager.health_check().await.expect("Health check failed");
+
+    assert_eq!(report, HealthReport::default());
+}
+
+#[tokio::test]
+async fn test_sync_now_is_a_no_op_without_replica() {
+    let manager = ConnectionManager::new_local(":memory:")
+        .await
+        .expect("Failed to open in-memory database");
+
+    assert!(!manager.is_embedded_replica());
+    assert_eq!(manager.sync_now().await.expect("Sync failed"), None);
+    assert_eq!(manager.last_sync(), None);
+    assert_eq!(
+        manager.health_check().await.expect("Health check failed").last_sync,
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_concurrent_reads_stay_within_pool_size() {
+    const MAX_CONNECTIONS: usize = 3;
+    const READS: usize = 12;
+
+    // Every connection to ":memory:" opens a database of its own, so the pooled connections share a file
+    let path = std::env::temp_dir().join(format!("tsuzuri-pool-{}.db", EventIdType::new()));
+    let manager = ConnectionManager::new_local(&path)
+        .await
+        .expect("Failed to open database file")
+        .with_max_connections(MAX_CONNECTIONS);
+    let store = LibSqlEventStore::new(manager);
+    store.create_tables().await.expect("Failed to create tables");
+
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNP";
+    let events: Vec<_> = (1..=3)
+        .map(|seq_nr| create_test_domain_event(aggregate_id, seq_nr, "TestEvent"))
+        .collect();
+    store
+        .persist(&events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let peak_in_use = AtomicUsize::new(0);
+    let reads = (0..READS).map(|_| async {
+        let conn = store.connection_manager().acquire().await?;
+        let in_use = store.connection_manager().pool_metrics().in_use;
+        peak_in_use.fetch_max(in_use, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let mut rows = conn.query("SELECT COUNT(*) FROM journal", ()).await?;
+        let count = rows.next().await?.expect("COUNT(*) returns a row").get::<i64>(0)?;
+        drop(conn);
+
+        let streamed: Vec<_> = store
+            .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+            .try_collect()
+            .await
+            .expect("Failed to stream events");
+        assert_eq!(streamed.len(), 3);
+        Ok::<i64, libsql::Error>(count)
+    });
+
+    let counts = tokio::time::timeout(Duration::from_secs(10), future::try_join_all(reads))
+        .await
+        .expect("Concurrent reads deadlocked")
+        .expect("Concurrent reads failed");
+
+    assert_eq!(counts, vec![3; READS]);
+    assert_eq!(peak_in_use.load(Ordering::SeqCst), MAX_CONNECTIONS);
+    let metrics = store.connection_manager().pool_metrics();
+    assert_eq!(metrics.in_use, 0);
+    assert!(metrics.idle <= MAX_CONNECTIONS);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[tokio::test]
+async fn test_get_connection_does_not_share_a_pooled_connection() {
+    let path = std::env::temp_dir().join(format!("tsuzuri-pool-{}.db", EventIdType::new()));
+    let manager = ConnectionManager::new_local(&path)
+        .await
+        .expect("Failed to open database file")
+        .with_max_connections(2);
+
+    let in_transaction = manager.acquire().await.expect("Failed to acquire connection");
+    in_transaction
+        .execute_batch("CREATE TABLE counter (value INTEGER); BEGIN; INSERT INTO counter VALUES (1);")
+        .await
+        .expect("Failed to start transaction");
+
+    // The uncommitted insert is invisible to the connection handed out next, unlike to a shared one
+    let other = manager.get_connection().await.expect("Failed to get connection");
+    assert_eq!(manager.pool_metrics().in_use, 2);
+    let mut rows = other
+        .query("SELECT COUNT(*) FROM counter", ())
+        .await
+        .expect("Failed to query");
+    let count = rows
+        .next()
+        .await
+        .unwrap()
+        .expect("COUNT(*) returns a row")
+        .get::<i64>(0)
+        .unwrap();
+    assert_eq!(count, 0);
+    drop(other);
+
+    // With the pool exhausted, get_connection waits instead of borrowing the connection in use
+    let _second = manager.acquire().await.expect("Failed to acquire connection");
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), manager.get_connection())
+            .await
+            .is_err()
+    );
+
+    in_transaction
+        .execute("ROLLBACK", ())
+        .await
+        .expect("Failed to roll back");
+    drop(in_transaction);
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,437 @@
+mod common;
+
+use common::*;
+use futures::StreamExt;
+use tsuzuri::{
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, Persister, SnapshotGetter, SnapshotIntervalProvider},
+    integration_event::SerializedIntegrationEvent,
+    persist::PersistenceError,
+    snapshot::PersistedSnapshot,
+    AggregateRoot, EventIdType,
+};
+
+#[tokio::test]
+async fn test_persist_and_stream_domain_events() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNP";
+
+    let domain_events = vec![
+        create_test_domain_event(aggregate_id, 1, "TestAggregateCreated"),
+        create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated"),
+    ];
+
+    store
+        .persist(&domain_events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    // Stream all events
+    let mut stream = store.stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All);
+    let mut streamed_events = Vec::new();
+
+    while let Some(event_result) = stream.next().await {
+        let event = event_result.expect("Failed to stream event");
+        streamed_events.push(event);
+    }
+
+    assert_eq!(streamed_events, domain_events);
+
+    // Stream from sequence number 2
+    let mut stream = store.stream_events::<TestAggregate>(aggregate_id, SequenceSelect::From(2));
+    let mut streamed_from_2 = Vec::new();
+
+    while let Some(event_result) = stream.next().await {
+        let event = event_result.expect("Failed to stream event");
+        streamed_from_2.push(event);
+    }
+
+    assert_eq!(streamed_from_2.len(), 1);
+    assert_eq!(streamed_from_2[0].seq_nr, 2);
+}
+
+#[tokio::test]
+async fn test_persist_preserves_metadata() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNT";
+
+    let mut event = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    event.metadata = serde_json::json!({ "user_id": "user-1" });
+
+    store
+        .persist(std::slice::from_ref(&event), &[], None, &[])
+        .await
+        .expect("Failed to persist event");
+
+    let streamed: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .collect()
+        .await;
+
+    assert_eq!(streamed.len(), 1);
+    assert_eq!(streamed[0].as_ref().unwrap().metadata, event.metadata);
+}
+
+#[tokio::test]
+async fn test_persist_preserves_schema_version() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNV";
+
+    let events = vec![
+        create_test_domain_event(aggregate_id, 1, "TestAggregateCreated"),
+        create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated").with_schema_version(3),
+    ];
+
+    store
+        .persist(&events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let schema_versions: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .map(|event| event.expect("Failed to stream event").schema_version)
+        .collect()
+        .await;
+
+    assert_eq!(schema_versions, vec![1, 3]);
+}
+
+#[tokio::test]
+async fn test_persist_preserves_occurred_at() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNW";
+
+    let occurred_at = tsuzuri::helper::from_rfc3339("2021-01-01T00:00:00.123456789Z").unwrap();
+    let event = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated").with_occurred_at(occurred_at);
+
+    store
+        .persist(std::slice::from_ref(&event), &[], None, &[])
+        .await
+        .expect("Failed to persist event");
+
+    let streamed: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .collect()
+        .await;
+
+    assert_eq!(streamed.len(), 1);
+    assert_eq!(streamed[0].as_ref().unwrap().occurred_at, occurred_at);
+}
+
+#[tokio::test]
+async fn test_count_events() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNW";
+
+    assert_eq!(store.count_events::<TestAggregate>(aggregate_id).await.unwrap(), 0);
+
+    let events: Vec<_> = (1..=3)
+        .map(|seq_nr| create_test_domain_event(aggregate_id, seq_nr, "TestAggregateUpdated"))
+        .collect();
+    store
+        .persist(&events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+    store
+        .persist(
+            &[create_test_domain_event(
+                "test-01J1234567890ABCDEFGHJKMNX",
+                1,
+                "TestAggregateCreated",
+            )],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist events");
+
+    assert_eq!(store.count_events::<TestAggregate>(aggregate_id).await.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_persist_with_integration_events() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNQ";
+
+    let domain_event = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    let integration_event = SerializedIntegrationEvent {
+        id: EventIdType::new().to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        event_type: "TestIntegrationEvent".to_string(),
+        payload: b"integration".to_vec(),
+        metadata: Default::default(),
+    };
+
+    store
+        .persist(&[domain_event], &[integration_event], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let count = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .count()
+        .await;
+    assert_eq!(count, 1);
+
+    let mut rows = store
+        .connection_manager()
+        .get_connection()
+        .await
+        .expect("Failed to get connection")
+        .query(
+            "SELECT status, attempts FROM outbox WHERE aggregate_id = ?1",
+            [aggregate_id],
+        )
+        .await
+        .expect("Failed to query outbox");
+    let row = rows.next().await.unwrap().expect("Outbox row should exist");
+    assert_eq!(row.get::<String>(0).unwrap(), "PENDING");
+    assert_eq!(row.get::<i64>(1).unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_persist_integration_events_alone() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNY";
+
+    let integration_event = SerializedIntegrationEvent {
+        id: EventIdType::new().to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        event_type: "TestIntegrationEvent".to_string(),
+        payload: b"integration".to_vec(),
+        metadata: Default::default(),
+    };
+
+    store
+        .persist(&[], &[integration_event], None, &[])
+        .await
+        .expect("Failed to persist integration events");
+
+    let mut rows = store
+        .connection_manager()
+        .get_connection()
+        .await
+        .expect("Failed to get connection")
+        .query("SELECT status FROM outbox WHERE aggregate_id = ?1", [aggregate_id])
+        .await
+        .expect("Failed to query outbox");
+    let row = rows.next().await.unwrap().expect("Outbox row should exist");
+    assert_eq!(row.get::<String>(0).unwrap(), "PENDING");
+}
+
+#[tokio::test]
+async fn test_snapshot_create_and_retrieve() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNR";
+
+    let snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: b"snapshot".to_vec(),
+        seq_nr: 5,
+        version: 1,
+    };
+    let domain_event = create_test_domain_event(aggregate_id, 5, "TestAggregateUpdated");
+
+    store
+        .persist(&[domain_event], &[], Some(&snapshot), &[])
+        .await
+        .expect("Failed to persist with snapshot");
+
+    let retrieved = store
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .expect("Failed to retrieve snapshot")
+        .expect("Snapshot should exist");
+
+    assert_eq!(retrieved, snapshot);
+}
+
+#[tokio::test]
+async fn test_snapshot_update() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNS";
+
+    let snapshot1 = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: b"initial".to_vec(),
+        seq_nr: 10,
+        version: 1,
+    };
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 10, "TestAggregateUpdated")],
+            &[],
+            Some(&snapshot1),
+            &[],
+        )
+        .await
+        .expect("Failed to persist first snapshot");
+
+    let snapshot2 = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: b"updated".to_vec(),
+        seq_nr: 20,
+        version: 2,
+    };
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 20, "TestAggregateUpdated")],
+            &[],
+            Some(&snapshot2),
+            &[],
+        )
+        .await
+        .expect("Failed to persist updated snapshot");
+
+    let retrieved = store
+        .get_snapshot::<TestAggregate>(aggregate_id)
+        .await
+        .expect("Failed to retrieve snapshot")
+        .expect("Snapshot should exist");
+
+    assert_eq!(retrieved.version, 2);
+    assert_eq!(retrieved.seq_nr, 20);
+    assert_eq!(retrieved.aggregate, b"updated".to_vec());
+}
+
+#[tokio::test]
+async fn test_stale_snapshot_version_is_rejected() {
+    let store = create_store().await;
+    let aggregate_id = "test-01J1234567890ABCDEFGHJKMNV";
+
+    let snapshot = PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate: b"v1".to_vec(),
+        seq_nr: 1,
+        version: 1,
+    };
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            Some(&snapshot),
+            &[],
+        )
+        .await
+        .expect("Failed to persist snapshot");
+
+    // Same version again: the previous writer already advanced it
+    let result = store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated")],
+            &[],
+            Some(&snapshot),
+            &[],
+        )
+        .await;
+    assert!(matches!(result, Err(PersistenceError::OptimisticLockError)));
+
+    // The rejected write must not leave its event behind
+    let count = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .count()
+        .await;
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn test_snapshot_interval_provider() {
+    let store = create_store().await;
+
+    assert_eq!(SnapshotIntervalProvider::snapshot_interval(&store), 10);
+}
+
+#[tokio::test]
+async fn test_concurrent_event_persistence() {
+    let store = create_store().await;
+    let aggregate_id = "test-concurrent";
+
+    let event1 = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+
+    store
+        .persist(std::slice::from_ref(&event1), &[], None, &[])
+        .await
+        .expect("Failed to persist first event");
+
+    // Try to persist same sequence number again (should fail)
+    let result = store.persist(&[event1], &[], None, &[]).await;
+
+    match result {
+        Err(PersistenceError::OptimisticConcurrency {
+            aggregate_id: conflicted_id,
+            expected_seq,
+        }) => {
+            assert_eq!(conflicted_id, aggregate_id);
+            assert_eq!(expected_seq, 0);
+        }
+        other => panic!("Expected OptimisticConcurrency, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_conflicting_batch_is_rolled_back() {
+    let store = create_store().await;
+    let aggregate_id = "test-rollback";
+
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated")],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist event");
+
+    // seq 1 is free but seq 2 conflicts, so neither may be written
+    let result = store
+        .persist(
+            &[
+                create_test_domain_event(aggregate_id, 1, "TestAggregateCreated"),
+                create_test_domain_event(aggregate_id, 2, "TestAggregateUpdated"),
+            ],
+            &[],
+            None,
+            &[],
+        )
+        .await;
+    assert!(result.is_err());
+
+    let streamed: Vec<_> = store
+        .stream_events::<TestAggregate>(aggregate_id, SequenceSelect::All)
+        .collect()
+        .await;
+    assert_eq!(streamed.len(), 1);
+    assert_eq!(streamed[0].as_ref().unwrap().seq_nr, 2);
+}
+
+#[tokio::test]
+async fn test_empty_event_stream() {
+    let store = create_store().await;
+
+    let count = store
+        .stream_events::<TestAggregate>("non-existent", SequenceSelect::All)
+        .count()
+        .await;
+
+    assert_eq!(count, 0, "Should return empty stream for non-existent aggregate");
+}
+
+#[tokio::test]
+async fn test_missing_snapshot() {
+    let store = create_store().await;
+
+    let snapshot = store
+        .get_snapshot::<TestAggregate>("non-existent")
+        .await
+        .expect("Failed to query snapshot");
+
+    assert!(snapshot.is_none());
+}
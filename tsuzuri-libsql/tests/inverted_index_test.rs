@@ -0,0 +1,151 @@
+mod common;
+
+use common::{create_store, create_test_domain_event};
+use tsuzuri::{
+    event_store::Persister,
+    inverted_index_store::{AggregateIdsLoader, IndexOp, InvertedIndexCommiter, InvertedIndexRemover},
+};
+
+#[tokio::test]
+async fn test_commit_and_get_aggregate_ids() {
+    let store = create_store().await;
+
+    store
+        .commit("agg-2", "user:john")
+        .await
+        .expect("Failed to commit keyword");
+    store
+        .commit("agg-1", "user:john")
+        .await
+        .expect("Failed to commit keyword");
+    store
+        .commit("agg-3", "user:jane")
+        .await
+        .expect("Failed to commit keyword");
+
+    let john_aggs = store
+        .get_aggregate_ids("user:john")
+        .await
+        .expect("Failed to get aggregate IDs");
+    assert_eq!(john_aggs, vec!["agg-1".to_string(), "agg-2".to_string()]);
+
+    let jane_aggs = store
+        .get_aggregate_ids("user:jane")
+        .await
+        .expect("Failed to get aggregate IDs");
+    assert_eq!(jane_aggs, vec!["agg-3".to_string()]);
+}
+
+#[tokio::test]
+async fn test_commit_is_idempotent() {
+    let store = create_store().await;
+
+    store
+        .commit("agg-1", "keyword")
+        .await
+        .expect("Failed to commit keyword");
+    store
+        .commit("agg-1", "keyword")
+        .await
+        .expect("Committing the same pair twice should succeed");
+
+    let ids = store.get_aggregate_ids("keyword").await.unwrap();
+    assert_eq!(ids, vec!["agg-1".to_string()]);
+}
+
+#[tokio::test]
+async fn test_remove() {
+    let store = create_store().await;
+
+    store.commit("agg-1", "user:john").await.unwrap();
+    store.commit("agg-2", "user:john").await.unwrap();
+
+    store
+        .remove("agg-1", "user:john")
+        .await
+        .expect("Failed to remove keyword");
+
+    let ids = store.get_aggregate_ids("user:john").await.unwrap();
+    assert_eq!(ids, vec!["agg-2".to_string()]);
+}
+
+#[tokio::test]
+async fn test_empty_keyword_removal() {
+    let store = create_store().await;
+
+    store.commit("agg-1", "temp:keyword").await.unwrap();
+    assert_eq!(store.get_aggregate_ids("temp:keyword").await.unwrap().len(), 1);
+
+    store.remove("agg-1", "temp:keyword").await.unwrap();
+    assert!(store.get_aggregate_ids("temp:keyword").await.unwrap().is_empty());
+
+    // No row is left behind for the keyword
+    let mut rows = store
+        .connection_manager()
+        .get_connection()
+        .await
+        .expect("Failed to get connection")
+        .query(
+            "SELECT COUNT(*) FROM inverted_index WHERE keyword = ?1",
+            ["temp:keyword"],
+        )
+        .await
+        .unwrap();
+    let row = rows.next().await.unwrap().unwrap();
+    assert_eq!(row.get::<i64>(0).unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_remove_missing_entry() {
+    let store = create_store().await;
+
+    store
+        .remove("agg-1", "missing")
+        .await
+        .expect("Removing a missing entry should succeed");
+    assert!(store.get_aggregate_ids("missing").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_persist_applies_index_ops() {
+    let store = create_store().await;
+    let aggregate_id = "test-index-ops";
+    store.commit(aggregate_id, "email:old").await.unwrap();
+
+    store
+        .persist(
+            &[create_test_domain_event(aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[
+                IndexOp::delete("email:old", aggregate_id),
+                IndexOp::put("email:new", aggregate_id),
+            ],
+        )
+        .await
+        .expect("Failed to persist events with index ops");
+
+    assert!(store.get_aggregate_ids("email:old").await.unwrap().is_empty());
+    assert_eq!(
+        store.get_aggregate_ids("email:new").await.unwrap(),
+        vec![aggregate_id.to_string()]
+    );
+}
+
+#[tokio::test]
+async fn test_conflicting_persist_does_not_write_index_ops() {
+    let store = create_store().await;
+    let aggregate_id = "test-index-rollback";
+    let event = create_test_domain_event(aggregate_id, 1, "TestAggregateCreated");
+    store
+        .persist(std::slice::from_ref(&event), &[], None, &[])
+        .await
+        .unwrap();
+
+    let result = store
+        .persist(&[event], &[], None, &[IndexOp::put("email:new", aggregate_id)])
+        .await;
+
+    assert!(result.is_err());
+    assert!(store.get_aggregate_ids("email:new").await.unwrap().is_empty());
+}
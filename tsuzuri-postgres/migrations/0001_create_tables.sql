@@ -0,0 +1,39 @@
+CREATE TABLE IF NOT EXISTS journal (
+    event_id TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    aggregate_type TEXT NOT NULL,
+    seq_nr BIGINT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload BYTEA NOT NULL,
+    metadata JSONB NOT NULL,
+    schema_version BIGINT NOT NULL DEFAULT 1,
+    PRIMARY KEY (aggregate_id, seq_nr)
+);
+
+CREATE TABLE IF NOT EXISTS snapshot (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    seq_nr BIGINT NOT NULL,
+    version BIGINT NOT NULL,
+    payload BYTEA NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id)
+);
+
+CREATE TABLE IF NOT EXISTS outbox (
+    event_id TEXT NOT NULL PRIMARY KEY,
+    aggregate_id TEXT NOT NULL,
+    aggregate_type TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload BYTEA NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS outbox_status_index ON outbox (status);
+
+CREATE TABLE IF NOT EXISTS inverted_index (
+    keyword TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    PRIMARY KEY (keyword, aggregate_id)
+);
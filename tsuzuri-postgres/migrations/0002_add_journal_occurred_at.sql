@@ -0,0 +1 @@
+ALTER TABLE journal ADD COLUMN IF NOT EXISTS occurred_at TEXT NOT NULL DEFAULT '1970-01-01T00:00:00+00:00';
@@ -0,0 +1,5 @@
+pub mod relay;
+pub mod store;
+
+pub use relay::PostgresOutboxRelay;
+pub use store::{Postgres, MIGRATOR, OUTBOX_CHANNEL};
@@ -0,0 +1,142 @@
+use crate::store::{error::PostgresAggregateError, OUTBOX_CHANNEL, OUTBOX_STATUS_DISPATCHED, OUTBOX_STATUS_PENDING};
+use futures::stream::{self, BoxStream, StreamExt};
+use sqlx::{postgres::PgListener, PgPool, Row};
+use std::{collections::VecDeque, time::Duration};
+use tsuzuri::{integration_event::SerializedIntegrationEvent, persist::PersistenceError};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Streams integration events from the outbox as they are committed.
+///
+/// The relay `LISTEN`s on [`OUTBOX_CHANNEL`] and claims pending outbox rows whenever a commit notifies it. Notifications
+/// are not persisted, so it also claims rows every `poll_interval` to pick up events committed while it was not
+/// listening.
+///
+/// Claiming marks a row `DISPATCHED` before the event is yielded, using `FOR UPDATE SKIP LOCKED` so that several
+/// relays can share one outbox without yielding an event twice. An event whose delivery fails after it was yielded
+/// is not yielded again.
+///
+/// Yielded events carry the metadata stored with their outbox row, i.e. that of the domain event they were prepared
+/// from; rows written before the `metadata` column was added yield `Null`.
+#[derive(Debug, Clone)]
+pub struct PostgresOutboxRelay {
+    pool: PgPool,
+    poll_interval: Duration,
+    batch_size: usize,
+}
+
+struct RelayState {
+    pool: PgPool,
+    listener: PgListener,
+    poll_interval: Duration,
+    batch_size: usize,
+    pending: VecDeque<SerializedIntegrationEvent>,
+}
+
+impl PostgresOutboxRelay {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Interval of the fallback poll that runs when no notification arrives.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Maximum number of outbox rows claimed per query.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Starts listening and returns the stream of claimed events, oldest first.
+    ///
+    /// The stream never ends on its own. Errors of a single claim or of the listener connection are yielded as items
+    /// and the stream continues afterwards.
+    pub async fn stream(
+        &self,
+    ) -> Result<BoxStream<'static, Result<SerializedIntegrationEvent, PersistenceError>>, PersistenceError> {
+        let mut listener = PgListener::connect_with(&self.pool)
+            .await
+            .map_err(PostgresAggregateError::from)?;
+        listener
+            .listen(OUTBOX_CHANNEL)
+            .await
+            .map_err(PostgresAggregateError::from)?;
+        let state = RelayState {
+            pool: self.pool.clone(),
+            listener,
+            poll_interval: self.poll_interval,
+            batch_size: self.batch_size,
+            pending: VecDeque::new(),
+        };
+        Ok(stream::unfold(state, next_event).boxed())
+    }
+}
+
+async fn next_event(
+    mut state: RelayState,
+) -> Option<(Result<SerializedIntegrationEvent, PersistenceError>, RelayState)> {
+    loop {
+        if let Some(event) = state.pending.pop_front() {
+            return Some((Ok(event), state));
+        }
+        match claim_pending(&state.pool, state.batch_size).await {
+            Ok(events) if !events.is_empty() => {
+                state.pending.extend(events);
+                continue;
+            }
+            Ok(_) => {}
+            Err(err) => return Some((Err(err.into()), state)),
+        }
+        // Wake up on the next notification, or after the poll interval to catch missed ones.
+        if let Ok(Err(err)) = tokio::time::timeout(state.poll_interval, state.listener.recv()).await {
+            return Some((Err(PostgresAggregateError::from(err).into()), state));
+        }
+    }
+}
+
+/// Marks up to `batch_size` pending outbox rows as dispatched and returns their events, oldest first.
+async fn claim_pending(
+    pool: &PgPool,
+    batch_size: usize,
+) -> Result<Vec<SerializedIntegrationEvent>, PostgresAggregateError> {
+    let rows = sqlx::query(
+        "WITH claimed AS ( \
+             SELECT event_id FROM outbox WHERE status = $1 \
+             ORDER BY created_at, event_id LIMIT $2 FOR UPDATE SKIP LOCKED \
+         ) \
+         UPDATE outbox SET status = $3 FROM claimed WHERE outbox.event_id = claimed.event_id \
+         RETURNING outbox.event_id, outbox.aggregate_id, outbox.aggregate_type, outbox.event_type, outbox.payload, \
+         outbox.metadata, (EXTRACT(EPOCH FROM outbox.created_at) * 1000000)::BIGINT",
+    )
+    .bind(OUTBOX_STATUS_PENDING)
+    .bind(batch_size as i64)
+    .bind(OUTBOX_STATUS_DISPATCHED)
+    .fetch_all(pool)
+    .await?;
+
+    // RETURNING does not keep the order of the claiming query.
+    let mut claimed = rows
+        .iter()
+        .map(|row| {
+            let event = SerializedIntegrationEvent {
+                id: row.try_get::<String, _>(0)?,
+                aggregate_id: row.try_get::<String, _>(1)?,
+                aggregate_type: row.try_get::<String, _>(2)?,
+                event_type: row.try_get::<String, _>(3)?,
+                payload: row.try_get::<Vec<u8>, _>(4)?,
+                metadata: row.try_get::<serde_json::Value, _>(5)?,
+            };
+            Ok((row.try_get::<i64, _>(6)?, event))
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+    claimed.sort_by(|(a_created, a), (b_created, b)| (a_created, &a.id).cmp(&(b_created, &b.id)));
+    Ok(claimed.into_iter().map(|(_, event)| event).collect())
+}
@@ -0,0 +1,110 @@
+use sqlx::migrate::MigrateError;
+use tsuzuri::{error::AggregateError, persist::PersistenceError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PostgresAggregateError {
+    #[error("optimistic lock error")]
+    OptimisticLock,
+    #[error("optimistic concurrency conflict on aggregate {aggregate_id}: expected sequence number {expected_seq}")]
+    OptimisticConcurrency { aggregate_id: String, expected_seq: usize },
+    #[error("invalid column: {0}")]
+    InvalidColumn(String),
+    #[error(transparent)]
+    ConnectionError(sqlx::Error),
+    #[error(transparent)]
+    UnknownError(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl From<sqlx::Error> for PostgresAggregateError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::Database(ref err) if err.is_unique_violation() => Self::OptimisticLock,
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => Self::ConnectionError(error),
+            _ => Self::UnknownError(Box::new(error)),
+        }
+    }
+}
+
+impl From<MigrateError> for PostgresAggregateError {
+    fn from(err: MigrateError) -> Self {
+        Self::UnknownError(Box::new(err))
+    }
+}
+
+impl From<serde_json::Error> for PostgresAggregateError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::UnknownError(Box::new(err))
+    }
+}
+
+impl PostgresAggregateError {
+    /// Turns a rejected write into an [`PostgresAggregateError::OptimisticConcurrency`] for the given aggregate.
+    pub fn into_concurrency_error(self, aggregate_id: &str, expected_seq: usize) -> Self {
+        match self {
+            Self::OptimisticLock => Self::OptimisticConcurrency {
+                aggregate_id: aggregate_id.to_string(),
+                expected_seq,
+            },
+            error => error,
+        }
+    }
+}
+
+impl<T: std::error::Error> From<PostgresAggregateError> for AggregateError<T> {
+    fn from(error: PostgresAggregateError) -> Self {
+        match error {
+            PostgresAggregateError::OptimisticLock => Self::AggregateConflict,
+            PostgresAggregateError::OptimisticConcurrency { .. } => Self::AggregateConflict,
+            PostgresAggregateError::InvalidColumn(_) => Self::DeserializationError(Box::new(error)),
+            PostgresAggregateError::ConnectionError(err) => Self::DatabaseConnectionError(Box::new(err)),
+            PostgresAggregateError::UnknownError(err) => Self::UnexpectedError(err),
+        }
+    }
+}
+
+impl From<PostgresAggregateError> for PersistenceError {
+    fn from(error: PostgresAggregateError) -> Self {
+        match error {
+            PostgresAggregateError::OptimisticLock => Self::OptimisticLockError,
+            PostgresAggregateError::OptimisticConcurrency {
+                aggregate_id,
+                expected_seq,
+            } => Self::OptimisticConcurrency {
+                aggregate_id,
+                expected_seq,
+            },
+            PostgresAggregateError::InvalidColumn(_) => Self::DeserializationError(Box::new(error)),
+            PostgresAggregateError::ConnectionError(err) => Self::ConnectionError(Box::new(err)),
+            PostgresAggregateError::UnknownError(err) => Self::UnknownError(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_errors_are_connection_errors() {
+        assert!(matches!(
+            PersistenceError::from(PostgresAggregateError::from(sqlx::Error::PoolTimedOut)),
+            PersistenceError::ConnectionError(_)
+        ));
+    }
+
+    #[test]
+    fn test_optimistic_lock_becomes_concurrency_error() {
+        assert!(matches!(
+            PostgresAggregateError::OptimisticLock.into_concurrency_error("agg-1", 3),
+            PostgresAggregateError::OptimisticConcurrency { aggregate_id, expected_seq: 3 } if aggregate_id == "agg-1"
+        ));
+        assert!(matches!(
+            PostgresAggregateError::from(sqlx::Error::RowNotFound).into_concurrency_error("agg-1", 3),
+            PostgresAggregateError::UnknownError(_)
+        ));
+    }
+}
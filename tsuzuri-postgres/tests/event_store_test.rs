@@ -0,0 +1,201 @@
+mod common;
+
+use common::*;
+use futures::StreamExt;
+use sqlx::Row;
+use tsuzuri::{
+    event::SequenceSelect,
+    event_store::{AggregateEventStreamer, Persister, SnapshotGetter},
+    integration_event::SerializedIntegrationEvent,
+    inverted_index_store::{AggregateIdsLoader, IndexOp},
+    persist::PersistenceError,
+    snapshot::PersistedSnapshot,
+    AggregateRoot, EventIdType,
+};
+
+#[tokio::test]
+async fn test_persist_and_stream_domain_events() {
+    let Some(store) = create_store().await else {
+        return;
+    };
+    let aggregate_id = unique_aggregate_id();
+
+    let domain_events = vec![
+        create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated"),
+        create_test_domain_event(&aggregate_id, 2, "TestAggregateUpdated"),
+    ];
+
+    store
+        .persist(&domain_events, &[], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let streamed: Vec<_> = store
+        .stream_events::<TestAggregate>(&aggregate_id, SequenceSelect::All)
+        .map(|event| event.expect("Failed to stream event"))
+        .collect()
+        .await;
+    assert_eq!(streamed, domain_events);
+
+    let streamed_from_2: Vec<_> = store
+        .stream_events::<TestAggregate>(&aggregate_id, SequenceSelect::From(2))
+        .map(|event| event.expect("Failed to stream event"))
+        .collect()
+        .await;
+    assert_eq!(streamed_from_2.len(), 1);
+    assert_eq!(streamed_from_2[0].seq_nr, 2);
+
+    assert_eq!(store.count_events::<TestAggregate>(&aggregate_id).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_persist_with_integration_events() {
+    let Some(store) = create_store().await else {
+        return;
+    };
+    let aggregate_id = unique_aggregate_id();
+
+    let domain_event = create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated");
+    let integration_event = SerializedIntegrationEvent {
+        id: EventIdType::new().to_string(),
+        aggregate_id: aggregate_id.clone(),
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        event_type: "TestIntegrationEvent".to_string(),
+        payload: b"integration".to_vec(),
+        metadata: Default::default(),
+    };
+
+    store
+        .persist(&[domain_event], &[integration_event], None, &[])
+        .await
+        .expect("Failed to persist events");
+
+    let row = sqlx::query("SELECT status, attempts FROM outbox WHERE aggregate_id = $1")
+        .bind(&aggregate_id)
+        .fetch_one(store.pool())
+        .await
+        .expect("Outbox row should exist");
+    assert_eq!(row.get::<String, _>(0), "PENDING");
+    assert_eq!(row.get::<i32, _>(1), 0);
+}
+
+#[tokio::test]
+async fn test_snapshot_create_and_update() {
+    let Some(store) = create_store().await else {
+        return;
+    };
+    let aggregate_id = unique_aggregate_id();
+
+    let snapshot = |seq_nr, version, aggregate: &[u8]| PersistedSnapshot {
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        aggregate_id: aggregate_id.clone(),
+        aggregate: aggregate.to_vec(),
+        seq_nr,
+        version,
+    };
+
+    store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            Some(&snapshot(1, 1, b"initial")),
+            &[],
+        )
+        .await
+        .expect("Failed to persist first snapshot");
+    store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 2, "TestAggregateUpdated")],
+            &[],
+            Some(&snapshot(2, 2, b"updated")),
+            &[],
+        )
+        .await
+        .expect("Failed to persist updated snapshot");
+
+    let retrieved = store
+        .get_snapshot::<TestAggregate>(&aggregate_id)
+        .await
+        .expect("Failed to retrieve snapshot")
+        .expect("Snapshot should exist");
+    assert_eq!(retrieved, snapshot(2, 2, b"updated"));
+
+    // A writer that still expects version 1 must be rejected, together with its event
+    let result = store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 3, "TestAggregateUpdated")],
+            &[],
+            Some(&snapshot(3, 2, b"stale")),
+            &[],
+        )
+        .await;
+    assert!(matches!(result, Err(PersistenceError::OptimisticLockError)));
+    assert_eq!(store.count_events::<TestAggregate>(&aggregate_id).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_duplicate_seq_nr_reports_optimistic_concurrency() {
+    let Some(store) = create_store().await else {
+        return;
+    };
+    let aggregate_id = unique_aggregate_id();
+
+    store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 2, "TestAggregateUpdated")],
+            &[],
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist event");
+
+    // seq 1 is free but seq 2 conflicts, so neither may be written
+    let result = store
+        .persist(
+            &[
+                create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated"),
+                create_test_domain_event(&aggregate_id, 2, "TestAggregateUpdated"),
+            ],
+            &[],
+            None,
+            &[],
+        )
+        .await;
+    match result {
+        Err(PersistenceError::OptimisticConcurrency {
+            aggregate_id: conflicted_id,
+            expected_seq,
+        }) => {
+            assert_eq!(conflicted_id, aggregate_id);
+            assert_eq!(expected_seq, 0);
+        }
+        other => panic!("Expected OptimisticConcurrency, got {other:?}"),
+    }
+    assert_eq!(store.count_events::<TestAggregate>(&aggregate_id).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_index_ops_commit_with_events() {
+    let Some(store) = create_store().await else {
+        return;
+    };
+    let aggregate_id = unique_aggregate_id();
+    let keyword = format!("keyword:{aggregate_id}");
+
+    store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated")],
+            &[],
+            None,
+            &[IndexOp::put(&keyword, &aggregate_id)],
+        )
+        .await
+        .expect("Failed to persist events with index ops");
+
+    let ids = store
+        .get_aggregate_ids(keyword.as_str())
+        .await
+        .expect("Failed to get aggregate IDs");
+    assert_eq!(ids, vec![aggregate_id]);
+}
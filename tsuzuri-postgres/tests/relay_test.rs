@@ -0,0 +1,71 @@
+mod common;
+
+use common::*;
+use futures::StreamExt;
+use std::time::Duration;
+use tsuzuri::{
+    event_store::Persister, integration_event::SerializedIntegrationEvent, persist::PersistenceError, AggregateRoot,
+    EventIdType,
+};
+use tsuzuri_postgres::{Postgres, PostgresOutboxRelay};
+
+fn integration_event(aggregate_id: &str) -> SerializedIntegrationEvent {
+    SerializedIntegrationEvent {
+        id: EventIdType::new().to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: TestAggregate::TYPE.to_string(),
+        event_type: "TestIntegrationEvent".to_string(),
+        payload: b"integration".to_vec(),
+        metadata: serde_json::json!({ "correlation_id": "req-1" }),
+    }
+}
+
+/// Waits until the relay yields the event with `id`, skipping rows left pending by earlier runs.
+async fn next_with_id(
+    events: &mut (impl futures::Stream<Item = Result<SerializedIntegrationEvent, PersistenceError>> + Unpin),
+    id: &str,
+) -> SerializedIntegrationEvent {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let event = events.next().await.expect("Relay stream ended").expect("Relay failed");
+            if event.id == id {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("Integration event was not delivered")
+}
+
+async fn commit_integration_event(store: &Postgres) -> SerializedIntegrationEvent {
+    let aggregate_id = unique_aggregate_id();
+    let event = integration_event(&aggregate_id);
+    store
+        .persist(
+            &[create_test_domain_event(&aggregate_id, 1, "TestAggregateCreated")],
+            std::slice::from_ref(&event),
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to persist events");
+    event
+}
+
+#[tokio::test]
+async fn test_relay_delivers_committed_integration_events() {
+    let Some(store) = create_store().await else {
+        return;
+    };
+
+    // Committed before the relay listens, so only its claim of pending rows can deliver it
+    let missed = commit_integration_event(&store).await;
+
+    // The poll interval outlasts the test timeout, so later events can only arrive through notifications
+    let relay = PostgresOutboxRelay::new(store.pool().clone()).with_poll_interval(Duration::from_secs(60));
+    let mut events = relay.stream().await.expect("Failed to start relay");
+    assert_eq!(next_with_id(&mut events, &missed.id).await, missed);
+
+    let notified = commit_integration_event(&store).await;
+    assert_eq!(next_with_id(&mut events, &notified.id).await, notified);
+}
@@ -3,6 +3,7 @@ use crate::{
     command::Command,
     domain_event::DomainEvent,
     integration_event::{IntegrationEvent, IntoIntegrationEvents},
+    inverted_index_store::IndexKeyword,
 };
 use std::fmt;
 
@@ -25,14 +26,51 @@ pub trait AggregateRoot: fmt::Debug + Send + Sync + 'static {
     /// Handles a command and returns a domain event or an error.
     fn handle(&mut self, cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error>;
 
+    /// Handles a command and returns every domain event it produces, in order.
+    ///
+    /// The default implementation wraps [`AggregateRoot::handle`] in a one-element vector.
+    /// Override it for commands that emit several events.
+    fn handle_many(&mut self, cmd: Self::Command) -> Result<Vec<Self::DomainEvent>, Self::Error> {
+        self.handle(cmd).map(|event| vec![event])
+    }
+
     /// Applies changes to the aggregate's state.
     fn apply(&mut self, event: Self::DomainEvent);
+
+    /// Applies changes to the aggregate's state, rejecting events that cannot follow the current state.
+    ///
+    /// Replay uses this instead of [`AggregateRoot::apply`], so a corrupted event stream, such as `Shipped` before
+    /// `Created`, fails the load with
+    /// [`PersistenceError::CorruptStream`](crate::persist::PersistenceError::CorruptStream) rather than producing a
+    /// broken aggregate. The default implementation calls `apply` and accepts every event.
+    fn try_apply(&mut self, event: Self::DomainEvent) -> Result<(), Self::Error> {
+        self.apply(event);
+        Ok(())
+    }
+
+    /// Returns whether the aggregate reached a final state no later event can change, such as a delivered order.
+    ///
+    /// Once it does, replay stops deserializing and applying the remaining events of the stream and only advances
+    /// the sequence number past them, so commits still expect the latest one. The default implementation never
+    /// reports a terminal state.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    /// Returns the keywords the aggregate is indexed under in the inverted index.
+    ///
+    /// `EventSourced::commit` compares the keywords before and after the committed events and adds or removes
+    /// the aggregate from the index accordingly. The default implementation indexes nothing.
+    fn index_keywords(&self) -> Vec<IndexKeyword> {
+        Vec::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{event_id::EventIdType, integration_event, message, test::TestFramework};
+    use crate::{event_id::EventIdType, integration_event, message, serde::Json, test::TestFramework};
+    use serde::{Deserialize, Serialize};
     use std::sync::Arc;
 
     // Test ID types
@@ -337,7 +375,7 @@ mod tests {
     }
 
     // Test Aggregates
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     #[allow(dead_code)]
     struct OrderAggregate {
         id: AggregateId<OrderId>,
@@ -346,7 +384,7 @@ mod tests {
         status: OrderStatus,
     }
 
-    #[derive(Debug, Clone, PartialEq)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     enum OrderStatus {
         Pending,
         Confirmed,
@@ -952,4 +990,133 @@ mod tests {
         assert_eq!(user.email, "john.doe@example.com");
         assert_eq!(user.name, "John Doe"); // Name should remain unchanged
     }
+
+    #[test]
+    fn test_handle_many_defaults_to_single_event() {
+        let order_id = AggregateId::<OrderId>::new();
+        let user_id = AggregateId::<UserId>::new();
+        let mut order = OrderAggregate::init(order_id);
+
+        let events = order
+            .handle_many(OrderCommand::Create {
+                id: order_id,
+                user_id,
+                total_amount: 1000,
+            })
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], OrderEvent::Created { total_amount: 1000, .. }));
+
+        // Errors from handle are passed through unchanged
+        let result = order.handle_many(OrderCommand::Ship { id: order_id });
+        assert!(matches!(result, Err(OrderError::InvalidStateTransition)));
+    }
+
+    #[test]
+    fn test_order_shipped_maps_to_tracking_integration_event() {
+        let order_id = AggregateId::<OrderId>::new();
+        let user_id = AggregateId::<UserId>::new();
+        let confirmed = vec![
+            OrderEvent::Created {
+                id: EventIdType::new(),
+                user_id,
+                total_amount: 25000,
+            },
+            OrderEvent::Confirmed { id: EventIdType::new() },
+        ];
+
+        TestFramework::with(OrderAggregate::init(order_id))
+            .given(confirmed.clone())
+            .when(OrderCommand::Ship { id: order_id })
+            .then_expect_integration_events(vec![OrderIntegrationEvent::OrderShippedForTracking {
+                order_id,
+                tracking_number: "TRACK123456".to_string(),
+            }]);
+
+        TestFramework::with(OrderAggregate::init(order_id))
+            .given(confirmed)
+            .when(OrderCommand::Ship { id: order_id })
+            .then_verify_integration_events(|events| {
+                assert_eq!(events.len(), 1);
+                assert!(matches!(
+                    &events[0],
+                    OrderIntegrationEvent::OrderShippedForTracking { tracking_number, .. }
+                        if tracking_number == "TRACK123456"
+                ));
+            });
+
+        // Confirming an order has no integration events
+        TestFramework::with(OrderAggregate::init(order_id))
+            .given(vec![OrderEvent::Created {
+                id: EventIdType::new(),
+                user_id,
+                total_amount: 25000,
+            }])
+            .when(OrderCommand::Confirm { id: order_id })
+            .then_expect_integration_events(vec![]);
+    }
+
+    #[test]
+    fn test_order_lifecycle_with_when_many() {
+        let order_id = AggregateId::<OrderId>::new();
+        let user_id = AggregateId::<UserId>::new();
+        let lifecycle = vec![
+            OrderCommand::Create {
+                id: order_id,
+                user_id,
+                total_amount: 25000,
+            },
+            OrderCommand::Confirm { id: order_id },
+            OrderCommand::Ship { id: order_id },
+        ];
+
+        TestFramework::with(OrderAggregate::init(order_id))
+            .given_no_previous_events()
+            .when_many(lifecycle.clone())
+            .then_verify(|result| {
+                let event_types: Vec<_> = result.unwrap().iter().map(DomainEvent::event_type).collect();
+                assert_eq!(event_types, vec!["OrderCreated", "OrderConfirmed", "OrderShipped"]);
+            });
+
+        TestFramework::with(OrderAggregate::init(order_id))
+            .given_no_previous_events()
+            .when_many(lifecycle)
+            .then_aggregate_state(|order| {
+                assert_eq!(order.status, OrderStatus::Shipped);
+                assert_eq!(order.total_amount, 25000);
+            });
+
+        // Shipping before confirming fails and the remaining commands are not handled
+        TestFramework::with(OrderAggregate::init(order_id))
+            .given_no_previous_events()
+            .when_many(vec![
+                OrderCommand::Create {
+                    id: order_id,
+                    user_id,
+                    total_amount: 25000,
+                },
+                OrderCommand::Ship { id: order_id },
+                OrderCommand::Confirm { id: order_id },
+            ])
+            .then_expect_error_matches(|e| matches!(e, OrderError::InvalidStateTransition));
+    }
+
+    #[test]
+    fn test_order_replay_consistency_across_snapshots() {
+        let order_id = AggregateId::<OrderId>::new();
+
+        TestFramework::with(OrderAggregate::init(order_id)).assert_replay_consistency(
+            Json::<OrderAggregate>::default(),
+            vec![
+                OrderEvent::Created {
+                    id: EventIdType::new(),
+                    user_id: AggregateId::<UserId>::new(),
+                    total_amount: 25000,
+                },
+                OrderEvent::Confirmed { id: EventIdType::new() },
+                OrderEvent::Shipped { id: EventIdType::new() },
+                OrderEvent::Delivered { id: EventIdType::new() },
+            ],
+        );
+    }
 }
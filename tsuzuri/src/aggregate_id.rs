@@ -1,21 +1,84 @@
 use serde::{Deserialize, Serialize};
-use std::{fmt, marker::PhantomData, str::FromStr};
+use std::{fmt, marker::PhantomData, str::FromStr, sync::Mutex};
 use thiserror::Error;
 use ulid::Ulid;
 
-#[derive(Debug, Error, Clone)]
-pub enum AggregateIdError {
-    #[error("aggregate id is empty")]
-    Empty,
-    #[error("aggregate id is invalid")]
-    Invalid,
+/// Reasons an aggregate ID string fails to parse.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AggregateIdParseError {
+    #[error("aggregate id has no prefix")]
+    MissingPrefix,
+    #[error("aggregate id has prefix {found:?}, expected {expected:?}")]
+    WrongPrefix { expected: &'static str, found: String },
+    #[error("aggregate id is not a valid ULID: {0}")]
+    InvalidUlid(#[from] ulid::DecodeError),
+    #[cfg(feature = "uuid_v7")]
+    #[error("aggregate id is not a valid UUIDv7: {0}")]
+    InvalidUuidV7(String),
 }
 
+#[deprecated(note = "renamed to `AggregateIdParseError`")]
+pub type AggregateIdError = AggregateIdParseError;
+
 /// Trait that aggregates must implement to provide their ID prefix
 pub trait HasIdPrefix: Clone + Send + Sync + 'static {
     const PREFIX: &'static str;
 }
 
+/// Source of the ULIDs backing new aggregate IDs, see [`AggregateId::new_with`].
+pub trait IdGenerator {
+    fn generate(&self) -> Ulid;
+}
+
+/// Generates random ULIDs from the current time; used by [`AggregateId::new`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UlidGenerator;
+
+impl IdGenerator for UlidGenerator {
+    fn generate(&self) -> Ulid {
+        Ulid::new()
+    }
+}
+
+/// Generates UUIDv7s, stored as the ULID with the same 128 bits.
+///
+/// Both formats start with a 48-bit millisecond timestamp, so the IDs sort by creation time like ULIDs do, and
+/// IDs generated in the same process are strictly increasing.
+#[cfg(feature = "uuid_v7")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7Generator;
+
+#[cfg(feature = "uuid_v7")]
+impl IdGenerator for UuidV7Generator {
+    fn generate(&self) -> Ulid {
+        Ulid(uuid::Uuid::now_v7().as_u128())
+    }
+}
+
+/// Generates a deterministic sequence of ULIDs starting at a fixed value, for tests that need stable IDs.
+#[derive(Debug)]
+pub struct FixedIdGenerator {
+    next: Mutex<Ulid>,
+}
+
+impl FixedIdGenerator {
+    pub fn new(first: Ulid) -> Self {
+        Self {
+            next: Mutex::new(first),
+        }
+    }
+}
+
+impl IdGenerator for FixedIdGenerator {
+    /// Returns the next ULID of the sequence, each one greater than the previous.
+    fn generate(&self) -> Ulid {
+        let mut next = self.next.lock().unwrap();
+        let id = *next;
+        *next = Ulid(id.0.wrapping_add(1));
+        id
+    }
+}
+
 /// Generic ID structure for aggregates
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct AggregateId<T: HasIdPrefix> {
@@ -25,10 +88,12 @@ pub struct AggregateId<T: HasIdPrefix> {
 
 impl<T: HasIdPrefix> AggregateId<T> {
     pub fn new() -> Self {
-        Self {
-            id: Ulid::new(),
-            _phantom: PhantomData,
-        }
+        Self::new_with(&UlidGenerator)
+    }
+
+    /// Creates an ID backed by the next ULID of `generator`.
+    pub fn new_with(generator: &impl IdGenerator) -> Self {
+        Self::from_ulid(generator.generate())
     }
 
     pub fn from_ulid(ulid: Ulid) -> Self {
@@ -38,6 +103,12 @@ impl<T: HasIdPrefix> AggregateId<T> {
         }
     }
 
+    /// Returns the ID as a UUID, the hyphenated form accepted by [`FromStr`] next to the ULID form.
+    #[cfg(feature = "uuid_v7")]
+    pub fn to_uuid(&self) -> uuid::Uuid {
+        uuid::Uuid::from_u128(self.id.0)
+    }
+
     #[cfg(test)]
     pub fn into_inner(self) -> Ulid {
         self.id
@@ -57,19 +128,61 @@ impl<T: HasIdPrefix> fmt::Display for AggregateId<T> {
 }
 
 impl<T: HasIdPrefix> FromStr for AggregateId<T> {
-    type Err = AggregateIdError;
+    type Err = AggregateIdParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.is_empty() {
-            return Err(AggregateIdError::Empty);
+        let ulid_string = match s.strip_prefix(T::PREFIX).and_then(|rest| rest.strip_prefix('-')) {
+            Some(ulid_string) => ulid_string,
+            #[cfg(feature = "uuid_v7")]
+            None if is_hyphenated_uuid_suffix(s) => {
+                return Err(AggregateIdParseError::WrongPrefix {
+                    expected: T::PREFIX,
+                    found: s[..s.len() - HYPHENATED_UUID_LEN - 1].to_string(),
+                })
+            }
+            // ULIDs never contain `-`, so everything before the last one is the prefix.
+            None => match s.rsplit_once('-') {
+                Some((found, _)) => {
+                    return Err(AggregateIdParseError::WrongPrefix {
+                        expected: T::PREFIX,
+                        found: found.to_string(),
+                    })
+                }
+                None => return Err(AggregateIdParseError::MissingPrefix),
+            },
+        };
+
+        #[cfg(feature = "uuid_v7")]
+        if ulid_string.len() == HYPHENATED_UUID_LEN {
+            return parse_uuid_v7(ulid_string).map(Self::from_ulid);
         }
 
-        let ulid_string = s.strip_prefix(&format!("{}-", T::PREFIX)).unwrap_or(s);
+        Ok(Self::from_ulid(Ulid::from_string(ulid_string)?))
+    }
+}
 
-        let ulid = Ulid::from_string(ulid_string).map_err(|_| AggregateIdError::Invalid)?;
+#[cfg(feature = "uuid_v7")]
+const HYPHENATED_UUID_LEN: usize = 36;
 
-        Ok(Self::from_ulid(ulid))
+/// Returns `true` when `s` ends with `-` followed by something shaped like a hyphenated UUID.
+#[cfg(feature = "uuid_v7")]
+fn is_hyphenated_uuid_suffix(s: &str) -> bool {
+    s.len() > HYPHENATED_UUID_LEN
+        && s.is_char_boundary(s.len() - HYPHENATED_UUID_LEN - 1)
+        && s[s.len() - HYPHENATED_UUID_LEN - 1..].starts_with('-')
+        && uuid::Uuid::try_parse(&s[s.len() - HYPHENATED_UUID_LEN..]).is_ok()
+}
+
+#[cfg(feature = "uuid_v7")]
+fn parse_uuid_v7(s: &str) -> Result<Ulid, AggregateIdParseError> {
+    let uuid = uuid::Uuid::try_parse(s).map_err(|e| AggregateIdParseError::InvalidUuidV7(e.to_string()))?;
+    if uuid.get_version() != Some(uuid::Version::SortRand) {
+        return Err(AggregateIdParseError::InvalidUuidV7(format!(
+            "expected version 7, found version {}",
+            uuid.get_version_num()
+        )));
     }
+    Ok(Ulid(uuid.as_u128()))
 }
 
 impl<T: HasIdPrefix> Serialize for AggregateId<T> {
@@ -119,11 +232,47 @@ mod tests {
 
         let parsed_id = ProjectIdType::from_str(&id_string).unwrap();
         assert_eq!(id, parsed_id);
+    }
 
-        // Test without prefix
-        let ulid_only = id.clone().into_inner().to_string();
-        let parsed_id2 = ProjectIdType::from_str(&ulid_only).unwrap();
-        assert_eq!(id, parsed_id2);
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct OrderId;
+
+    impl HasIdPrefix for OrderId {
+        const PREFIX: &'static str = "ord";
+    }
+
+    #[test]
+    fn test_parse_missing_prefix() {
+        let ulid_only = ProjectIdType::new().into_inner().to_string();
+        assert_eq!(
+            ProjectIdType::from_str(&ulid_only),
+            Err(AggregateIdParseError::MissingPrefix)
+        );
+        assert_eq!(ProjectIdType::from_str(""), Err(AggregateIdParseError::MissingPrefix));
+    }
+
+    #[test]
+    fn test_parse_wrong_prefix() {
+        let user_id = format!("usr-{}", Ulid::new());
+        assert_eq!(
+            AggregateId::<OrderId>::from_str(&user_id),
+            Err(AggregateIdParseError::WrongPrefix {
+                expected: "ord",
+                found: "usr".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_ulid() {
+        assert!(matches!(
+            ProjectIdType::from_str("pj-not-a-ulid"),
+            Err(AggregateIdParseError::InvalidUlid(_))
+        ));
+        assert!(matches!(
+            ProjectIdType::from_str("pj-"),
+            Err(AggregateIdParseError::InvalidUlid(_))
+        ));
     }
 
     #[test]
@@ -133,4 +282,88 @@ mod tests {
         let deserialized: ProjectIdType = serde_json::from_str(&serialized).unwrap();
         assert_eq!(id, deserialized);
     }
+
+    #[test]
+    fn test_new_with_ulid_generator() {
+        let first = ProjectIdType::new_with(&UlidGenerator);
+        let second = ProjectIdType::new_with(&UlidGenerator);
+
+        assert_ne!(first, second);
+        assert_eq!(ProjectIdType::from_str(&first.to_string()), Ok(first));
+    }
+
+    #[test]
+    fn test_new_with_fixed_generator_is_deterministic() {
+        let seed = Ulid::from_string("01J1234567890ABCDEFGHJKMNP").unwrap();
+        let generator = FixedIdGenerator::new(seed);
+
+        let first = ProjectIdType::new_with(&generator);
+        let second = ProjectIdType::new_with(&generator);
+
+        assert_eq!(first.to_string(), "pj-01J1234567890ABCDEFGHJKMNP");
+        assert_eq!(second.to_string(), "pj-01J1234567890ABCDEFGHJKMNQ");
+        assert!(first.clone().into_inner() < second.clone().into_inner());
+        assert_eq!(
+            ProjectIdType::new_with(&FixedIdGenerator::new(seed)),
+            first,
+            "the same seed yields the same IDs"
+        );
+        assert_eq!(ProjectIdType::from_str(&second.to_string()), Ok(second));
+        assert!(matches!(
+            AggregateId::<OrderId>::from_str(&first.to_string()),
+            Err(AggregateIdParseError::WrongPrefix { .. })
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "uuid_v7"))]
+mod uuid_v7_tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct OrderId;
+
+    impl HasIdPrefix for OrderId {
+        const PREFIX: &'static str = "ord";
+    }
+
+    type OrderIdType = AggregateId<OrderId>;
+
+    #[test]
+    fn test_uuid_v7_ids_are_monotonic() {
+        let ids: Vec<_> = (0..1_000).map(|_| OrderIdType::new_with(&UuidV7Generator)).collect();
+
+        for pair in ids.windows(2) {
+            assert!(pair[0].clone().into_inner() < pair[1].clone().into_inner());
+            assert!(pair[0].to_string() < pair[1].to_string());
+        }
+    }
+
+    #[test]
+    fn test_parse_accepts_ulid_and_uuid_v7_forms() {
+        let id = OrderIdType::new_with(&UuidV7Generator);
+        let uuid_form = format!("ord-{}", id.to_uuid());
+
+        assert_eq!(OrderIdType::from_str(&id.to_string()), Ok(id.clone()));
+        assert_eq!(OrderIdType::from_str(&uuid_form), Ok(id.clone()));
+        assert_eq!(id.to_uuid().get_version(), Some(uuid::Version::SortRand));
+    }
+
+    #[test]
+    fn test_parse_rejects_other_uuid_versions_and_prefixes() {
+        let v4 = "ord-9b2f4c1e-3d5a-4b6c-8d7e-0f1a2b3c4d5e";
+        assert!(matches!(
+            OrderIdType::from_str(v4),
+            Err(AggregateIdParseError::InvalidUuidV7(_))
+        ));
+
+        let user_id = format!("usr-{}", uuid::Uuid::now_v7());
+        assert_eq!(
+            OrderIdType::from_str(&user_id),
+            Err(AggregateIdParseError::WrongPrefix {
+                expected: "ord",
+                found: "usr".to_string(),
+            })
+        );
+    }
 }
@@ -4,7 +4,9 @@ use crate::{
 };
 use std::fmt;
 
+pub mod bus;
 pub mod handler;
+pub mod middleware;
 pub mod repository;
 
 #[allow(dead_code)]
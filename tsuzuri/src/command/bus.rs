@@ -0,0 +1,729 @@
+use crate::{
+    aggregate::AggregateRoot,
+    command::{
+        middleware::{CommandMiddleware, Next},
+        repository::Repository,
+        Command, Envelope,
+    },
+    domain_event::DomainEvent,
+    idempotency_store::{IdempotencyStore, Reservation},
+    message::Metadata,
+    persist::PersistenceError,
+    serde::{Serde, SerdeDirection},
+};
+use std::{error, fmt, sync::Arc, time::Duration};
+use tracing::warn;
+
+/// How long [`CommandBus::dispatch_idempotent`] remembers a key unless configured otherwise.
+const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Error returned by [`CommandBus::dispatch`].
+#[derive(Debug, thiserror::Error)]
+pub enum CommandError<E: error::Error> {
+    /// The aggregate rejected the command.
+    #[error("{0}")]
+    Domain(E),
+    /// A [`CommandMiddleware`] rejected the command before it reached the aggregate.
+    #[error("command rejected: {0}")]
+    Rejected(String),
+    #[error(transparent)]
+    Persistence(#[from] PersistenceError),
+}
+
+/// Dispatches commands to the aggregates of type `T`: every command is handled by the aggregate its
+/// [`Command::id`] points at, loaded from and committed to the repository.
+///
+/// Middleware added with [`CommandBus::with_middleware`] wraps every dispatch, the first added outermost.
+pub struct CommandBus<T: AggregateRoot> {
+    repository: Arc<dyn Repository<T>>,
+    middleware: Vec<Arc<dyn CommandMiddleware<T>>>,
+    idempotency: Option<Idempotency<T>>,
+    idempotency_ttl: Duration,
+}
+
+/// Where [`CommandBus::dispatch_idempotent`] records the events of a key, and how it serializes them.
+struct Idempotency<T: AggregateRoot> {
+    store: Arc<dyn IdempotencyStore>,
+    serde: Arc<dyn Serde<T::DomainEvent>>,
+}
+
+impl<T: AggregateRoot> Clone for CommandBus<T> {
+    fn clone(&self) -> Self {
+        Self {
+            repository: Arc::clone(&self.repository),
+            middleware: self.middleware.clone(),
+            idempotency: self.idempotency.as_ref().map(|idempotency| Idempotency {
+                store: Arc::clone(&idempotency.store),
+                serde: Arc::clone(&idempotency.serde),
+            }),
+            idempotency_ttl: self.idempotency_ttl,
+        }
+    }
+}
+
+impl<T: AggregateRoot> fmt::Debug for CommandBus<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandBus")
+            .field("aggregate_type", &T::TYPE)
+            .field("middleware", &self.middleware.len())
+            .field(
+                "idempotency_store",
+                &self.idempotency.as_ref().map(|idempotency| &idempotency.store),
+            )
+            .field("idempotency_ttl", &self.idempotency_ttl)
+            .finish()
+    }
+}
+
+impl<T> CommandBus<T>
+where
+    T: AggregateRoot,
+    T::Command: Command<ID = T::ID>,
+{
+    pub fn new(repository: impl Repository<T>) -> Self {
+        Self {
+            repository: Arc::new(repository),
+            middleware: Vec::new(),
+            idempotency: None,
+            idempotency_ttl: DEFAULT_IDEMPOTENCY_TTL,
+        }
+    }
+
+    /// Records the events of [`CommandBus::dispatch_idempotent`] in `store`, serialized with `serde`.
+    pub fn with_idempotency_store(
+        mut self,
+        store: impl IdempotencyStore,
+        serde: impl Serde<T::DomainEvent> + 'static,
+    ) -> Self {
+        self.idempotency = Some(Idempotency {
+            store: Arc::new(store),
+            serde: Arc::new(serde),
+        });
+        self
+    }
+
+    /// How long an idempotency key is remembered after its command was dispatched; 24 hours by default.
+    pub fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.idempotency_ttl = ttl;
+        self
+    }
+
+    /// Wraps dispatches in `middleware`, inside the middleware added before it.
+    pub fn with_middleware(mut self, middleware: impl CommandMiddleware<T>) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Loads the aggregate of `cmd`, handles the command, commits the resulting events and returns them.
+    ///
+    /// Nothing is committed when the aggregate rejects the command. A commit that loses a race against another
+    /// writer fails with a [`PersistenceError`] for which `is_concurrency_conflict` holds; dispatching the command
+    /// again handles it on fresh state.
+    pub async fn dispatch(&self, cmd: T::Command) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>> {
+        self.dispatch_with_metadata(cmd, Metadata::default()).await
+    }
+
+    /// Dispatches `cmd` like [`CommandBus::dispatch`], attaching `metadata` to the envelope of every event it
+    /// produces, so context such as the acting user or request ID is persisted with the events.
+    pub async fn dispatch_with_metadata(
+        &self,
+        cmd: T::Command,
+        metadata: Metadata,
+    ) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>> {
+        Next::new(self, &self.middleware, &metadata).run(cmd).await
+    }
+
+    /// Dispatches `cmd` like [`CommandBus::dispatch`] unless a command was already dispatched with
+    /// `idempotency_key`, in which case the events it produced are returned and nothing is committed.
+    ///
+    /// The key is reserved before the command runs and its events are recorded once they are committed, both for
+    /// the TTL set with [`CommandBus::with_idempotency_ttl`]. A retry racing the original dispatch fails with
+    /// [`PersistenceError::IdempotencyKeyInProgress`] instead of running the command a second time. A failed
+    /// dispatch releases the key, so retrying it runs the command again. Fails when the bus has no
+    /// [`IdempotencyStore`].
+    pub async fn dispatch_idempotent(
+        &self,
+        idempotency_key: &str,
+        cmd: T::Command,
+    ) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>> {
+        let Some(idempotency) = &self.idempotency else {
+            return Err(PersistenceError::UnknownError(
+                format!("CommandBus of {} has no idempotency store", T::TYPE).into(),
+            )
+            .into());
+        };
+
+        match idempotency.store.reserve(idempotency_key, self.idempotency_ttl).await? {
+            Reservation::Reserved => {}
+            Reservation::InProgress => {
+                return Err(PersistenceError::IdempotencyKeyInProgress {
+                    key: idempotency_key.to_string(),
+                }
+                .into())
+            }
+            Reservation::Completed(recorded) => {
+                return recorded
+                    .iter()
+                    .map(|payload| {
+                        idempotency.serde.deserialize(payload).map_err(|err| {
+                            PersistenceError::serialization(T::TYPE, SerdeDirection::Deserialize, err).into()
+                        })
+                    })
+                    .collect();
+            }
+        }
+
+        let events = match self.dispatch(cmd).await {
+            Ok(events) => events,
+            Err(err) => {
+                if let Err(release_err) = idempotency.store.release(idempotency_key).await {
+                    warn!(idempotency_key, error = %release_err, "Failed to release idempotency key");
+                }
+                return Err(err);
+            }
+        };
+        let payloads = events
+            .iter()
+            .map(|event| {
+                idempotency
+                    .serde
+                    .serialize(event)
+                    .map_err(|err| PersistenceError::serialization(event.event_type(), SerdeDirection::Serialize, err))
+            })
+            .collect::<Result<_, _>>()?;
+        idempotency
+            .store
+            .put(idempotency_key, payloads, self.idempotency_ttl)
+            .await?;
+        Ok(events)
+    }
+
+    /// Dispatches `cmd` to the aggregate, past every middleware, and commits its events with `metadata`.
+    pub(crate) async fn execute(
+        &self,
+        cmd: T::Command,
+        metadata: &Metadata,
+    ) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>> {
+        let mut versioned_aggregate = self.repository.load_aggregate(&cmd.id()).await?;
+        let events = versioned_aggregate.handle_many(cmd).map_err(CommandError::Domain)?;
+        let envelopes = events
+            .iter()
+            .cloned()
+            .map(|event| Envelope::from(event).set_metadata(metadata.clone()))
+            .collect();
+        self.repository.commit(&versioned_aggregate, envelopes).await?;
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregate_id::{AggregateId, HasIdPrefix},
+        command::middleware::{CommandMetrics, LoggingMiddleware, MetricsMiddleware},
+        command::repository::{AggregateLoader, EventSourced},
+        domain_event::DomainEvent,
+        event::SequenceSelect,
+        event_id::EventIdType,
+        event_store::AggregateEventStreamer,
+        idempotency_store::MemoryIdempotencyStore,
+        integration_event::{IntegrationEvent, IntoIntegrationEvents},
+        mem_store::MemoryStore,
+        message,
+        serde::Json,
+    };
+    use async_trait::async_trait;
+    use futures::TryStreamExt;
+    use serde::{Deserialize, Serialize};
+    use std::{sync::Mutex, time::Duration};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct OrderId;
+
+    impl HasIdPrefix for OrderId {
+        const PREFIX: &'static str = "ord";
+    }
+
+    #[derive(Debug, Clone)]
+    enum OrderCommand {
+        Create {
+            id: AggregateId<OrderId>,
+            total_amount: u64,
+        },
+        Confirm {
+            id: AggregateId<OrderId>,
+        },
+        Ship {
+            id: AggregateId<OrderId>,
+        },
+    }
+
+    impl message::Message for OrderCommand {
+        fn name(&self) -> &'static str {
+            "OrderCommand"
+        }
+    }
+
+    impl Command for OrderCommand {
+        type ID = OrderId;
+
+        fn id(&self) -> AggregateId<Self::ID> {
+            match self {
+                Self::Create { id, .. } => *id,
+                Self::Confirm { id } => *id,
+                Self::Ship { id } => *id,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum OrderEvent {
+        Created { id: EventIdType, total_amount: u64 },
+        Confirmed { id: EventIdType },
+        Shipped { id: EventIdType },
+    }
+
+    impl message::Message for OrderEvent {
+        fn name(&self) -> &'static str {
+            "OrderEvent"
+        }
+    }
+
+    impl DomainEvent for OrderEvent {
+        fn id(&self) -> EventIdType {
+            match self {
+                Self::Created { id, .. } => *id,
+                Self::Confirmed { id } => *id,
+                Self::Shipped { id } => *id,
+            }
+        }
+
+        fn event_type(&self) -> &'static str {
+            match self {
+                Self::Created { .. } => "OrderCreated",
+                Self::Confirmed { .. } => "OrderConfirmed",
+                Self::Shipped { .. } => "OrderShipped",
+            }
+        }
+    }
+
+    impl IntoIntegrationEvents for OrderEvent {
+        type IntegrationEvent = OrderShippedForTracking;
+        type IntoIter = Vec<OrderShippedForTracking>;
+
+        fn into_integration_events(self) -> Self::IntoIter {
+            match self {
+                Self::Shipped { .. } => vec![OrderShippedForTracking],
+                _ => vec![],
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct OrderShippedForTracking;
+
+    impl message::Message for OrderShippedForTracking {
+        fn name(&self) -> &'static str {
+            "OrderShippedForTracking"
+        }
+    }
+
+    impl IntegrationEvent for OrderShippedForTracking {
+        fn id(&self) -> String {
+            EventIdType::new().to_string()
+        }
+
+        fn event_type(&self) -> &'static str {
+            "order.shipped"
+        }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    enum OrderError {
+        #[error("order is not {0}")]
+        InvalidStatus(&'static str),
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    enum OrderStatus {
+        Draft,
+        Created,
+        Confirmed,
+        Shipped,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        id: AggregateId<OrderId>,
+        status: OrderStatus,
+        total_amount: u64,
+    }
+
+    impl AggregateRoot for Order {
+        const TYPE: &'static str = "Order";
+        type ID = OrderId;
+        type Command = OrderCommand;
+        type DomainEvent = OrderEvent;
+        type IntegrationEvent = OrderShippedForTracking;
+        type Error = OrderError;
+
+        fn init(id: AggregateId<Self::ID>) -> Self {
+            Self {
+                id,
+                status: OrderStatus::Draft,
+                total_amount: 0,
+            }
+        }
+
+        fn id(&self) -> &AggregateId<Self::ID> {
+            &self.id
+        }
+
+        fn handle(&mut self, cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+            let id = EventIdType::new();
+            match (cmd, self.status) {
+                (OrderCommand::Create { total_amount, .. }, OrderStatus::Draft) => {
+                    Ok(OrderEvent::Created { id, total_amount })
+                }
+                (OrderCommand::Create { .. }, _) => Err(OrderError::InvalidStatus("a draft")),
+                (OrderCommand::Confirm { .. }, OrderStatus::Created) => Ok(OrderEvent::Confirmed { id }),
+                (OrderCommand::Confirm { .. }, _) => Err(OrderError::InvalidStatus("created")),
+                (OrderCommand::Ship { .. }, OrderStatus::Confirmed) => Ok(OrderEvent::Shipped { id }),
+                (OrderCommand::Ship { .. }, _) => Err(OrderError::InvalidStatus("confirmed")),
+            }
+        }
+
+        fn apply(&mut self, event: Self::DomainEvent) {
+            match event {
+                OrderEvent::Created { total_amount, .. } => {
+                    self.status = OrderStatus::Created;
+                    self.total_amount = total_amount;
+                }
+                OrderEvent::Confirmed { .. } => self.status = OrderStatus::Confirmed,
+                OrderEvent::Shipped { .. } => self.status = OrderStatus::Shipped,
+            }
+        }
+    }
+
+    type OrderRepository =
+        EventSourced<Order, MemoryStore, Json<Order>, Json<OrderEvent>, Json<OrderShippedForTracking>>;
+
+    fn create_bus() -> (CommandBus<Order>, OrderRepository) {
+        let store = MemoryStore::new(10);
+        let bus = CommandBus::new(EventSourced::new(
+            store.clone(),
+            Json::default(),
+            Json::default(),
+            Json::default(),
+        ));
+        (
+            bus,
+            EventSourced::new(store, Json::default(), Json::default(), Json::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_create_confirm_ship() {
+        let (bus, repository) = create_bus();
+        let id = AggregateId::<OrderId>::new();
+
+        let created = bus
+            .dispatch(OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+        let confirmed = bus.dispatch(OrderCommand::Confirm { id }).await.unwrap();
+        let shipped = bus.dispatch(OrderCommand::Ship { id }).await.unwrap();
+
+        assert!(matches!(
+            created.as_slice(),
+            [OrderEvent::Created { total_amount: 300, .. }]
+        ));
+        assert!(matches!(confirmed.as_slice(), [OrderEvent::Confirmed { .. }]));
+        assert!(matches!(shipped.as_slice(), [OrderEvent::Shipped { .. }]));
+
+        let order = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(order.seq_nr(), 3);
+        assert_eq!(order.aggregate().status, OrderStatus::Shipped);
+        assert_eq!(order.aggregate().total_amount, 300);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_with_metadata_attaches_it_to_persisted_events() {
+        let store = MemoryStore::new(10);
+        let bus = CommandBus::<Order>::new(EventSourced::new(
+            store.clone(),
+            Json::default(),
+            Json::default(),
+            Json::default(),
+        ));
+        let id = AggregateId::<OrderId>::new();
+        let metadata = message::Metadata::from([("actor", "user-1"), (message::CORRELATION_ID, "req-1")]);
+
+        bus.dispatch_with_metadata(OrderCommand::Create { id, total_amount: 300 }, metadata.clone())
+            .await
+            .unwrap();
+        bus.dispatch(OrderCommand::Confirm { id }).await.unwrap();
+
+        let persisted: Vec<_> = store
+            .stream_events::<Order>(&id.to_string(), SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(persisted.len(), 2);
+        let created: message::Metadata = serde_json::from_value(persisted[0].metadata.clone()).unwrap();
+        assert_eq!(created, metadata);
+        let confirmed: message::Metadata = serde_json::from_value(persisted[1].metadata.clone()).unwrap();
+        assert!(confirmed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_rejected_command_commits_nothing() {
+        let (bus, repository) = create_bus();
+        let id = AggregateId::<OrderId>::new();
+        bus.dispatch(OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+
+        let result = bus.dispatch(OrderCommand::Ship { id }).await;
+
+        assert!(matches!(
+            result,
+            Err(CommandError::Domain(OrderError::InvalidStatus("confirmed")))
+        ));
+        let order = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(order.seq_nr(), 1);
+        assert_eq!(order.aggregate().status, OrderStatus::Created);
+    }
+
+    struct RecordingMiddleware {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl CommandMiddleware<Order> for RecordingMiddleware {
+        async fn handle(
+            &self,
+            cmd: OrderCommand,
+            next: Next<'_, Order>,
+        ) -> Result<Vec<OrderEvent>, CommandError<OrderError>> {
+            self.log.lock().unwrap().push(format!("{}:before", self.name));
+            let result = next.run(cmd).await;
+            self.log.lock().unwrap().push(format!("{}:after", self.name));
+            result
+        }
+    }
+
+    struct NoShipping;
+
+    #[async_trait]
+    impl CommandMiddleware<Order> for NoShipping {
+        async fn handle(
+            &self,
+            cmd: OrderCommand,
+            next: Next<'_, Order>,
+        ) -> Result<Vec<OrderEvent>, CommandError<OrderError>> {
+            match cmd {
+                OrderCommand::Ship { .. } => Err(CommandError::Rejected("shipping is paused".to_string())),
+                cmd => next.run(cmd).await,
+            }
+        }
+    }
+
+    /// Yields before every dispatch, so concurrent dispatches interleave.
+    struct Yielding;
+
+    #[async_trait]
+    impl CommandMiddleware<Order> for Yielding {
+        async fn handle(
+            &self,
+            cmd: OrderCommand,
+            next: Next<'_, Order>,
+        ) -> Result<Vec<OrderEvent>, CommandError<OrderError>> {
+            tokio::task::yield_now().await;
+            next.run(cmd).await
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingMetrics {
+        dispatches: Mutex<Vec<(&'static str, &'static str, bool)>>,
+    }
+
+    impl CommandMetrics for Arc<RecordingMetrics> {
+        fn record_dispatch(
+            &self,
+            aggregate_type: &'static str,
+            command: &'static str,
+            _latency: Duration,
+            succeeded: bool,
+        ) {
+            self.dispatches
+                .lock()
+                .unwrap()
+                .push((aggregate_type, command, succeeded));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_runs_outermost_first() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (bus, _) = create_bus();
+        let bus = bus
+            .with_middleware(RecordingMiddleware {
+                name: "outer",
+                log: log.clone(),
+            })
+            .with_middleware(RecordingMiddleware {
+                name: "inner",
+                log: log.clone(),
+            });
+
+        let id = AggregateId::<OrderId>::new();
+        bus.dispatch(OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer:before", "inner:before", "inner:after", "outer:after"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_middleware_short_circuits_command() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (bus, repository) = create_bus();
+        let bus = bus.with_middleware(NoShipping).with_middleware(RecordingMiddleware {
+            name: "inner",
+            log: log.clone(),
+        });
+        let id = AggregateId::<OrderId>::new();
+        bus.dispatch(OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+        bus.dispatch(OrderCommand::Confirm { id }).await.unwrap();
+        log.lock().unwrap().clear();
+
+        let result = bus.dispatch(OrderCommand::Ship { id }).await;
+
+        assert!(matches!(result, Err(CommandError::Rejected(reason)) if reason == "shipping is paused"));
+        assert!(log.lock().unwrap().is_empty());
+        let order = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(order.seq_nr(), 2);
+        assert_eq!(order.aggregate().status, OrderStatus::Confirmed);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_middleware_records_every_dispatch() {
+        let metrics = Arc::new(RecordingMetrics::default());
+        let (bus, _) = create_bus();
+        let bus = bus
+            .with_middleware(LoggingMiddleware)
+            .with_middleware(MetricsMiddleware::new(metrics.clone()));
+        let id = AggregateId::<OrderId>::new();
+
+        bus.dispatch(OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+        bus.dispatch(OrderCommand::Ship { id }).await.unwrap_err();
+
+        assert_eq!(
+            *metrics.dispatches.lock().unwrap(),
+            vec![("Order", "OrderCommand", true), ("Order", "OrderCommand", false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_idempotent_returns_recorded_events_on_repeat_key() {
+        let store = MemoryStore::new(10);
+        let bus = CommandBus::<Order>::new(EventSourced::new(
+            store.clone(),
+            Json::<Order>::default(),
+            Json::<OrderEvent>::default(),
+            Json::<OrderShippedForTracking>::default(),
+        ))
+        .with_idempotency_store(MemoryIdempotencyStore::new(), Json::<OrderEvent>::default());
+        let id = AggregateId::<OrderId>::new();
+
+        let first = bus
+            .dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+        let retried = bus
+            .dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+
+        assert_eq!(retried, first);
+        assert_eq!(store.count_events::<Order>(&id.to_string()).await.unwrap(), 1);
+
+        // A new key runs the command, which the aggregate rejects this time; nothing is recorded for it.
+        let result = bus
+            .dispatch_idempotent("req-2", OrderCommand::Create { id, total_amount: 300 })
+            .await;
+        assert!(matches!(result, Err(CommandError::Domain(_))));
+        bus.dispatch_idempotent("req-2", OrderCommand::Confirm { id })
+            .await
+            .unwrap();
+        assert_eq!(store.count_events::<Order>(&id.to_string()).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_idempotent_runs_concurrent_retries_once() {
+        let (bus, repository) = create_bus();
+        let bus = bus
+            .with_middleware(Yielding)
+            .with_idempotency_store(MemoryIdempotencyStore::new(), Json::<OrderEvent>::default());
+        let id = AggregateId::<OrderId>::new();
+
+        let (first, retried) = tokio::join!(
+            bus.dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 }),
+            bus.dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 }),
+        );
+
+        let first = first.unwrap();
+        assert!(matches!(
+            retried,
+            Err(CommandError::Persistence(PersistenceError::IdempotencyKeyInProgress { key })) if key == "req-1"
+        ));
+        assert_eq!(repository.load_aggregate(&id).await.unwrap().seq_nr(), 1);
+        let retried = bus
+            .dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+        assert_eq!(retried, first);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_idempotent_runs_again_after_ttl() {
+        let (bus, repository) = create_bus();
+        let bus = bus
+            .with_idempotency_store(MemoryIdempotencyStore::new(), Json::<OrderEvent>::default())
+            .with_idempotency_ttl(Duration::ZERO);
+        let id = AggregateId::<OrderId>::new();
+        bus.dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 })
+            .await
+            .unwrap();
+
+        let result = bus
+            .dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 })
+            .await;
+
+        assert!(matches!(result, Err(CommandError::Domain(_))));
+        assert_eq!(repository.load_aggregate(&id).await.unwrap().seq_nr(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_idempotent_requires_idempotency_store() {
+        let (bus, _) = create_bus();
+        let id = AggregateId::<OrderId>::new();
+
+        let result = bus
+            .dispatch_idempotent("req-1", OrderCommand::Create { id, total_amount: 300 })
+            .await;
+
+        assert!(matches!(result, Err(CommandError::Persistence(_))));
+    }
+}
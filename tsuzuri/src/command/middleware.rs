@@ -0,0 +1,138 @@
+use crate::{
+    aggregate::AggregateRoot,
+    command::{
+        bus::{CommandBus, CommandError},
+        Command,
+    },
+    message::{Message, Metadata},
+};
+use async_trait::async_trait;
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// Cross-cutting step wrapped around [`CommandBus::dispatch`], such as validation, authorization, logging or
+/// metrics.
+///
+/// A middleware passes the command on with [`Next::run`] and sees its outcome, or returns without calling it to
+/// short-circuit the command, typically with [`CommandError::Rejected`].
+#[async_trait]
+pub trait CommandMiddleware<T>: Send + Sync + 'static
+where
+    T: AggregateRoot,
+{
+    async fn handle(&self, cmd: T::Command, next: Next<'_, T>) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>>;
+}
+
+/// Rest of the middleware chain of a dispatch, ending at the aggregate.
+pub struct Next<'a, T: AggregateRoot> {
+    bus: &'a CommandBus<T>,
+    middleware: &'a [Arc<dyn CommandMiddleware<T>>],
+    metadata: &'a Metadata,
+}
+
+impl<'a, T> Next<'a, T>
+where
+    T: AggregateRoot,
+    T::Command: Command<ID = T::ID>,
+{
+    pub(crate) fn new(
+        bus: &'a CommandBus<T>,
+        middleware: &'a [Arc<dyn CommandMiddleware<T>>],
+        metadata: &'a Metadata,
+    ) -> Self {
+        Self {
+            bus,
+            middleware,
+            metadata,
+        }
+    }
+
+    /// Metadata the command was dispatched with, attached to every event it produces.
+    pub fn metadata(&self) -> &Metadata {
+        self.metadata
+    }
+
+    /// Passes `cmd` to the next middleware, or to the aggregate after the last one.
+    pub async fn run(self, cmd: T::Command) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>> {
+        match self.middleware.split_first() {
+            Some((middleware, rest)) => middleware.handle(cmd, Next::new(self.bus, rest, self.metadata)).await,
+            None => self.bus.execute(cmd, self.metadata).await,
+        }
+    }
+}
+
+/// Logs every dispatched command with its outcome and latency.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl<T> CommandMiddleware<T> for LoggingMiddleware
+where
+    T: AggregateRoot,
+    T::Command: Command<ID = T::ID>,
+{
+    async fn handle(&self, cmd: T::Command, next: Next<'_, T>) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>> {
+        let command = cmd.name();
+        let aggregate_id = cmd.id().to_string();
+        let started = Instant::now();
+        let result = next.run(cmd).await;
+        let latency = started.elapsed();
+        match &result {
+            Ok(events) => debug!(
+                aggregate_type = T::TYPE,
+                aggregate_id,
+                command,
+                events = events.len(),
+                ?latency,
+                "Command dispatched"
+            ),
+            Err(err) => warn!(
+                aggregate_type = T::TYPE,
+                aggregate_id,
+                command,
+                error = %err,
+                ?latency,
+                "Command failed"
+            ),
+        }
+        result
+    }
+}
+
+/// Hooks invoked by [`MetricsMiddleware`] after every dispatch.
+///
+/// Implement this to bridge the command bus to a metrics library such as `metrics` or `prometheus`.
+pub trait CommandMetrics: Send + Sync + 'static {
+    /// Called with the [`Message::name`] of the command, whether or not it succeeded.
+    fn record_dispatch(&self, aggregate_type: &'static str, command: &'static str, latency: Duration, succeeded: bool);
+}
+
+/// Reports the latency and outcome of every dispatched command to a [`CommandMetrics`].
+#[derive(Debug, Clone, Default)]
+pub struct MetricsMiddleware<M> {
+    metrics: M,
+}
+
+impl<M: CommandMetrics> MetricsMiddleware<M> {
+    pub fn new(metrics: M) -> Self {
+        Self { metrics }
+    }
+}
+
+#[async_trait]
+impl<T, M> CommandMiddleware<T> for MetricsMiddleware<M>
+where
+    T: AggregateRoot,
+    T::Command: Command<ID = T::ID>,
+    M: CommandMetrics,
+{
+    async fn handle(&self, cmd: T::Command, next: Next<'_, T>) -> Result<Vec<T::DomainEvent>, CommandError<T::Error>> {
+        let command = cmd.name();
+        let started = Instant::now();
+        let result = next.run(cmd).await;
+        self.metrics
+            .record_dispatch(T::TYPE, command, started.elapsed(), result.is_ok());
+        result
+    }
+}
@@ -1,22 +1,34 @@
 use crate::{
     aggregate_id::AggregateId,
     domain_event::{DomainEvent, SerializedDomainEvent},
-    event::{Envelope, SequenceSelect},
-    event_store::EventStore,
+    error::AggregateError,
+    event::{Envelope, SequenceSelect, Stream},
+    event_store::{EventStore, TOMBSTONE_EVENT_TYPE},
+    helper::{now_timestamp_with, Clock, SystemClock},
     integration_event::{IntegrationEvent, IntoIntegrationEvents, SerializedIntegrationEvent},
-    inverted_index_store::InvertedIndexStore,
+    inverted_index_store::{IndexKeyword, IndexOp, InvertedIndexStore},
     persist::PersistenceError,
-    serde::Serde,
+    sequence_number::SequenceNumber,
+    serde::{Serde, SerdeDirection, SerdeError},
     snapshot::PersistedSnapshot,
+    upcaster::{Upcaster, UpcasterRegistry},
     AggregateRoot, VersionedAggregate,
 };
 use async_trait::async_trait;
 use futures::{
-    stream::{self, StreamExt},
+    future,
+    stream::{self, BoxStream, StreamExt},
     TryStreamExt,
 };
-use std::marker::PhantomData;
-use tracing::warn;
+use prost_types::Timestamp;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    marker::PhantomData,
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{field, instrument, warn, Span};
 
 pub trait Repository<T>:
     AggregateLoader<T> + AggregatesLoader<T> + AggregateCommiter<T> + Send + Sync + 'static
@@ -38,6 +50,59 @@ where
     T: AggregateRoot,
 {
     async fn load_aggregate(&self, id: &AggregateId<T::ID>) -> Result<VersionedAggregate<T>, PersistenceError>;
+
+    /// Loads the aggregate like [`AggregateLoader::load_aggregate`], calling `on_event` with the sequence number of
+    /// every event replayed on top of the snapshot, e.g. to report progress while a long journal is rehydrated.
+    async fn load_aggregate_with_progress(
+        &self,
+        id: &AggregateId<T::ID>,
+        on_event: &mut (dyn FnMut(SequenceNumber) + Send),
+    ) -> Result<VersionedAggregate<T>, PersistenceError>;
+
+    /// Loads the aggregate from its latest snapshot alone, returning `None` when no snapshot exists.
+    ///
+    /// Events stored after the snapshot are not replayed, so the aggregate may lag behind the journal by up to a
+    /// snapshot interval. Use it only for views that tolerate stale state, and never to handle commands.
+    async fn load_aggregate_snapshot_only(
+        &self,
+        id: &AggregateId<T::ID>,
+    ) -> Result<Option<VersionedAggregate<T>>, PersistenceError>;
+
+    /// Loads the aggregate as it was at `at`, replaying only the events that occurred at or before it.
+    ///
+    /// The latest snapshot is used when it was taken at or before `at`; otherwise the journal is replayed from the
+    /// start. Events are assumed to be stored in `occurred_at` order, so replay stops at the first later event.
+    /// The result is a historical view and must not be used to handle commands.
+    async fn load_aggregate_as_of(
+        &self,
+        id: &AggregateId<T::ID>,
+        at: Timestamp,
+    ) -> Result<VersionedAggregate<T>, PersistenceError>;
+
+    /// Loads the aggregate like [`AggregateLoader::load_aggregate`], telling an aggregate that was never persisted
+    /// apart from an existing one and reporting a tombstoned aggregate as [`AggregateState::Tombstoned`] instead of
+    /// an error.
+    async fn load_state(&self, id: &AggregateId<T::ID>) -> Result<AggregateState<T>, PersistenceError> {
+        match self.load_aggregate(id).await {
+            Ok(versioned_aggregate) if versioned_aggregate.exists() => {
+                Ok(AggregateState::Existing(versioned_aggregate))
+            }
+            Ok(versioned_aggregate) => Ok(AggregateState::New(versioned_aggregate)),
+            Err(PersistenceError::Tombstoned { .. }) => Ok(AggregateState::Tombstoned),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// State of an aggregate returned by [`AggregateLoader::load_state`].
+#[derive(Debug)]
+pub enum AggregateState<T: AggregateRoot> {
+    /// Nothing was persisted for the ID yet; the aggregate is `T::init(id)` at version 0.
+    New(VersionedAggregate<T>),
+    /// The aggregate has events or a snapshot.
+    Existing(VersionedAggregate<T>),
+    /// The aggregate was deleted with [`Persister::tombstone`](crate::event_store::Persister::tombstone).
+    Tombstoned,
 }
 
 #[async_trait]
@@ -46,6 +111,25 @@ where
     T: AggregateRoot,
 {
     async fn load_aggregates(&self, keyword: &str) -> Result<Vec<VersionedAggregate<T>>, PersistenceError>;
+
+    /// Loads at most `limit` aggregates matching `keyword`, skipping the first `offset` in aggregate ID order.
+    ///
+    /// Returns the page together with whether more aggregates follow it.
+    async fn load_aggregates_paged(
+        &self,
+        keyword: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<VersionedAggregate<T>>, bool), PersistenceError>;
+
+    /// Streams the aggregates matching `keyword` as they load, so callers can process and drop them one by one.
+    ///
+    /// Unlike [`AggregatesLoader::load_aggregates`], aggregates that fail to parse or load are yielded as `Err`
+    /// items instead of being skipped; the stream goes on after them.
+    fn load_aggregates_stream<'a>(
+        &'a self,
+        keyword: &'a str,
+    ) -> BoxStream<'a, Result<VersionedAggregate<T>, PersistenceError>>;
 }
 
 #[async_trait]
@@ -53,13 +137,103 @@ pub trait AggregateCommiter<T>: Send + Sync + 'static
 where
     T: AggregateRoot,
 {
+    /// Persists `events` in order, numbering them contiguously after the aggregate's current sequence number.
+    ///
+    /// Committing no events is a no-op that does not touch the store.
     async fn commit(
         &self,
         versioned_aggregate: &VersionedAggregate<T>,
-        event: Envelope<T::DomainEvent>,
+        events: Vec<Envelope<T::DomainEvent>>,
     ) -> Result<(), PersistenceError>;
 }
 
+/// Delay between [`EventSourced::commit_with_retry`] attempts.
+///
+/// The n-th retry waits `base_delay * 2^(n - 1)` plus a random jitter of up to `max_jitter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBackoff {
+    pub base_delay: Duration,
+    pub max_jitter: Duration,
+}
+
+impl RetryBackoff {
+    pub fn new(base_delay: Duration, max_jitter: Duration) -> Self {
+        Self { base_delay, max_jitter }
+    }
+
+    /// Returns the delay to wait before the given retry (1-based).
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        let max_jitter_nanos = self.max_jitter.as_nanos();
+        if max_jitter_nanos == 0 {
+            return exponential;
+        }
+        // The random part of a fresh ULID is a cheap source of jitter without pulling in `rand`.
+        let jitter_nanos = ulid::Ulid::new().random() % (max_jitter_nanos + 1);
+        exponential.saturating_add(Duration::from_nanos(jitter_nanos as u64))
+    }
+}
+
+impl Default for RetryBackoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(50), Duration::from_millis(50))
+    }
+}
+
+/// What replay does with a stored event that the domain event serde cannot deserialize, such as an event type
+/// written by a newer release during a rolling deploy.
+///
+/// Skipped events still advance the aggregate's sequence number, so commits made after the load stay in sequence;
+/// the state the aggregate was loaded with just lacks their effect.
+#[derive(Clone, Default)]
+pub enum UnknownEventPolicy {
+    /// Fails the load with the deserialization error.
+    #[default]
+    Fail,
+    /// Logs the event with `warn!` and skips it.
+    Skip,
+    /// Hands the event and the error to the callback and skips it.
+    Capture(UnknownEventHandler),
+}
+
+/// Callback of [`UnknownEventPolicy::Capture`].
+pub type UnknownEventHandler = Arc<dyn Fn(&SerializedDomainEvent, &SerdeError) + Send + Sync>;
+
+impl UnknownEventPolicy {
+    /// Returns whether replay skips `event` of `aggregate_id`, which failed to deserialize with `err`.
+    fn skips(&self, aggregate_id: &str, event: &SerializedDomainEvent, err: &SerdeError) -> bool {
+        match self {
+            Self::Fail => false,
+            Self::Skip => {
+                warn!(
+                    aggregate_id,
+                    seq_nr = event.seq_nr,
+                    event_type = %event.event_type,
+                    error = %err,
+                    "Skipping event that failed to deserialize"
+                );
+                true
+            }
+            Self::Capture(capture) => {
+                capture(event, err);
+                true
+            }
+        }
+    }
+}
+
+impl fmt::Debug for UnknownEventPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fail => f.write_str("Fail"),
+            Self::Skip => f.write_str("Skip"),
+            Self::Capture(_) => f.write_str("Capture(..)"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct EventSourced<T, S, AggSerde, DEvtSerde, IEvtSerde>
 where
@@ -75,6 +249,17 @@ where
     pub integration_event_serde: IEvtSerde,
     pub aggregate: PhantomData<T>,
     pub concurrent_limit: usize,
+    pub retry_backoff: RetryBackoff,
+    /// Applied to stored payloads before they are deserialized during replay.
+    pub upcasters: UpcasterRegistry,
+    /// Stamps the `occurred_at` of committed events.
+    pub clock: Arc<dyn Clock>,
+    /// Handling of stored events that fail to deserialize during replay.
+    pub on_unknown_event: UnknownEventPolicy,
+    /// Largest serialized domain event payload a commit accepts; unlimited when `None`.
+    pub max_event_payload_bytes: Option<usize>,
+    /// Most events a load replays on top of the snapshot; unlimited when `None`.
+    pub max_replay_events: Option<usize>,
 }
 
 impl<T, S, AggSerde, DEvtSerde, IEvtSerde> EventSourced<T, S, AggSerde, DEvtSerde, IEvtSerde>
@@ -98,6 +283,12 @@ where
             integration_event_serde,
             aggregate: PhantomData,
             concurrent_limit: 10,
+            retry_backoff: RetryBackoff::default(),
+            upcasters: UpcasterRegistry::default(),
+            clock: Arc::new(SystemClock),
+            on_unknown_event: UnknownEventPolicy::default(),
+            max_event_payload_bytes: None,
+            max_replay_events: None,
         }
     }
 
@@ -106,70 +297,399 @@ where
         self
     }
 
+    pub fn with_retry_backoff(mut self, base_delay: Duration, max_jitter: Duration) -> Self {
+        self.retry_backoff = RetryBackoff::new(base_delay, max_jitter);
+        self
+    }
+
+    /// Registers an upcaster for events of `event_type` read back during replay.
+    pub fn with_upcaster(mut self, event_type: impl Into<String>, upcaster: impl Upcaster) -> Self {
+        self.upcasters.register(event_type, upcaster);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn with_unknown_event_policy(mut self, policy: UnknownEventPolicy) -> Self {
+        self.on_unknown_event = policy;
+        self
+    }
+
+    /// Rejects commits with [`PersistenceError::EventTooLarge`] when a serialized domain event payload exceeds
+    /// `bytes`, before anything is persisted.
+    ///
+    /// Catches events carrying unexpectedly large data early, instead of an opaque store failure such as a write
+    /// beyond DynamoDB's 400 KB item limit.
+    pub fn with_max_event_payload_bytes(mut self, bytes: usize) -> Self {
+        self.max_event_payload_bytes = Some(bytes);
+        self
+    }
+
+    /// Fails loads with [`PersistenceError::ReplayLimitExceeded`] when more than `limit` events follow the snapshot,
+    /// instead of replaying a corrupt or runaway journal indefinitely.
+    pub fn with_max_replay_events(mut self, limit: usize) -> Self {
+        self.max_replay_events = Some(limit);
+        self
+    }
+
     async fn prepare_events(
         &self,
         versioned_aggregate: &VersionedAggregate<T>,
-        event: Envelope<T::DomainEvent>,
-    ) -> Result<(SerializedDomainEvent, Vec<SerializedIntegrationEvent>), PersistenceError> {
-        let domain_event = event.message;
-        let event_id = domain_event.id();
+        events: Vec<Envelope<T::DomainEvent>>,
+    ) -> Result<(Vec<SerializedDomainEvent>, Vec<SerializedIntegrationEvent>), PersistenceError> {
         let aggregate_id = versioned_aggregate.id();
         let aggregate_type = T::TYPE;
-        let event_type = domain_event.event_type();
-        let seq_nr = versioned_aggregate.seq_nr();
-        let serialized_event = SerializedDomainEvent::new(
-            event_id.to_string(),
-            aggregate_id.to_string(),
-            seq_nr.saturating_add(1),
-            aggregate_type.to_string(),
-            event_type.to_string(),
-            self.domain_event_serde.serialize(&domain_event)?,
-            serde_json::to_value(event.metadata)?,
-        );
-        let serialized_integration_events = domain_event
-            .into_integration_events()
-            .into_iter()
-            .map(|integration_event| {
-                Ok(SerializedIntegrationEvent::new(
-                    integration_event.id().to_string(),
+        let mut seq_nr = versioned_aggregate.seq_nr();
+        let mut serialized_events = Vec::with_capacity(events.len());
+        let mut serialized_integration_events = Vec::new();
+        let mut occurred_at = Timestamp::default();
+
+        for event in events {
+            let domain_event = event.message;
+            seq_nr = seq_nr.saturating_add(1);
+            // Keep the timestamps of one commit in order even if the clock steps back.
+            let now = now_timestamp_with(self.clock.as_ref()).unwrap_or_default();
+            if (now.seconds, now.nanos) > (occurred_at.seconds, occurred_at.nanos) {
+                occurred_at = now;
+            }
+            let payload = self.domain_event_serde.serialize(&domain_event).map_err(|err| {
+                PersistenceError::serialization(domain_event.event_type(), SerdeDirection::Serialize, err)
+            })?;
+            if self.max_event_payload_bytes.is_some_and(|limit| payload.len() > limit) {
+                return Err(PersistenceError::EventTooLarge {
+                    event_type: domain_event.event_type().to_string(),
+                    bytes: payload.len(),
+                });
+            }
+            let metadata = serde_json::to_value(event.metadata)?;
+            serialized_events.push(
+                SerializedDomainEvent::new(
+                    domain_event.id().to_string(),
                     aggregate_id.to_string(),
-                    T::TYPE.to_string(),
-                    integration_event.event_type().to_string(),
-                    self.integration_event_serde.serialize(&integration_event)?,
-                ))
-            })
-            .collect::<Result<Vec<_>, PersistenceError>>()?;
-        Ok((serialized_event, serialized_integration_events))
+                    seq_nr,
+                    aggregate_type.to_string(),
+                    domain_event.event_type().to_string(),
+                    payload,
+                    metadata.clone(),
+                )
+                .with_schema_version(domain_event.schema_version())
+                .with_occurred_at(occurred_at),
+            );
+            for integration_event in domain_event.into_integration_events() {
+                serialized_integration_events.push(
+                    SerializedIntegrationEvent::new(
+                        integration_event.id().to_string(),
+                        aggregate_id.to_string(),
+                        T::TYPE.to_string(),
+                        integration_event.event_type().to_string(),
+                        self.integration_event_serde
+                            .serialize(&integration_event)
+                            .map_err(|err| {
+                                PersistenceError::serialization(
+                                    integration_event.event_type(),
+                                    SerdeDirection::Serialize,
+                                    err,
+                                )
+                            })?,
+                    )
+                    .with_metadata(metadata.clone()),
+                );
+            }
+        }
+        Ok((serialized_events, serialized_integration_events))
     }
 
+    /// Returns the snapshot to persist along with `events` committed on top of `versioned_aggregate`, if they
+    /// reach the snapshot interval.
+    ///
+    /// The snapshot holds the state after the last of `events` that lands on an interval boundary, so a commit of
+    /// several events that crosses one or more boundaries snapshots at the last boundary it reaches.
     async fn prepare_snapshot_if_needed(
         &self,
         versioned_aggregate: &VersionedAggregate<T>,
+        events: &[Envelope<T::DomainEvent>],
     ) -> Result<Option<PersistedSnapshot>, PersistenceError> {
-        let aggregate = versioned_aggregate.aggregate();
         let version = versioned_aggregate.version();
         let seq_nr = versioned_aggregate.seq_nr();
-        let aggregate_id = aggregate.id();
-        // ライブラリの仕様上、1つのイベントを保存するので、
-        // 固定で1を指定する
-        let num_events = 1;
-        let commit_snapshot_to_event = self.store.commit_snapshot_with_addl_events(seq_nr, num_events);
+        let num_events = events.len();
+        let commit_snapshot_to_event = self.store.commit_snapshot_with_addl_events_for::<T>(seq_nr, num_events);
 
         if commit_snapshot_to_event == 0 {
             return Ok(None);
         }
 
-        let payload = self.aggregate_serde.serialize(aggregate)?;
+        // Aggregates are not required to be `Clone`, so the snapshotted state is built from a serialized copy.
+        let mut aggregate = self
+            .aggregate_serde
+            .deserialize(&self.aggregate_serde.serialize(versioned_aggregate.aggregate())?)?;
+        for event in &events[..commit_snapshot_to_event] {
+            aggregate.apply(event.message.clone());
+        }
+        let payload = self.aggregate_serde.serialize(&aggregate)?;
         let next_snapshot = version.saturating_add(1);
 
+        // Replay resumes at the event after the last one the snapshot holds.
         Ok(Some(PersistedSnapshot::new(
             T::TYPE.to_string(),
-            aggregate_id.to_string(),
+            versioned_aggregate.id().to_string(),
             payload,
-            seq_nr,
+            seq_nr.saturating_add(commit_snapshot_to_event).saturating_add(1),
             next_snapshot,
         )))
     }
+
+    /// Returns the inverted index writes that bring the aggregate's keywords up to date once `events` are applied.
+    fn index_changes(
+        &self,
+        versioned_aggregate: &VersionedAggregate<T>,
+        events: &[Envelope<T::DomainEvent>],
+    ) -> Result<Vec<IndexOp>, PersistenceError> {
+        if events.is_empty() {
+            return Ok(Vec::new());
+        }
+        let before: BTreeSet<IndexKeyword> = versioned_aggregate.aggregate().index_keywords().into_iter().collect();
+        // Aggregates are not required to be `Clone`, so the state after the events is built from a serialized copy.
+        let mut aggregate = self
+            .aggregate_serde
+            .deserialize(&self.aggregate_serde.serialize(versioned_aggregate.aggregate())?)?;
+        for event in events {
+            aggregate.apply(event.message.clone());
+        }
+        let after: BTreeSet<IndexKeyword> = aggregate.index_keywords().into_iter().collect();
+        let aggregate_id = versioned_aggregate.id().to_string();
+        let removed = before
+            .difference(&after)
+            .map(|keyword| IndexOp::delete(keyword, &aggregate_id));
+        let added = after
+            .difference(&before)
+            .map(|keyword| IndexOp::put(keyword, &aggregate_id));
+        Ok(removed.chain(added).collect())
+    }
+
+    /// Rebuilds the snapshot of `id` from the full journal, ignoring the stored snapshot payload.
+    ///
+    /// Use this to repair a snapshot that drifted from the journal. The stored snapshot is only consulted for its
+    /// version, so the rebuilt snapshot supersedes it. Nothing is written when the journal is empty. The journal is
+    /// replayed regardless of [`EventSourced::max_replay_events`].
+    ///
+    /// Fails with [`PersistenceError::CorruptStream`], leaving the stored snapshot as it is, once the journal no
+    /// longer starts at its first event, as after [`FileEventStore::compact`](crate::file_store::FileEventStore::compact).
+    pub async fn rebuild_snapshot(&self, id: &AggregateId<T::ID>) -> Result<VersionedAggregate<T>, PersistenceError> {
+        let version = self
+            .store
+            .get_snapshot::<T>(&id.to_string())
+            .await?
+            .map_or(0, |snapshot| snapshot.version);
+        let next_version = version.saturating_add(1);
+
+        let events = self.store.stream_events::<T>(&id.to_string(), SequenceSelect::All);
+        let versioned_aggregate = self
+            .replay_stream(
+                id,
+                VersionedAggregate::new(T::init(id.clone()), next_version, 0),
+                events,
+                &mut |_| {},
+            )
+            .await?;
+        if versioned_aggregate.seq_nr() == 0 {
+            return Ok(VersionedAggregate::new(T::init(id.clone()), version, 0));
+        }
+
+        let snapshot = PersistedSnapshot::new(
+            T::TYPE.to_string(),
+            id.to_string(),
+            self.aggregate_serde.serialize(versioned_aggregate.aggregate())?,
+            versioned_aggregate.seq_nr().saturating_add(1),
+            next_version,
+        );
+        self.store.persist(&[], &[], Some(&snapshot), &[]).await?;
+        Ok(versioned_aggregate)
+    }
+
+    async fn replay_events(
+        &self,
+        id: &AggregateId<T::ID>,
+        versioned_aggregate: VersionedAggregate<T>,
+        select: SequenceSelect,
+        on_event: &mut (dyn FnMut(SequenceNumber) + Send),
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        let events = self.store.stream_events::<T>(&id.to_string(), select);
+        let events = self.limit_replay(id, events);
+        self.replay_stream(id, versioned_aggregate, events, on_event).await
+    }
+
+    /// Replays the `events` of `id` that occurred at or before `at`.
+    async fn replay_events_until(
+        &self,
+        id: &AggregateId<T::ID>,
+        versioned_aggregate: VersionedAggregate<T>,
+        events: Stream<'_, SerializedDomainEvent, PersistenceError>,
+        at: Timestamp,
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        let events = events
+            .try_take_while(move |persisted| future::ready(Ok(occurred_at_or_before(&persisted.occurred_at, &at))))
+            .boxed();
+        let events = self.limit_replay(id, events);
+        self.replay_stream(id, versioned_aggregate, events, &mut |_| {}).await
+    }
+
+    /// Fails `events` with [`PersistenceError::ReplayLimitExceeded`] once more than
+    /// [`EventSourced::max_replay_events`] of them were read.
+    fn limit_replay<'a>(
+        &self,
+        id: &AggregateId<T::ID>,
+        events: Stream<'a, SerializedDomainEvent, PersistenceError>,
+    ) -> Stream<'a, SerializedDomainEvent, PersistenceError> {
+        let Some(limit) = self.max_replay_events else {
+            return events;
+        };
+        let aggregate_id = id.to_string();
+        events
+            .enumerate()
+            .map(move |(replayed, persisted)| {
+                if replayed < limit {
+                    persisted
+                } else {
+                    Err(PersistenceError::ReplayLimitExceeded {
+                        aggregate_id: aggregate_id.clone(),
+                        limit,
+                    })
+                }
+            })
+            .boxed()
+    }
+
+    async fn replay_stream(
+        &self,
+        id: &AggregateId<T::ID>,
+        versioned_aggregate: VersionedAggregate<T>,
+        events: Stream<'_, SerializedDomainEvent, PersistenceError>,
+        on_event: &mut (dyn FnMut(SequenceNumber) + Send),
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        events
+            .try_fold(versioned_aggregate, |mut versioned_aggregate, persisted| {
+                let replayed = self.replay_event(id, &mut versioned_aggregate, persisted).map(|()| {
+                    on_event(versioned_aggregate.seq_nr());
+                    versioned_aggregate
+                });
+                future::ready(replayed)
+            })
+            .await
+            .map_err(|err| match err {
+                PersistenceError::Tombstoned { .. }
+                | PersistenceError::KeyShredded { .. }
+                | PersistenceError::CorruptStream { .. }
+                | PersistenceError::ReplayLimitExceeded { .. }
+                | PersistenceError::Serialization { .. } => err,
+                err => {
+                    PersistenceError::UnknownError(format!("Failed to replay events for aggregate {id}: {err}").into())
+                }
+            })
+    }
+
+    /// Applies the `persisted` event of `id` to `versioned_aggregate` and advances its sequence number.
+    fn replay_event(
+        &self,
+        id: &AggregateId<T::ID>,
+        versioned_aggregate: &mut VersionedAggregate<T>,
+        persisted: SerializedDomainEvent,
+    ) -> Result<(), PersistenceError> {
+        if persisted.event_type == TOMBSTONE_EVENT_TYPE {
+            return Err(PersistenceError::Tombstoned {
+                aggregate_id: id.to_string(),
+            });
+        }
+        // Replaying from `T::init` only yields the aggregate's state when the journal still starts at its first
+        // event, which compaction and archival remove.
+        if versioned_aggregate.seq_nr() == 0 && persisted.seq_nr != 1 {
+            return Err(PersistenceError::CorruptStream {
+                aggregate_id: id.to_string(),
+                seq_nr: persisted.seq_nr,
+                reason: "journal does not start at sequence number 1, so it cannot be replayed from the initial state"
+                    .to_string(),
+            });
+        }
+        if versioned_aggregate.aggregate().is_terminal() {
+            versioned_aggregate.set_seq_nr(persisted.seq_nr);
+            return Ok(());
+        }
+        let payload = self
+            .upcasters
+            .upcast(&persisted.event_type, persisted.schema_version, &persisted.payload)?;
+        let event = match self.domain_event_serde.deserialize(&payload) {
+            Ok(event) => event,
+            Err(err @ SerdeError::KeyShredded(_)) => return Err(err.into()),
+            Err(err) if self.on_unknown_event.skips(&id.to_string(), &persisted, &err) => {
+                versioned_aggregate.set_seq_nr(persisted.seq_nr);
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(PersistenceError::serialization(
+                    persisted.event_type,
+                    SerdeDirection::Deserialize,
+                    err,
+                ))
+            }
+        };
+        versioned_aggregate
+            .try_apply(event)
+            .map_err(|err| PersistenceError::CorruptStream {
+                aggregate_id: id.to_string(),
+                seq_nr: persisted.seq_nr,
+                reason: err.to_string(),
+            })?;
+        versioned_aggregate.set_seq_nr(persisted.seq_nr);
+        Ok(())
+    }
+}
+
+impl<T, S, AggSerde, DEvtSerde, IEvtSerde> EventSourced<T, S, AggSerde, DEvtSerde, IEvtSerde>
+where
+    T: AggregateRoot,
+    T::Command: Clone,
+    S: EventStore + InvertedIndexStore,
+    AggSerde: Serde<T> + 'static,
+    DEvtSerde: Serde<T::DomainEvent> + 'static,
+    IEvtSerde: Serde<T::IntegrationEvent> + 'static,
+{
+    /// Loads the aggregate, handles `cmd` and commits the resulting events.
+    ///
+    /// When the commit loses a race against another writer, the aggregate is reloaded and the command
+    /// handled again after waiting according to [`EventSourced::retry_backoff`]. After `max_attempts`
+    /// attempts the last error is returned. Command errors are never retried.
+    pub async fn commit_with_retry(
+        &self,
+        id: &AggregateId<T::ID>,
+        cmd: T::Command,
+        max_attempts: usize,
+    ) -> Result<(), AggregateError<T::Error>> {
+        let mut attempt: usize = 1;
+        loop {
+            let mut versioned_aggregate = self.load_aggregate(id).await?;
+            let events = versioned_aggregate
+                .handle_many(cmd.clone())
+                .map_err(AggregateError::UserError)?;
+            let envelopes = events.into_iter().map(Envelope::from).collect();
+            match self.commit(&versioned_aggregate, envelopes).await {
+                Ok(()) => return Ok(()),
+                Err(err) if err.is_concurrency_conflict() && attempt < max_attempts => {
+                    warn!(
+                        aggregate_id = %id,
+                        attempt,
+                        error = %err,
+                        "Concurrency conflict on commit, retrying"
+                    );
+                    let retry = u32::try_from(attempt).unwrap_or(u32::MAX);
+                    tokio::time::sleep(self.retry_backoff.delay(retry)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -182,40 +702,86 @@ where
     IEvtSerde: Serde<T::IntegrationEvent> + 'static,
 {
     async fn load_aggregate(&self, id: &AggregateId<T::ID>) -> Result<VersionedAggregate<T>, PersistenceError> {
-        let (aggregate, version, seq_nr) = match self.store.get_snapshot::<T>(&id.to_string()).await {
-            Ok(Some(snapshot)) => (
-                self.aggregate_serde.deserialize(&snapshot.aggregate)?,
-                snapshot.version,
-                snapshot.seq_nr,
-            ),
-            Ok(None) => (T::init(id.clone()), 0, 0),
-            Err(err) => {
-                return Err(PersistenceError::UnknownError(
-                    format!("Failed to get snapshot for aggregate {id}: {err}").into(),
-                ))
-            }
-        };
+        self.load_aggregate_with_progress(id, &mut |_| {}).await
+    }
 
-        let versioned_aggregate = VersionedAggregate::from_snapshot(aggregate, version, seq_nr);
+    /// Records the replay outcome on a `load_aggregate` span of its own, whether it is called directly or through
+    /// [`AggregateLoader::load_aggregate`].
+    #[instrument(
+        name = "load_aggregate",
+        skip_all,
+        fields(
+            aggregate_type = T::TYPE,
+            aggregate_id = %id,
+            seq_nr = field::Empty,
+            version = field::Empty,
+            events_replayed = field::Empty,
+        )
+    )]
+    async fn load_aggregate_with_progress(
+        &self,
+        id: &AggregateId<T::ID>,
+        on_event: &mut (dyn FnMut(SequenceNumber) + Send),
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        let snapshot = self.store.get_snapshot::<T>(&id.to_string()).await.map_err(|err| {
+            PersistenceError::UnknownError(format!("Failed to get snapshot for aggregate {id}: {err}").into())
+        })?;
+        self.replay_after_snapshot(id, snapshot, on_event).await
+    }
 
-        let ctx = self
-            .store
-            .stream_events::<T>(&id.to_string(), SequenceSelect::From(seq_nr))
-            .try_fold(versioned_aggregate, |mut versioned_aggregate, persisted| async move {
-                let event = self.domain_event_serde.deserialize(&persisted.payload)?;
-                versioned_aggregate.set_seq_nr(persisted.seq_nr);
-                versioned_aggregate.apply(event);
-                Ok(versioned_aggregate)
-            })
-            .await
-            .map_err(|err| {
-                PersistenceError::UnknownError(format!("Failed to replay events for aggregate {id}: {err}").into())
-            })?;
+    async fn load_aggregate_snapshot_only(
+        &self,
+        id: &AggregateId<T::ID>,
+    ) -> Result<Option<VersionedAggregate<T>>, PersistenceError> {
+        let Some(snapshot) = self.store.get_snapshot::<T>(&id.to_string()).await? else {
+            return Ok(None);
+        };
+        let aggregate = self.aggregate_serde.deserialize(&snapshot.aggregate)?;
+        Ok(Some(VersionedAggregate::from_snapshot(
+            aggregate,
+            snapshot.version,
+            snapshot.seq_nr.saturating_sub(1),
+        )))
+    }
+
+    async fn load_aggregate_as_of(
+        &self,
+        id: &AggregateId<T::ID>,
+        at: Timestamp,
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        let aggregate_id = id.to_string();
+        if let Some(snapshot) = self.store.get_snapshot::<T>(&aggregate_id).await? {
+            // The snapshot holds the state up to the event before `snapshot.seq_nr`, so it was taken at or before
+            // `at` exactly when that event occurred by then.
+            let covered = snapshot.seq_nr.saturating_sub(1);
+            let mut events = self
+                .store
+                .stream_events::<T>(&aggregate_id, SequenceSelect::From(covered.max(1)));
+            let snapshot_usable = if covered == 0 {
+                true
+            } else {
+                events
+                    .try_next()
+                    .await?
+                    .is_some_and(|last| last.seq_nr == covered && occurred_at_or_before(&last.occurred_at, &at))
+            };
+            if snapshot_usable {
+                let aggregate = self.aggregate_serde.deserialize(&snapshot.aggregate)?;
+                let versioned_aggregate = VersionedAggregate::from_snapshot(aggregate, snapshot.version, covered);
+                return self.replay_events_until(id, versioned_aggregate, events, at).await;
+            }
+        }
 
-        Ok(ctx)
+        let versioned_aggregate = VersionedAggregate::new(T::init(id.clone()), 0, 0);
+        let events = self.store.stream_events::<T>(&aggregate_id, SequenceSelect::All);
+        self.replay_events_until(id, versioned_aggregate, events, at).await
     }
 }
 
+fn occurred_at_or_before(occurred_at: &Timestamp, at: &Timestamp) -> bool {
+    (occurred_at.seconds, occurred_at.nanos) <= (at.seconds, at.nanos)
+}
+
 #[async_trait]
 impl<T, S, AggSerde, DEvtSerde, IEvtSerde> AggregatesLoader<T> for EventSourced<T, S, AggSerde, DEvtSerde, IEvtSerde>
 where
@@ -232,51 +798,200 @@ where
             return Ok(vec![]);
         }
 
-        let aggregates: Vec<VersionedAggregate<T>> = stream::iter(aggregate_ids)
-            .map(|id| async move {
-                match id.parse::<AggregateId<T::ID>>() {
-                    Ok(aggregate_id) => match self.load_aggregate(&aggregate_id).await {
-                        Ok(agg) => Ok(Some(agg)),
-                        Err(e) => {
-                            warn!(
-                                aggregate_id = %aggregate_id,
-                                error = %e,
-                                "Failed to load aggregate, skipping"
-                            );
-                            Ok(None)
-                        }
-                    },
-                    Err(e) => {
-                        warn!(
-                            aggregate_id = %id,
-                            error = ?e,
-                            "Failed to parse aggregate ID, skipping"
-                        );
-                        Ok(None)
-                    }
-                }
-            })
-            .buffer_unordered(self.concurrent_limit)
-            .filter_map(
-                |result: Result<Option<VersionedAggregate<T>>, PersistenceError>| async move {
-                    match result {
-                        Ok(Some(agg)) => Some(agg),
-                        Ok(None) => None,
-                        Err(e) => {
-                            warn!(
-                                error = %e,
-                                "Unexpected error in aggregate loading stream"
-                            );
-                            None
-                        }
-                    }
-                },
-            )
+        let aggregates: Vec<VersionedAggregate<T>> = self
+            .load_indexed_aggregates(aggregate_ids)
+            .filter_map(|(id, aggregate)| future::ready(ok_or_skip(&id, aggregate)))
             .collect()
             .await;
 
         Ok(aggregates)
     }
+
+    async fn load_aggregates_paged(
+        &self,
+        keyword: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<VersionedAggregate<T>>, bool), PersistenceError> {
+        let mut aggregate_ids = self.store.get_aggregate_ids(keyword).await?;
+        aggregate_ids.sort_unstable();
+        aggregate_ids.dedup();
+        let has_more = aggregate_ids.len() > offset.saturating_add(limit);
+
+        let page = aggregate_ids.into_iter().skip(offset).take(limit).collect();
+        let aggregates: Vec<VersionedAggregate<T>> = self
+            .load_indexed_aggregates(page)
+            .filter_map(|(id, aggregate)| future::ready(ok_or_skip(&id, aggregate)))
+            .collect()
+            .await;
+
+        Ok((aggregates, has_more))
+    }
+
+    fn load_aggregates_stream<'a>(
+        &'a self,
+        keyword: &'a str,
+    ) -> BoxStream<'a, Result<VersionedAggregate<T>, PersistenceError>> {
+        stream::once(self.store.get_aggregate_ids(keyword))
+            .map(move |aggregate_ids| match aggregate_ids {
+                // Snapshots are read a batch at a time, so the first aggregates arrive before all are fetched.
+                Ok(aggregate_ids) => stream::iter(aggregate_ids)
+                    .chunks(self.concurrent_limit.max(1))
+                    .flat_map(|ids| self.load_indexed_aggregates(ids))
+                    .map(|(_, aggregate)| aggregate)
+                    .left_stream(),
+                Err(err) => stream::once(future::ready(Err(err))).right_stream(),
+            })
+            .flatten()
+            .boxed()
+    }
+}
+
+impl<T, S, AggSerde, DEvtSerde, IEvtSerde> EventSourced<T, S, AggSerde, DEvtSerde, IEvtSerde>
+where
+    T: AggregateRoot,
+    S: EventStore + InvertedIndexStore,
+    AggSerde: Serde<T> + 'static,
+    DEvtSerde: Serde<T::DomainEvent> + 'static,
+    IEvtSerde: Serde<T::IntegrationEvent> + 'static,
+{
+    /// Loads the aggregates with the raw `ids` from the inverted index, in order, each paired with its ID.
+    ///
+    /// The snapshots of all of them are read with a single [`SnapshotGetter::get_snapshots`] call, after which only
+    /// the events following each snapshot are replayed.
+    ///
+    /// [`SnapshotGetter::get_snapshots`]: crate::event_store::SnapshotGetter::get_snapshots
+    fn load_indexed_aggregates(
+        &self,
+        ids: Vec<String>,
+    ) -> impl futures::Stream<Item = (String, Result<VersionedAggregate<T>, PersistenceError>)> + Send + '_ {
+        stream::once(self.prefetch_snapshots(ids)).flat_map(move |prefetched| {
+            stream::iter(prefetched)
+                .map(move |(id, prefetched)| async move {
+                    let aggregate = match prefetched {
+                        Ok((aggregate_id, snapshot)) => self.load_with_snapshot(&aggregate_id, snapshot).await,
+                        Err(err) => Err(err),
+                    };
+                    (id, aggregate)
+                })
+                .buffered(self.concurrent_limit)
+        })
+    }
+
+    /// Parses the raw `ids` and reads the snapshots of those that parse in one batch.
+    async fn prefetch_snapshots(&self, ids: Vec<String>) -> Vec<(String, PrefetchedSnapshot<T>)> {
+        let parsed: Vec<_> = ids
+            .into_iter()
+            .map(|id| {
+                let aggregate_id = id
+                    .parse::<AggregateId<T::ID>>()
+                    .map_err(|e| PersistenceError::DeserializationError(Box::new(e)));
+                (id, aggregate_id)
+            })
+            .collect();
+        let valid: Vec<&str> = parsed
+            .iter()
+            .filter(|(_, aggregate_id)| aggregate_id.is_ok())
+            .map(|(id, _)| id.as_str())
+            .collect();
+        let mut snapshots = if valid.is_empty() {
+            Ok(HashMap::new())
+        } else {
+            // Errors are not `Clone`, so a failed batch is reported to each of its aggregates by message.
+            self.store
+                .get_snapshots::<T>(&valid)
+                .await
+                .map_err(|err| err.to_string())
+        };
+
+        parsed
+            .into_iter()
+            .map(|(id, aggregate_id)| {
+                let prefetched = aggregate_id.and_then(|aggregate_id| match &mut snapshots {
+                    Ok(snapshots) => Ok((aggregate_id, snapshots.remove(&id))),
+                    Err(err) => Err(PersistenceError::UnknownError(
+                        format!("Failed to get snapshot for aggregate {id}: {err}").into(),
+                    )),
+                });
+                (id, prefetched)
+            })
+            .collect()
+    }
+
+    #[instrument(
+        name = "load_aggregate",
+        skip_all,
+        fields(
+            aggregate_type = T::TYPE,
+            aggregate_id = %id,
+            seq_nr = field::Empty,
+            version = field::Empty,
+            events_replayed = field::Empty,
+        )
+    )]
+    async fn load_with_snapshot(
+        &self,
+        id: &AggregateId<T::ID>,
+        snapshot: Option<PersistedSnapshot>,
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        self.replay_after_snapshot(id, snapshot, &mut |_| {}).await
+    }
+
+    /// Restores `snapshot`, or a fresh aggregate without one, and replays the events that follow it.
+    ///
+    /// Records the outcome on the current span, so it must only be called from within a `load_aggregate` span.
+    async fn replay_after_snapshot(
+        &self,
+        id: &AggregateId<T::ID>,
+        snapshot: Option<PersistedSnapshot>,
+        on_event: &mut (dyn FnMut(SequenceNumber) + Send),
+    ) -> Result<VersionedAggregate<T>, PersistenceError> {
+        let (aggregate, version, seq_nr) = match snapshot {
+            Some(snapshot) => (
+                self.aggregate_serde.deserialize(&snapshot.aggregate)?,
+                snapshot.version,
+                snapshot.seq_nr,
+            ),
+            None => (T::init(id.clone()), 0, 0),
+        };
+
+        let replay_from = seq_nr.saturating_sub(1);
+        let versioned_aggregate = VersionedAggregate::from_snapshot(aggregate, version, replay_from);
+
+        let versioned_aggregate = self
+            .replay_events(id, versioned_aggregate, SequenceSelect::From(seq_nr), on_event)
+            .await?;
+        let span = Span::current();
+        span.record("seq_nr", versioned_aggregate.seq_nr());
+        span.record("version", versioned_aggregate.version());
+        span.record(
+            "events_replayed",
+            versioned_aggregate.seq_nr().saturating_sub(replay_from),
+        );
+        Ok(versioned_aggregate)
+    }
+}
+
+/// Parsed aggregate ID and prefetched snapshot of an aggregate loaded from the inverted index.
+type PrefetchedSnapshot<T> =
+    Result<(AggregateId<<T as AggregateRoot>::ID>, Option<PersistedSnapshot>), PersistenceError>;
+
+/// Logs and skips an aggregate of the inverted index that failed to load.
+fn ok_or_skip<T: AggregateRoot>(
+    id: &str,
+    aggregate: Result<VersionedAggregate<T>, PersistenceError>,
+) -> Option<VersionedAggregate<T>> {
+    match aggregate {
+        Ok(aggregate) => Some(aggregate),
+        Err(e) => {
+            warn!(
+                aggregate_id = %id,
+                error = %e,
+                "Failed to load aggregate, skipping"
+            );
+            None
+        }
+    }
 }
 
 #[async_trait]
@@ -288,21 +1003,1660 @@ where
     DEvtSerde: Serde<T::DomainEvent> + 'static,
     IEvtSerde: Serde<T::IntegrationEvent> + 'static,
 {
+    #[instrument(
+        skip_all,
+        fields(
+            aggregate_type = T::TYPE,
+            aggregate_id = %versioned_aggregate.id(),
+            events = events.len(),
+            seq_nr = field::Empty,
+            version = field::Empty,
+            snapshot_taken = field::Empty,
+        )
+    )]
     async fn commit(
         &self,
         versioned_aggregate: &VersionedAggregate<T>,
-        event: Envelope<T::DomainEvent>,
+        events: Vec<Envelope<T::DomainEvent>>,
     ) -> Result<(), PersistenceError> {
-        let (serialized_domain_event, serialized_integration_events) =
-            self.prepare_events(versioned_aggregate, event).await?;
-        let serialized_snapshot = self.prepare_snapshot_if_needed(versioned_aggregate).await?;
+        // A command that produced no events leaves the aggregate as it is, so there is nothing to persist.
+        if events.is_empty() {
+            return Ok(());
+        }
+        let index_ops = self.index_changes(versioned_aggregate, &events)?;
+        let serialized_snapshot = self.prepare_snapshot_if_needed(versioned_aggregate, &events).await?;
+        let (serialized_domain_events, serialized_integration_events) =
+            self.prepare_events(versioned_aggregate, events).await?;
+
+        let span = Span::current();
+        span.record(
+            "seq_nr",
+            serialized_domain_events
+                .last()
+                .map_or(versioned_aggregate.seq_nr(), |event| event.seq_nr),
+        );
+        span.record(
+            "version",
+            serialized_snapshot
+                .as_ref()
+                .map_or(versioned_aggregate.version(), |snapshot| snapshot.version),
+        );
+        span.record("snapshot_taken", serialized_snapshot.is_some());
+
         self.store
             .persist(
-                &[serialized_domain_event],
+                &serialized_domain_events,
                 serialized_integration_events.as_ref(),
                 serialized_snapshot.as_ref(),
+                &index_ops,
             )
             .await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregate_id::HasIdPrefix,
+        command::Command,
+        event::Stream,
+        event_id::EventIdType,
+        event_store::{AggregateEventStreamer, Persister, SnapshotGetter, SnapshotIntervalProvider},
+        helper::FixedClock,
+        inverted_index_store::{
+            AggregateIdsLoader, IndexKeyword, IndexOp, InvertedIndexCommiter, InvertedIndexRemover,
+        },
+        mem_store::MemoryStore,
+        message,
+        serde::Json,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct AccountId;
+
+    impl HasIdPrefix for AccountId {
+        const PREFIX: &'static str = "acc";
+    }
+
+    #[derive(Debug, Clone)]
+    enum AccountCommand {
+        Deposit { id: AggregateId<AccountId>, amount: u64 },
+        Close { id: AggregateId<AccountId> },
+    }
+
+    impl message::Message for AccountCommand {
+        fn name(&self) -> &'static str {
+            "AccountCommand"
+        }
+    }
+
+    impl Command for AccountCommand {
+        type ID = AccountId;
+
+        fn id(&self) -> AggregateId<Self::ID> {
+            match self {
+                Self::Deposit { id, .. } => *id,
+                Self::Close { id } => *id,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum AccountEvent {
+        Deposited { id: EventIdType, amount: u64 },
+        BalanceZeroed { id: EventIdType },
+        Closed { id: EventIdType },
+    }
+
+    impl message::Message for AccountEvent {
+        fn name(&self) -> &'static str {
+            "AccountEvent"
+        }
+    }
+
+    impl DomainEvent for AccountEvent {
+        fn id(&self) -> EventIdType {
+            match self {
+                Self::Deposited { id, .. } => *id,
+                Self::BalanceZeroed { id } => *id,
+                Self::Closed { id } => *id,
+            }
+        }
+
+        fn event_type(&self) -> &'static str {
+            match self {
+                Self::Deposited { .. } => "AccountDeposited",
+                Self::BalanceZeroed { .. } => "AccountBalanceZeroed",
+                Self::Closed { .. } => "AccountClosed",
+            }
+        }
+
+        // Version 1 of `Deposited` stored the amount as `value`.
+        fn schema_version(&self) -> u32 {
+            2
+        }
+    }
+
+    impl IntoIntegrationEvents for AccountEvent {
+        type IntegrationEvent = AccountIntegrationEvent;
+        type IntoIter = Vec<AccountIntegrationEvent>;
+
+        fn into_integration_events(self) -> Self::IntoIter {
+            match self {
+                Self::Closed { .. } => vec![AccountIntegrationEvent],
+                _ => vec![],
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct AccountIntegrationEvent;
+
+    impl message::Message for AccountIntegrationEvent {
+        fn name(&self) -> &'static str {
+            "AccountIntegrationEvent"
+        }
+    }
+
+    impl IntegrationEvent for AccountIntegrationEvent {
+        fn id(&self) -> String {
+            EventIdType::new().to_string()
+        }
+
+        fn event_type(&self) -> &'static str {
+            "account.closed"
+        }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    enum AccountError {
+        #[error("Account is closed")]
+        Closed,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Account {
+        id: AggregateId<AccountId>,
+        balance: u64,
+        closed: bool,
+    }
+
+    impl AggregateRoot for Account {
+        const TYPE: &'static str = "Account";
+        type ID = AccountId;
+        type Command = AccountCommand;
+        type DomainEvent = AccountEvent;
+        type IntegrationEvent = AccountIntegrationEvent;
+        type Error = AccountError;
+
+        fn init(id: AggregateId<Self::ID>) -> Self {
+            Self {
+                id,
+                balance: 0,
+                closed: false,
+            }
+        }
+
+        fn id(&self) -> &AggregateId<Self::ID> {
+            &self.id
+        }
+
+        fn handle(&mut self, cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+            if self.closed {
+                return Err(AccountError::Closed);
+            }
+            match cmd {
+                AccountCommand::Deposit { amount, .. } => Ok(AccountEvent::Deposited {
+                    id: EventIdType::new(),
+                    amount,
+                }),
+                AccountCommand::Close { .. } => Ok(AccountEvent::Closed { id: EventIdType::new() }),
+            }
+        }
+
+        fn handle_many(&mut self, cmd: Self::Command) -> Result<Vec<Self::DomainEvent>, Self::Error> {
+            match cmd {
+                AccountCommand::Close { .. } if !self.closed && self.balance > 0 => Ok(vec![
+                    AccountEvent::BalanceZeroed { id: EventIdType::new() },
+                    AccountEvent::Closed { id: EventIdType::new() },
+                ]),
+                AccountCommand::Deposit { amount: 0, .. } if !self.closed => Ok(vec![]),
+                cmd => self.handle(cmd).map(|event| vec![event]),
+            }
+        }
+
+        fn apply(&mut self, event: Self::DomainEvent) {
+            match event {
+                AccountEvent::Deposited { amount, .. } => self.balance += amount,
+                AccountEvent::BalanceZeroed { .. } => self.balance = 0,
+                AccountEvent::Closed { .. } => self.closed = true,
+            }
+        }
+
+        fn try_apply(&mut self, event: Self::DomainEvent) -> Result<(), Self::Error> {
+            if self.closed {
+                return Err(AccountError::Closed);
+            }
+            self.apply(event);
+            Ok(())
+        }
+    }
+
+    type TestRepository =
+        EventSourced<Account, MemoryStore, Json<Account>, Json<AccountEvent>, Json<AccountIntegrationEvent>>;
+
+    fn create_repository(snapshot_interval: usize) -> TestRepository {
+        EventSourced::new(
+            MemoryStore::new(snapshot_interval),
+            Json::default(),
+            Json::default(),
+            Json::default(),
+        )
+    }
+
+    async fn execute(repository: &TestRepository, id: &AggregateId<AccountId>, cmd: AccountCommand) {
+        let mut versioned = repository.load_aggregate(id).await.unwrap();
+        let events = versioned.handle_many(cmd).unwrap();
+        repository
+            .commit(&versioned, events.into_iter().map(Envelope::from).collect())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_commit_multiple_events_with_contiguous_seq_nr() {
+        let repository = create_repository(100);
+        let id = AggregateId::<AccountId>::new();
+
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 50 }).await;
+        execute(&repository, &id, AccountCommand::Close { id }).await;
+
+        let persisted: Vec<_> = repository
+            .store
+            .stream_events::<Account>(&id.to_string(), SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+        let seq_nrs: Vec<_> = persisted.iter().map(|event| event.seq_nr).collect();
+        let event_types: Vec<_> = persisted.iter().map(|event| event.event_type.as_str()).collect();
+        assert_eq!(seq_nrs, vec![1, 2, 3]);
+        assert_eq!(
+            event_types,
+            vec!["AccountDeposited", "AccountBalanceZeroed", "AccountClosed"]
+        );
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 3);
+        assert_eq!(loaded.aggregate().balance, 0);
+        assert!(loaded.aggregate().closed);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_from_snapshot_does_not_reapply_events() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+
+        for amount in [10, 20, 30, 40, 50] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+
+        let snapshot = repository.store.get_snapshot::<Account>(&id.to_string()).await.unwrap();
+        assert!(snapshot.is_some());
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 5);
+        assert_eq!(loaded.aggregate().balance, 150);
+    }
+
+    #[tokio::test]
+    async fn test_multi_event_commit_snapshots_at_interval_boundary() {
+        let repository = create_repository(3);
+        let id = AggregateId::<AccountId>::new();
+
+        for amount in [10, 20] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+        assert!(repository
+            .store
+            .get_snapshot::<Account>(&id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+
+        // Events 3 to 5 cross the boundary at 3, so the snapshot holds the state after event 3.
+        let mut versioned = repository.load_aggregate(&id).await.unwrap();
+        let events = versioned
+            .handle_many(AccountCommand::Deposit { id, amount: 30 })
+            .unwrap();
+        let closing = versioned.handle_many(AccountCommand::Close { id }).unwrap();
+        repository
+            .commit(
+                &versioned,
+                events.into_iter().chain(closing).map(Envelope::from).collect(),
+            )
+            .await
+            .unwrap();
+
+        let snapshot = repository
+            .store
+            .get_snapshot::<Account>(&id.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((snapshot.seq_nr, snapshot.version), (4, 1));
+        let snapshot_only = repository.load_aggregate_snapshot_only(&id).await.unwrap().unwrap();
+        assert_eq!(snapshot_only.seq_nr(), 3);
+        assert_eq!(snapshot_only.aggregate().balance, 60);
+        assert!(!snapshot_only.aggregate().closed);
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 5);
+        assert_eq!(loaded.aggregate().balance, 0);
+        assert!(loaded.aggregate().closed);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_fails_beyond_max_replay_events() {
+        let store = MemoryStore::new(100);
+        let repository = EventSourced::new(store.clone(), Json::default(), Json::default(), Json::default());
+        let id = AggregateId::<AccountId>::new();
+        for amount in [10, 20, 30, 40] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+
+        let limited: TestRepository =
+            EventSourced::new(store, Json::default(), Json::default(), Json::default()).with_max_replay_events(3);
+        let result = limited.load_aggregate(&id).await;
+        let expected_id = id.to_string();
+        assert!(matches!(
+            result,
+            Err(PersistenceError::ReplayLimitExceeded { aggregate_id, limit: 3 }) if aggregate_id == expected_id
+        ));
+
+        let rebuilt = limited.rebuild_snapshot(&id).await.unwrap();
+        assert_eq!(rebuilt.seq_nr(), 4);
+        let loaded = limited.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 4);
+        assert_eq!(loaded.aggregate().balance, 100);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_snapshot_only_skips_event_replay() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+        assert!(repository.load_aggregate_snapshot_only(&id).await.unwrap().is_none());
+
+        for amount in [10, 20, 30] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+
+        let snapshot_only = repository.load_aggregate_snapshot_only(&id).await.unwrap().unwrap();
+        assert_eq!(snapshot_only.seq_nr(), 2);
+        assert_eq!(snapshot_only.aggregate().balance, 30);
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 3);
+        assert_eq!(loaded.aggregate().balance, 60);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_with_progress_reports_events_after_snapshot() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+        for amount in [10, 20, 30, 40, 50] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+        let snapshot = repository.load_aggregate_snapshot_only(&id).await.unwrap().unwrap();
+
+        let mut replayed = Vec::new();
+        let loaded = repository
+            .load_aggregate_with_progress(&id, &mut |seq_nr| replayed.push(seq_nr))
+            .await
+            .unwrap();
+
+        assert_eq!(loaded.seq_nr(), 5);
+        assert_eq!(loaded.aggregate().balance, 150);
+        assert_eq!(replayed.len(), loaded.seq_nr() - snapshot.seq_nr());
+        assert_eq!(replayed, ((snapshot.seq_nr() + 1)..=5).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_load_tombstoned_aggregate_fails() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+        for amount in [10, 20, 30] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+
+        repository
+            .store
+            .tombstone(Account::TYPE, &id.to_string())
+            .await
+            .unwrap();
+
+        assert!(repository
+            .store
+            .get_snapshot::<Account>(&id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            repository.load_aggregate(&id).await,
+            Err(PersistenceError::Tombstoned { aggregate_id }) if aggregate_id == id.to_string()
+        ));
+        assert!(matches!(
+            repository.load_state(&id).await.unwrap(),
+            AggregateState::Tombstoned
+        ));
+
+        let other = AggregateId::<AccountId>::new();
+        execute(&repository, &other, AccountCommand::Deposit { id: other, amount: 10 }).await;
+        assert!(matches!(
+            repository.load_state(&other).await.unwrap(),
+            AggregateState::Existing(loaded) if loaded.aggregate().balance == 10
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_state_tells_new_from_existing_aggregates() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+
+        let fresh = repository.load_aggregate(&id).await.unwrap();
+        assert!(!fresh.exists());
+        assert!(matches!(
+            repository.load_state(&id).await.unwrap(),
+            AggregateState::New(loaded) if loaded.seq_nr() == 0 && loaded.version() == 0
+        ));
+
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 10 }).await;
+        assert!(repository.load_aggregate(&id).await.unwrap().exists());
+        assert!(matches!(
+            repository.load_state(&id).await.unwrap(),
+            AggregateState::Existing(loaded) if loaded.seq_nr() == 1
+        ));
+
+        // Loaded from the snapshot taken at the second event.
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 20 }).await;
+        let snapshot_only = repository.load_aggregate_snapshot_only(&id).await.unwrap().unwrap();
+        assert!(snapshot_only.exists());
+        assert!(matches!(
+            repository.load_state(&id).await.unwrap(),
+            AggregateState::Existing(loaded) if loaded.aggregate().balance == 30
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_rejects_events_after_close() {
+        let repository = create_repository(100);
+        let id = AggregateId::<AccountId>::new();
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 10 }).await;
+        execute(&repository, &id, AccountCommand::Close { id }).await;
+
+        let out_of_order = SerializedDomainEvent::new(
+            EventIdType::new().to_string(),
+            id.to_string(),
+            4,
+            Account::TYPE.to_string(),
+            "AccountDeposited".to_string(),
+            serde_json::to_vec(&AccountEvent::Deposited {
+                id: EventIdType::new(),
+                amount: 5,
+            })
+            .unwrap(),
+            serde_json::json!({}),
+        );
+        repository.store.persist(&[out_of_order], &[], None, &[]).await.unwrap();
+
+        assert!(matches!(
+            repository.load_aggregate(&id).await,
+            Err(PersistenceError::CorruptStream { aggregate_id, seq_nr: 4, .. }) if aggregate_id == id.to_string()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_stops_applying_events_once_terminal() {
+        let repository: EventSourced<
+            Savings,
+            MemoryStore,
+            Json<Savings>,
+            Json<AccountEvent>,
+            Json<AccountIntegrationEvent>,
+        > = EventSourced::new(MemoryStore::new(100), Json::default(), Json::default(), Json::default());
+        let id = AggregateId::<AccountId>::new();
+        for cmd in [AccountCommand::Deposit { id, amount: 10 }, AccountCommand::Close { id }] {
+            let mut versioned = repository.load_aggregate(&id).await.unwrap();
+            let events = versioned.handle_many(cmd).unwrap();
+            repository
+                .commit(&versioned, events.into_iter().map(Envelope::from).collect())
+                .await
+                .unwrap();
+        }
+
+        // Events after the terminal one are neither applied nor even deserialized.
+        let noise = [
+            (
+                3,
+                "AccountDeposited",
+                serde_json::to_vec(&AccountEvent::Deposited {
+                    id: EventIdType::new(),
+                    amount: 5,
+                })
+                .unwrap(),
+            ),
+            (4, "Unreadable", b"not json".to_vec()),
+        ]
+        .map(|(seq_nr, event_type, payload)| {
+            SerializedDomainEvent::new(
+                EventIdType::new().to_string(),
+                id.to_string(),
+                seq_nr,
+                Savings::TYPE.to_string(),
+                event_type.to_string(),
+                payload,
+                serde_json::json!({}),
+            )
+        });
+        repository.store.persist(&noise, &[], None, &[]).await.unwrap();
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert!(loaded.aggregate().is_terminal());
+        assert_eq!(loaded.aggregate().0.balance, 10);
+        assert_eq!(loaded.seq_nr(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_commit_rejects_oversized_event_payload() {
+        let repository = create_conflicting_repository(0);
+        let id = AggregateId::<AccountId>::new();
+
+        repository
+            .commit_with_retry(&id, AccountCommand::Deposit { id, amount: 10 }, 1)
+            .await
+            .unwrap();
+
+        let versioned = repository.load_aggregate(&id).await.unwrap();
+        let oversized = AccountEvent::Closed { id: EventIdType::new() };
+        let bytes = serde_json::to_vec(&oversized).unwrap().len();
+        let repository = repository.with_max_event_payload_bytes(bytes - 1);
+        let result = repository.commit(&versioned, vec![Envelope::from(oversized)]).await;
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::EventTooLarge { event_type, bytes: actual })
+                if event_type == "AccountClosed" && actual == bytes
+        ));
+        assert_eq!(repository.store.persist_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(repository.load_aggregate(&id).await.unwrap().seq_nr(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_commit_preserves_correlation_and_causation_ids() {
+        let repository = create_repository(100);
+        let id = AggregateId::<AccountId>::new();
+        let mut versioned = repository.load_aggregate(&id).await.unwrap();
+        let events = versioned
+            .handle_many(AccountCommand::Deposit { id, amount: 10 })
+            .unwrap();
+        let envelopes = events
+            .into_iter()
+            .map(|event| {
+                Envelope::from(event)
+                    .with_correlation_id("req-1")
+                    .with_causation_id("cmd-1")
+            })
+            .collect();
+        repository.commit(&versioned, envelopes).await.unwrap();
+
+        let persisted: Vec<_> = repository
+            .store
+            .stream_events::<Account>(&id.to_string(), SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+        let metadata: message::Metadata = serde_json::from_value(persisted[0].metadata.clone()).unwrap();
+        assert_eq!(metadata.get_str(message::CORRELATION_ID), Some("req-1"));
+        assert_eq!(metadata.get_str(message::CAUSATION_ID), Some("cmd-1"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_copies_metadata_onto_integration_events() {
+        let repository = create_repository(100);
+        let id = AggregateId::<AccountId>::new();
+        let mut versioned = repository.load_aggregate(&id).await.unwrap();
+        let events = versioned.handle_many(AccountCommand::Close { id }).unwrap();
+        let envelopes = events
+            .into_iter()
+            .map(|event| Envelope::from(event).with_correlation_id("req-1"))
+            .collect();
+        repository.commit(&versioned, envelopes).await.unwrap();
+
+        let integration_events = repository.store.export_state().integration_events;
+        assert_eq!(integration_events.len(), 1);
+        let metadata: message::Metadata = serde_json::from_value(integration_events[0].metadata.clone()).unwrap();
+        assert_eq!(metadata.get_str(message::CORRELATION_ID), Some("req-1"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_stamps_occurred_at_from_clock() {
+        let repository = create_repository(100).with_clock(FixedClock::from_unix_seconds(1609459200));
+        let id = AggregateId::<AccountId>::new();
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 50 }).await;
+
+        let persisted: Vec<_> = repository
+            .store
+            .stream_events::<Account>(&id.to_string(), SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(persisted.len(), 1);
+        assert_eq!(
+            persisted[0].occurred_at,
+            Timestamp {
+                seconds: 1609459200,
+                nanos: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_occurred_at_is_monotonic_for_sequential_events() {
+        let repository = create_repository(100);
+        let id = AggregateId::<AccountId>::new();
+        for amount in [10, 20] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+        execute(&repository, &id, AccountCommand::Close { id }).await;
+
+        let persisted: Vec<_> = repository
+            .store
+            .stream_events::<Account>(&id.to_string(), SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(persisted.len(), 4);
+        let timestamps: Vec<_> = persisted
+            .iter()
+            .map(|event| (event.occurred_at.seconds, event.occurred_at.nanos))
+            .collect();
+        assert!(timestamps.iter().all(|&timestamp| timestamp > (0, 0)));
+        assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_as_of_reconstructs_past_states() {
+        let mut repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+        for (seconds, amount) in [(100, 10), (200, 20), (300, 30)] {
+            repository.clock = Arc::new(FixedClock::from_unix_seconds(seconds));
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+        repository.clock = Arc::new(FixedClock::from_unix_seconds(400));
+        execute(&repository, &id, AccountCommand::Close { id }).await;
+        assert!(repository
+            .store
+            .get_snapshot::<Account>(&id.to_string())
+            .await
+            .unwrap()
+            .is_some());
+
+        let as_of = |seconds| Timestamp { seconds, nanos: 0 };
+
+        let opened = repository.load_aggregate_as_of(&id, as_of(150)).await.unwrap();
+        assert_eq!(opened.seq_nr(), 1);
+        assert_eq!(opened.aggregate().balance, 10);
+
+        let funded = repository.load_aggregate_as_of(&id, as_of(300)).await.unwrap();
+        assert_eq!(funded.seq_nr(), 3);
+        assert_eq!(funded.aggregate().balance, 60);
+        assert!(!funded.aggregate().closed);
+
+        let closed = repository.load_aggregate_as_of(&id, as_of(500)).await.unwrap();
+        assert_eq!(closed.seq_nr(), 5);
+        assert_eq!(closed.aggregate().balance, 0);
+        assert!(closed.aggregate().closed);
+
+        let before_creation = repository.load_aggregate_as_of(&id, as_of(50)).await.unwrap();
+        assert_eq!(before_creation.seq_nr(), 0);
+        assert_eq!(before_creation.aggregate().balance, 0);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregates_paged() {
+        let repository = create_repository(100);
+        let mut ids = Vec::new();
+        for amount in 1..=25 {
+            let id = AggregateId::<AccountId>::new();
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+            InvertedIndexCommiter::commit(&repository.store, &id.to_string(), "vip")
+                .await
+                .unwrap();
+            ids.push(id.to_string());
+        }
+        ids.sort();
+
+        let mut loaded = Vec::new();
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        loop {
+            let (page, has_more) = repository.load_aggregates_paged("vip", offset, 10).await.unwrap();
+            pages.push((page.len(), has_more));
+            loaded.extend(page.iter().map(|versioned| versioned.id().to_string()));
+            if !has_more {
+                break;
+            }
+            offset += 10;
+        }
+
+        assert_eq!(pages, vec![(10, true), (10, true), (5, false)]);
+        assert_eq!(loaded, ids);
+
+        let (page, has_more) = repository.load_aggregates_paged("vip", 30, 10).await.unwrap();
+        assert!(page.is_empty());
+        assert!(!has_more);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregates_stream_yields_lazily() {
+        let repository = create_repository(100);
+        let mut ids = Vec::new();
+        for amount in 1..=3 {
+            let id = AggregateId::<AccountId>::new();
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+            InvertedIndexCommiter::commit(&repository.store, &id.to_string(), "vip")
+                .await
+                .unwrap();
+            ids.push(id.to_string());
+        }
+        InvertedIndexCommiter::commit(&repository.store, "not-an-account", "vip")
+            .await
+            .unwrap();
+
+        let mut stream = repository.load_aggregates_stream("vip");
+        let mut loaded = Vec::new();
+        let mut failures = 0;
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(versioned) => loaded.push(versioned.id().to_string()),
+                Err(PersistenceError::DeserializationError(_)) => failures += 1,
+                Err(err) => panic!("unexpected error: {err}"),
+            }
+        }
+        loaded.sort();
+        ids.sort();
+
+        assert_eq!(loaded, ids);
+        assert_eq!(failures, 1);
+        assert!(repository.load_aggregates_stream("unknown").next().await.is_none());
+    }
+
+    /// Same behaviour as [`Account`] under a different aggregate type.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Savings(Account);
+
+    impl AggregateRoot for Savings {
+        const TYPE: &'static str = "Savings";
+        type ID = AccountId;
+        type Command = AccountCommand;
+        type DomainEvent = AccountEvent;
+        type IntegrationEvent = AccountIntegrationEvent;
+        type Error = AccountError;
+
+        fn init(id: AggregateId<Self::ID>) -> Self {
+            Self(Account::init(id))
+        }
+
+        fn id(&self) -> &AggregateId<Self::ID> {
+            self.0.id()
+        }
+
+        fn handle(&mut self, cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+            self.0.handle(cmd)
+        }
+
+        fn apply(&mut self, event: Self::DomainEvent) {
+            self.0.apply(event);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.0.closed
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct UserId;
+
+    impl HasIdPrefix for UserId {
+        const PREFIX: &'static str = "usr";
+    }
+
+    #[derive(Debug, Clone)]
+    struct ChangeEmail {
+        id: AggregateId<UserId>,
+        email: String,
+    }
+
+    impl message::Message for ChangeEmail {
+        fn name(&self) -> &'static str {
+            "ChangeEmail"
+        }
+    }
+
+    impl Command for ChangeEmail {
+        type ID = UserId;
+
+        fn id(&self) -> AggregateId<Self::ID> {
+            self.id
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct EmailChanged {
+        id: EventIdType,
+        email: String,
+    }
+
+    impl message::Message for EmailChanged {
+        fn name(&self) -> &'static str {
+            "EmailChanged"
+        }
+    }
+
+    impl DomainEvent for EmailChanged {
+        fn id(&self) -> EventIdType {
+            self.id
+        }
+
+        fn event_type(&self) -> &'static str {
+            "EmailChanged"
+        }
+    }
+
+    impl IntoIntegrationEvents for EmailChanged {
+        type IntegrationEvent = AccountIntegrationEvent;
+        type IntoIter = Vec<AccountIntegrationEvent>;
+
+        fn into_integration_events(self) -> Self::IntoIter {
+            vec![]
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct User {
+        id: AggregateId<UserId>,
+        email: Option<String>,
+    }
+
+    impl AggregateRoot for User {
+        const TYPE: &'static str = "User";
+        type ID = UserId;
+        type Command = ChangeEmail;
+        type DomainEvent = EmailChanged;
+        type IntegrationEvent = AccountIntegrationEvent;
+        type Error = AccountError;
+
+        fn init(id: AggregateId<Self::ID>) -> Self {
+            Self { id, email: None }
+        }
+
+        fn id(&self) -> &AggregateId<Self::ID> {
+            &self.id
+        }
+
+        fn handle(&mut self, cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+            Ok(EmailChanged {
+                id: EventIdType::new(),
+                email: cmd.email,
+            })
+        }
+
+        fn apply(&mut self, event: Self::DomainEvent) {
+            self.email = Some(event.email);
+        }
+
+        fn index_keywords(&self) -> Vec<IndexKeyword> {
+            self.email
+                .iter()
+                .map(|email| IndexKeyword::field("email", email))
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_moves_aggregate_between_index_keywords() {
+        let users: EventSourced<User, MemoryStore, Json<User>, Json<EmailChanged>, Json<AccountIntegrationEvent>> =
+            EventSourced::new(MemoryStore::new(100), Json::default(), Json::default(), Json::default());
+        let id = AggregateId::<UserId>::new();
+
+        for email in ["old@example.com", "new@example.com"] {
+            let mut versioned = users.load_aggregate(&id).await.unwrap();
+            let event = versioned
+                .handle(ChangeEmail {
+                    id,
+                    email: email.to_string(),
+                })
+                .unwrap();
+            users.commit(&versioned, vec![Envelope::from(event)]).await.unwrap();
+        }
+
+        let old_bucket = users.store.get_aggregate_ids("email:old@example.com").await.unwrap();
+        let new_bucket = users.store.get_aggregate_ids("email:new@example.com").await.unwrap();
+        assert!(old_bucket.is_empty());
+        assert_eq!(new_bucket, vec![id.to_string()]);
+
+        let (found, _) = users
+            .load_aggregates_paged("email:new@example.com", 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(found[0].aggregate().email.as_deref(), Some("new@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_interval_per_aggregate_type() {
+        let store = MemoryStore::new(100)
+            .with_snapshot_interval_for(Account::TYPE, 2)
+            .with_snapshot_interval_for(Savings::TYPE, 3);
+        let accounts: TestRepository =
+            EventSourced::new(store.clone(), Json::default(), Json::default(), Json::default());
+        let savings: EventSourced<
+            Savings,
+            MemoryStore,
+            Json<Savings>,
+            Json<AccountEvent>,
+            Json<AccountIntegrationEvent>,
+        > = EventSourced::new(store, Json::default(), Json::default(), Json::default());
+        let account_id = AggregateId::<AccountId>::new();
+        let savings_id = AggregateId::<AccountId>::new();
+
+        for amount in [10, 20, 30, 40, 50] {
+            execute(
+                &accounts,
+                &account_id,
+                AccountCommand::Deposit { id: account_id, amount },
+            )
+            .await;
+
+            let mut versioned = savings.load_aggregate(&savings_id).await.unwrap();
+            let events = versioned
+                .handle_many(AccountCommand::Deposit { id: savings_id, amount })
+                .unwrap();
+            savings
+                .commit(&versioned, events.into_iter().map(Envelope::from).collect())
+                .await
+                .unwrap();
+        }
+
+        let account_snapshot = accounts
+            .store
+            .get_snapshot::<Account>(&account_id.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((account_snapshot.seq_nr, account_snapshot.version), (5, 2));
+        let savings_snapshot = savings
+            .store
+            .get_snapshot::<Savings>(&savings_id.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((savings_snapshot.seq_nr, savings_snapshot.version), (4, 1));
+    }
+
+    type SpanFields = std::collections::HashMap<String, String>;
+
+    /// Collects the name and fields of every closed span.
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: Arc<std::sync::Mutex<Vec<(&'static str, SpanFields)>>>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut SpanFields);
+
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for SpanRecorder
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = SpanFields::new();
+            attrs.record(&mut FieldVisitor(&mut fields));
+            ctx.span(id).unwrap().extensions_mut().insert(fields);
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let span = ctx.span(id).unwrap();
+            let mut extensions = span.extensions_mut();
+            values.record(&mut FieldVisitor(extensions.get_mut::<SpanFields>().unwrap()));
+        }
+
+        fn on_close(&self, id: tracing::span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+            let span = ctx.span(&id).unwrap();
+            let fields = span.extensions_mut().remove::<SpanFields>().unwrap();
+            self.spans.lock().unwrap().push((span.name(), fields));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_and_commit_emit_spans() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 10 }).await;
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 20 }).await;
+
+        let spans = recorder.spans.lock().unwrap();
+        let names: Vec<_> = spans.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["load_aggregate", "commit", "load_aggregate", "commit"]);
+
+        let (_, load) = &spans[2];
+        assert_eq!(load["aggregate_type"], "Account");
+        assert_eq!(load["aggregate_id"], id.to_string());
+        assert_eq!(load["seq_nr"], "1");
+        assert_eq!(load["version"], "0");
+        assert_eq!(load["events_replayed"], "1");
+
+        let (_, commit) = &spans[3];
+        assert_eq!(commit["aggregate_type"], "Account");
+        assert_eq!(commit["aggregate_id"], id.to_string());
+        assert_eq!(commit["events"], "1");
+        assert_eq!(commit["seq_nr"], "2");
+        assert_eq!(commit["version"], "1");
+        assert_eq!(commit["snapshot_taken"], "true");
+        assert!(commit.keys().all(|key| key != "payload"));
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_with_progress_records_its_own_span() {
+        use tracing::Instrument;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 10 }).await;
+
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let caller = tracing::info_span!("caller", seq_nr = field::Empty);
+        let loaded = repository
+            .load_aggregate_with_progress(&id, &mut |_| {})
+            .instrument(caller)
+            .await
+            .unwrap();
+        assert_eq!(loaded.seq_nr(), 1);
+
+        let spans = recorder.spans.lock().unwrap();
+        let names: Vec<_> = spans.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["load_aggregate", "caller"]);
+        assert_eq!(spans[0].1["seq_nr"], "1");
+        assert_eq!(spans[0].1["events_replayed"], "1");
+        assert!(!spans[1].1.contains_key("seq_nr"));
+    }
+
+    fn upcast_deposited(version: u32, payload: &[u8]) -> Result<Vec<u8>, PersistenceError> {
+        let mut value: serde_json::Value = serde_json::from_slice(payload)?;
+        if version == 1 {
+            if let Some(deposited) = value.get_mut("Deposited").and_then(|v| v.as_object_mut()) {
+                if let Some(amount) = deposited.remove("value") {
+                    deposited.insert("amount".to_string(), amount);
+                }
+            }
+        }
+        Ok(serde_json::to_vec(&value)?)
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_upcasts_legacy_payloads() {
+        let repository = create_repository(100).with_upcaster("AccountDeposited", upcast_deposited);
+        let id = AggregateId::<AccountId>::new();
+
+        let legacy_payload = serde_json::to_vec(&serde_json::json!({
+            "Deposited": { "id": EventIdType::new(), "value": 40 }
+        }))
+        .unwrap();
+        let legacy_event = SerializedDomainEvent::new(
+            EventIdType::new().to_string(),
+            id.to_string(),
+            1,
+            Account::TYPE.to_string(),
+            "AccountDeposited".to_string(),
+            legacy_payload,
+            serde_json::json!({}),
+        );
+        repository.store.persist(&[legacy_event], &[], None, &[]).await.unwrap();
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 2 }).await;
+
+        let persisted: Vec<_> = repository
+            .store
+            .stream_events::<Account>(&id.to_string(), SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+        let schema_versions: Vec<_> = persisted.iter().map(|event| event.schema_version).collect();
+        assert_eq!(schema_versions, vec![1, 2]);
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 2);
+        assert_eq!(loaded.aggregate().balance, 42);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_without_upcaster_fails_on_legacy_payloads() {
+        let repository = create_repository(100);
+        let id = AggregateId::<AccountId>::new();
+
+        let legacy_payload = serde_json::to_vec(&serde_json::json!({
+            "Deposited": { "id": EventIdType::new(), "value": 40 }
+        }))
+        .unwrap();
+        let legacy_event = SerializedDomainEvent::new(
+            EventIdType::new().to_string(),
+            id.to_string(),
+            1,
+            Account::TYPE.to_string(),
+            "AccountDeposited".to_string(),
+            legacy_payload,
+            serde_json::json!({}),
+        );
+        repository.store.persist(&[legacy_event], &[], None, &[]).await.unwrap();
+
+        assert!(repository.load_aggregate(&id).await.is_err());
+    }
+
+    /// Event at sequence number 2 of a type written by a newer release.
+    fn unknown_event(id: &AggregateId<AccountId>) -> SerializedDomainEvent {
+        SerializedDomainEvent::new(
+            EventIdType::new().to_string(),
+            id.to_string(),
+            2,
+            Account::TYPE.to_string(),
+            "AccountFrozen".to_string(),
+            serde_json::to_vec(&serde_json::json!({ "Frozen": { "id": EventIdType::new() } })).unwrap(),
+            serde_json::json!({}),
+        )
+    }
+
+    /// Persists a deposit of 10, an unknown event and a deposit of 5.
+    async fn persist_stream_with_unknown_event(repository: &TestRepository, id: &AggregateId<AccountId>) {
+        execute(repository, id, AccountCommand::Deposit { id: *id, amount: 10 }).await;
+        repository
+            .store
+            .persist(&[unknown_event(id)], &[], None, &[])
+            .await
+            .unwrap();
+        execute(repository, id, AccountCommand::Deposit { id: *id, amount: 5 }).await;
+    }
+
+    #[tokio::test]
+    async fn test_unknown_event_policy_fail_rejects_load() {
+        let repository = create_repository(100);
+        let id = AggregateId::<AccountId>::new();
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 10 }).await;
+        repository
+            .store
+            .persist(&[unknown_event(&id)], &[], None, &[])
+            .await
+            .unwrap();
+
+        match repository.load_aggregate(&id).await {
+            Err(PersistenceError::Serialization {
+                event_type, direction, ..
+            }) => {
+                assert_eq!(event_type, "AccountFrozen");
+                assert_eq!(direction, SerdeDirection::Deserialize);
+            }
+            other => panic!("expected a serialization error, got {other:?}"),
+        }
+    }
+
+    /// Domain event serde that cannot serialize, standing in for an event the format does not support.
+    struct UnserializableEvents;
+
+    impl crate::serde::Serializer<AccountEvent> for UnserializableEvents {
+        fn serialize(&self, _value: &AccountEvent) -> Result<Vec<u8>, SerdeError> {
+            Err(SerdeError::ConversionError("unsupported".to_string()))
+        }
+    }
+
+    impl crate::serde::Deserializer<AccountEvent> for UnserializableEvents {
+        fn deserialize(&self, data: &[u8]) -> Result<AccountEvent, SerdeError> {
+            Json::default().deserialize(data)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_reports_event_type_of_unserializable_event() {
+        let repository = EventSourced::<Account, _, _, _, _>::new(
+            MemoryStore::new(100),
+            Json::default(),
+            UnserializableEvents,
+            Json::<AccountIntegrationEvent>::default(),
+        );
+        let id = AggregateId::<AccountId>::new();
+        let versioned = repository.load_aggregate(&id).await.unwrap();
+        let event = AccountEvent::Deposited {
+            id: EventIdType::new(),
+            amount: 10,
+        };
+
+        let err = repository.commit(&versioned, vec![event.into()]).await.unwrap_err();
+
+        assert!(matches!(
+            &err,
+            PersistenceError::Serialization {
+                event_type,
+                direction: SerdeDirection::Serialize,
+                source: SerdeError::ConversionError(_),
+            } if event_type == "AccountDeposited"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "failed to serialize event AccountDeposited: failed to convert type values: unsupported"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_event_policy_skip_advances_seq_nr() {
+        let repository = create_repository(100).with_unknown_event_policy(UnknownEventPolicy::Skip);
+        let id = AggregateId::<AccountId>::new();
+        persist_stream_with_unknown_event(&repository, &id).await;
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+
+        assert_eq!(loaded.seq_nr(), 3);
+        assert_eq!(loaded.aggregate().balance, 15);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_event_policy_capture_receives_skipped_events() {
+        let captured = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = captured.clone();
+        let repository = create_repository(100).with_unknown_event_policy(UnknownEventPolicy::Capture(Arc::new(
+            move |event: &SerializedDomainEvent, _err: &SerdeError| {
+                sink.lock().unwrap().push((event.seq_nr, event.event_type.clone()));
+            },
+        )));
+        let id = AggregateId::<AccountId>::new();
+        persist_stream_with_unknown_event(&repository, &id).await;
+        captured.lock().unwrap().clear();
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+
+        assert_eq!(loaded.seq_nr(), 3);
+        assert_eq!(loaded.aggregate().balance, 15);
+        assert_eq!(*captured.lock().unwrap(), vec![(2, "AccountFrozen".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_snapshot_repairs_corrupted_snapshot() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+
+        for amount in [10, 20, 30, 40, 50] {
+            execute(&repository, &id, AccountCommand::Deposit { id, amount }).await;
+        }
+        let stored = repository
+            .store
+            .get_snapshot::<Account>(&id.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        let corrupted = PersistedSnapshot::new(
+            stored.aggregate_type,
+            stored.aggregate_id,
+            b"not an account".to_vec(),
+            stored.seq_nr,
+            stored.version,
+        );
+        repository.store.persist(&[], &[], Some(&corrupted), &[]).await.unwrap();
+        assert!(repository.load_aggregate(&id).await.is_err());
+
+        let rebuilt = repository.rebuild_snapshot(&id).await.unwrap();
+
+        assert_eq!(rebuilt.seq_nr(), 5);
+        assert_eq!(rebuilt.version(), stored.version + 1);
+        assert_eq!(rebuilt.aggregate().balance, 150);
+        let snapshot = repository
+            .store
+            .get_snapshot::<Account>(&id.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(snapshot.seq_nr, 6);
+        assert_eq!(snapshot.version, stored.version + 1);
+
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 5);
+        assert_eq!(loaded.aggregate().balance, 150);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_snapshot_without_events_writes_nothing() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+
+        let rebuilt = repository.rebuild_snapshot(&id).await.unwrap();
+
+        assert_eq!(rebuilt.seq_nr(), 0);
+        assert_eq!(rebuilt.version(), 0);
+        assert!(repository
+            .store
+            .get_snapshot::<Account>(&id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    /// Memory store that rejects the next `conflicts` writes as if another writer got there first, and counts
+    /// its snapshot reads.
+    #[derive(Clone)]
+    struct ConflictingStore {
+        inner: MemoryStore,
+        conflicts: Arc<AtomicUsize>,
+        persist_calls: Arc<AtomicUsize>,
+        snapshot_calls: Arc<AtomicUsize>,
+        snapshot_batches: Arc<AtomicUsize>,
+    }
+
+    impl ConflictingStore {
+        fn new(conflicts: usize) -> Self {
+            Self {
+                inner: MemoryStore::new(100),
+                conflicts: Arc::new(AtomicUsize::new(conflicts)),
+                persist_calls: Arc::new(AtomicUsize::new(0)),
+                snapshot_calls: Arc::new(AtomicUsize::new(0)),
+                snapshot_batches: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+    }
+
+    impl SnapshotIntervalProvider for ConflictingStore {
+        fn snapshot_interval(&self) -> usize {
+            self.inner.snapshot_interval()
+        }
+
+        fn snapshot_interval_for<A: AggregateRoot>(&self) -> usize {
+            self.inner.snapshot_interval_for::<A>()
+        }
+    }
+
+    impl AggregateEventStreamer for ConflictingStore {
+        fn stream_events<A: AggregateRoot>(
+            &self,
+            id: &str,
+            select: SequenceSelect,
+        ) -> Stream<'_, SerializedDomainEvent, PersistenceError> {
+            self.inner.stream_events::<A>(id, select)
+        }
+    }
+
+    #[async_trait]
+    impl Persister for ConflictingStore {
+        async fn persist(
+            &self,
+            domain_events: &[SerializedDomainEvent],
+            integration_events: &[SerializedIntegrationEvent],
+            snapshot_update: Option<&PersistedSnapshot>,
+            index_ops: &[IndexOp],
+        ) -> Result<(), PersistenceError> {
+            self.persist_calls.fetch_add(1, Ordering::SeqCst);
+            let injected = self
+                .conflicts
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if injected {
+                return Err(PersistenceError::OptimisticConcurrency {
+                    aggregate_id: domain_events[0].aggregate_id.clone(),
+                    expected_seq: domain_events[0].seq_nr - 1,
+                });
+            }
+            self.inner
+                .persist(domain_events, integration_events, snapshot_update, index_ops)
+                .await
+        }
+    }
+
+    #[async_trait]
+    impl SnapshotGetter for ConflictingStore {
+        async fn get_snapshot<A: AggregateRoot>(
+            &self,
+            id: &str,
+        ) -> Result<Option<PersistedSnapshot>, PersistenceError> {
+            self.snapshot_calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.get_snapshot::<A>(id).await
+        }
+
+        async fn get_snapshots<A: AggregateRoot>(
+            &self,
+            ids: &[&str],
+        ) -> Result<HashMap<String, PersistedSnapshot>, PersistenceError> {
+            self.snapshot_batches.fetch_add(1, Ordering::SeqCst);
+            self.inner.get_snapshots::<A>(ids).await
+        }
+    }
+
+    #[async_trait]
+    impl AggregateIdsLoader for ConflictingStore {
+        async fn get_aggregate_ids(
+            &self,
+            keyword: impl Into<IndexKeyword> + Send,
+        ) -> Result<Vec<String>, PersistenceError> {
+            self.inner.get_aggregate_ids(keyword).await
+        }
+    }
+
+    #[async_trait]
+    impl InvertedIndexCommiter for ConflictingStore {
+        async fn commit(
+            &self,
+            aggregate_id: &str,
+            keyword: impl Into<IndexKeyword> + Send,
+        ) -> Result<(), PersistenceError> {
+            InvertedIndexCommiter::commit(&self.inner, aggregate_id, keyword).await
+        }
+    }
+
+    #[async_trait]
+    impl InvertedIndexRemover for ConflictingStore {
+        async fn remove(
+            &self,
+            aggregate_id: &str,
+            keyword: impl Into<IndexKeyword> + Send,
+        ) -> Result<(), PersistenceError> {
+            self.inner.remove(aggregate_id, keyword).await
+        }
+    }
+
+    fn create_conflicting_repository(
+        conflicts: usize,
+    ) -> EventSourced<Account, ConflictingStore, Json<Account>, Json<AccountEvent>, Json<AccountIntegrationEvent>> {
+        EventSourced::new(
+            ConflictingStore::new(conflicts),
+            Json::default(),
+            Json::default(),
+            Json::default(),
+        )
+        .with_retry_backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregates_reads_snapshots_in_one_batch() {
+        let repository = EventSourced::new(
+            ConflictingStore {
+                inner: MemoryStore::new(2),
+                ..ConflictingStore::new(0)
+            },
+            Json::<Account>::default(),
+            Json::<AccountEvent>::default(),
+            Json::<AccountIntegrationEvent>::default(),
+        );
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            let id = AggregateId::<AccountId>::new();
+            for amount in [10, 20, 30] {
+                repository
+                    .commit_with_retry(&id, AccountCommand::Deposit { id, amount }, 1)
+                    .await
+                    .unwrap();
+            }
+            InvertedIndexCommiter::commit(&repository.store, &id.to_string(), "vip")
+                .await
+                .unwrap();
+            ids.push(id.to_string());
+        }
+        ids.sort();
+        let snapshots = repository
+            .store
+            .inner
+            .get_snapshots::<Account>(&ids.iter().map(String::as_str).collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert_eq!(snapshots.len(), 5);
+
+        repository.store.snapshot_calls.store(0, Ordering::SeqCst);
+        let (page, has_more) = repository.load_aggregates_paged("vip", 0, 10).await.unwrap();
+        assert!(!has_more);
+        assert_eq!(
+            page.iter()
+                .map(|versioned| versioned.id().to_string())
+                .collect::<Vec<_>>(),
+            ids
+        );
+        assert!(page
+            .iter()
+            .all(|versioned| versioned.seq_nr() == 3 && versioned.aggregate().balance == 60));
+        assert_eq!(repository.store.snapshot_batches.load(Ordering::SeqCst), 1);
+
+        assert_eq!(repository.load_aggregates("vip").await.unwrap().len(), 5);
+        assert_eq!(repository.store.snapshot_batches.load(Ordering::SeqCst), 2);
+
+        let streamed: Vec<_> = repository.load_aggregates_stream("vip").try_collect().await.unwrap();
+        assert_eq!(streamed.len(), 5);
+        assert_eq!(repository.store.snapshot_batches.load(Ordering::SeqCst), 3);
+        assert_eq!(repository.store.snapshot_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_commit_with_retry_recovers_from_conflicts() {
+        let repository = create_conflicting_repository(2);
+        let id = AggregateId::<AccountId>::new();
+
+        repository
+            .commit_with_retry(&id, AccountCommand::Deposit { id, amount: 25 }, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(repository.store.persist_calls.load(Ordering::SeqCst), 3);
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 1);
+        assert_eq!(loaded.aggregate().balance, 25);
+    }
+
+    #[tokio::test]
+    async fn test_commit_with_retry_gives_up_after_max_attempts() {
+        let repository = create_conflicting_repository(usize::MAX);
+        let id = AggregateId::<AccountId>::new();
+
+        let result = repository
+            .commit_with_retry(&id, AccountCommand::Deposit { id, amount: 25 }, 3)
+            .await;
+
+        assert!(matches!(result, Err(AggregateError::AggregateConflict)));
+        assert_eq!(repository.store.persist_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_commit_with_retry_does_not_retry_command_errors() {
+        let repository = create_conflicting_repository(0);
+        let id = AggregateId::<AccountId>::new();
+
+        repository
+            .commit_with_retry(&id, AccountCommand::Close { id }, 3)
+            .await
+            .unwrap();
+        let result = repository
+            .commit_with_retry(&id, AccountCommand::Deposit { id, amount: 10 }, 3)
+            .await;
+
+        assert!(matches!(result, Err(AggregateError::UserError(AccountError::Closed))));
+        assert_eq!(repository.store.persist_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_commit_without_events_does_not_touch_store() {
+        let repository = create_conflicting_repository(0);
+        let id = AggregateId::<AccountId>::new();
+
+        repository
+            .commit_with_retry(&id, AccountCommand::Deposit { id, amount: 10 }, 1)
+            .await
+            .unwrap();
+        repository
+            .commit_with_retry(&id, AccountCommand::Deposit { id, amount: 0 }, 1)
+            .await
+            .unwrap();
+
+        assert_eq!(repository.store.persist_calls.load(Ordering::SeqCst), 1);
+        let loaded = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.seq_nr(), 1);
+        assert_eq!(loaded.aggregate().balance, 10);
+    }
+
+    #[tokio::test]
+    async fn test_commit_without_events_takes_no_snapshot() {
+        let repository = create_repository(2);
+        let id = AggregateId::<AccountId>::new();
+
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 10 }).await;
+        execute(&repository, &id, AccountCommand::Deposit { id, amount: 0 }).await;
+
+        let snapshot = repository.store.get_snapshot::<Account>(&id.to_string()).await.unwrap();
+        assert!(snapshot.is_none());
+    }
+
+    #[test]
+    fn test_retry_backoff_delay() {
+        let backoff = RetryBackoff::new(Duration::from_millis(10), Duration::ZERO);
+        assert_eq!(backoff.delay(1), Duration::from_millis(10));
+        assert_eq!(backoff.delay(2), Duration::from_millis(20));
+        assert_eq!(backoff.delay(4), Duration::from_millis(80));
+
+        let jittered = RetryBackoff::new(Duration::from_millis(10), Duration::from_millis(5));
+        for retry in 1..=3 {
+            let delay = jittered.delay(retry);
+            let base = Duration::from_millis(10 * 2u64.pow(retry - 1));
+            assert!(delay >= base && delay <= base + Duration::from_millis(5));
+        }
+    }
+}
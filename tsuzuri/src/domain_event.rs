@@ -1,4 +1,5 @@
 use crate::{event_id::EventIdType, message, sequence_number::SequenceNumber};
+use prost_types::Timestamp;
 use serde_json::Value;
 use std::fmt;
 
@@ -10,8 +11,15 @@ pub trait DomainEvent: fmt::Debug + Clone + message::Message + Send + Sync + 'st
     fn index_keywords(&self) -> Vec<String> {
         vec![]
     }
+    /// Version of the payload layout, stored with each event so older payloads can be upcast on replay.
+    fn schema_version(&self) -> u32 {
+        DEFAULT_SCHEMA_VERSION
+    }
 }
 
+/// Schema version assumed for events written before versions were recorded.
+pub const DEFAULT_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SerializedDomainEvent {
     pub id: String,
@@ -21,6 +29,9 @@ pub struct SerializedDomainEvent {
     pub event_type: String,
     pub payload: Vec<u8>,
     pub metadata: Value,
+    pub schema_version: u32,
+    /// When the event was appended. Events written before it was recorded read back as the UNIX epoch.
+    pub occurred_at: Timestamp,
 }
 
 #[allow(dead_code)]
@@ -42,6 +53,18 @@ impl SerializedDomainEvent {
             event_type,
             payload,
             metadata,
+            schema_version: DEFAULT_SCHEMA_VERSION,
+            occurred_at: Timestamp::default(),
         }
     }
+
+    pub fn with_schema_version(mut self, schema_version: u32) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+
+    pub fn with_occurred_at(mut self, occurred_at: Timestamp) -> Self {
+        self.occurred_at = occurred_at;
+        self
+    }
 }
@@ -6,6 +6,12 @@ pub enum AggregateError<T: error::Error> {
     UserError(T),
     #[error("aggregate conflict")]
     AggregateConflict,
+    #[error("aggregate {aggregate_id} already exists")]
+    AlreadyExists { aggregate_id: String },
+    #[error("aggregate {aggregate_id} is tombstoned")]
+    Tombstoned { aggregate_id: String },
+    #[error("encryption key of aggregate {aggregate_id} was destroyed")]
+    KeyShredded { aggregate_id: String },
     #[error("{0}")]
     DatabaseConnectionError(Box<dyn error::Error + Send + Sync + 'static>),
     #[error("{0}")]
@@ -1,10 +1,10 @@
 /// This file defines the types and traits used in the event system of Tsuzuri.
 use crate::{message, sequence_number::SequenceNumber};
 use futures::stream::BoxStream;
-use std::collections::HashMap;
+use serde_json::Value;
 
 pub type Envelope<T> = message::Envelope<T>;
-pub type Metadata = HashMap<String, String>;
+pub type Metadata = message::Metadata;
 pub type Stream<'a, SerializedDomainEvent, Err> = BoxStream<'a, Result<SerializedDomainEvent, Err>>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -12,3 +12,32 @@ pub enum SequenceSelect {
     All,
     From(SequenceNumber),
 }
+
+/// Key/value pairs that the metadata of an event must all contain, used by
+/// [`AggregateEventStreamer::stream_events_filtered`](crate::event_store::AggregateEventStreamer::stream_events_filtered).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataFilter {
+    entries: Vec<(String, Value)>,
+}
+
+impl MetadataFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches events stored with the correlation ID `correlation_id`.
+    pub fn correlation_id(correlation_id: impl Into<String>) -> Self {
+        Self::new().with(message::CORRELATION_ID, correlation_id.into())
+    }
+
+    /// Additionally requires the metadata entry `key` to equal `value`.
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.entries.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns `true` when `metadata` contains every entry of the filter; an empty filter matches everything.
+    pub fn matches(&self, metadata: &Value) -> bool {
+        self.entries.iter().all(|(key, value)| metadata.get(key) == Some(value))
+    }
+}
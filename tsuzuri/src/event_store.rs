@@ -1,15 +1,22 @@
 use crate::{
     aggregate::AggregateRoot,
     domain_event::SerializedDomainEvent,
-    event::{SequenceSelect, Stream},
+    event::{MetadataFilter, SequenceSelect, Stream},
     integration_event::SerializedIntegrationEvent,
+    inverted_index_store::IndexOp,
     persist::PersistenceError,
+    sequence_number::SequenceNumber,
     snapshot::PersistedSnapshot,
 };
 use async_trait::async_trait;
+use futures::{future, TryStreamExt};
+use std::collections::HashMap;
 
 pub type SnapshotInterval = usize;
 
+/// Event type of the terminal journal entry written by [`Persister::tombstone`].
+pub const TOMBSTONE_EVENT_TYPE: &str = "Tombstoned";
+
 /// Trait that defines the capabilities of an event store.
 pub trait EventStore:
     SnapshotIntervalProvider + AggregateEventStreamer + Persister + SnapshotGetter + Send + Sync + 'static
@@ -18,18 +25,30 @@ pub trait EventStore:
     /// This method determines when the next snapshot should be taken based on the current sequence number
     /// and the number of events that have occurred since the last snapshot.
     fn commit_snapshot_with_addl_events(&self, current_sequence: usize, num_events: usize) -> usize {
-        let max_size = self.snapshot_interval();
-        let next_snapshot_at = max_size - (current_sequence % max_size);
+        snapshot_events_to_apply(self.snapshot_interval(), current_sequence, num_events)
+    }
 
-        if num_events < next_snapshot_at {
-            return 0;
-        }
+    /// Same as [`EventStore::commit_snapshot_with_addl_events`], using the snapshot interval configured for `T`.
+    fn commit_snapshot_with_addl_events_for<T: AggregateRoot>(
+        &self,
+        current_sequence: usize,
+        num_events: usize,
+    ) -> usize {
+        snapshot_events_to_apply(self.snapshot_interval_for::<T>(), current_sequence, num_events)
+    }
+}
 
-        let addl_events_after_next_snapshot = num_events - next_snapshot_at;
-        let addl_events_after_next_snapshot_to_apply =
-            addl_events_after_next_snapshot - (addl_events_after_next_snapshot % max_size);
-        next_snapshot_at + addl_events_after_next_snapshot_to_apply
+fn snapshot_events_to_apply(max_size: SnapshotInterval, current_sequence: usize, num_events: usize) -> usize {
+    let next_snapshot_at = max_size - (current_sequence % max_size);
+
+    if num_events < next_snapshot_at {
+        return 0;
     }
+
+    let addl_events_after_next_snapshot = num_events - next_snapshot_at;
+    let addl_events_after_next_snapshot_to_apply =
+        addl_events_after_next_snapshot - (addl_events_after_next_snapshot % max_size);
+    next_snapshot_at + addl_events_after_next_snapshot_to_apply
 }
 
 /// A marker trait for types that can be used as an event store.
@@ -49,26 +68,175 @@ pub trait SnapshotIntervalProvider: Send + Sync + 'static {
     ///
     /// A `SnapshotInterval` value representing the number of events after which a snapshot should be taken.
     fn snapshot_interval(&self) -> SnapshotInterval;
+
+    /// Returns the snapshot interval for aggregates of type `T`.
+    ///
+    /// Defaults to [`SnapshotIntervalProvider::snapshot_interval`]; stores that allow per-type intervals override it.
+    fn snapshot_interval_for<T: AggregateRoot>(&self) -> SnapshotInterval {
+        self.snapshot_interval()
+    }
 }
 
 /// Trait for streaming aggregate events from the event store.
+#[async_trait]
 pub trait AggregateEventStreamer: Send + Sync + 'static {
     fn stream_events<T: AggregateRoot>(
         &self,
         id: &str,
         select: SequenceSelect,
     ) -> Stream<'_, SerializedDomainEvent, PersistenceError>;
+
+    /// Streams the events of the aggregate whose metadata matches `filter`.
+    ///
+    /// The default implementation filters the output of [`AggregateEventStreamer::stream_events`] as it is read,
+    /// so non-matching events are still fetched from the store.
+    fn stream_events_filtered<T: AggregateRoot>(
+        &self,
+        id: &str,
+        select: SequenceSelect,
+        filter: MetadataFilter,
+    ) -> Stream<'_, SerializedDomainEvent, PersistenceError> {
+        Box::pin(
+            self.stream_events::<T>(id, select)
+                .try_filter(move |event| future::ready(filter.matches(&event.metadata))),
+        )
+    }
+
+    /// Returns the number of events stored for the aggregate.
+    ///
+    /// The default implementation drains [`AggregateEventStreamer::stream_events`]; stores that can count
+    /// without reading the events should override it.
+    async fn count_events<T: AggregateRoot>(&self, id: &str) -> Result<usize, PersistenceError> {
+        self.stream_events::<T>(id, SequenceSelect::All)
+            .try_fold(0, |count, _| async move { Ok(count + 1) })
+            .await
+    }
 }
 
 /// Trait for persisting events and snapshots in the event store.
 #[async_trait]
 pub trait Persister: Send + Sync + 'static {
+    /// Writes the events, the optional snapshot and `index_ops` atomically: either all of them are stored or none.
     async fn persist(
         &self,
         domain_events: &[SerializedDomainEvent],
         integration_events: &[SerializedIntegrationEvent],
         snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
     ) -> Result<(), PersistenceError>;
+
+    /// Same as [`Persister::persist`], but the write only succeeds while the aggregate's journal matches
+    /// `expected_state`, checked atomically with the write.
+    ///
+    /// Fails with [`PersistenceError::AlreadyExists`] when [`ExpectedState::MustNotExist`] does not hold and with
+    /// [`PersistenceError::OptimisticConcurrency`] when [`ExpectedState::ExpectedVersion`] does not. The default
+    /// implementation handles [`ExpectedState::Any`] and reports the other states as unsupported.
+    async fn persist_expecting(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+        expected_state: ExpectedState,
+    ) -> Result<(), PersistenceError> {
+        match expected_state {
+            ExpectedState::Any => {
+                self.persist(domain_events, integration_events, snapshot_update, index_ops)
+                    .await
+            }
+            expected_state => Err(PersistenceError::UnknownError(
+                format!("{expected_state:?} is not supported by this store").into(),
+            )),
+        }
+    }
+
+    /// Persists the writes of several aggregates.
+    ///
+    /// Every batch is written atomically, but batches are independent of each other: when one fails, batches
+    /// before it may already be stored. The default implementation calls [`Persister::persist`] once per batch;
+    /// stores that can combine batches into fewer round trips should override it.
+    async fn persist_batch(&self, batches: &[PersistBatch]) -> Result<(), PersistenceError> {
+        for batch in batches {
+            self.persist(
+                &batch.domain_events,
+                &batch.integration_events,
+                batch.snapshot_update.as_ref(),
+                &batch.index_ops,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Marks the aggregate as deleted while keeping the fact of deletion.
+    ///
+    /// Appends a terminal [`TOMBSTONE_EVENT_TYPE`] entry to the journal and deletes the snapshot and the pending
+    /// integration events of the aggregate; loading it afterwards fails with [`PersistenceError::Tombstoned`].
+    /// Tombstoning an already tombstoned aggregate does nothing. The default implementation reports that the
+    /// store does not support tombstones.
+    async fn tombstone(&self, aggregate_type: &str, id: &str) -> Result<(), PersistenceError> {
+        Err(PersistenceError::UnknownError(
+            format!("Tombstoning aggregate {aggregate_type} {id} is not supported by this store").into(),
+        ))
+    }
+}
+
+/// State of an aggregate's journal that [`Persister::persist_expecting`] requires before writing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpectedState {
+    /// No check beyond the uniqueness of every event's sequence number.
+    #[default]
+    Any,
+    /// The aggregate has no events yet.
+    MustNotExist,
+    /// The last event of the aggregate has this sequence number; `0` means it has no events yet.
+    ExpectedVersion(SequenceNumber),
+}
+
+/// Writes of a single aggregate passed to [`Persister::persist_batch`], mirroring the arguments of
+/// [`Persister::persist`].
+#[derive(Debug, Default, PartialEq)]
+pub struct PersistBatch {
+    pub domain_events: Vec<SerializedDomainEvent>,
+    pub integration_events: Vec<SerializedIntegrationEvent>,
+    pub snapshot_update: Option<PersistedSnapshot>,
+    pub index_ops: Vec<IndexOp>,
+}
+
+impl PersistBatch {
+    pub fn new(domain_events: Vec<SerializedDomainEvent>) -> Self {
+        Self {
+            domain_events,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_integration_events(mut self, integration_events: Vec<SerializedIntegrationEvent>) -> Self {
+        self.integration_events = integration_events;
+        self
+    }
+
+    pub fn with_snapshot(mut self, snapshot: PersistedSnapshot) -> Self {
+        self.snapshot_update = Some(snapshot);
+        self
+    }
+
+    pub fn with_index_ops(mut self, index_ops: Vec<IndexOp>) -> Self {
+        self.index_ops = index_ops;
+        self
+    }
+
+    /// ID of the aggregate the batch writes to, taken from its first event or its snapshot.
+    pub fn aggregate_id(&self) -> Option<&str> {
+        self.domain_events
+            .first()
+            .map(|event| event.aggregate_id.as_str())
+            .or_else(|| {
+                self.snapshot_update
+                    .as_ref()
+                    .map(|snapshot| snapshot.aggregate_id.as_str())
+            })
+    }
 }
 
 /// Trait for retrieving snapshots from the event store.
@@ -78,6 +246,23 @@ pub trait SnapshotGetter: Send + Sync + 'static {
     async fn get_snapshot<T>(&self, id: &str) -> Result<Option<PersistedSnapshot>, PersistenceError>
     where
         T: AggregateRoot;
+
+    /// Retrieves the snapshots of several aggregates of type `T`, keyed by aggregate ID.
+    ///
+    /// Aggregates without a snapshot are left out of the map. The default implementation calls
+    /// [`SnapshotGetter::get_snapshot`] once per ID; stores that can read snapshots in bulk should override it.
+    async fn get_snapshots<T>(&self, ids: &[&str]) -> Result<HashMap<String, PersistedSnapshot>, PersistenceError>
+    where
+        T: AggregateRoot,
+    {
+        let mut snapshots = HashMap::with_capacity(ids.len());
+        for id in ids {
+            if let Some(snapshot) = self.get_snapshot::<T>(id).await? {
+                snapshots.insert((*id).to_string(), snapshot);
+            }
+        }
+        Ok(snapshots)
+    }
 }
 
 #[cfg(test)]
@@ -263,6 +448,7 @@ mod tests {
             domain_events: &[SerializedDomainEvent],
             integration_events: &[SerializedIntegrationEvent],
             snapshot_update: Option<&PersistedSnapshot>,
+            _index_ops: &[IndexOp],
         ) -> Result<(), PersistenceError> {
             // Store domain events
             if !domain_events.is_empty() {
@@ -381,7 +567,7 @@ mod tests {
                 ),
             ];
 
-            store.persist(&events, &[], None).await.unwrap();
+            store.persist(&events, &[], None, &[]).await.unwrap();
 
             // Test streaming all events
             let mut stream = store.stream_events::<TestAggregate>("test-agg-1", SequenceSelect::All);
@@ -404,6 +590,66 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_count_events_default_drains_stream() {
+        futures::executor::block_on(async {
+            let store = MockEventStore::new(10);
+            assert_eq!(store.count_events::<TestAggregate>("test-agg-1").await.unwrap(), 0);
+
+            let events: Vec<_> = (1..=3)
+                .map(|seq_nr| {
+                    SerializedDomainEvent::new(
+                        format!("evt-{seq_nr}"),
+                        "test-agg-1".to_string(),
+                        seq_nr,
+                        "TestAggregate".to_string(),
+                        "TestEvent".to_string(),
+                        vec![],
+                        json!({}),
+                    )
+                })
+                .collect();
+            store.persist(&events, &[], None, &[]).await.unwrap();
+
+            assert_eq!(store.count_events::<TestAggregate>("test-agg-1").await.unwrap(), 3);
+        });
+    }
+
+    #[test]
+    fn test_persist_batch_default_persists_every_batch() {
+        futures::executor::block_on(async {
+            let store = MockEventStore::new(10);
+            let event = |aggregate_id: &str| {
+                SerializedDomainEvent::new(
+                    format!("evt-{aggregate_id}"),
+                    aggregate_id.to_string(),
+                    1,
+                    "TestAggregate".to_string(),
+                    "TestEvent".to_string(),
+                    vec![],
+                    json!({}),
+                )
+            };
+            let batches = [
+                PersistBatch::new(vec![event("test-agg-1")]),
+                PersistBatch::new(vec![event("test-agg-2")]).with_snapshot(PersistedSnapshot {
+                    aggregate_type: "TestAggregate".to_string(),
+                    aggregate_id: "test-agg-2".to_string(),
+                    aggregate: vec![],
+                    seq_nr: 2,
+                    version: 1,
+                }),
+            ];
+
+            store.persist_batch(&batches).await.unwrap();
+
+            assert_eq!(store.count_events::<TestAggregate>("test-agg-1").await.unwrap(), 1);
+            assert_eq!(store.count_events::<TestAggregate>("test-agg-2").await.unwrap(), 1);
+            assert!(store.snapshots.lock().unwrap().contains_key("test-agg-2"));
+            assert_eq!(batches[1].aggregate_id(), Some("test-agg-2"));
+        });
+    }
+
     #[test]
     fn test_snapshot_persister() {
         futures::executor::block_on(async {
@@ -439,7 +685,7 @@ mod tests {
             };
 
             let result = store
-                .persist(&domain_events, &integration_events, Some(&snapshot))
+                .persist(&domain_events, &integration_events, Some(&snapshot), &[])
                 .await;
 
             assert!(result.is_ok());
@@ -477,7 +723,7 @@ mod tests {
                 version: 5,
             };
 
-            store.persist(&[], &[], Some(&snapshot)).await.unwrap();
+            store.persist(&[], &[], Some(&snapshot), &[]).await.unwrap();
 
             // Test getting existing snapshot
             let result = store.get_snapshot::<TestAggregate>("test-agg-1").await;
@@ -510,7 +756,7 @@ mod tests {
             }
 
             // Persist events in batches
-            store.persist(&all_events[0..5], &[], None).await.unwrap();
+            store.persist(&all_events[0..5], &[], None, &[]).await.unwrap();
 
             // Check if snapshot is needed
             let snapshot_at = store.commit_snapshot_with_addl_events(0, 5);
@@ -525,7 +771,10 @@ mod tests {
                 version: 1,
             };
 
-            store.persist(&all_events[5..10], &[], Some(&snapshot)).await.unwrap();
+            store
+                .persist(&all_events[5..10], &[], Some(&snapshot), &[])
+                .await
+                .unwrap();
 
             // Verify we can stream all events
             let mut stream = store.stream_events::<TestAggregate>("test-agg-1", SequenceSelect::All);
@@ -0,0 +1,1172 @@
+use crate::{
+    aggregate::AggregateRoot,
+    domain_event::SerializedDomainEvent,
+    event::{SequenceSelect, Stream},
+    event_store::{
+        AggregateEventStreamer, ExpectedState, Persister, SnapshotGetter, SnapshotIntervalProvider,
+        TOMBSTONE_EVENT_TYPE,
+    },
+    helper::now_timestamp,
+    integration_event::SerializedIntegrationEvent,
+    inverted_index_store::{AggregateIdsLoader, IndexKeyword, IndexOp, InvertedIndexCommiter, InvertedIndexRemover},
+    mem_store::{check_expected_state, check_unique_seq_nrs},
+    persist::PersistenceError,
+    sequence_number::SequenceNumber,
+    snapshot::PersistedSnapshot,
+    version::Version,
+};
+use async_trait::async_trait;
+use futures::{stream, TryStreamExt};
+use prost_types::Timestamp;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+
+/// Bytes of the big-endian length written before every frame of a log.
+const FRAME_HEADER_LEN: usize = 4;
+
+/// Append-only file event store for single-node deployments and local development
+///
+/// Every aggregate has a journal under `journal/` and its latest snapshot in a sidecar file under `snapshots/`;
+/// integration events go to `integration_events.log` and inverted indexes to `indexes.json`. Logs are sequences of
+/// length-prefixed JSON frames, one per persist, so a write torn by a crash loses the whole persist and never part
+/// of it. [`FileEventStore::open`] truncates such a torn last frame, and an append that fails without a crash
+/// truncates its partial frame itself.
+///
+/// Writes are serialized within the store and its clones; several processes must not share a directory. The
+/// journal, outbox, snapshot and index files of one persist are written one after the other, journal first. A
+/// persist that fails partway undoes the writes it made, so its events are never committed without its
+/// integration events; only a crash between the writes can keep the events of a persist but lose its other writes.
+#[derive(Debug, Clone)]
+pub struct FileEventStore {
+    root: PathBuf,
+    snapshot_interval: usize,
+    snapshot_intervals: HashMap<&'static str, usize>,
+    // Held while writing, so the journal checks of a persist and its appends are not interleaved with another.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl FileEventStore {
+    /// Opens the store in `root`, creating the directory if needed, and truncates the torn last frame a crash
+    /// during an append may have left in a log.
+    pub async fn open(root: impl Into<PathBuf>, snapshot_interval: usize) -> Result<Self, PersistenceError> {
+        let store = Self {
+            root: root.into(),
+            snapshot_interval,
+            snapshot_intervals: HashMap::new(),
+            write_lock: Arc::new(Mutex::new(())),
+        };
+        fs::create_dir_all(store.journal_dir()).await.map_err(io_error)?;
+        fs::create_dir_all(store.snapshot_dir()).await.map_err(io_error)?;
+
+        let mut journals = fs::read_dir(store.journal_dir()).await.map_err(io_error)?;
+        while let Some(entry) = journals.next_entry().await.map_err(io_error)? {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == "log") {
+                recover_log(&path).await?;
+            }
+        }
+        recover_log(&store.outbox_path()).await?;
+        Ok(store)
+    }
+
+    /// Overrides the snapshot interval for aggregates whose `TYPE` is `aggregate_type`.
+    pub fn with_snapshot_interval_for(mut self, aggregate_type: &'static str, interval: usize) -> Self {
+        self.snapshot_intervals.insert(aggregate_type, interval);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Integration events in the outbox, in persist order.
+    pub async fn integration_events(&self) -> Result<Vec<SerializedIntegrationEvent>, PersistenceError> {
+        let records: Vec<IntegrationEventRecord> = read_log(&self.outbox_path()).await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    /// Rewrites the journal of the aggregate without the events its snapshot covers, returning how many were
+    /// dropped.
+    ///
+    /// The journal is replaced through a temporary file and a rename, so a crash leaves either the old or the
+    /// compacted journal. The last covered event is kept, as
+    /// [`AggregateLoader::load_aggregate_as_of`](crate::command::repository::AggregateLoader::load_aggregate_as_of)
+    /// reads it to date the snapshot, and so is the last event, as it records the aggregate's sequence number; an
+    /// aggregate without a snapshot is left as it is. The compacted journal can no longer be replayed from the
+    /// initial state, so [`EventSourced::rebuild_snapshot`](crate::EventSourced::rebuild_snapshot) fails on it.
+    pub async fn compact(&self, id: &str) -> Result<usize, PersistenceError> {
+        let _write = self.write_lock.lock().await;
+
+        let Some(snapshot) = self.read_snapshot(id).await? else {
+            return Ok(0);
+        };
+        let journal = self.read_journal(id).await?;
+        let Some(last_seq_nr) = journal.last().map(|event| event.seq_nr) else {
+            return Ok(0);
+        };
+        let keep_from = snapshot.seq_nr.saturating_sub(1).max(1).min(last_seq_nr);
+        let records: Vec<_> = journal
+            .iter()
+            .filter(|event| event.seq_nr >= keep_from)
+            .map(EventRecord::from)
+            .collect();
+        let dropped = journal.len() - records.len();
+        if dropped > 0 {
+            write_atomically(&self.journal_path(id), &encode_frame(&records)?).await?;
+        }
+        Ok(dropped)
+    }
+
+    fn journal_dir(&self) -> PathBuf {
+        self.root.join("journal")
+    }
+
+    fn snapshot_dir(&self) -> PathBuf {
+        self.root.join("snapshots")
+    }
+
+    fn journal_path(&self, id: &str) -> PathBuf {
+        self.journal_dir().join(format!("{}.log", file_name(id)))
+    }
+
+    fn snapshot_path(&self, id: &str) -> PathBuf {
+        self.snapshot_dir().join(format!("{}.snapshot", file_name(id)))
+    }
+
+    fn outbox_path(&self) -> PathBuf {
+        self.root.join("integration_events.log")
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.root.join("indexes.json")
+    }
+
+    /// Events of the aggregate in `seq_nr` order.
+    async fn read_journal(&self, id: &str) -> Result<Vec<SerializedDomainEvent>, PersistenceError> {
+        let records: Vec<EventRecord> = read_log(&self.journal_path(id)).await?;
+        let mut events: Vec<SerializedDomainEvent> = records.into_iter().map(Into::into).collect();
+        events.sort_by_key(|event| event.seq_nr);
+        Ok(events)
+    }
+
+    async fn read_snapshot(&self, id: &str) -> Result<Option<PersistedSnapshot>, PersistenceError> {
+        let Some(bytes) = read_file(&self.snapshot_path(id)).await? else {
+            return Ok(None);
+        };
+        let record: SnapshotRecord =
+            serde_json::from_slice(&bytes).map_err(|e| PersistenceError::DeserializationError(Box::new(e)))?;
+        Ok(Some(record.into()))
+    }
+
+    async fn read_indexes(&self) -> Result<HashMap<String, BTreeSet<String>>, PersistenceError> {
+        let Some(bytes) = read_file(&self.index_path()).await? else {
+            return Ok(HashMap::new());
+        };
+        serde_json::from_slice(&bytes).map_err(|e| PersistenceError::DeserializationError(Box::new(e)))
+    }
+
+    /// Writes the files of a persist, recording in `undo` how to take back each write that succeeded; callers
+    /// hold the write lock.
+    async fn write_persist(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+        expected_state: ExpectedState,
+        undo: &mut Vec<Undo>,
+    ) -> Result<(), PersistenceError> {
+        if !domain_events.is_empty() {
+            let aggregate_id = &domain_events[0].aggregate_id;
+            let journal = self.read_journal(aggregate_id).await?;
+            let last_seq_nr = journal.last().map_or(0, |event| event.seq_nr);
+            check_expected_state(aggregate_id, last_seq_nr, expected_state)?;
+            check_unique_seq_nrs(aggregate_id, &journal, domain_events)?;
+            let records: Vec<_> = domain_events.iter().map(EventRecord::from).collect();
+            let path = self.journal_path(aggregate_id);
+            let len = append_frame(&path, &records).await?;
+            undo.push(Undo::Truncate(path, len));
+        }
+
+        if !integration_events.is_empty() {
+            let records: Vec<_> = integration_events.iter().map(IntegrationEventRecord::from).collect();
+            let path = self.outbox_path();
+            let len = append_frame(&path, &records).await?;
+            undo.push(Undo::Truncate(path, len));
+        }
+
+        if let Some(snapshot) = snapshot_update {
+            let bytes = serde_json::to_vec(&SnapshotRecord::from(snapshot))
+                .map_err(|e| PersistenceError::UnknownError(Box::new(e)))?;
+            let path = self.snapshot_path(&snapshot.aggregate_id);
+            let previous = read_file(&path).await?;
+            write_atomically(&path, &bytes).await?;
+            undo.push(Undo::Restore(path, previous));
+        }
+
+        self.apply_index_ops(index_ops).await
+    }
+
+    /// Applies `index_ops` to `indexes.json`; callers hold the write lock.
+    async fn apply_index_ops(&self, index_ops: &[IndexOp]) -> Result<(), PersistenceError> {
+        if index_ops.is_empty() {
+            return Ok(());
+        }
+        let mut indexes = self.read_indexes().await?;
+        for op in index_ops {
+            match op {
+                IndexOp::Put { keyword, aggregate_id } => {
+                    indexes
+                        .entry(keyword.to_string())
+                        .or_default()
+                        .insert(aggregate_id.clone());
+                }
+                IndexOp::Delete { keyword, aggregate_id } => {
+                    if let Some(set) = indexes.get_mut(keyword.as_str()) {
+                        set.remove(aggregate_id);
+                        if set.is_empty() {
+                            indexes.remove(keyword.as_str());
+                        }
+                    }
+                }
+            }
+        }
+        let bytes = serde_json::to_vec(&indexes).map_err(|e| PersistenceError::UnknownError(Box::new(e)))?;
+        write_atomically(&self.index_path(), &bytes).await
+    }
+}
+
+impl SnapshotIntervalProvider for FileEventStore {
+    fn snapshot_interval(&self) -> usize {
+        self.snapshot_interval
+    }
+
+    fn snapshot_interval_for<T: AggregateRoot>(&self) -> usize {
+        self.snapshot_intervals
+            .get(T::TYPE)
+            .copied()
+            .unwrap_or(self.snapshot_interval)
+    }
+}
+
+#[async_trait]
+impl AggregateEventStreamer for FileEventStore {
+    fn stream_events<T: AggregateRoot>(
+        &self,
+        id: &str,
+        select: SequenceSelect,
+    ) -> Stream<'_, SerializedDomainEvent, PersistenceError> {
+        let id = id.to_string();
+        let events = stream::once(async move { self.read_journal(&id).await }).map_ok(move |events| {
+            let events = events.into_iter().filter(move |event| match select {
+                SequenceSelect::All => true,
+                SequenceSelect::From(seq) => event.seq_nr >= seq,
+            });
+            stream::iter(events.map(Ok))
+        });
+        Box::pin(events.try_flatten())
+    }
+}
+
+#[async_trait]
+impl Persister for FileEventStore {
+    async fn persist(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+    ) -> Result<(), PersistenceError> {
+        self.persist_expecting(
+            domain_events,
+            integration_events,
+            snapshot_update,
+            index_ops,
+            ExpectedState::Any,
+        )
+        .await
+    }
+
+    async fn persist_expecting(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+        expected_state: ExpectedState,
+    ) -> Result<(), PersistenceError> {
+        let _write = self.write_lock.lock().await;
+
+        let mut undo = Vec::new();
+        let written = self
+            .write_persist(
+                domain_events,
+                integration_events,
+                snapshot_update,
+                index_ops,
+                expected_state,
+                &mut undo,
+            )
+            .await;
+        if written.is_err() {
+            roll_back(undo).await;
+        }
+        written
+    }
+
+    async fn tombstone(&self, aggregate_type: &str, id: &str) -> Result<(), PersistenceError> {
+        let _write = self.write_lock.lock().await;
+
+        let journal = self.read_journal(id).await?;
+        let last = journal.last();
+        if last.is_some_and(|event| event.event_type == TOMBSTONE_EVENT_TYPE) {
+            return Ok(());
+        }
+        let seq_nr = last.map_or(1, |event| event.seq_nr + 1);
+        let tombstone = SerializedDomainEvent::new(
+            ulid::Ulid::new().to_string(),
+            id.to_string(),
+            seq_nr,
+            aggregate_type.to_string(),
+            TOMBSTONE_EVENT_TYPE.to_string(),
+            vec![],
+            Value::Null,
+        )
+        .with_occurred_at(now_timestamp().unwrap_or_default());
+        append_frame(&self.journal_path(id), &[EventRecord::from(&tombstone)]).await?;
+
+        match fs::remove_file(self.snapshot_path(id)).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(io_error(err)),
+            _ => {}
+        }
+        let outbox: Vec<IntegrationEventRecord> = read_log(&self.outbox_path()).await?;
+        if outbox.iter().any(|record| record.aggregate_id == id) {
+            let remaining: Vec<_> = outbox.into_iter().filter(|record| record.aggregate_id != id).collect();
+            let bytes = if remaining.is_empty() {
+                vec![]
+            } else {
+                encode_frame(&remaining)?
+            };
+            write_atomically(&self.outbox_path(), &bytes).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SnapshotGetter for FileEventStore {
+    async fn get_snapshot<T>(&self, id: &str) -> Result<Option<PersistedSnapshot>, PersistenceError>
+    where
+        T: AggregateRoot,
+    {
+        self.read_snapshot(id).await
+    }
+}
+
+#[async_trait]
+impl AggregateIdsLoader for FileEventStore {
+    async fn get_aggregate_ids(
+        &self,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<Vec<String>, PersistenceError> {
+        let keyword = keyword.into();
+        let indexes = self.read_indexes().await?;
+        Ok(indexes
+            .get(keyword.as_str())
+            .map(|set| set.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl InvertedIndexCommiter for FileEventStore {
+    async fn commit(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        let op = IndexOp::put(keyword, aggregate_id);
+        let _write = self.write_lock.lock().await;
+        self.apply_index_ops(&[op]).await
+    }
+}
+
+#[async_trait]
+impl InvertedIndexRemover for FileEventStore {
+    async fn remove(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        let op = IndexOp::delete(keyword, aggregate_id);
+        let _write = self.write_lock.lock().await;
+        self.apply_index_ops(&[op]).await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EventRecord {
+    id: String,
+    aggregate_id: String,
+    seq_nr: SequenceNumber,
+    aggregate_type: String,
+    event_type: String,
+    payload: Vec<u8>,
+    metadata: Value,
+    schema_version: u32,
+    occurred_at_seconds: i64,
+    occurred_at_nanos: i32,
+}
+
+impl From<&SerializedDomainEvent> for EventRecord {
+    fn from(event: &SerializedDomainEvent) -> Self {
+        Self {
+            id: event.id.clone(),
+            aggregate_id: event.aggregate_id.clone(),
+            seq_nr: event.seq_nr,
+            aggregate_type: event.aggregate_type.clone(),
+            event_type: event.event_type.clone(),
+            payload: event.payload.clone(),
+            metadata: event.metadata.clone(),
+            schema_version: event.schema_version,
+            occurred_at_seconds: event.occurred_at.seconds,
+            occurred_at_nanos: event.occurred_at.nanos,
+        }
+    }
+}
+
+impl From<EventRecord> for SerializedDomainEvent {
+    fn from(record: EventRecord) -> Self {
+        SerializedDomainEvent::new(
+            record.id,
+            record.aggregate_id,
+            record.seq_nr,
+            record.aggregate_type,
+            record.event_type,
+            record.payload,
+            record.metadata,
+        )
+        .with_schema_version(record.schema_version)
+        .with_occurred_at(Timestamp {
+            seconds: record.occurred_at_seconds,
+            nanos: record.occurred_at_nanos,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IntegrationEventRecord {
+    id: String,
+    aggregate_id: String,
+    aggregate_type: String,
+    event_type: String,
+    payload: Vec<u8>,
+    #[serde(default)]
+    metadata: Value,
+}
+
+impl From<&SerializedIntegrationEvent> for IntegrationEventRecord {
+    fn from(event: &SerializedIntegrationEvent) -> Self {
+        Self {
+            id: event.id.clone(),
+            aggregate_id: event.aggregate_id.clone(),
+            aggregate_type: event.aggregate_type.clone(),
+            event_type: event.event_type.clone(),
+            payload: event.payload.clone(),
+            metadata: event.metadata.clone(),
+        }
+    }
+}
+
+impl From<IntegrationEventRecord> for SerializedIntegrationEvent {
+    fn from(record: IntegrationEventRecord) -> Self {
+        SerializedIntegrationEvent::new(
+            record.id,
+            record.aggregate_id,
+            record.aggregate_type,
+            record.event_type,
+            record.payload,
+        )
+        .with_metadata(record.metadata)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotRecord {
+    aggregate_type: String,
+    aggregate_id: String,
+    aggregate: Vec<u8>,
+    seq_nr: SequenceNumber,
+    version: Version,
+}
+
+impl From<&PersistedSnapshot> for SnapshotRecord {
+    fn from(snapshot: &PersistedSnapshot) -> Self {
+        Self {
+            aggregate_type: snapshot.aggregate_type.clone(),
+            aggregate_id: snapshot.aggregate_id.clone(),
+            aggregate: snapshot.aggregate.clone(),
+            seq_nr: snapshot.seq_nr,
+            version: snapshot.version,
+        }
+    }
+}
+
+impl From<SnapshotRecord> for PersistedSnapshot {
+    fn from(record: SnapshotRecord) -> Self {
+        PersistedSnapshot::new(
+            record.aggregate_type,
+            record.aggregate_id,
+            record.aggregate,
+            record.seq_nr,
+            record.version,
+        )
+    }
+}
+
+/// Write of a persist that is taken back when a later write of the same persist fails.
+enum Undo {
+    /// Truncates the log at the path back to the length it had before an append.
+    Truncate(PathBuf, u64),
+    /// Puts back the previous contents of the file at the path, or removes it when it did not exist.
+    Restore(PathBuf, Option<Vec<u8>>),
+}
+
+/// Takes back the writes of a failed persist, newest first. Writes that cannot be taken back are logged, as the
+/// persist already fails with the error that caused the roll back.
+async fn roll_back(undo: Vec<Undo>) {
+    for step in undo.into_iter().rev() {
+        let (path, undone) = match step {
+            Undo::Truncate(path, len) => {
+                let undone = truncate_log(&path, len).await;
+                (path, undone)
+            }
+            Undo::Restore(path, Some(bytes)) => {
+                let undone = write_atomically(&path, &bytes).await;
+                (path, undone)
+            }
+            Undo::Restore(path, None) => {
+                let undone = match fs::remove_file(&path).await {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => Err(io_error(err)),
+                    _ => Ok(()),
+                };
+                (path, undone)
+            }
+        };
+        if let Err(err) = undone {
+            tracing::warn!(path = %path.display(), error = %err, "Failed to roll back a write of a failed persist");
+        }
+    }
+}
+
+fn io_error(err: io::Error) -> PersistenceError {
+    PersistenceError::ConnectionError(Box::new(err))
+}
+
+/// Aggregate ID as a file name: bytes other than ASCII alphanumerics, `-` and `_` are percent-encoded.
+fn file_name(id: &str) -> String {
+    let mut name = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            name.push(byte as char);
+        } else {
+            let _ = write!(name, "%{byte:02X}");
+        }
+    }
+    name
+}
+
+fn encode_frame<T: Serialize>(records: &[T]) -> Result<Vec<u8>, PersistenceError> {
+    let body = serde_json::to_vec(records).map_err(|e| PersistenceError::UnknownError(Box::new(e)))?;
+    let len = u32::try_from(body.len()).map_err(|e| PersistenceError::UnknownError(Box::new(e)))?;
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend(body);
+    Ok(frame)
+}
+
+/// Splits the first complete frame off `bytes`, or returns `None` when `bytes` does not start with one.
+fn split_frame(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let header = bytes.get(..FRAME_HEADER_LEN)?;
+    let len = u32::from_be_bytes(header.try_into().ok()?) as usize;
+    let end = FRAME_HEADER_LEN.checked_add(len)?;
+    let frame = bytes.get(FRAME_HEADER_LEN..end)?;
+    Some((frame, &bytes[end..]))
+}
+
+/// Length of the complete frames at the start of `bytes`; anything after them is a torn write.
+fn complete_len(bytes: &[u8]) -> usize {
+    let mut rest = bytes;
+    while let Some((_, next)) = split_frame(rest) {
+        rest = next;
+    }
+    bytes.len() - rest.len()
+}
+
+/// Records of every complete frame of the log at `path`, which may not exist yet.
+async fn read_log<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, PersistenceError> {
+    let bytes = match fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(io_error(err)),
+    };
+    let mut records = Vec::new();
+    let mut rest = bytes.as_slice();
+    while let Some((frame, next)) = split_frame(rest) {
+        let frame: Vec<T> =
+            serde_json::from_slice(frame).map_err(|e| PersistenceError::DeserializationError(Box::new(e)))?;
+        records.extend(frame);
+        rest = next;
+    }
+    Ok(records)
+}
+
+/// Logs whose next append writes half of its frame and then fails, as on a full disk.
+#[cfg(test)]
+static FAILING_APPENDS: std::sync::Mutex<BTreeSet<PathBuf>> = std::sync::Mutex::new(BTreeSet::new());
+
+/// Appends a frame of `records` to the log at `path`, returning the length the log had before.
+///
+/// A failed append truncates the log back to that length, so later appends never land after a torn frame.
+async fn append_frame<T: Serialize>(path: &Path, records: &[T]) -> Result<u64, PersistenceError> {
+    let frame = encode_frame(records)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(io_error)?;
+    let len = file.metadata().await.map_err(io_error)?.len();
+    let written: io::Result<()> = async {
+        #[cfg(test)]
+        if FAILING_APPENDS.lock().unwrap().remove(path) {
+            file.write_all(&frame[..frame.len() / 2]).await?;
+            file.flush().await?;
+            return Err(io::Error::other("injected append failure"));
+        }
+        file.write_all(&frame).await?;
+        file.sync_data().await
+    }
+    .await;
+    if let Err(err) = written {
+        let truncated = match file.set_len(len).await {
+            Ok(()) => file.sync_all().await,
+            Err(err) => Err(err),
+        };
+        if let Err(truncate_err) = truncated {
+            tracing::warn!(
+                path = %path.display(),
+                error = %truncate_err,
+                "Failed to truncate failed append, the log is repaired on the next open"
+            );
+        }
+        return Err(io_error(err));
+    }
+    Ok(len)
+}
+
+/// Truncates the log at `path` after its last complete frame.
+async fn recover_log(path: &Path) -> Result<(), PersistenceError> {
+    let bytes = match fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(io_error(err)),
+    };
+    let len = complete_len(&bytes);
+    if len < bytes.len() {
+        tracing::warn!(
+            path = %path.display(),
+            torn_bytes = bytes.len() - len,
+            "Truncating torn write at the end of the log"
+        );
+        truncate_log(path, len as u64).await?;
+    }
+    Ok(())
+}
+
+/// Truncates the log at `path` to `len` bytes.
+async fn truncate_log(path: &Path, len: u64) -> Result<(), PersistenceError> {
+    let file = fs::OpenOptions::new().write(true).open(path).await.map_err(io_error)?;
+    file.set_len(len).await.map_err(io_error)?;
+    file.sync_all().await.map_err(io_error)
+}
+
+/// Contents of the file at `path`, or `None` when it does not exist.
+async fn read_file(path: &Path) -> Result<Option<Vec<u8>>, PersistenceError> {
+    match fs::read(path).await {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(io_error(err)),
+    }
+}
+
+/// Replaces the file at `path` with `bytes` through a temporary file and a rename, so a crash leaves either the
+/// old or the new contents.
+async fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), PersistenceError> {
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp).await.map_err(io_error)?;
+    file.write_all(bytes).await.map_err(io_error)?;
+    file.sync_all().await.map_err(io_error)?;
+    fs::rename(&tmp, path).await.map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregate_id::{AggregateId, HasIdPrefix},
+        command::{
+            repository::{AggregateCommiter, AggregateLoader},
+            Command,
+        },
+        domain_event::DomainEvent,
+        event::Envelope,
+        event_id::EventIdType,
+        integration_event::{IntegrationEvent, IntoIntegrationEvents},
+        message,
+        serde::Json,
+        EventSourced,
+    };
+    use serde_json::json;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("tsuzuri-file-store-{}", ulid::Ulid::new())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct CounterId;
+
+    impl HasIdPrefix for CounterId {
+        const PREFIX: &'static str = "counter";
+    }
+
+    #[derive(Debug, Clone)]
+    struct Increment {
+        id: AggregateId<CounterId>,
+        by: u64,
+    }
+
+    impl message::Message for Increment {
+        fn name(&self) -> &'static str {
+            "Increment"
+        }
+    }
+
+    impl Command for Increment {
+        type ID = CounterId;
+
+        fn id(&self) -> AggregateId<Self::ID> {
+            self.id
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Incremented {
+        id: EventIdType,
+        by: u64,
+    }
+
+    impl message::Message for Incremented {
+        fn name(&self) -> &'static str {
+            "Incremented"
+        }
+    }
+
+    impl DomainEvent for Incremented {
+        fn id(&self) -> EventIdType {
+            self.id
+        }
+
+        fn event_type(&self) -> &'static str {
+            "CounterIncremented"
+        }
+    }
+
+    impl IntoIntegrationEvents for Incremented {
+        type IntegrationEvent = CounterChanged;
+        type IntoIter = Vec<CounterChanged>;
+
+        fn into_integration_events(self) -> Self::IntoIter {
+            vec![]
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct CounterChanged;
+
+    impl message::Message for CounterChanged {
+        fn name(&self) -> &'static str {
+            "CounterChanged"
+        }
+    }
+
+    impl IntegrationEvent for CounterChanged {
+        fn id(&self) -> String {
+            EventIdType::new().to_string()
+        }
+
+        fn event_type(&self) -> &'static str {
+            "counter.changed"
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        id: AggregateId<CounterId>,
+        value: u64,
+    }
+
+    impl AggregateRoot for Counter {
+        const TYPE: &'static str = "Counter";
+        type ID = CounterId;
+        type Command = Increment;
+        type DomainEvent = Incremented;
+        type IntegrationEvent = CounterChanged;
+        type Error = std::convert::Infallible;
+
+        fn init(id: AggregateId<Self::ID>) -> Self {
+            Self { id, value: 0 }
+        }
+
+        fn id(&self) -> &AggregateId<Self::ID> {
+            &self.id
+        }
+
+        fn handle(&mut self, cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+            Ok(Incremented {
+                id: EventIdType::new(),
+                by: cmd.by,
+            })
+        }
+
+        fn apply(&mut self, event: Self::DomainEvent) {
+            self.value += event.by;
+        }
+    }
+
+    fn event(seq_nr: SequenceNumber) -> SerializedDomainEvent {
+        SerializedDomainEvent::new(
+            format!("evt-{seq_nr}"),
+            "counter-1".to_string(),
+            seq_nr,
+            "Counter".to_string(),
+            "CounterIncremented".to_string(),
+            vec![seq_nr as u8],
+            json!({ "correlation_id": "req-1" }),
+        )
+        .with_schema_version(2)
+        .with_occurred_at(Timestamp {
+            seconds: 1_700_000_000 + seq_nr as i64,
+            nanos: 500,
+        })
+    }
+
+    async fn stream_all(store: &FileEventStore, id: &str) -> Vec<SerializedDomainEvent> {
+        store
+            .stream_events::<Counter>(id, SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_persist_and_stream_events_across_reopen() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 10).await.unwrap();
+        let integration_events = vec![SerializedIntegrationEvent::new(
+            "int-evt-1".to_string(),
+            "counter-1".to_string(),
+            "Counter".to_string(),
+            "counter.changed".to_string(),
+            vec![1],
+        )];
+        store
+            .persist(
+                &[event(1), event(2)],
+                &integration_events,
+                None,
+                &[IndexOp::put("owner:alice", "counter-1")],
+            )
+            .await
+            .unwrap();
+        store.persist(&[event(3)], &[], None, &[]).await.unwrap();
+
+        assert!(matches!(
+            store.persist(&[event(3)], &[], None, &[]).await,
+            Err(PersistenceError::OptimisticConcurrency { expected_seq: 2, .. })
+        ));
+        assert!(matches!(
+            store
+                .persist_expecting(&[event(5)], &[], None, &[], ExpectedState::ExpectedVersion(4))
+                .await,
+            Err(PersistenceError::OptimisticConcurrency { expected_seq: 4, .. })
+        ));
+
+        let reopened = FileEventStore::open(&dir.0, 10).await.unwrap();
+        assert_eq!(
+            stream_all(&reopened, "counter-1").await,
+            vec![event(1), event(2), event(3)]
+        );
+        let from_two: Vec<_> = reopened
+            .stream_events::<Counter>("counter-1", SequenceSelect::From(2))
+            .map_ok(|event| event.seq_nr)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(from_two, vec![2, 3]);
+        assert_eq!(reopened.count_events::<Counter>("counter-1").await.unwrap(), 3);
+        assert!(stream_all(&reopened, "counter-2").await.is_empty());
+        assert_eq!(reopened.integration_events().await.unwrap(), integration_events);
+        assert_eq!(
+            reopened.get_aggregate_ids("owner:alice").await.unwrap(),
+            vec!["counter-1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_sidecar_replaced_and_removed_by_tombstone() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 10).await.unwrap();
+        assert!(store.get_snapshot::<Counter>("counter-1").await.unwrap().is_none());
+
+        for seq_nr in [2, 4] {
+            let snapshot = PersistedSnapshot::new(
+                "Counter".to_string(),
+                "counter-1".to_string(),
+                vec![seq_nr as u8],
+                seq_nr,
+                1,
+            );
+            store
+                .persist(&[event(seq_nr - 1), event(seq_nr)], &[], Some(&snapshot), &[])
+                .await
+                .unwrap();
+        }
+
+        let snapshot = store.get_snapshot::<Counter>("counter-1").await.unwrap().unwrap();
+        assert_eq!(snapshot.seq_nr, 4);
+        assert_eq!(snapshot.aggregate, vec![4]);
+
+        store.tombstone("Counter", "counter-1").await.unwrap();
+        store.tombstone("Counter", "counter-1").await.unwrap();
+
+        assert!(store.get_snapshot::<Counter>("counter-1").await.unwrap().is_none());
+        let journal = stream_all(&store, "counter-1").await;
+        assert_eq!(journal.len(), 5);
+        assert_eq!(journal[4].event_type, TOMBSTONE_EVENT_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_open_truncates_torn_last_record() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 10).await.unwrap();
+        store.persist(&[event(1), event(2)], &[], None, &[]).await.unwrap();
+        store.persist(&[event(3), event(4)], &[], None, &[]).await.unwrap();
+
+        // Simulate a crash halfway through the append of the second persist.
+        let path = store.journal_path("counter-1");
+        let len = std::fs::metadata(&path).unwrap().len();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - 10).unwrap();
+        drop(file);
+
+        let recovered = FileEventStore::open(&dir.0, 10).await.unwrap();
+        assert_eq!(stream_all(&recovered, "counter-1").await, vec![event(1), event(2)]);
+
+        // The torn bytes are gone, so appends after recovery read back.
+        recovered.persist(&[event(3)], &[], None, &[]).await.unwrap();
+        assert_eq!(
+            stream_all(&recovered, "counter-1").await,
+            vec![event(1), event(2), event(3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_append_leaves_no_torn_frame() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 10).await.unwrap();
+        store.persist(&[event(1)], &[], None, &[]).await.unwrap();
+
+        FAILING_APPENDS.lock().unwrap().insert(store.journal_path("counter-1"));
+        assert!(matches!(
+            store.persist(&[event(2), event(3)], &[], None, &[]).await,
+            Err(PersistenceError::ConnectionError(_))
+        ));
+        assert_eq!(stream_all(&store, "counter-1").await, vec![event(1)]);
+
+        // Without a reopen, the next append lands right after the last complete frame.
+        store.persist(&[event(2)], &[], None, &[]).await.unwrap();
+        assert_eq!(stream_all(&store, "counter-1").await, vec![event(1), event(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_outbox_append_rolls_back_journal() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 10).await.unwrap();
+        let integration_events = vec![SerializedIntegrationEvent::new(
+            "int-evt-1".to_string(),
+            "counter-1".to_string(),
+            "Counter".to_string(),
+            "counter.changed".to_string(),
+            vec![1],
+        )];
+        let snapshot = PersistedSnapshot::new("Counter".to_string(), "counter-1".to_string(), vec![2], 3, 1);
+
+        FAILING_APPENDS.lock().unwrap().insert(store.outbox_path());
+        assert!(matches!(
+            store
+                .persist(&[event(1), event(2)], &integration_events, Some(&snapshot), &[])
+                .await,
+            Err(PersistenceError::ConnectionError(_))
+        ));
+        assert!(stream_all(&store, "counter-1").await.is_empty());
+        assert!(store.integration_events().await.unwrap().is_empty());
+        assert!(store.get_snapshot::<Counter>("counter-1").await.unwrap().is_none());
+
+        // The retry commits the events together with their integration events instead of conflicting.
+        store
+            .persist(&[event(1), event(2)], &integration_events, Some(&snapshot), &[])
+            .await
+            .unwrap();
+        assert_eq!(stream_all(&store, "counter-1").await, vec![event(1), event(2)]);
+        assert_eq!(store.integration_events().await.unwrap(), integration_events);
+    }
+
+    type CounterRepository =
+        EventSourced<Counter, FileEventStore, Json<Counter>, Json<Incremented>, Json<CounterChanged>>;
+
+    async fn increment(repository: &CounterRepository, id: &AggregateId<CounterId>, by: u64) {
+        let mut versioned = repository.load_aggregate(id).await.unwrap();
+        let events = versioned.handle_many(Increment { id: *id, by }).unwrap();
+        repository
+            .commit(&versioned, events.into_iter().map(Envelope::from).collect())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compact_keeps_loaded_state() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 3).await.unwrap();
+        let repository = EventSourced::new(store.clone(), Json::default(), Json::default(), Json::default());
+        let id = AggregateId::<CounterId>::new();
+        for by in 1..=7 {
+            increment(&repository, &id, by).await;
+        }
+        let before = repository.load_aggregate(&id).await.unwrap();
+        let snapshot = store.get_snapshot::<Counter>(&id.to_string()).await.unwrap().unwrap();
+
+        let dropped = store.compact(&id.to_string()).await.unwrap();
+
+        assert_eq!(dropped, snapshot.seq_nr - 2);
+        let journal = stream_all(&store, &id.to_string()).await;
+        assert_eq!(journal.first().map(|event| event.seq_nr), Some(snapshot.seq_nr - 1));
+        assert_eq!(journal.last().map(|event| event.seq_nr), Some(7));
+        let after = repository.load_aggregate(&id).await.unwrap();
+        assert_eq!(after.aggregate(), before.aggregate());
+        assert_eq!(after.seq_nr(), before.seq_nr());
+        assert_eq!(store.compact(&id.to_string()).await.unwrap(), 0);
+
+        // The compacted journal keeps taking writes, also after a reopen.
+        increment(&repository, &id, 8).await;
+        let reopened = EventSourced::new(
+            FileEventStore::open(&dir.0, 3).await.unwrap(),
+            Json::<Counter>::default(),
+            Json::<Incremented>::default(),
+            Json::<CounterChanged>::default(),
+        );
+        let loaded = reopened.load_aggregate(&id).await.unwrap();
+        assert_eq!(loaded.aggregate().value, (1..=8).sum::<u64>());
+        assert_eq!(loaded.seq_nr(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_load_aggregate_as_of_after_compact_uses_snapshot() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 3).await.unwrap();
+        let repository = EventSourced::new(store.clone(), Json::default(), Json::default(), Json::default());
+        let id = AggregateId::<CounterId>::new();
+        for by in 1..=7 {
+            increment(&repository, &id, by).await;
+        }
+        assert!(store.compact(&id.to_string()).await.unwrap() > 0);
+
+        let now = now_timestamp().unwrap();
+        let loaded = repository.load_aggregate_as_of(&id, now).await.unwrap();
+
+        assert_eq!(loaded.aggregate().value, (1..=7).sum::<u64>());
+        assert_eq!(loaded.seq_nr(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_snapshot_after_compact_keeps_snapshot() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 3).await.unwrap();
+        let repository = EventSourced::new(store.clone(), Json::default(), Json::default(), Json::default());
+        let id = AggregateId::<CounterId>::new();
+        for by in 1..=7 {
+            increment(&repository, &id, by).await;
+        }
+        assert!(store.compact(&id.to_string()).await.unwrap() > 0);
+        let snapshot = store.get_snapshot::<Counter>(&id.to_string()).await.unwrap().unwrap();
+
+        let result = repository.rebuild_snapshot(&id).await;
+
+        assert!(matches!(result, Err(PersistenceError::CorruptStream { seq_nr, .. }) if seq_nr > 1));
+        let kept = store.get_snapshot::<Counter>(&id.to_string()).await.unwrap().unwrap();
+        assert_eq!(kept.aggregate, snapshot.aggregate);
+        assert_eq!(kept.version, snapshot.version);
+        assert_eq!(
+            repository.load_aggregate(&id).await.unwrap().aggregate().value,
+            (1..=7).sum::<u64>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compact_keeps_last_event_and_skips_aggregates_without_snapshot() {
+        let dir = TempDir::new();
+        let store = FileEventStore::open(&dir.0, 10).await.unwrap();
+        store.persist(&[event(1), event(2)], &[], None, &[]).await.unwrap();
+        assert_eq!(store.compact("counter-1").await.unwrap(), 0);
+
+        // A snapshot that covers every event leaves the last one as the record of the sequence number.
+        let snapshot = PersistedSnapshot::new("Counter".to_string(), "counter-1".to_string(), vec![], 3, 1);
+        store.persist(&[], &[], Some(&snapshot), &[]).await.unwrap();
+        assert_eq!(store.compact("counter-1").await.unwrap(), 1);
+        assert_eq!(stream_all(&store, "counter-1").await, vec![event(2)]);
+        assert!(matches!(
+            store
+                .persist_expecting(&[event(3)], &[], None, &[], ExpectedState::MustNotExist)
+                .await,
+            Err(PersistenceError::AlreadyExists { .. })
+        ));
+        store
+            .persist_expecting(&[event(3)], &[], None, &[], ExpectedState::ExpectedVersion(2))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_file_name_escapes_path_characters() {
+        assert_eq!(file_name("counter-01J_x"), "counter-01J_x");
+        assert_eq!(file_name("../a/b c"), "%2E%2E%2Fa%2Fb%20c");
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use prost_types::{Timestamp, TimestampError};
 use std::convert::TryInto;
+use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Convert a `prost_types::Timestamp` to a `SystemTime`.
@@ -13,16 +14,63 @@ pub fn system_time_to_timestamp(time: SystemTime) -> Result<Timestamp, String> {
         })
 }
 
+/// Source of the current time for timestamps.
+///
+/// Use [`SystemClock`] in production and [`FixedClock`] in tests that assert exact timestamps.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// Clock reading the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock that always returns the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(SystemTime);
+
+impl FixedClock {
+    pub fn new(time: SystemTime) -> Self {
+        Self(time)
+    }
+
+    /// Clock fixed at `seconds` after the UNIX epoch.
+    pub fn from_unix_seconds(seconds: u64) -> Self {
+        Self(UNIX_EPOCH + std::time::Duration::from_secs(seconds))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
 /// Convert a `SystemTime` to a `prost_types::Timestamp`.
 pub fn now_timestamp() -> Option<Timestamp> {
-    system_time_to_timestamp(SystemTime::now()).ok()
+    now_timestamp_with(&SystemClock)
+}
+
+/// Current time of `clock` as a `prost_types::Timestamp`.
+pub fn now_timestamp_with(clock: &dyn Clock) -> Option<Timestamp> {
+    system_time_to_timestamp(clock.now()).ok()
 }
 
 /// Convert a `SystemTime` to a `prost_types::Timestamp`.
 pub fn days_from_now_timestamp(days: u64) -> Option<Timestamp> {
-    let now = SystemTime::now();
+    days_from_now_timestamp_with(&SystemClock, days)
+}
+
+/// Time `days` after the current time of `clock` as a `prost_types::Timestamp`.
+pub fn days_from_now_timestamp_with(clock: &dyn Clock, days: u64) -> Option<Timestamp> {
     let duration = std::time::Duration::from_secs(60 * 60 * 24 * days);
-    let future_time = now.checked_add(duration)?;
+    let future_time = clock.now().checked_add(duration)?;
     system_time_to_timestamp(future_time).ok()
 }
 
@@ -101,6 +149,37 @@ mod tests {
         assert!(seven_days.seconds - now.seconds <= expected_diff + 1);
     }
 
+    #[test]
+    fn test_now_timestamp_with_fixed_clock() {
+        let clock = FixedClock::new(UNIX_EPOCH + Duration::from_secs(1609459200) + Duration::from_nanos(500));
+
+        let timestamp = now_timestamp_with(&clock).unwrap();
+        assert_eq!(timestamp.seconds, 1609459200);
+        assert_eq!(timestamp.nanos, 500);
+
+        // A fixed clock does not advance
+        assert_eq!(now_timestamp_with(&clock), Some(timestamp));
+    }
+
+    #[test]
+    fn test_days_from_now_timestamp_with_fixed_clock() {
+        let clock = FixedClock::from_unix_seconds(1609459200);
+
+        let zero_days = days_from_now_timestamp_with(&clock, 0).unwrap();
+        assert_eq!(zero_days.seconds, 1609459200);
+        assert_eq!(zero_days.nanos, 0);
+
+        let seven_days = days_from_now_timestamp_with(&clock, 7).unwrap();
+        assert_eq!(seven_days.seconds, 1609459200 + 60 * 60 * 24 * 7);
+        assert_eq!(to_rfc3339(&seven_days).unwrap(), "2021-01-08T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_fixed_clock_before_epoch() {
+        let clock = FixedClock::new(UNIX_EPOCH - Duration::from_secs(1));
+        assert!(now_timestamp_with(&clock).is_none());
+    }
+
     #[test]
     fn test_to_rfc3339() {
         // Test with epoch
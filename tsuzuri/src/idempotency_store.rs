@@ -0,0 +1,140 @@
+use crate::persist::PersistenceError;
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Events produced by commands dispatched with an idempotency key, so a client retrying a command gets the
+/// recorded events back instead of running it again.
+///
+/// Used by [`CommandBus::dispatch_idempotent`](crate::command::bus::CommandBus::dispatch_idempotent); events are
+/// stored serialized, in the order the command produced them.
+///
+/// A key is reserved with [`IdempotencyStore::reserve`] before its command runs, so of two dispatches racing on
+/// the same key only one runs the command.
+#[async_trait]
+pub trait IdempotencyStore: fmt::Debug + Send + Sync + 'static {
+    /// Returns the events recorded under `key`, or `None` when there are none, they expired or the key is only
+    /// reserved.
+    async fn get(&self, key: &str) -> Result<Option<Vec<Vec<u8>>>, PersistenceError>;
+
+    /// Reserves `key` for the caller unless it is reserved or recorded already, to be forgotten after `ttl`.
+    ///
+    /// Must be atomic: of concurrent reservations of a key, exactly one gets [`Reservation::Reserved`].
+    async fn reserve(&self, key: &str, ttl: Duration) -> Result<Reservation, PersistenceError>;
+
+    /// Records `events` under `key`, replacing its reservation or any earlier record, to be forgotten after `ttl`.
+    async fn put(&self, key: &str, events: Vec<Vec<u8>>, ttl: Duration) -> Result<(), PersistenceError>;
+
+    /// Drops the reservation of `key`, so the key can be reserved again. Recorded events are kept.
+    async fn release(&self, key: &str) -> Result<(), PersistenceError>;
+}
+
+/// Outcome of [`IdempotencyStore::reserve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reservation {
+    /// The key is now held by the caller.
+    Reserved,
+    /// Another caller holds the key and has not recorded its events yet.
+    InProgress,
+    /// Events were recorded under the key already.
+    Completed(Vec<Vec<u8>>),
+}
+
+/// Expiry and recorded events of every key; a reserved key has no events yet.
+type Records = HashMap<String, (Instant, Option<Vec<Vec<u8>>>)>;
+
+/// Memory-based idempotency store for testing and local debugging
+#[derive(Debug, Clone, Default)]
+pub struct MemoryIdempotencyStore {
+    records: Arc<RwLock<Records>>,
+}
+
+impl MemoryIdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for MemoryIdempotencyStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<Vec<u8>>>, PersistenceError> {
+        let records = self.records.read().unwrap();
+        Ok(records
+            .get(key)
+            .filter(|(expires_at, _)| *expires_at > Instant::now())
+            .and_then(|(_, events)| events.clone()))
+    }
+
+    async fn reserve(&self, key: &str, ttl: Duration) -> Result<Reservation, PersistenceError> {
+        let mut records = self.records.write().unwrap();
+        let now = Instant::now();
+        match records.get(key).filter(|(expires_at, _)| *expires_at > now) {
+            Some((_, Some(events))) => Ok(Reservation::Completed(events.clone())),
+            Some((_, None)) => Ok(Reservation::InProgress),
+            None => {
+                records.insert(key.to_string(), (now + ttl, None));
+                Ok(Reservation::Reserved)
+            }
+        }
+    }
+
+    async fn put(&self, key: &str, events: Vec<Vec<u8>>, ttl: Duration) -> Result<(), PersistenceError> {
+        self.records
+            .write()
+            .unwrap()
+            .insert(key.to_string(), (Instant::now() + ttl, Some(events)));
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> Result<(), PersistenceError> {
+        let mut records = self.records.write().unwrap();
+        if records.get(key).is_some_and(|(_, events)| events.is_none()) {
+            records.remove(key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_idempotency_store_forgets_expired_keys() {
+        let store = MemoryIdempotencyStore::new();
+        assert_eq!(store.get("key-1").await.unwrap(), None);
+
+        store
+            .put("key-1", vec![b"event-1".to_vec()], Duration::from_secs(60))
+            .await
+            .unwrap();
+        store.put("key-2", vec![], Duration::ZERO).await.unwrap();
+
+        assert_eq!(store.get("key-1").await.unwrap(), Some(vec![b"event-1".to_vec()]));
+        assert_eq!(store.get("key-2").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_idempotency_store_reserves_keys_once() {
+        let store = MemoryIdempotencyStore::new();
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(store.reserve("key-1", ttl).await.unwrap(), Reservation::Reserved);
+        assert_eq!(store.reserve("key-1", ttl).await.unwrap(), Reservation::InProgress);
+        assert_eq!(store.get("key-1").await.unwrap(), None);
+
+        store.release("key-1").await.unwrap();
+        assert_eq!(store.reserve("key-1", ttl).await.unwrap(), Reservation::Reserved);
+
+        store.put("key-1", vec![b"event-1".to_vec()], ttl).await.unwrap();
+        store.release("key-1").await.unwrap();
+        assert_eq!(
+            store.reserve("key-1", ttl).await.unwrap(),
+            Reservation::Completed(vec![b"event-1".to_vec()])
+        );
+    }
+}
@@ -1,7 +1,9 @@
 pub mod adapter;
+pub mod envelope;
 pub mod error;
 pub mod processor;
 
 pub use adapter::*;
+pub use envelope::*;
 pub use error::*;
 pub use processor::*;
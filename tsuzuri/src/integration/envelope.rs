@@ -0,0 +1,69 @@
+use crate::{
+    event::{Envelope, Metadata},
+    integration::error::Result,
+    message::Message,
+    serde::Serde,
+};
+
+/// Rebuilds an envelope from the raw payload and metadata of an event received over any transport.
+///
+/// `metadata` is the JSON object stored next to the event, as written by the event store; an empty slice stands
+/// for no metadata. Transport adapters (SQS, Kafka, ...) use this to feed envelopes to an
+/// [`Executer`](crate::integration::adapter::Executer) uniformly.
+pub fn decode_envelope<E: Message>(payload: &[u8], metadata: &[u8], serde: &impl Serde<E>) -> Result<Envelope<E>> {
+    let message = serde.deserialize(payload)?;
+    let metadata = if metadata.is_empty() {
+        Metadata::default()
+    } else {
+        serde_json::from_slice(metadata)?
+    };
+    Ok(Envelope::from(message).set_metadata(metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{integration::error::IntegrationError, message::CORRELATION_ID, serde::Json};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct OrderShipped {
+        order_id: String,
+    }
+
+    impl Message for OrderShipped {
+        fn name(&self) -> &'static str {
+            "OrderShipped"
+        }
+    }
+
+    #[test]
+    fn test_decode_envelope_restores_message_and_metadata() {
+        let serde = Json::<OrderShipped>::default();
+        let payload = serde_json::to_vec(&OrderShipped {
+            order_id: "ord-1".to_string(),
+        })
+        .unwrap();
+        let metadata = serde_json::to_vec(&Metadata::from([(CORRELATION_ID, "corr-1")])).unwrap();
+
+        let envelope = decode_envelope(&payload, &metadata, &serde).unwrap();
+        assert_eq!(envelope.message.order_id, "ord-1");
+        assert_eq!(envelope.correlation_id(), Some("corr-1"));
+
+        let envelope = decode_envelope(&payload, &[], &serde).unwrap();
+        assert!(envelope.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_decode_envelope_rejects_malformed_input() {
+        let serde = Json::<OrderShipped>::default();
+        assert!(matches!(
+            decode_envelope(b"not json", b"{}", &serde),
+            Err(IntegrationError::Serialization(_))
+        ));
+        assert!(matches!(
+            decode_envelope(br#"{"order_id":"ord-1"}"#, b"[1]", &serde),
+            Err(IntegrationError::Json(_))
+        ));
+    }
+}
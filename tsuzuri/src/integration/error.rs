@@ -1,4 +1,4 @@
-use crate::serde::SerdeError;
+use crate::{persist::PersistenceError, serde::SerdeError};
 
 #[derive(thiserror::Error, Debug)]
 pub enum IntegrationError {
@@ -12,6 +12,15 @@ pub enum IntegrationError {
     StreamProcessing(String),
     #[error("Json error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("No route registered for event: {0}")]
+    RouteNotFound(String),
+}
+
+/// Failures of a [`ProcessedEventStore`](crate::processed_event_store::ProcessedEventStore) surface as database errors.
+impl From<PersistenceError> for IntegrationError {
+    fn from(err: PersistenceError) -> Self {
+        Self::Database(err.to_string())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, IntegrationError>;
@@ -30,6 +39,9 @@ mod tests {
 
         let stream_error = IntegrationError::StreamProcessing("Stream closed".to_string());
         assert_eq!(stream_error.to_string(), "Stream processing error: Stream closed");
+
+        let route_error = IntegrationError::RouteNotFound("OrderShipped".to_string());
+        assert_eq!(route_error.to_string(), "No route registered for event: OrderShipped");
     }
 
     #[test]
@@ -1,10 +1,11 @@
 use crate::{
     event::Envelope,
-    integration::{adapter::Adapter, error::Result},
+    integration::{adapter::Adapter, envelope::decode_envelope, error::Result},
     integration_event::IntegrationEvent,
+    processed_event_store::ProcessedEventStore,
     serde,
 };
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Arc};
 
 /// Integration-specific processor that handles integration events
 #[derive(Debug, Clone)]
@@ -12,6 +13,7 @@ pub struct Processor<A, E, EvtSerde> {
     pub adapter: A,
     pub event_serde: EvtSerde,
     pub event: PhantomData<E>,
+    pub processed_events: Option<Arc<dyn ProcessedEventStore>>,
 }
 
 impl<A, E, EvtSerde> Processor<A, E, EvtSerde> {
@@ -20,8 +22,15 @@ impl<A, E, EvtSerde> Processor<A, E, EvtSerde> {
             adapter,
             event_serde,
             event: PhantomData,
+            processed_events: None,
         }
     }
+
+    /// Skip events whose `IntegrationEvent::id()` is already marked in `store`, and mark every event handled
+    pub fn with_processed_event_store(mut self, store: impl ProcessedEventStore) -> Self {
+        self.processed_events = Some(Arc::new(store));
+        self
+    }
 }
 
 impl<A, E, EvtSerde> Processor<A, E, EvtSerde>
@@ -31,8 +40,23 @@ where
     EvtSerde: serde::Serde<E>,
 {
     pub async fn process_bytes(&mut self, payload: &[u8]) -> Result<()> {
-        let event = self.to_integration_event(payload)?;
+        self.process_bytes_with_metadata(payload, &[]).await
+    }
+
+    /// Processes `payload` like [`Processor::process_bytes`], handing the adapter an envelope carrying `metadata`,
+    /// the JSON object stored next to the event; an empty slice stands for no metadata.
+    pub async fn process_bytes_with_metadata(&mut self, payload: &[u8], metadata: &[u8]) -> Result<()> {
+        let event = decode_envelope(payload, metadata, &self.event_serde)?;
+        let event_id = event.message.id();
+        if let Some(store) = &self.processed_events {
+            if store.has_processed(&event_id).await? {
+                return Ok(());
+            }
+        }
         self.adapter.execute(event).await?;
+        if let Some(store) = &self.processed_events {
+            store.mark_processed(&event_id).await?;
+        }
         Ok(())
     }
 
@@ -49,6 +73,7 @@ mod tests {
     use crate::{
         integration::{adapter::Executer, error::IntegrationError},
         message::{self, Metadata},
+        processed_event_store::MemoryProcessedEventStore,
         serde::SerdeError,
     };
     use async_trait::async_trait;
@@ -79,6 +104,7 @@ mod tests {
     #[derive(Clone)]
     struct MockAdapter {
         calls: Arc<Mutex<Vec<TestIntegrationEvent>>>,
+        metadata: Arc<Mutex<Vec<Metadata>>>,
         should_fail: bool,
     }
 
@@ -86,6 +112,7 @@ mod tests {
         fn new(should_fail: bool) -> Self {
             Self {
                 calls: Arc::new(Mutex::new(Vec::new())),
+                metadata: Arc::new(Mutex::new(Vec::new())),
                 should_fail,
             }
         }
@@ -101,6 +128,7 @@ mod tests {
             if self.should_fail {
                 return Err(IntegrationError::Database("Mock adapter failed".to_string()));
             }
+            self.metadata.lock().unwrap().push(event.metadata);
             self.calls.lock().unwrap().push(event.message);
             Ok(())
         }
@@ -161,6 +189,46 @@ mod tests {
         assert_eq!(calls[0].id, "event-12");
     }
 
+    #[tokio::test]
+    async fn test_process_bytes_with_metadata_restores_envelope_metadata() {
+        let adapter = MockAdapter::new(false);
+        let mut processor = Processor::new(adapter.clone(), MockSerde::new(false));
+        let metadata = Metadata::from([(message::CORRELATION_ID, "req-1")]);
+
+        processor
+            .process_bytes_with_metadata(b"test-payload", &serde_json::to_vec(&metadata).unwrap())
+            .await
+            .unwrap();
+        processor.process_bytes(b"test-payload").await.unwrap();
+
+        assert_eq!(adapter.get_calls().len(), 2);
+        assert_eq!(*adapter.metadata.lock().unwrap(), vec![metadata, Metadata::default()]);
+    }
+
+    #[tokio::test]
+    async fn test_process_bytes_skips_processed_events() {
+        let adapter = MockAdapter::new(false);
+        let store = MemoryProcessedEventStore::new();
+        let mut processor =
+            Processor::new(adapter.clone(), MockSerde::new(false)).with_processed_event_store(store.clone());
+
+        processor.process_bytes(b"test-payload").await.unwrap();
+        processor.process_bytes(b"test-payload").await.unwrap();
+
+        assert_eq!(adapter.get_calls().len(), 1);
+        assert!(store.has_processed("event-12").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_process_bytes_does_not_mark_failed_events() {
+        let store = MemoryProcessedEventStore::new();
+        let mut processor =
+            Processor::new(MockAdapter::new(true), MockSerde::new(false)).with_processed_event_store(store.clone());
+
+        assert!(processor.process_bytes(b"test-payload").await.is_err());
+        assert!(!store.has_processed("event-12").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_process_bytes_serde_failure() {
         let adapter = MockAdapter::new(false);
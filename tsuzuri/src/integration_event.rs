@@ -1,4 +1,5 @@
 use crate::{domain_event::DomainEvent, message};
+use serde_json::Value;
 use std::fmt;
 
 /// Marker trait for integration events that communicate changes to external systems.
@@ -22,6 +23,9 @@ pub struct SerializedIntegrationEvent {
     pub aggregate_type: String,
     pub event_type: String,
     pub payload: Vec<u8>,
+    /// Metadata of the domain event the integration event stems from, carrying its correlation and causation IDs
+    /// to consumers. `Null` when the event has none.
+    pub metadata: Value,
 }
 
 #[allow(dead_code)]
@@ -33,6 +37,12 @@ impl SerializedIntegrationEvent {
             aggregate_type,
             event_type,
             payload,
+            metadata: Value::Null,
         }
     }
+
+    pub fn with_metadata(mut self, metadata: Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
@@ -1,5 +1,101 @@
 use crate::persist::PersistenceError;
 use async_trait::async_trait;
+use std::fmt;
+
+/// Keyword an aggregate is indexed under in the inverted index.
+///
+/// [`IndexKeyword::field`] builds `name:value` keywords, escaping `\` and `:` in both parts so distinct
+/// fields never produce the same keyword. Strings convert unchanged, so existing raw keywords keep working.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IndexKeyword(String);
+
+impl IndexKeyword {
+    /// Uses `keyword` as is, without escaping.
+    pub fn new(keyword: impl Into<String>) -> Self {
+        Self(keyword.into())
+    }
+
+    /// Builds the keyword `name:value`.
+    pub fn field(name: &str, value: impl fmt::Display) -> Self {
+        Self(format!("{}:{}", escape(name), escape(&value.to_string())))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn escape(part: &str) -> String {
+    part.replace('\\', "\\\\").replace(':', "\\:")
+}
+
+impl fmt::Display for IndexKeyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for IndexKeyword {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for IndexKeyword {
+    fn from(keyword: &str) -> Self {
+        Self::new(keyword)
+    }
+}
+
+impl From<String> for IndexKeyword {
+    fn from(keyword: String) -> Self {
+        Self(keyword)
+    }
+}
+
+impl From<&String> for IndexKeyword {
+    fn from(keyword: &String) -> Self {
+        Self::new(keyword.as_str())
+    }
+}
+
+impl From<&IndexKeyword> for IndexKeyword {
+    fn from(keyword: &IndexKeyword) -> Self {
+        keyword.clone()
+    }
+}
+
+/// Inverted index write applied by [`Persister::persist`](crate::event_store::Persister::persist) in the same
+/// transaction as the events it accompanies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexOp {
+    /// Indexes `aggregate_id` under `keyword`.
+    Put {
+        keyword: IndexKeyword,
+        aggregate_id: String,
+    },
+    /// Removes `aggregate_id` from `keyword`.
+    Delete {
+        keyword: IndexKeyword,
+        aggregate_id: String,
+    },
+}
+
+impl IndexOp {
+    pub fn put(keyword: impl Into<IndexKeyword>, aggregate_id: impl Into<String>) -> Self {
+        Self::Put {
+            keyword: keyword.into(),
+            aggregate_id: aggregate_id.into(),
+        }
+    }
+
+    pub fn delete(keyword: impl Into<IndexKeyword>, aggregate_id: impl Into<String>) -> Self {
+        Self::Delete {
+            keyword: keyword.into(),
+            aggregate_id: aggregate_id.into(),
+        }
+    }
+}
 
 pub trait InvertedIndexStore:
     AggregateIdsLoader + InvertedIndexCommiter + InvertedIndexRemover + Send + Sync + 'static
@@ -14,17 +110,21 @@ impl<T> InvertedIndexStore for T where
 
 #[async_trait]
 pub trait AggregateIdsLoader: Send + Sync + 'static {
-    async fn get_aggregate_ids(&self, keyword: &str) -> Result<Vec<String>, PersistenceError>;
+    /// Returns the IDs of the aggregates indexed under `keyword`, sorted ascending.
+    async fn get_aggregate_ids(&self, keyword: impl Into<IndexKeyword> + Send)
+        -> Result<Vec<String>, PersistenceError>;
 }
 
 #[async_trait]
 pub trait InvertedIndexCommiter: Send + Sync + 'static {
-    async fn commit(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError>;
+    async fn commit(&self, aggregate_id: &str, keyword: impl Into<IndexKeyword> + Send)
+        -> Result<(), PersistenceError>;
 }
 
 #[async_trait]
 pub trait InvertedIndexRemover: Send + Sync + 'static {
-    async fn remove(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError>;
+    async fn remove(&self, aggregate_id: &str, keyword: impl Into<IndexKeyword> + Send)
+        -> Result<(), PersistenceError>;
 }
 
 #[cfg(test)]
@@ -48,10 +148,14 @@ mod tests {
 
     #[async_trait]
     impl AggregateIdsLoader for MockInvertedIndexStore {
-        async fn get_aggregate_ids(&self, keyword: &str) -> Result<Vec<String>, PersistenceError> {
+        async fn get_aggregate_ids(
+            &self,
+            keyword: impl Into<IndexKeyword> + Send,
+        ) -> Result<Vec<String>, PersistenceError> {
+            let keyword = keyword.into();
             let indexes = self.indexes.lock().unwrap();
             Ok(indexes
-                .get(keyword)
+                .get(keyword.as_str())
                 .map(|set| set.iter().cloned().collect())
                 .unwrap_or_default())
         }
@@ -59,7 +163,12 @@ mod tests {
 
     #[async_trait]
     impl InvertedIndexCommiter for MockInvertedIndexStore {
-        async fn commit(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
+        async fn commit(
+            &self,
+            aggregate_id: &str,
+            keyword: impl Into<IndexKeyword> + Send,
+        ) -> Result<(), PersistenceError> {
+            let keyword = keyword.into();
             let mut indexes = self.indexes.lock().unwrap();
             indexes
                 .entry(keyword.to_string())
@@ -71,12 +180,17 @@ mod tests {
 
     #[async_trait]
     impl InvertedIndexRemover for MockInvertedIndexStore {
-        async fn remove(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
+        async fn remove(
+            &self,
+            aggregate_id: &str,
+            keyword: impl Into<IndexKeyword> + Send,
+        ) -> Result<(), PersistenceError> {
+            let keyword = keyword.into();
             let mut indexes = self.indexes.lock().unwrap();
-            if let Some(set) = indexes.get_mut(keyword) {
+            if let Some(set) = indexes.get_mut(keyword.as_str()) {
                 set.remove(aggregate_id);
                 if set.is_empty() {
-                    indexes.remove(keyword);
+                    indexes.remove(keyword.as_str());
                 }
             }
             Ok(())
@@ -242,4 +356,39 @@ mod tests {
         let result = store.get_aggregate_ids("concurrent").await.unwrap();
         assert_eq!(result.len(), 20);
     }
+
+    #[test]
+    fn test_index_keyword_field_formatting() {
+        assert_eq!(IndexKeyword::field("user", "john").as_str(), "user:john");
+        assert_eq!(IndexKeyword::field("age", 42).to_string(), "age:42");
+        assert_eq!(IndexKeyword::from("user:john"), IndexKeyword::field("user", "john"));
+        assert_eq!(IndexKeyword::from("raw keyword").as_ref(), "raw keyword");
+    }
+
+    #[test]
+    fn test_index_keyword_field_escapes_separators() {
+        assert_eq!(IndexKeyword::field("url", "http://a").as_str(), r"url:http\://a");
+        assert_eq!(IndexKeyword::field("a:b", "c").as_str(), r"a\:b:c");
+        assert_eq!(IndexKeyword::field("path", r"c:\tmp").as_str(), r"path:c\:\\tmp");
+        assert_ne!(IndexKeyword::field("a:b", "c"), IndexKeyword::field("a", "b:c"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_and_get_with_index_keyword() {
+        let store = MockInvertedIndexStore::new();
+        let keyword = IndexKeyword::field("email", "john@example.com");
+
+        store.commit("agg-1", &keyword).await.unwrap();
+
+        assert_eq!(
+            store.get_aggregate_ids("email:john@example.com").await.unwrap(),
+            vec!["agg-1"]
+        );
+        store.remove("agg-1", keyword).await.unwrap();
+        assert!(store
+            .get_aggregate_ids("email:john@example.com")
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }
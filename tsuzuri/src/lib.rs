@@ -6,23 +6,34 @@ pub mod error;
 pub mod event;
 mod event_id;
 pub mod event_store;
+pub mod file_store;
 pub mod helper;
+pub mod idempotency_store;
 pub mod integration;
 pub mod integration_event;
 pub mod inverted_index_store;
 pub mod mem_store;
 pub mod message;
 pub mod persist;
+pub mod processed_event_store;
 pub mod projection;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub mod sequence_number;
 pub mod serde;
 pub mod snapshot;
 pub mod test;
+pub mod upcaster;
 pub mod version;
 mod versioned_aggregate;
 
 pub use aggregate::AggregateRoot;
-pub use command::repository::{AggregateCommiter, AggregateLoader, EventSourced, Repository};
+pub use command::bus::{CommandBus, CommandError};
+pub use command::middleware::{CommandMiddleware, LoggingMiddleware, MetricsMiddleware};
+pub use command::repository::{
+    AggregateCommiter, AggregateLoader, AggregateState, EventSourced, Repository, UnknownEventHandler,
+    UnknownEventPolicy,
+};
 pub use command::{handler, repository, Command};
 pub use event_id::{EventId, EventIdType};
 pub use versioned_aggregate::VersionedAggregate;
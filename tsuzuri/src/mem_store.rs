@@ -2,21 +2,27 @@ use crate::{
     aggregate::AggregateRoot,
     domain_event::SerializedDomainEvent,
     event::{SequenceSelect, Stream},
-    event_store::{AggregateEventStreamer, Persister, SnapshotGetter, SnapshotIntervalProvider},
+    event_store::{
+        AggregateEventStreamer, ExpectedState, Persister, SnapshotGetter, SnapshotIntervalProvider,
+        TOMBSTONE_EVENT_TYPE,
+    },
+    helper::now_timestamp,
     integration_event::SerializedIntegrationEvent,
-    inverted_index_store::{AggregateIdsLoader, InvertedIndexCommiter, InvertedIndexRemover},
+    inverted_index_store::{AggregateIdsLoader, IndexKeyword, IndexOp, InvertedIndexCommiter, InvertedIndexRemover},
     persist::PersistenceError,
+    sequence_number::SequenceNumber,
     snapshot::PersistedSnapshot,
 };
 use async_trait::async_trait;
 use futures::stream;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 
 /// Memory-based event store for testing and development
 #[derive(Clone)]
 pub struct MemoryEventStore {
     snapshot_interval: usize,
+    snapshot_intervals: HashMap<&'static str, usize>,
     events: Arc<RwLock<HashMap<String, Vec<SerializedDomainEvent>>>>,
     snapshots: Arc<RwLock<HashMap<String, PersistedSnapshot>>>,
     integration_events: Arc<RwLock<Vec<SerializedIntegrationEvent>>>,
@@ -26,19 +32,34 @@ impl MemoryEventStore {
     pub fn new(snapshot_interval: usize) -> Self {
         Self {
             snapshot_interval,
+            snapshot_intervals: HashMap::new(),
             events: Arc::new(RwLock::new(HashMap::new())),
             snapshots: Arc::new(RwLock::new(HashMap::new())),
             integration_events: Arc::new(RwLock::new(Vec::new())),
         }
     }
+
+    /// Overrides the snapshot interval for aggregates whose `TYPE` is `aggregate_type`.
+    pub fn with_snapshot_interval_for(mut self, aggregate_type: &'static str, interval: usize) -> Self {
+        self.snapshot_intervals.insert(aggregate_type, interval);
+        self
+    }
 }
 
 impl SnapshotIntervalProvider for MemoryEventStore {
     fn snapshot_interval(&self) -> usize {
         self.snapshot_interval
     }
+
+    fn snapshot_interval_for<T: AggregateRoot>(&self) -> usize {
+        self.snapshot_intervals
+            .get(T::TYPE)
+            .copied()
+            .unwrap_or(self.snapshot_interval)
+    }
 }
 
+#[async_trait]
 impl AggregateEventStreamer for MemoryEventStore {
     fn stream_events<T: AggregateRoot>(
         &self,
@@ -55,8 +76,14 @@ impl AggregateEventStreamer for MemoryEventStore {
 
         Box::pin(stream::iter(filtered_events.into_iter().map(Ok)))
     }
+
+    async fn count_events<T: AggregateRoot>(&self, id: &str) -> Result<usize, PersistenceError> {
+        let events = self.events.read().unwrap();
+        Ok(events.get(id).map_or(0, Vec::len))
+    }
 }
 
+/// `MemoryEventStore` keeps no inverted index, so `index_ops` are ignored; use [`MemoryStore`] to apply them.
 #[async_trait]
 impl Persister for MemoryEventStore {
     async fn persist(
@@ -64,15 +91,40 @@ impl Persister for MemoryEventStore {
         domain_events: &[SerializedDomainEvent],
         integration_events: &[SerializedIntegrationEvent],
         snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+    ) -> Result<(), PersistenceError> {
+        self.persist_expecting(
+            domain_events,
+            integration_events,
+            snapshot_update,
+            index_ops,
+            ExpectedState::Any,
+        )
+        .await
+    }
+
+    async fn persist_expecting(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        _index_ops: &[IndexOp],
+        expected_state: ExpectedState,
     ) -> Result<(), PersistenceError> {
         // Store domain events
         if !domain_events.is_empty() {
             let mut events = self.events.write().unwrap();
             let aggregate_id = &domain_events[0].aggregate_id;
-            events
-                .entry(aggregate_id.clone())
-                .or_default()
-                .extend(domain_events.iter().cloned());
+            let last_seq_nr = events
+                .get(aggregate_id)
+                .and_then(|aggregate_events| aggregate_events.last())
+                .map_or(0, |event| event.seq_nr);
+            check_expected_state(aggregate_id, last_seq_nr, expected_state)?;
+            let aggregate_events = events.entry(aggregate_id.clone()).or_default();
+            check_unique_seq_nrs(aggregate_id, aggregate_events, domain_events)?;
+            aggregate_events.extend(domain_events.iter().cloned());
+            // Keep the journal in `seq_nr` order, like DynamoDB's range key, when events are persisted out of order.
+            aggregate_events.sort_by_key(|event| event.seq_nr);
         }
 
         // Store integration events
@@ -98,6 +150,72 @@ impl Persister for MemoryEventStore {
 
         Ok(())
     }
+
+    async fn tombstone(&self, aggregate_type: &str, id: &str) -> Result<(), PersistenceError> {
+        {
+            let mut events = self.events.write().unwrap();
+            let aggregate_events = events.entry(id.to_string()).or_default();
+            let last = aggregate_events.last();
+            if last.is_some_and(|event| event.event_type == TOMBSTONE_EVENT_TYPE) {
+                return Ok(());
+            }
+            let seq_nr = last.map_or(1, |event| event.seq_nr + 1);
+            aggregate_events.push(
+                SerializedDomainEvent::new(
+                    ulid::Ulid::new().to_string(),
+                    id.to_string(),
+                    seq_nr,
+                    aggregate_type.to_string(),
+                    TOMBSTONE_EVENT_TYPE.to_string(),
+                    vec![],
+                    serde_json::Value::Null,
+                )
+                .with_occurred_at(now_timestamp().unwrap_or_default()),
+            );
+        }
+
+        self.snapshots.write().unwrap().remove(id);
+        self.integration_events
+            .write()
+            .unwrap()
+            .retain(|event| event.aggregate_id != id);
+        Ok(())
+    }
+}
+
+pub(crate) fn check_expected_state(
+    aggregate_id: &str,
+    last_seq_nr: SequenceNumber,
+    expected_state: ExpectedState,
+) -> Result<(), PersistenceError> {
+    match expected_state {
+        ExpectedState::MustNotExist if last_seq_nr > 0 => Err(PersistenceError::AlreadyExists {
+            aggregate_id: aggregate_id.to_string(),
+        }),
+        ExpectedState::ExpectedVersion(expected_seq) if last_seq_nr != expected_seq => {
+            Err(PersistenceError::OptimisticConcurrency {
+                aggregate_id: aggregate_id.to_string(),
+                expected_seq,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Mirrors DynamoDB's `attribute_not_exists` condition: a taken `seq_nr` means another writer got there first.
+pub(crate) fn check_unique_seq_nrs(
+    aggregate_id: &str,
+    journal: &[SerializedDomainEvent],
+    domain_events: &[SerializedDomainEvent],
+) -> Result<(), PersistenceError> {
+    let mut seq_nrs: HashSet<_> = journal.iter().map(|event| event.seq_nr).collect();
+    if domain_events.iter().all(|event| seq_nrs.insert(event.seq_nr)) {
+        return Ok(());
+    }
+    Err(PersistenceError::OptimisticConcurrency {
+        aggregate_id: aggregate_id.to_string(),
+        expected_seq: domain_events[0].seq_nr.saturating_sub(1),
+    })
 }
 
 #[async_trait]
@@ -120,7 +238,8 @@ impl SnapshotGetter for MemoryEventStore {
 /// Memory-based inverted index store for testing and development
 #[derive(Clone)]
 pub struct MemoryInvertedIndexStore {
-    indexes: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+    // Ordered sets so `get_aggregate_ids` returns sorted IDs.
+    indexes: Arc<RwLock<HashMap<String, BTreeSet<String>>>>,
 }
 
 impl MemoryInvertedIndexStore {
@@ -139,10 +258,14 @@ impl Default for MemoryInvertedIndexStore {
 
 #[async_trait]
 impl AggregateIdsLoader for MemoryInvertedIndexStore {
-    async fn get_aggregate_ids(&self, keyword: &str) -> Result<Vec<String>, PersistenceError> {
+    async fn get_aggregate_ids(
+        &self,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<Vec<String>, PersistenceError> {
+        let keyword = keyword.into();
         let indexes = self.indexes.read().unwrap();
         Ok(indexes
-            .get(keyword)
+            .get(keyword.as_str())
             .map(|set| set.iter().cloned().collect())
             .unwrap_or_default())
     }
@@ -150,7 +273,12 @@ impl AggregateIdsLoader for MemoryInvertedIndexStore {
 
 #[async_trait]
 impl InvertedIndexCommiter for MemoryInvertedIndexStore {
-    async fn commit(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
+    async fn commit(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        let keyword = keyword.into();
         let mut indexes = self.indexes.write().unwrap();
         indexes
             .entry(keyword.to_string())
@@ -162,18 +290,38 @@ impl InvertedIndexCommiter for MemoryInvertedIndexStore {
 
 #[async_trait]
 impl InvertedIndexRemover for MemoryInvertedIndexStore {
-    async fn remove(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
+    async fn remove(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
+        let keyword = keyword.into();
         let mut indexes = self.indexes.write().unwrap();
-        if let Some(set) = indexes.get_mut(keyword) {
+        if let Some(set) = indexes.get_mut(keyword.as_str()) {
             set.remove(aggregate_id);
             if set.is_empty() {
-                indexes.remove(keyword);
+                indexes.remove(keyword.as_str());
             }
         }
         Ok(())
     }
 }
 
+/// Contents of a [`MemoryStore`], as exported by [`MemoryStore::export_state`].
+///
+/// Build one by hand to seed a store with a history, or compare exports to assert on everything a test wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStoreState {
+    /// Journal of every aggregate, keyed by aggregate ID, in `seq_nr` order.
+    pub events: HashMap<String, Vec<SerializedDomainEvent>>,
+    /// Latest snapshot of every aggregate that has one, keyed by aggregate ID.
+    pub snapshots: HashMap<String, PersistedSnapshot>,
+    /// Outbox of integration events, in persist order.
+    pub integration_events: Vec<SerializedIntegrationEvent>,
+    /// Aggregate IDs of every inverted index keyword.
+    pub indexes: HashMap<String, BTreeSet<String>>,
+}
+
 /// Combined memory store that implements both EventStore and InvertedIndexStore
 #[derive(Clone)]
 pub struct MemoryStore {
@@ -189,6 +337,12 @@ impl MemoryStore {
         }
     }
 
+    /// Overrides the snapshot interval for aggregates whose `TYPE` is `aggregate_type`.
+    pub fn with_snapshot_interval_for(mut self, aggregate_type: &'static str, interval: usize) -> Self {
+        self.event_store = self.event_store.with_snapshot_interval_for(aggregate_type, interval);
+        self
+    }
+
     /// Get reference to the event store component
     pub fn event_store(&self) -> &MemoryEventStore {
         &self.event_store
@@ -198,6 +352,24 @@ impl MemoryStore {
     pub fn inverted_index_store(&self) -> &MemoryInvertedIndexStore {
         &self.inverted_index_store
     }
+
+    /// Copies the events, snapshots, integration events and inverted indexes held by the store.
+    pub fn export_state(&self) -> MemoryStoreState {
+        MemoryStoreState {
+            events: self.event_store.events.read().unwrap().clone(),
+            snapshots: self.event_store.snapshots.read().unwrap().clone(),
+            integration_events: self.event_store.integration_events.read().unwrap().clone(),
+            indexes: self.inverted_index_store.indexes.read().unwrap().clone(),
+        }
+    }
+
+    /// Replaces the contents of the store with `state`; clones of the store see the new contents too.
+    pub fn import_state(&self, state: MemoryStoreState) {
+        *self.event_store.events.write().unwrap() = state.events;
+        *self.event_store.snapshots.write().unwrap() = state.snapshots;
+        *self.event_store.integration_events.write().unwrap() = state.integration_events;
+        *self.inverted_index_store.indexes.write().unwrap() = state.indexes;
+    }
 }
 
 // Implement all EventStore traits by delegating to event_store
@@ -205,8 +377,13 @@ impl SnapshotIntervalProvider for MemoryStore {
     fn snapshot_interval(&self) -> usize {
         self.event_store.snapshot_interval()
     }
+
+    fn snapshot_interval_for<T: AggregateRoot>(&self) -> usize {
+        self.event_store.snapshot_interval_for::<T>()
+    }
 }
 
+#[async_trait]
 impl AggregateEventStreamer for MemoryStore {
     fn stream_events<T: AggregateRoot>(
         &self,
@@ -215,6 +392,10 @@ impl AggregateEventStreamer for MemoryStore {
     ) -> Stream<'_, SerializedDomainEvent, PersistenceError> {
         self.event_store.stream_events::<T>(id, select)
     }
+
+    async fn count_events<T: AggregateRoot>(&self, id: &str) -> Result<usize, PersistenceError> {
+        self.event_store.count_events::<T>(id).await
+    }
 }
 
 #[async_trait]
@@ -224,10 +405,44 @@ impl Persister for MemoryStore {
         domain_events: &[SerializedDomainEvent],
         integration_events: &[SerializedIntegrationEvent],
         snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+    ) -> Result<(), PersistenceError> {
+        self.persist_expecting(
+            domain_events,
+            integration_events,
+            snapshot_update,
+            index_ops,
+            ExpectedState::Any,
+        )
+        .await
+    }
+
+    async fn persist_expecting(
+        &self,
+        domain_events: &[SerializedDomainEvent],
+        integration_events: &[SerializedIntegrationEvent],
+        snapshot_update: Option<&PersistedSnapshot>,
+        index_ops: &[IndexOp],
+        expected_state: ExpectedState,
     ) -> Result<(), PersistenceError> {
         self.event_store
-            .persist(domain_events, integration_events, snapshot_update)
-            .await
+            .persist_expecting(domain_events, integration_events, snapshot_update, &[], expected_state)
+            .await?;
+        for op in index_ops {
+            match op {
+                IndexOp::Put { keyword, aggregate_id } => {
+                    self.inverted_index_store.commit(aggregate_id, keyword).await?
+                }
+                IndexOp::Delete { keyword, aggregate_id } => {
+                    self.inverted_index_store.remove(aggregate_id, keyword).await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn tombstone(&self, aggregate_type: &str, id: &str) -> Result<(), PersistenceError> {
+        self.event_store.tombstone(aggregate_type, id).await
     }
 }
 
@@ -244,21 +459,32 @@ impl SnapshotGetter for MemoryStore {
 // Implement all InvertedIndexStore traits by delegating to inverted_index_store
 #[async_trait]
 impl AggregateIdsLoader for MemoryStore {
-    async fn get_aggregate_ids(&self, keyword: &str) -> Result<Vec<String>, PersistenceError> {
+    async fn get_aggregate_ids(
+        &self,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<Vec<String>, PersistenceError> {
         self.inverted_index_store.get_aggregate_ids(keyword).await
     }
 }
 
 #[async_trait]
 impl InvertedIndexCommiter for MemoryStore {
-    async fn commit(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
+    async fn commit(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
         self.inverted_index_store.commit(aggregate_id, keyword).await
     }
 }
 
 #[async_trait]
 impl InvertedIndexRemover for MemoryStore {
-    async fn remove(&self, aggregate_id: &str, keyword: &str) -> Result<(), PersistenceError> {
+    async fn remove(
+        &self,
+        aggregate_id: &str,
+        keyword: impl Into<IndexKeyword> + Send,
+    ) -> Result<(), PersistenceError> {
         self.inverted_index_store.remove(aggregate_id, keyword).await
     }
 }
@@ -270,6 +496,7 @@ mod tests {
         aggregate_id::{AggregateId, HasIdPrefix},
         command::Command,
         domain_event::DomainEvent,
+        event::MetadataFilter,
         event_id::EventIdType,
         integration_event::{self, IntegrationEvent},
         message,
@@ -418,7 +645,7 @@ mod tests {
             ),
         ];
 
-        let result = store.persist(&events, &[], None).await;
+        let result = store.persist(&events, &[], None, &[]).await;
         assert!(result.is_ok());
 
         // Test streaming events
@@ -433,6 +660,69 @@ mod tests {
         assert_eq!(count, 2);
     }
 
+    #[tokio::test]
+    async fn test_stream_events_in_seq_nr_order_after_out_of_order_persists() {
+        let store = MemoryEventStore::new(10);
+        let event = |seq_nr| {
+            SerializedDomainEvent::new(
+                format!("evt-{seq_nr}"),
+                "agg-1".to_string(),
+                seq_nr,
+                "TestAggregate".to_string(),
+                "TestEvent".to_string(),
+                vec![],
+                json!({}),
+            )
+        };
+
+        store.persist(&[event(4), event(3)], &[], None, &[]).await.unwrap();
+        store.persist(&[event(1)], &[], None, &[]).await.unwrap();
+        store.persist(&[event(2)], &[], None, &[]).await.unwrap();
+
+        use futures::TryStreamExt;
+        let seq_nrs: Vec<_> = store
+            .stream_events::<TestAggregate>("agg-1", SequenceSelect::All)
+            .map_ok(|event| event.seq_nr)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(seq_nrs, vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_event_persistence() {
+        let store = MemoryEventStore::new(10);
+        let event = |id: &str, seq_nr| {
+            SerializedDomainEvent::new(
+                id.to_string(),
+                "agg-1".to_string(),
+                seq_nr,
+                "TestAggregate".to_string(),
+                "TestEvent".to_string(),
+                vec![],
+                json!({}),
+            )
+        };
+        store.persist(&[event("evt-1", 1)], &[], None, &[]).await.unwrap();
+
+        // A second writer that also loaded the aggregate before any event appends seq_nr 1 again.
+        let result = store
+            .persist(&[event("evt-2", 1), event("evt-3", 2)], &[], None, &[])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::OptimisticConcurrency { aggregate_id, expected_seq: 0 }) if aggregate_id == "agg-1"
+        ));
+        assert!(matches!(
+            store
+                .persist(&[event("evt-4", 2), event("evt-5", 2)], &[], None, &[])
+                .await,
+            Err(PersistenceError::OptimisticConcurrency { expected_seq: 1, .. })
+        ));
+        assert_eq!(store.count_events::<TestAggregate>("agg-1").await.unwrap(), 1);
+    }
+
     #[tokio::test]
     async fn test_memory_inverted_index_store() {
         let store = MemoryInvertedIndexStore::new();
@@ -470,7 +760,7 @@ mod tests {
             json!({"test": true}),
         )];
 
-        store.persist(&events, &[], None).await.unwrap();
+        store.persist(&events, &[], None, &[]).await.unwrap();
 
         // Test inverted index functionality
         store.commit("agg-1", "type:test").await.unwrap();
@@ -487,12 +777,60 @@ mod tests {
             version: 1,
         };
 
-        store.persist(&[], &[], Some(&snapshot)).await.unwrap();
+        store.persist(&[], &[], Some(&snapshot), &[]).await.unwrap();
         let retrieved = store.get_snapshot::<TestAggregate>("agg-1").await.unwrap();
         assert!(retrieved.is_some());
         assert_eq!(retrieved.unwrap().version, 1);
     }
 
+    #[tokio::test]
+    async fn test_memory_store_persist_applies_index_ops() {
+        let store = MemoryStore::new(5);
+        store.commit("agg-1", "email:old").await.unwrap();
+
+        let events = vec![SerializedDomainEvent::new(
+            "evt-1".to_string(),
+            "agg-1".to_string(),
+            1,
+            "TestAggregate".to_string(),
+            "TestEvent".to_string(),
+            vec![],
+            json!({}),
+        )];
+        let index_ops = [
+            IndexOp::delete("email:old", "agg-1"),
+            IndexOp::put("email:new", "agg-1"),
+        ];
+        store.persist(&events, &[], None, &index_ops).await.unwrap();
+
+        assert!(store.get_aggregate_ids("email:old").await.unwrap().is_empty());
+        assert_eq!(store.get_aggregate_ids("email:new").await.unwrap(), vec!["agg-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_count_events() {
+        let store = MemoryStore::new(10);
+        assert_eq!(store.count_events::<TestAggregate>("agg-1").await.unwrap(), 0);
+
+        let events: Vec<_> = (1..=3)
+            .map(|seq_nr| {
+                SerializedDomainEvent::new(
+                    format!("evt-{seq_nr}"),
+                    "agg-1".to_string(),
+                    seq_nr,
+                    "TestAggregate".to_string(),
+                    "TestEvent".to_string(),
+                    vec![],
+                    json!({}),
+                )
+            })
+            .collect();
+        store.persist(&events, &[], None, &[]).await.unwrap();
+
+        assert_eq!(store.count_events::<TestAggregate>("agg-1").await.unwrap(), 3);
+        assert_eq!(store.count_events::<TestAggregate>("agg-2").await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_snapshot_interval_calculation() {
         let store = MemoryStore::new(10);
@@ -526,7 +864,7 @@ mod tests {
             ),
         ];
 
-        let result = store.persist(&[], &integration_events, None).await;
+        let result = store.persist(&[], &integration_events, None, &[]).await;
         assert!(result.is_ok());
 
         // Verify integration events were stored
@@ -534,6 +872,214 @@ mod tests {
         assert_eq!(stored_events.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_tombstone_appends_terminal_entry_and_deletes_snapshot_and_outbox() {
+        let store = MemoryStore::new(10);
+        let events = vec![SerializedDomainEvent::new(
+            "evt-1".to_string(),
+            "agg-1".to_string(),
+            1,
+            "TestAggregate".to_string(),
+            "TestEvent".to_string(),
+            vec![],
+            json!({}),
+        )];
+        let integration_events = vec![SerializedIntegrationEvent::new(
+            "int-evt-1".to_string(),
+            "agg-1".to_string(),
+            "TestAggregate".to_string(),
+            "test.event".to_string(),
+            vec![],
+        )];
+        let snapshot = PersistedSnapshot::new("TestAggregate".to_string(), "agg-1".to_string(), vec![], 2, 1);
+        store
+            .persist(&events, &integration_events, Some(&snapshot), &[])
+            .await
+            .unwrap();
+
+        store.tombstone("TestAggregate", "agg-1").await.unwrap();
+        store.tombstone("TestAggregate", "agg-1").await.unwrap();
+
+        use futures::TryStreamExt;
+        let stored: Vec<_> = store
+            .stream_events::<TestAggregate>("agg-1", SequenceSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[1].seq_nr, 2);
+        assert_eq!(stored[1].event_type, TOMBSTONE_EVENT_TYPE);
+        assert!(store.get_snapshot::<TestAggregate>("agg-1").await.unwrap().is_none());
+        assert!(store.event_store().integration_events.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_events_filtered_by_correlation_id() {
+        let store = MemoryEventStore::new(10);
+        let events: Vec<_> = [(1, "req-1"), (2, "req-2"), (3, "req-1")]
+            .into_iter()
+            .map(|(seq_nr, correlation_id)| {
+                SerializedDomainEvent::new(
+                    format!("evt-{seq_nr}"),
+                    "agg-1".to_string(),
+                    seq_nr,
+                    "TestAggregate".to_string(),
+                    "TestEvent".to_string(),
+                    vec![],
+                    json!({ message::CORRELATION_ID: correlation_id, "tenant": "acme" }),
+                )
+            })
+            .collect();
+        store.persist(&events, &[], None, &[]).await.unwrap();
+
+        use futures::TryStreamExt;
+        let store = &store;
+        let stream_seq_nrs = |select, filter| async move {
+            store
+                .stream_events_filtered::<TestAggregate>("agg-1", select, filter)
+                .map_ok(|event| event.seq_nr)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+        };
+
+        assert_eq!(
+            stream_seq_nrs(SequenceSelect::All, MetadataFilter::correlation_id("req-1")).await,
+            vec![1, 3]
+        );
+        assert_eq!(
+            stream_seq_nrs(SequenceSelect::From(2), MetadataFilter::correlation_id("req-1")).await,
+            vec![3]
+        );
+        assert_eq!(
+            stream_seq_nrs(
+                SequenceSelect::All,
+                MetadataFilter::correlation_id("req-2").with("tenant", "other")
+            )
+            .await,
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            stream_seq_nrs(SequenceSelect::All, MetadataFilter::new()).await,
+            vec![1, 2, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_snapshots_skips_aggregates_without_snapshot() {
+        let store = MemoryStore::new(10);
+        for (aggregate_id, seq_nr) in [("agg-1", 3), ("agg-2", 7)] {
+            let snapshot =
+                PersistedSnapshot::new("TestAggregate".to_string(), aggregate_id.to_string(), vec![], seq_nr, 1);
+            store.persist(&[], &[], Some(&snapshot), &[]).await.unwrap();
+        }
+
+        let snapshots = store
+            .get_snapshots::<TestAggregate>(&["agg-1", "agg-2", "agg-3"])
+            .await
+            .unwrap();
+
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots["agg-1"].seq_nr, 3);
+        assert_eq!(snapshots["agg-2"].seq_nr, 7);
+        assert!(store.get_snapshots::<TestAggregate>(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_persist_expecting_checks_journal_state() {
+        let store = MemoryStore::new(10);
+        let event = |seq_nr| {
+            SerializedDomainEvent::new(
+                format!("evt-{seq_nr}"),
+                "agg-1".to_string(),
+                seq_nr,
+                "TestAggregate".to_string(),
+                "TestEvent".to_string(),
+                vec![],
+                json!({}),
+            )
+        };
+
+        store
+            .persist_expecting(&[event(1)], &[], None, &[], ExpectedState::MustNotExist)
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .persist_expecting(&[event(1)], &[], None, &[], ExpectedState::MustNotExist)
+                .await,
+            Err(PersistenceError::AlreadyExists { aggregate_id }) if aggregate_id == "agg-1"
+        ));
+
+        assert!(matches!(
+            store
+                .persist_expecting(&[event(3)], &[], None, &[], ExpectedState::ExpectedVersion(2))
+                .await,
+            Err(PersistenceError::OptimisticConcurrency { expected_seq: 2, .. })
+        ));
+        store
+            .persist_expecting(&[event(2)], &[], None, &[], ExpectedState::ExpectedVersion(1))
+            .await
+            .unwrap();
+        assert_eq!(store.count_events::<TestAggregate>("agg-1").await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_state_restores_exported_store() {
+        let store = MemoryStore::new(10);
+        let events: Vec<_> = (1..=3)
+            .map(|seq_nr| {
+                SerializedDomainEvent::new(
+                    format!("evt-{seq_nr}"),
+                    "agg-1".to_string(),
+                    seq_nr,
+                    "TestAggregate".to_string(),
+                    "TestEvent".to_string(),
+                    vec![],
+                    json!({}),
+                )
+            })
+            .collect();
+        let integration_events = vec![SerializedIntegrationEvent::new(
+            "int-evt-1".to_string(),
+            "agg-1".to_string(),
+            "TestAggregate".to_string(),
+            "test.event".to_string(),
+            vec![],
+        )];
+        let snapshot = PersistedSnapshot::new("TestAggregate".to_string(), "agg-1".to_string(), vec![1], 3, 1);
+        let index_ops = [IndexOp::put("user:john", "agg-1")];
+        store
+            .persist(&events, &integration_events, Some(&snapshot), &index_ops)
+            .await
+            .unwrap();
+
+        let state = store.export_state();
+        assert_eq!(state.events["agg-1"], events);
+        assert_eq!(state.integration_events, integration_events);
+
+        let restored = MemoryStore::new(10);
+        restored.import_state(state.clone());
+
+        use futures::TryStreamExt;
+        let streamed: Vec<_> = restored
+            .stream_events::<TestAggregate>("agg-1", SequenceSelect::From(2))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(streamed, events[1..]);
+        assert_eq!(
+            restored.get_snapshot::<TestAggregate>("agg-1").await.unwrap(),
+            Some(snapshot)
+        );
+        assert_eq!(restored.get_aggregate_ids("user:john").await.unwrap(), vec!["agg-1"]);
+        assert_eq!(restored.export_state(), state);
+
+        restored.import_state(MemoryStoreState::default());
+        assert_eq!(restored.count_events::<TestAggregate>("agg-1").await.unwrap(), 0);
+        assert_eq!(store.count_events::<TestAggregate>("agg-1").await.unwrap(), 3);
+    }
+
     #[tokio::test]
     async fn test_empty_keyword_removal() {
         let store = MemoryInvertedIndexStore::new();
@@ -1,11 +1,100 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 pub trait Message {
     fn name(&self) -> &'static str;
 }
 
-pub type Metadata = HashMap<String, String>;
+/// Context attached to a message, persisted as a JSON object alongside the event.
+///
+/// Values can be any serializable type. String values serialize exactly as the former `HashMap<String, String>`
+/// metadata did, so existing blobs read back unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Metadata(Map<String, Value>);
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `value` under `key`, replacing any previous value.
+    pub fn insert<T: Serialize>(&mut self, key: impl Into<String>, value: T) -> Result<(), serde_json::Error> {
+        self.0.insert(key.into(), serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    /// Reads the value under `key` as `T`; `None` when the key is missing or holds a value of another shape.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.0.get(key).and_then(|value| T::deserialize(value).ok())
+    }
+
+    /// Borrows the value under `key` when it is a string.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(Value::as_str)
+    }
+
+    pub fn get_value(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.0.remove(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.0.iter()
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Metadata
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), Value::String(value.into())))
+                .collect(),
+        )
+    }
+}
+
+impl<K, V, const N: usize> From<[(K, V); N]> for Metadata
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<HashMap<String, String>> for Metadata {
+    fn from(entries: HashMap<String, String>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+/// Metadata key of the ID shared by every message that stems from the same original request.
+pub const CORRELATION_ID: &str = "correlation_id";
+
+/// Metadata key of the ID of the message that caused this one.
+pub const CAUSATION_ID: &str = "causation_id";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Envelope<T>
@@ -22,7 +111,7 @@ where
 {
     #[must_use]
     pub fn with_metadata(mut self, key: String, value: String) -> Self {
-        self.metadata.insert(key, value);
+        self.metadata.0.insert(key, Value::String(value));
         self
     }
 
@@ -31,6 +120,32 @@ where
         self.metadata = metadata;
         self
     }
+
+    #[must_use]
+    pub fn with_correlation_id(self, correlation_id: impl Into<String>) -> Self {
+        self.with_metadata(CORRELATION_ID.to_string(), correlation_id.into())
+    }
+
+    #[must_use]
+    pub fn with_causation_id(self, causation_id: impl Into<String>) -> Self {
+        self.with_metadata(CAUSATION_ID.to_string(), causation_id.into())
+    }
+
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.metadata.get_str(CORRELATION_ID)
+    }
+
+    pub fn causation_id(&self) -> Option<&str> {
+        self.metadata.get_str(CAUSATION_ID)
+    }
+
+    /// Transforms the message with `f`, keeping the metadata as is.
+    pub fn map<U: Message>(self, f: impl FnOnce(T) -> U) -> Envelope<U> {
+        Envelope {
+            message: f(self.message),
+            metadata: self.metadata,
+        }
+    }
 }
 
 impl<T> From<T> for Envelope<T>
@@ -81,4 +196,103 @@ mod tests {
 
         assert_eq!(message, new_message);
     }
+
+    #[test]
+    fn correlation_and_causation_ids_survive_serialization() {
+        let message = Envelope::from(StringMessage("hello"))
+            .with_correlation_id("req-1")
+            .with_causation_id("cmd-1");
+
+        assert_eq!(message.correlation_id(), Some("req-1"));
+        assert_eq!(message.causation_id(), Some("cmd-1"));
+
+        let blob = serde_json::to_vec(&message.metadata).unwrap();
+        let metadata: Metadata = serde_json::from_slice(&blob).unwrap();
+        assert_eq!(metadata.get_str(CORRELATION_ID), Some("req-1"));
+        assert_eq!(metadata.get_str(CAUSATION_ID), Some("cmd-1"));
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Republished(StringMessage);
+
+    impl Message for Republished {
+        fn name(&self) -> &'static str {
+            "republished"
+        }
+    }
+
+    #[test]
+    fn map_keeps_metadata() {
+        let message = Envelope::from(StringMessage("hello"))
+            .with_correlation_id("req-1")
+            .with_causation_id("cmd-1")
+            .with_metadata("tenant_id".into(), "tenant-1".into());
+        let metadata = message.metadata.clone();
+
+        let mapped = message.map(Republished);
+
+        assert_eq!(mapped.message, Republished(StringMessage("hello")));
+        assert_eq!(mapped.metadata, metadata);
+        assert_eq!(mapped.correlation_id(), Some("req-1"));
+        assert_eq!(mapped.causation_id(), Some("cmd-1"));
+        assert_eq!(
+            mapped.map(|republished| republished.0).metadata.get_str("tenant_id"),
+            Some("tenant-1")
+        );
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Actor {
+        id: String,
+        roles: Vec<String>,
+    }
+
+    #[test]
+    fn metadata_insert_and_get_typed_values() {
+        let actor = Actor {
+            id: "user-1".to_string(),
+            roles: vec!["admin".to_string()],
+        };
+        let mut metadata = Metadata::new();
+        metadata.insert("tenant_id", "tenant-1").unwrap();
+        metadata.insert("retry_count", 3_u32).unwrap();
+        metadata.insert("actor", &actor).unwrap();
+
+        assert_eq!(metadata.get::<String>("tenant_id"), Some("tenant-1".to_string()));
+        assert_eq!(metadata.get_str("tenant_id"), Some("tenant-1"));
+        assert_eq!(metadata.get::<u32>("retry_count"), Some(3));
+        assert_eq!(metadata.get::<Actor>("actor"), Some(actor.clone()));
+
+        let blob = serde_json::to_vec(&metadata).unwrap();
+        let read_back: Metadata = serde_json::from_slice(&blob).unwrap();
+        assert_eq!(read_back.get::<Actor>("actor"), Some(actor));
+    }
+
+    #[test]
+    fn metadata_get_missing_or_mismatched_value() {
+        let mut metadata = Metadata::new();
+        metadata.insert("tenant_id", "tenant-1").unwrap();
+
+        assert_eq!(metadata.get::<String>("actor"), None);
+        assert_eq!(metadata.get::<u32>("tenant_id"), None);
+        assert_eq!(metadata.get_str("missing"), None);
+    }
+
+    #[test]
+    fn metadata_string_values_serialize_like_legacy_blobs() {
+        let legacy: HashMap<String, String> = HashMap::from([(CORRELATION_ID.to_string(), "req-1".to_string())]);
+        let legacy_blob = serde_json::to_vec(&legacy).unwrap();
+
+        let metadata: Metadata = serde_json::from_slice(&legacy_blob).unwrap();
+        assert_eq!(metadata, Metadata::from(legacy));
+        assert_eq!(serde_json::to_vec(&metadata).unwrap(), legacy_blob);
+    }
+
+    #[test]
+    fn missing_correlation_and_causation_ids() {
+        let message = Envelope::from(StringMessage("hello"));
+
+        assert_eq!(message.correlation_id(), None);
+        assert_eq!(message.causation_id(), None);
+    }
 }
@@ -1,10 +1,59 @@
-use crate::{error::AggregateError, serde};
+use crate::{
+    error::AggregateError,
+    serde::{self, SerdeDirection, SerdeError},
+};
 use std::error;
 
 #[derive(Debug, thiserror::Error)]
 pub enum PersistenceError {
     #[error("optimistic lock error")]
     OptimisticLockError,
+    /// Another writer appended to the aggregate first; `expected_seq` is the sequence number the rejected
+    /// events were built on.
+    #[error("optimistic concurrency conflict on aggregate {aggregate_id}: expected sequence number {expected_seq}")]
+    OptimisticConcurrency { aggregate_id: String, expected_seq: usize },
+    /// The write expected the aggregate not to exist, see
+    /// [`ExpectedState::MustNotExist`](crate::event_store::ExpectedState::MustNotExist).
+    #[error("aggregate {aggregate_id} already exists")]
+    AlreadyExists { aggregate_id: String },
+    /// The aggregate was tombstoned with [`Persister::tombstone`](crate::event_store::Persister::tombstone) and
+    /// can no longer be loaded.
+    #[error("aggregate {aggregate_id} is tombstoned")]
+    Tombstoned { aggregate_id: String },
+    /// [`AggregateRoot::try_apply`](crate::aggregate::AggregateRoot::try_apply) rejected the event at `seq_nr`
+    /// during replay, so the journal of the aggregate holds events in an order its state cannot follow. Also
+    /// returned when a replay from the initial state finds a journal that does not start at sequence number 1.
+    #[error("event stream of aggregate {aggregate_id} is corrupt at sequence number {seq_nr}: {reason}")]
+    CorruptStream {
+        aggregate_id: String,
+        seq_nr: usize,
+        reason: String,
+    },
+    /// The encryption key of the aggregate was destroyed, see [`serde::KeyProvider`](crate::serde::KeyProvider).
+    #[error("encryption key of aggregate {aggregate_id} was destroyed")]
+    KeyShredded { aggregate_id: String },
+    /// The serde of an event failed, which points at a bug or an incompatible payload rather than at the
+    /// storage.
+    #[error("failed to {direction} event {event_type}: {source}")]
+    Serialization {
+        event_type: String,
+        direction: SerdeDirection,
+        source: SerdeError,
+    },
+    /// The serialized payload of an event exceeds
+    /// [`EventSourced::max_event_payload_bytes`](crate::command::repository::EventSourced::max_event_payload_bytes),
+    /// so the commit was rejected before reaching the store.
+    #[error("event {event_type} has a payload of {bytes} bytes, exceeding the configured limit")]
+    EventTooLarge { event_type: String, bytes: usize },
+    /// More events follow the snapshot of the aggregate than
+    /// [`EventSourced::max_replay_events`](crate::command::repository::EventSourced::max_replay_events) allows, so
+    /// the load was aborted. Rebuilding the snapshot brings the tail back under the limit.
+    #[error("replay of aggregate {aggregate_id} exceeded {limit} events, consider rebuilding its snapshot")]
+    ReplayLimitExceeded { aggregate_id: String, limit: usize },
+    /// Another [`CommandBus::dispatch_idempotent`](crate::command::bus::CommandBus::dispatch_idempotent) reserved
+    /// the idempotency key and has not finished yet; retrying once it did returns its events.
+    #[error("idempotency key {key} is reserved by a dispatch in progress")]
+    IdempotencyKeyInProgress { key: String },
     #[error("{0}")]
     ConnectionError(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("{0}")]
@@ -13,10 +62,43 @@ pub enum PersistenceError {
     UnknownError(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
 
+impl PersistenceError {
+    /// Returns `true` when the write lost a race against another writer and can be retried on fresh state.
+    pub fn is_concurrency_conflict(&self) -> bool {
+        matches!(self, Self::OptimisticLockError | Self::OptimisticConcurrency { .. })
+    }
+
+    /// Wraps a failure of the serde of events of `event_type`. A destroyed encryption key stays
+    /// [`PersistenceError::KeyShredded`], since it is expected after crypto-shredding.
+    pub fn serialization(event_type: impl Into<String>, direction: SerdeDirection, err: SerdeError) -> Self {
+        match err {
+            SerdeError::KeyShredded(aggregate_id) => Self::KeyShredded { aggregate_id },
+            source => Self::Serialization {
+                event_type: event_type.into(),
+                direction,
+                source,
+            },
+        }
+    }
+}
+
 impl<T: std::error::Error> From<PersistenceError> for AggregateError<T> {
     fn from(err: PersistenceError) -> Self {
         match err {
             PersistenceError::OptimisticLockError => Self::AggregateConflict,
+            PersistenceError::OptimisticConcurrency { .. } => Self::AggregateConflict,
+            PersistenceError::AlreadyExists { aggregate_id } => Self::AlreadyExists { aggregate_id },
+            PersistenceError::Tombstoned { aggregate_id } => Self::Tombstoned { aggregate_id },
+            PersistenceError::KeyShredded { aggregate_id } => Self::KeyShredded { aggregate_id },
+            err @ PersistenceError::CorruptStream { .. } => Self::DeserializationError(Box::new(err)),
+            err @ PersistenceError::EventTooLarge { .. } => Self::UnexpectedError(Box::new(err)),
+            err @ PersistenceError::ReplayLimitExceeded { .. } => Self::UnexpectedError(Box::new(err)),
+            err @ PersistenceError::IdempotencyKeyInProgress { .. } => Self::UnexpectedError(Box::new(err)),
+            err @ PersistenceError::Serialization {
+                direction: SerdeDirection::Deserialize,
+                ..
+            } => Self::DeserializationError(Box::new(err)),
+            err @ PersistenceError::Serialization { .. } => Self::UnexpectedError(Box::new(err)),
             PersistenceError::ConnectionError(error) => Self::DatabaseConnectionError(error),
             PersistenceError::DeserializationError(error) => Self::DeserializationError(error),
             PersistenceError::UnknownError(error) => Self::UnexpectedError(error),
@@ -66,6 +148,13 @@ impl From<serde::SerdeError> for PersistenceError {
             }
             serde::SerdeError::JsonError(err) => Self::DeserializationError(Box::new(err)),
             serde::SerdeError::ProtobufDeserializationError(err) => Self::DeserializationError(Box::new(err)),
+            serde::SerdeError::KeyShredded(aggregate_id) => Self::KeyShredded { aggregate_id },
+            #[cfg(feature = "messagepack")]
+            serde::SerdeError::MessagePackSerializationError(err) => Self::DeserializationError(Box::new(err)),
+            #[cfg(feature = "messagepack")]
+            serde::SerdeError::MessagePackDeserializationError(err) => Self::DeserializationError(Box::new(err)),
+            #[cfg(feature = "compression")]
+            serde::SerdeError::CompressionError(err) => Self::DeserializationError(Box::new(err)),
         }
     }
 }
@@ -78,6 +167,13 @@ impl<T: error::Error> From<serde::SerdeError> for AggregateError<T> {
             }
             serde::SerdeError::JsonError(err) => Self::DeserializationError(Box::new(err)),
             serde::SerdeError::ProtobufDeserializationError(err) => Self::DeserializationError(Box::new(err)),
+            serde::SerdeError::KeyShredded(aggregate_id) => Self::KeyShredded { aggregate_id },
+            #[cfg(feature = "messagepack")]
+            serde::SerdeError::MessagePackSerializationError(err) => Self::DeserializationError(Box::new(err)),
+            #[cfg(feature = "messagepack")]
+            serde::SerdeError::MessagePackDeserializationError(err) => Self::DeserializationError(Box::new(err)),
+            #[cfg(feature = "compression")]
+            serde::SerdeError::CompressionError(err) => Self::DeserializationError(Box::new(err)),
         }
     }
 }
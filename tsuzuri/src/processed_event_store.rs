@@ -0,0 +1,60 @@
+use crate::persist::PersistenceError;
+use async_trait::async_trait;
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+/// IDs of the events a consumer has already handled, used to make at-least-once delivery idempotent.
+///
+/// Consumers check [`ProcessedEventStore::has_processed`] before handling an event and call
+/// [`ProcessedEventStore::mark_processed`] once it succeeded, so a redelivered event is skipped. An event whose
+/// handling fails is not marked and is handled again on redelivery.
+#[async_trait]
+pub trait ProcessedEventStore: fmt::Debug + Send + Sync + 'static {
+    async fn has_processed(&self, event_id: &str) -> Result<bool, PersistenceError>;
+
+    async fn mark_processed(&self, event_id: &str) -> Result<(), PersistenceError>;
+}
+
+/// Memory-based processed event store for testing and local debugging
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProcessedEventStore {
+    event_ids: Arc<RwLock<HashSet<String>>>,
+}
+
+impl MemoryProcessedEventStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ProcessedEventStore for MemoryProcessedEventStore {
+    async fn has_processed(&self, event_id: &str) -> Result<bool, PersistenceError> {
+        Ok(self.event_ids.read().unwrap().contains(event_id))
+    }
+
+    async fn mark_processed(&self, event_id: &str) -> Result<(), PersistenceError> {
+        self.event_ids.write().unwrap().insert(event_id.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_processed_event_store_marks_event_ids() {
+        let store = MemoryProcessedEventStore::new();
+        assert!(!store.has_processed("event-1").await.unwrap());
+
+        store.mark_processed("event-1").await.unwrap();
+        store.mark_processed("event-1").await.unwrap();
+
+        assert!(store.has_processed("event-1").await.unwrap());
+        assert!(!store.has_processed("event-2").await.unwrap());
+    }
+}
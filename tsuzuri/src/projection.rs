@@ -1,7 +1,9 @@
 pub mod adapter;
 pub mod error;
 pub mod processor;
+pub mod runner;
 
 pub use adapter::*;
 pub use error::*;
 pub use processor::*;
+pub use runner::*;
@@ -0,0 +1,298 @@
+use crate::{
+    domain_event::DomainEvent, event::Envelope, processed_event_store::ProcessedEventStore, projection::error::Result,
+};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+/// Read model built by applying domain events one at a time, in stream order.
+///
+/// Unlike a [`crate::projection::Projector`], a projection owns its state and knows how far into the source stream
+/// it has got, which lets a [`ProjectionRunner`] checkpoint it.
+#[async_trait]
+pub trait Projection<E>: Send + Sync + 'static
+where
+    E: DomainEvent,
+{
+    async fn apply(&mut self, event: Envelope<E>) -> Result<()>;
+
+    /// Position in the source stream of the last event applied, or `None` while there is nothing to checkpoint.
+    fn position(&self) -> Option<String>;
+}
+
+/// Durable positions of projections, keyed by projection name.
+#[async_trait]
+pub trait ProjectionCheckpointStore: Send + Sync + 'static {
+    /// Returns the last position saved for `projection`, if any.
+    async fn load_position(&self, projection: &str) -> Result<Option<String>>;
+
+    async fn save_position(&self, projection: &str, position: &str) -> Result<()>;
+}
+
+/// Memory-based checkpoint store for testing and local debugging
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProjectionCheckpointStore {
+    positions: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl MemoryProjectionCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ProjectionCheckpointStore for MemoryProjectionCheckpointStore {
+    async fn load_position(&self, projection: &str) -> Result<Option<String>> {
+        Ok(self.positions.read().unwrap().get(projection).cloned())
+    }
+
+    async fn save_position(&self, projection: &str, position: &str) -> Result<()> {
+        self.positions
+            .write()
+            .unwrap()
+            .insert(projection.to_string(), position.to_string());
+        Ok(())
+    }
+}
+
+/// Drives a [`Projection`] from a stream of events and saves its position after every event applied.
+///
+/// The source stream is up to the caller, e.g. the outbox relay or a Kinesis consumer; on restart it should start
+/// after [`ProjectionRunner::checkpoint`]. Events are applied one at a time and the first error stops the run
+/// without saving the position of the failed event, so it is applied again on the next run.
+///
+/// With [`ProjectionRunner::with_processed_event_store`] events already applied are skipped, which keeps the
+/// projection correct when the source redelivers events from before the checkpoint.
+#[derive(Debug, Clone)]
+pub struct ProjectionRunner<P, C> {
+    name: String,
+    projection: P,
+    checkpoint_store: C,
+    processed_events: Option<Arc<dyn ProcessedEventStore>>,
+}
+
+impl<P, C> ProjectionRunner<P, C>
+where
+    C: ProjectionCheckpointStore,
+{
+    pub fn new(name: impl Into<String>, projection: P, checkpoint_store: C) -> Self {
+        Self {
+            name: name.into(),
+            projection,
+            checkpoint_store,
+            processed_events: None,
+        }
+    }
+
+    /// Skips events whose ID is already marked in `store`, and marks every event applied.
+    pub fn with_processed_event_store(mut self, store: impl ProcessedEventStore) -> Self {
+        self.processed_events = Some(Arc::new(store));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn projection(&self) -> &P {
+        &self.projection
+    }
+
+    pub fn into_projection(self) -> P {
+        self.projection
+    }
+
+    /// Position saved by the last run, to resume the source stream after.
+    pub async fn checkpoint(&self) -> Result<Option<String>> {
+        self.checkpoint_store.load_position(&self.name).await
+    }
+
+    /// Applies every event of `events` until the stream ends, returning how many were applied; skipped events are
+    /// not counted.
+    pub async fn run<E, S>(&mut self, events: S) -> Result<usize>
+    where
+        E: DomainEvent,
+        P: Projection<E>,
+        S: Stream<Item = Result<Envelope<E>>> + Send,
+    {
+        let mut events = std::pin::pin!(events);
+        let mut applied = 0;
+        while let Some(event) = events.next().await {
+            let event = event?;
+            let event_id = event.message.id().to_string();
+            if let Some(store) = &self.processed_events {
+                if store.has_processed(&event_id).await? {
+                    continue;
+                }
+            }
+            self.projection.apply(event).await?;
+            applied += 1;
+            if let Some(store) = &self.processed_events {
+                store.mark_processed(&event_id).await?;
+            }
+            if let Some(position) = self.projection.position() {
+                self.checkpoint_store.save_position(&self.name, &position).await?;
+            }
+        }
+        Ok(applied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event_id::EventIdType, message, processed_event_store::MemoryProcessedEventStore,
+        projection::error::ProjectionError,
+    };
+    use futures::stream;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestEvent {
+        pub id: EventIdType,
+        pub position: u64,
+    }
+
+    impl message::Message for TestEvent {
+        fn name(&self) -> &'static str {
+            "TestEvent"
+        }
+    }
+
+    impl DomainEvent for TestEvent {
+        fn id(&self) -> EventIdType {
+            self.id
+        }
+
+        fn event_type(&self) -> &'static str {
+            "TestEvent"
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingProjection {
+        count: usize,
+        position: Option<u64>,
+        fail_at: Option<u64>,
+    }
+
+    #[async_trait]
+    impl Projection<TestEvent> for CountingProjection {
+        async fn apply(&mut self, event: Envelope<TestEvent>) -> Result<()> {
+            if self.fail_at == Some(event.message.position) {
+                return Err(ProjectionError::Database("Mock projection failed".to_string()));
+            }
+            self.count += 1;
+            self.position = Some(event.message.position);
+            Ok(())
+        }
+
+        fn position(&self) -> Option<String> {
+            self.position.map(|position| position.to_string())
+        }
+    }
+
+    fn events(positions: std::ops::RangeInclusive<u64>) -> Vec<Result<Envelope<TestEvent>>> {
+        positions
+            .map(|position| {
+                Ok(TestEvent {
+                    id: EventIdType::new(),
+                    position,
+                }
+                .into())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_run_applies_events_and_saves_position() {
+        let checkpoint_store = MemoryProjectionCheckpointStore::new();
+        let mut runner = ProjectionRunner::new("counter", CountingProjection::default(), checkpoint_store.clone());
+        assert_eq!(runner.checkpoint().await.unwrap(), None);
+
+        let applied = runner.run(stream::iter(events(1..=3))).await.unwrap();
+
+        assert_eq!(applied, 3);
+        assert_eq!(runner.projection().count, 3);
+        assert_eq!(runner.checkpoint().await.unwrap().as_deref(), Some("3"));
+        assert_eq!(
+            checkpoint_store.load_position("counter").await.unwrap().as_deref(),
+            Some("3")
+        );
+
+        let applied = runner.run(stream::iter(events(4..=5))).await.unwrap();
+        assert_eq!(applied, 2);
+        assert_eq!(runner.into_projection().count, 5);
+        assert_eq!(
+            checkpoint_store.load_position("counter").await.unwrap().as_deref(),
+            Some("5")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_at_first_error_keeping_last_position() {
+        let checkpoint_store = MemoryProjectionCheckpointStore::new();
+        let projection = CountingProjection {
+            fail_at: Some(3),
+            ..Default::default()
+        };
+        let mut runner = ProjectionRunner::new("counter", projection, checkpoint_store);
+
+        let result = runner.run(stream::iter(events(1..=5))).await;
+
+        assert!(matches!(result, Err(ProjectionError::Database(_))));
+        assert_eq!(runner.projection().count, 2);
+        assert_eq!(runner.checkpoint().await.unwrap().as_deref(), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_at_stream_error() {
+        let mut runner = ProjectionRunner::new(
+            "counter",
+            CountingProjection::default(),
+            MemoryProjectionCheckpointStore::new(),
+        );
+        let mut source = events(1..=1);
+        source.push(Err(ProjectionError::StreamProcessing("Stream closed".to_string())));
+        source.extend(events(2..=2));
+
+        let result = runner.run(stream::iter(source)).await;
+
+        assert!(matches!(result, Err(ProjectionError::StreamProcessing(_))));
+        assert_eq!(runner.projection().count, 1);
+        assert_eq!(runner.checkpoint().await.unwrap().as_deref(), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn test_run_applies_replayed_events_once() {
+        let processed_events = MemoryProcessedEventStore::new();
+        let mut runner = ProjectionRunner::new(
+            "counter",
+            CountingProjection::default(),
+            MemoryProjectionCheckpointStore::new(),
+        )
+        .with_processed_event_store(processed_events.clone());
+        let source: Vec<_> = events(1..=3).into_iter().map(|event| event.unwrap()).collect();
+
+        let applied = runner
+            .run(stream::iter(source.clone().into_iter().map(Ok)))
+            .await
+            .unwrap();
+        assert_eq!(applied, 3);
+
+        let replayed = runner
+            .run(stream::iter(source.clone().into_iter().map(Ok)))
+            .await
+            .unwrap();
+        assert_eq!(replayed, 0);
+        assert_eq!(runner.projection().count, 3);
+        assert!(processed_events
+            .has_processed(&source[0].message.id.to_string())
+            .await
+            .unwrap());
+    }
+}
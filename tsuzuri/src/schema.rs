@@ -0,0 +1,97 @@
+//! JSON Schema export of event payloads, for publishing the wire format of events to the teams that consume them.
+
+use crate::message::Message;
+use schemars::JsonSchema;
+use serde_json::Value;
+
+/// Returns the JSON Schema of `E`, titled with its schema name, which is the type name unless overridden with
+/// `#[schemars(rename = "...")]`.
+///
+/// The schema describes the JSON form of the event, i.e. payloads written by [`crate::serde::Json`].
+pub fn export_event_schema<E>() -> Value
+where
+    E: Message + JsonSchema,
+{
+    export_schema::<E>(E::schema_name().into_owned())
+}
+
+/// Returns the JSON Schema of `E`, titled with the [`Message::name`] of `event`.
+pub fn export_event_schema_of<E>(event: &E) -> Value
+where
+    E: Message + JsonSchema,
+{
+    export_schema::<E>(event.name().to_string())
+}
+
+fn export_schema<E: JsonSchema>(title: String) -> Value {
+    let mut schema = schemars::schema_for!(E);
+    schema.insert("title".to_string(), Value::String(title));
+    schema.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration_event::IntegrationEvent;
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Serialize, JsonSchema)]
+    struct OrderShipped {
+        id: String,
+        order_id: String,
+        carrier: Option<String>,
+        items: Vec<ShippedItem>,
+    }
+
+    #[derive(Debug, Clone, Serialize, JsonSchema)]
+    struct ShippedItem {
+        sku: String,
+        quantity: u32,
+    }
+
+    impl Message for OrderShipped {
+        fn name(&self) -> &'static str {
+            "order.shipped"
+        }
+    }
+
+    impl IntegrationEvent for OrderShipped {
+        fn id(&self) -> String {
+            self.id.clone()
+        }
+
+        fn event_type(&self) -> &'static str {
+            "order.shipped"
+        }
+    }
+
+    #[test]
+    fn test_export_event_schema_lists_properties() {
+        let schema = export_event_schema::<OrderShipped>();
+
+        assert_eq!(schema["title"], "OrderShipped");
+        assert_eq!(schema["type"], "object");
+        let properties = schema["properties"].as_object().unwrap();
+        let mut names: Vec<_> = properties.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["carrier", "id", "items", "order_id"]);
+        assert_eq!(properties["order_id"]["type"], "string");
+        assert_eq!(schema["required"], serde_json::json!(["id", "order_id", "items"]));
+        assert!(schema["$defs"]["ShippedItem"]["properties"]["quantity"].is_object());
+    }
+
+    #[test]
+    fn test_export_event_schema_of_uses_message_name() {
+        let event = OrderShipped {
+            id: "evt-1".to_string(),
+            order_id: "ord-1".to_string(),
+            carrier: None,
+            items: vec![],
+        };
+
+        let schema = export_event_schema_of(&event);
+
+        assert_eq!(schema["title"], event.event_type());
+        assert!(schema["properties"]["items"].is_object());
+    }
+}
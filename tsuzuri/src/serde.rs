@@ -1,7 +1,23 @@
+use crate::{domain_event::DomainEvent, integration_event::IntegrationEvent, AggregateRoot};
+#[cfg(feature = "encryption")]
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
 use prost::bytes::Bytes;
-use serde::{Deserialize, Serialize};
-use std::fmt::Display;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+#[cfg(feature = "encryption")]
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+#[cfg(feature = "compression")]
+use std::io::{Read, Write};
 use std::marker::PhantomData;
+#[cfg(feature = "encryption")]
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
 
 #[derive(Debug, thiserror::Error)]
 pub enum SerdeError {
@@ -11,6 +27,34 @@ pub enum SerdeError {
     JsonError(#[from] serde_json::Error),
     #[error("failed to deserialize protobuf message into value: {0}")]
     ProtobufDeserializationError(#[from] prost::DecodeError),
+    /// The encryption key of the aggregate was destroyed, so its payloads can no longer be read or written.
+    #[error("encryption key of aggregate {0} was destroyed")]
+    KeyShredded(String),
+    #[cfg(feature = "messagepack")]
+    #[error("failed to serialize value into MessagePack: {0}")]
+    MessagePackSerializationError(#[from] rmp_serde::encode::Error),
+    #[cfg(feature = "messagepack")]
+    #[error("failed to deserialize MessagePack into value: {0}")]
+    MessagePackDeserializationError(#[from] rmp_serde::decode::Error),
+    #[cfg(feature = "compression")]
+    #[error("failed to compress or decompress payload: {0}")]
+    CompressionError(#[from] std::io::Error),
+}
+
+/// Whether a [`SerdeError`] occurred while serializing or deserializing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SerdeDirection {
+    Serialize,
+    Deserialize,
+}
+
+impl Display for SerdeDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialize => f.write_str("serialize"),
+            Self::Deserialize => f.write_str("deserialize"),
+        }
+    }
 }
 
 pub trait Serializer<T>: Send + Sync {
@@ -120,11 +164,60 @@ where
     }
 }
 
+/// Compact binary encoding using MessagePack. Structs are written as maps so fields can be added or reordered
+/// without breaking previously stored payloads.
+#[cfg(feature = "messagepack")]
+#[derive(Debug, Clone, Copy)]
+pub struct MessagePack<T>(PhantomData<T>)
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>;
+
+#[cfg(feature = "messagepack")]
+impl<T> Default for MessagePack<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg(feature = "messagepack")]
+impl<T> Serializer<T> for MessagePack<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    fn serialize(&self, value: &T) -> Result<Vec<u8>, SerdeError> {
+        Ok(rmp_serde::to_vec_named(value)?)
+    }
+}
+
+#[cfg(feature = "messagepack")]
+impl<T> Deserializer<T> for MessagePack<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    fn deserialize(&self, data: &[u8]) -> Result<T, SerdeError> {
+        Ok(rmp_serde::from_slice(data)?)
+    }
+}
+
+/// Encodes messages generated from `.proto` definitions in the protobuf binary format.
+///
+/// Protobuf decoding skips unknown fields and defaults missing ones, so payloads stay readable in both directions
+/// when fields are added under new tags.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Protobuf<T>(PhantomData<T>)
 where
     T: prost::Message + Default;
 
+/// Alias of [`Protobuf`], named after the `prost` messages it encodes.
+pub type ProstSerde<T> = Protobuf<T>;
+
 impl<T> Serializer<T> for Protobuf<T>
 where
     T: prost::Message + Default,
@@ -169,3 +262,978 @@ where
         Json::<T>::default().deserialize(data)
     }
 }
+
+/// CloudEvents specification version written to and accepted from the `specversion` attribute.
+pub const CLOUD_EVENTS_SPEC_VERSION: &str = "1.0";
+
+/// Integration events in the CloudEvents 1.0 structured JSON format, for consumers such as EventBridge rules or
+/// Kafka clients that expect CloudEvents. The event itself is the JSON `data` attribute; `id` and `type` come from
+/// the `IntegrationEvent` and `source` is fixed per serde.
+#[derive(Debug, Clone)]
+pub struct CloudEventsSerde<E> {
+    source: String,
+    event: PhantomData<E>,
+}
+
+impl<E> CloudEventsSerde<E> {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            event: PhantomData,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CloudEvent<T> {
+    specversion: String,
+    id: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    datacontenttype: Option<String>,
+    data: T,
+}
+
+impl<E> Serializer<E> for CloudEventsSerde<E>
+where
+    E: IntegrationEvent + Serialize,
+{
+    fn serialize(&self, value: &E) -> Result<Vec<u8>, SerdeError> {
+        let cloud_event = CloudEvent {
+            specversion: CLOUD_EVENTS_SPEC_VERSION.to_string(),
+            id: value.id(),
+            event_type: value.event_type().to_string(),
+            source: self.source.clone(),
+            time: Some(DateTime::<Utc>::from(SystemTime::now()).to_rfc3339_opts(SecondsFormat::Millis, true)),
+            datacontenttype: Some("application/json".to_string()),
+            data: value,
+        };
+        Ok(serde_json::to_vec(&cloud_event)?)
+    }
+}
+
+impl<E> Deserializer<E> for CloudEventsSerde<E>
+where
+    E: IntegrationEvent + DeserializeOwned,
+{
+    fn deserialize(&self, data: &[u8]) -> Result<E, SerdeError> {
+        let cloud_event: CloudEvent<E> = serde_json::from_slice(data)?;
+        if cloud_event.specversion != CLOUD_EVENTS_SPEC_VERSION {
+            return Err(SerdeError::ConversionError(format!(
+                "unsupported CloudEvents specversion: {}",
+                cloud_event.specversion
+            )));
+        }
+        Ok(cloud_event.data)
+    }
+}
+
+/// Domain event serdes of several aggregate types, looked up by `AggregateRoot::TYPE`.
+///
+/// Lets generic tooling, such as a replay over a journal holding many aggregate types, pick the serde of an event
+/// from the aggregate type stored next to it. Events pass through [`SerdeRegistry::serialize`] and
+/// [`SerdeRegistry::deserialize`] as `dyn Any` of the registered aggregate's `DomainEvent`.
+#[derive(Default)]
+pub struct SerdeRegistry {
+    serdes: HashMap<&'static str, Box<dyn RegisteredSerde>>,
+}
+
+impl SerdeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `S::default()` for the domain events of `T`, replacing any serde registered for `T::TYPE`.
+    pub fn register<T, S>(self) -> Self
+    where
+        T: AggregateRoot,
+        S: Serde<T::DomainEvent> + Default + 'static,
+    {
+        self.register_serde::<T>(S::default())
+    }
+
+    /// Registers `serde` for the domain events of `T`, replacing any serde registered for `T::TYPE`.
+    pub fn register_serde<T>(mut self, serde: impl Serde<T::DomainEvent> + 'static) -> Self
+    where
+        T: AggregateRoot,
+    {
+        self.serdes
+            .insert(T::TYPE, Box::new(EventSerde::<T::DomainEvent>(Box::new(serde))));
+        self
+    }
+
+    pub fn contains(&self, aggregate_type: &str) -> bool {
+        self.serdes.contains_key(aggregate_type)
+    }
+
+    /// Registered aggregate types, sorted.
+    pub fn aggregate_types(&self) -> Vec<&'static str> {
+        let mut aggregate_types: Vec<_> = self.serdes.keys().copied().collect();
+        aggregate_types.sort_unstable();
+        aggregate_types
+    }
+
+    /// The serde registered for `T`, for callers that know the aggregate statically.
+    pub fn serde_for<T>(&self) -> Option<&dyn Serde<T::DomainEvent>>
+    where
+        T: AggregateRoot,
+    {
+        self.serdes
+            .get(T::TYPE)?
+            .as_any()
+            .downcast_ref::<EventSerde<T::DomainEvent>>()
+            .map(|serde| serde.0.as_ref())
+    }
+
+    /// Serializes `event`, which must be the `DomainEvent` of the aggregate registered as `aggregate_type`.
+    pub fn serialize(&self, aggregate_type: &str, event: &dyn Any) -> Result<Vec<u8>, SerdeError> {
+        self.registered(aggregate_type)?.serialize_any(event)
+    }
+
+    /// Deserializes a `DomainEvent` of the aggregate registered as `aggregate_type`.
+    pub fn deserialize(&self, aggregate_type: &str, data: &[u8]) -> Result<Box<dyn Any + Send>, SerdeError> {
+        self.registered(aggregate_type)?.deserialize_any(data)
+    }
+
+    fn registered(&self, aggregate_type: &str) -> Result<&dyn RegisteredSerde, SerdeError> {
+        self.serdes.get(aggregate_type).map(Box::as_ref).ok_or_else(|| {
+            SerdeError::ConversionError(format!("no serde registered for aggregate type {aggregate_type}"))
+        })
+    }
+}
+
+impl fmt::Debug for SerdeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerdeRegistry")
+            .field("aggregate_types", &self.aggregate_types())
+            .finish()
+    }
+}
+
+/// Type-erased view of a registered serde.
+trait RegisteredSerde: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn serialize_any(&self, event: &dyn Any) -> Result<Vec<u8>, SerdeError>;
+    fn deserialize_any(&self, data: &[u8]) -> Result<Box<dyn Any + Send>, SerdeError>;
+}
+
+struct EventSerde<E>(Box<dyn Serde<E>>);
+
+impl<E> RegisteredSerde for EventSerde<E>
+where
+    E: DomainEvent,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn serialize_any(&self, event: &dyn Any) -> Result<Vec<u8>, SerdeError> {
+        let event = event.downcast_ref::<E>().ok_or_else(|| {
+            SerdeError::ConversionError(format!("expected an event of type {}", std::any::type_name::<E>()))
+        })?;
+        self.0.serialize(event)
+    }
+
+    fn deserialize_any(&self, data: &[u8]) -> Result<Box<dyn Any + Send>, SerdeError> {
+        Ok(Box::new(self.0.deserialize(data)?))
+    }
+}
+
+/// AES-256 key used by [`EncryptingSerde`].
+#[cfg(feature = "encryption")]
+pub type EncryptionKey = [u8; 32];
+
+/// Source of the per-aggregate keys used by [`EncryptingSerde`].
+///
+/// Destroying the key of an aggregate (crypto-shredding) leaves its encrypted payloads in the journal but makes
+/// them permanently unreadable.
+#[cfg(feature = "encryption")]
+pub trait KeyProvider: Send + Sync {
+    /// Returns the key to encrypt payloads of `aggregate_id` with, creating it on first use.
+    fn encryption_key(&self, aggregate_id: &str) -> Result<EncryptionKey, SerdeError>;
+
+    /// Returns the key of `aggregate_id`, or `None` once it was destroyed.
+    fn decryption_key(&self, aggregate_id: &str) -> Result<Option<EncryptionKey>, SerdeError>;
+}
+
+/// Memory-based key provider for testing and development
+#[cfg(feature = "encryption")]
+#[derive(Debug, Clone, Default)]
+pub struct MemoryKeyProvider {
+    keys: Arc<RwLock<HashMap<String, EncryptionKey>>>,
+    destroyed: Arc<RwLock<HashSet<String>>>,
+}
+
+#[cfg(feature = "encryption")]
+impl MemoryKeyProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Destroys the key of `aggregate_id`; later reads and writes of its payloads fail with
+    /// [`SerdeError::KeyShredded`].
+    pub fn destroy_key(&self, aggregate_id: &str) {
+        self.keys.write().unwrap().remove(aggregate_id);
+        self.destroyed.write().unwrap().insert(aggregate_id.to_string());
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl KeyProvider for MemoryKeyProvider {
+    fn encryption_key(&self, aggregate_id: &str) -> Result<EncryptionKey, SerdeError> {
+        if self.destroyed.read().unwrap().contains(aggregate_id) {
+            return Err(SerdeError::KeyShredded(aggregate_id.to_string()));
+        }
+        let mut keys = self.keys.write().unwrap();
+        let key = keys
+            .entry(aggregate_id.to_string())
+            .or_insert_with(|| Aes256Gcm::generate_key(OsRng).into());
+        Ok(*key)
+    }
+
+    fn decryption_key(&self, aggregate_id: &str) -> Result<Option<EncryptionKey>, SerdeError> {
+        Ok(self.keys.read().unwrap().get(aggregate_id).copied())
+    }
+}
+
+#[cfg(feature = "encryption")]
+const ENCRYPTED_PAYLOAD_VERSION: u8 = 1;
+
+#[cfg(feature = "encryption")]
+const NONCE_LEN: usize = 12;
+
+/// Encrypts the payloads of another serde with AES-256-GCM, using a key per aggregate from a [`KeyProvider`].
+///
+/// The aggregate ID is stored in plain text in front of the nonce and the ciphertext, so payloads can be decrypted
+/// without knowing which aggregate they belong to. Once the key of an aggregate is destroyed, deserializing its
+/// payloads fails with [`SerdeError::KeyShredded`].
+#[cfg(feature = "encryption")]
+pub struct EncryptingSerde<T, S, K> {
+    inner: S,
+    key_provider: K,
+    aggregate_id: fn(&T) -> String,
+}
+
+#[cfg(feature = "encryption")]
+impl<T, S, K> EncryptingSerde<T, S, K> {
+    /// `aggregate_id` returns the ID of the aggregate whose key encrypts `value`.
+    pub fn new(inner: S, key_provider: K, aggregate_id: fn(&T) -> String) -> Self {
+        Self {
+            inner,
+            key_provider,
+            aggregate_id,
+        }
+    }
+
+    pub fn key_provider(&self) -> &K {
+        &self.key_provider
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<T, S, K> Serializer<T> for EncryptingSerde<T, S, K>
+where
+    T: Send + Sync,
+    S: Serializer<T>,
+    K: KeyProvider,
+{
+    fn serialize(&self, value: &T) -> Result<Vec<u8>, SerdeError> {
+        let aggregate_id = (self.aggregate_id)(value);
+        let aggregate_id_len = u16::try_from(aggregate_id.len())
+            .map_err(|_| SerdeError::ConversionError(format!("aggregate ID too long: {aggregate_id}")))?;
+        let key = self.key_provider.encryption_key(&aggregate_id)?;
+        let plaintext = self.inner.serialize(value)?;
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| SerdeError::ConversionError(format!("failed to encrypt payload of {aggregate_id}")))?;
+
+        let mut payload = Vec::with_capacity(3 + aggregate_id.len() + NONCE_LEN + ciphertext.len());
+        payload.push(ENCRYPTED_PAYLOAD_VERSION);
+        payload.extend_from_slice(&aggregate_id_len.to_be_bytes());
+        payload.extend_from_slice(aggregate_id.as_bytes());
+        payload.extend_from_slice(&nonce);
+        payload.extend_from_slice(&ciphertext);
+        Ok(payload)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<T, S, K> Deserializer<T> for EncryptingSerde<T, S, K>
+where
+    T: Send + Sync,
+    S: Deserializer<T>,
+    K: KeyProvider,
+{
+    fn deserialize(&self, data: &[u8]) -> Result<T, SerdeError> {
+        let malformed = || SerdeError::ConversionError("malformed encrypted payload".to_string());
+        let (&version, rest) = data.split_first().ok_or_else(malformed)?;
+        if version != ENCRYPTED_PAYLOAD_VERSION {
+            return Err(SerdeError::ConversionError(format!(
+                "unsupported encrypted payload version: {version}"
+            )));
+        }
+        let (aggregate_id_len, rest) = rest.split_first_chunk::<2>().ok_or_else(malformed)?;
+        let aggregate_id_len = usize::from(u16::from_be_bytes(*aggregate_id_len));
+        if rest.len() < aggregate_id_len + NONCE_LEN {
+            return Err(malformed());
+        }
+        let (aggregate_id, rest) = rest.split_at(aggregate_id_len);
+        let aggregate_id = std::str::from_utf8(aggregate_id).map_err(|_| malformed())?;
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let key = self
+            .key_provider
+            .decryption_key(aggregate_id)?
+            .ok_or_else(|| SerdeError::KeyShredded(aggregate_id.to_string()))?;
+        let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| SerdeError::ConversionError(format!("failed to decrypt payload of {aggregate_id}")))?;
+        self.inner.deserialize(&plaintext)
+    }
+}
+
+/// Compression algorithm of a [`CompressingSerde`].
+#[cfg(feature = "compression")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+#[cfg(feature = "compression")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[cfg(feature = "compression")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compresses the payloads of another serde with gzip or zstd, e.g. `CompressingSerde<Json<Order>>` to shrink
+/// snapshots while events stay plain JSON.
+///
+/// Payloads are decompressed according to their gzip or zstd magic number rather than the configured
+/// compression, so switching algorithms keeps existing payloads readable. Payloads without either magic number are
+/// handed to the inner serde as they are, which lets compression be enabled on a store that holds uncompressed
+/// payloads.
+#[cfg(feature = "compression")]
+#[derive(Debug, Clone, Default)]
+pub struct CompressingSerde<S> {
+    inner: S,
+    compression: Compression,
+}
+
+#[cfg(feature = "compression")]
+impl<S> CompressingSerde<S> {
+    pub fn new(inner: S, compression: Compression) -> Self {
+        Self { inner, compression }
+    }
+
+    pub fn gzip(inner: S) -> Self {
+        Self::new(inner, Compression::Gzip)
+    }
+
+    pub fn zstd(inner: S) -> Self {
+        Self::new(inner, Compression::Zstd)
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<T, S> Serializer<T> for CompressingSerde<S>
+where
+    S: Serializer<T>,
+{
+    fn serialize(&self, value: &T) -> Result<Vec<u8>, SerdeError> {
+        let payload = self.inner.serialize(value)?;
+        match self.compression {
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&payload)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Zstd => Ok(zstd::encode_all(payload.as_slice(), zstd::DEFAULT_COMPRESSION_LEVEL)?),
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<T, S> Deserializer<T> for CompressingSerde<S>
+where
+    S: Deserializer<T>,
+{
+    fn deserialize(&self, data: &[u8]) -> Result<T, SerdeError> {
+        if data.starts_with(&GZIP_MAGIC) {
+            let mut payload = Vec::new();
+            flate2::read::GzDecoder::new(data).read_to_end(&mut payload)?;
+            self.inner.deserialize(&payload)
+        } else if data.starts_with(&ZSTD_MAGIC) {
+            self.inner.deserialize(&zstd::decode_all(data)?)
+        } else {
+            self.inner.deserialize(data)
+        }
+    }
+}
+
+#[cfg(test)]
+mod cloud_events_tests {
+    use super::*;
+    use crate::message::Message;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct OrderShipped {
+        order_id: String,
+        carrier: String,
+    }
+
+    impl Message for OrderShipped {
+        fn name(&self) -> &'static str {
+            "OrderShipped"
+        }
+    }
+
+    impl IntegrationEvent for OrderShipped {
+        fn id(&self) -> String {
+            format!("shipment-{}", self.order_id)
+        }
+
+        fn event_type(&self) -> &'static str {
+            "com.example.order.shipped"
+        }
+    }
+
+    fn order_shipped() -> OrderShipped {
+        OrderShipped {
+            order_id: "01J1234567890ABCDEFGHJKMNP".to_string(),
+            carrier: "yamato".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cloud_events_round_trip() {
+        let serde = CloudEventsSerde::<OrderShipped>::new("/orders");
+        let event = order_shipped();
+
+        let bytes = serde.serialize(&event).unwrap();
+        assert_eq!(serde.deserialize(&bytes).unwrap(), event);
+    }
+
+    #[test]
+    fn test_cloud_events_required_attributes() {
+        let serde = CloudEventsSerde::<OrderShipped>::new("https://example.com/orders");
+        let event = order_shipped();
+
+        let json: serde_json::Value = serde_json::from_slice(&serde.serialize(&event).unwrap()).unwrap();
+
+        for attribute in ["specversion", "id", "type", "source"] {
+            let value = json[attribute].as_str().unwrap_or_default();
+            assert!(!value.is_empty(), "missing required attribute {attribute}");
+        }
+        assert_eq!(json["specversion"], "1.0");
+        assert_eq!(json["id"], "shipment-01J1234567890ABCDEFGHJKMNP");
+        assert_eq!(json["type"], "com.example.order.shipped");
+        assert_eq!(json["source"], "https://example.com/orders");
+        assert_eq!(json["datacontenttype"], "application/json");
+        assert!(DateTime::parse_from_rfc3339(json["time"].as_str().unwrap()).is_ok());
+        assert_eq!(json["data"], serde_json::to_value(&event).unwrap());
+    }
+
+    #[test]
+    fn test_cloud_events_rejects_unsupported_spec_version() {
+        let serde = CloudEventsSerde::<OrderShipped>::new("/orders");
+        let data = serde_json::json!({
+            "specversion": "0.3",
+            "id": "shipment-1",
+            "type": "com.example.order.shipped",
+            "source": "/orders",
+            "data": order_shipped(),
+        });
+
+        let result = serde.deserialize(&serde_json::to_vec(&data).unwrap());
+        assert!(matches!(result, Err(SerdeError::ConversionError(_))));
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use super::*;
+    use crate::{
+        aggregate_id::{AggregateId, HasIdPrefix},
+        command::Command,
+        event_id::EventIdType,
+        integration_event::IntoIntegrationEvents,
+        message,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct OrderId;
+
+    impl HasIdPrefix for OrderId {
+        const PREFIX: &'static str = "ord";
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct UserId;
+
+    impl HasIdPrefix for UserId {
+        const PREFIX: &'static str = "usr";
+    }
+
+    #[derive(Debug)]
+    struct NoCommand;
+
+    impl message::Message for NoCommand {
+        fn name(&self) -> &'static str {
+            "NoCommand"
+        }
+    }
+
+    impl Command for NoCommand {
+        type ID = OrderId;
+
+        fn id(&self) -> AggregateId<Self::ID> {
+            unreachable!()
+        }
+    }
+
+    #[derive(Debug)]
+    struct NoIntegrationEvent;
+
+    impl message::Message for NoIntegrationEvent {
+        fn name(&self) -> &'static str {
+            "NoIntegrationEvent"
+        }
+    }
+
+    impl IntegrationEvent for NoIntegrationEvent {
+        fn id(&self) -> String {
+            unreachable!()
+        }
+
+        fn event_type(&self) -> &'static str {
+            "NoIntegrationEvent"
+        }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("unsupported")]
+    struct Unsupported;
+
+    macro_rules! test_aggregate {
+        ($aggregate:ident, $event:ident, $id:ident, $type:literal) => {
+            #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+            struct $event {
+                id: EventIdType,
+                value: String,
+            }
+
+            impl message::Message for $event {
+                fn name(&self) -> &'static str {
+                    stringify!($event)
+                }
+            }
+
+            impl DomainEvent for $event {
+                fn id(&self) -> EventIdType {
+                    self.id
+                }
+
+                fn event_type(&self) -> &'static str {
+                    stringify!($event)
+                }
+            }
+
+            impl IntoIntegrationEvents for $event {
+                type IntegrationEvent = NoIntegrationEvent;
+                type IntoIter = Vec<NoIntegrationEvent>;
+
+                fn into_integration_events(self) -> Self::IntoIter {
+                    Vec::new()
+                }
+            }
+
+            #[derive(Debug)]
+            struct $aggregate {
+                id: AggregateId<$id>,
+            }
+
+            impl AggregateRoot for $aggregate {
+                const TYPE: &'static str = $type;
+                type ID = $id;
+                type Command = NoCommand;
+                type DomainEvent = $event;
+                type IntegrationEvent = NoIntegrationEvent;
+                type Error = Unsupported;
+
+                fn init(id: AggregateId<Self::ID>) -> Self {
+                    Self { id }
+                }
+
+                fn id(&self) -> &AggregateId<Self::ID> {
+                    &self.id
+                }
+
+                fn handle(&mut self, _cmd: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+                    Err(Unsupported)
+                }
+
+                fn apply(&mut self, _event: Self::DomainEvent) {}
+            }
+        };
+    }
+
+    test_aggregate!(Order, OrderPlaced, OrderId, "Order");
+    test_aggregate!(User, UserRegistered, UserId, "User");
+
+    fn registry() -> SerdeRegistry {
+        SerdeRegistry::new()
+            .register::<Order, Json<OrderPlaced>>()
+            .register_serde::<User>(Json::<UserRegistered>::default())
+    }
+
+    #[test]
+    fn test_registry_round_trips_events_by_aggregate_type() {
+        let registry = registry();
+        let order_placed = OrderPlaced {
+            id: EventIdType::new(),
+            value: "order-1".to_string(),
+        };
+        let user_registered = UserRegistered {
+            id: EventIdType::new(),
+            value: "alice".to_string(),
+        };
+
+        let order_bytes = registry.serialize("Order", &order_placed).unwrap();
+        let user_bytes = registry.serialize("User", &user_registered).unwrap();
+
+        let order_event = registry.deserialize("Order", &order_bytes).unwrap();
+        let user_event = registry.deserialize("User", &user_bytes).unwrap();
+        assert_eq!(order_event.downcast_ref::<OrderPlaced>(), Some(&order_placed));
+        assert_eq!(user_event.downcast_ref::<UserRegistered>(), Some(&user_registered));
+        assert_eq!(registry.aggregate_types(), vec!["Order", "User"]);
+    }
+
+    #[test]
+    fn test_registry_typed_lookup() {
+        let registry = registry();
+        let order_placed = OrderPlaced {
+            id: EventIdType::new(),
+            value: "order-1".to_string(),
+        };
+
+        let serde = registry.serde_for::<Order>().unwrap();
+        let bytes = serde.serialize(&order_placed).unwrap();
+
+        assert_eq!(serde.deserialize(&bytes).unwrap(), order_placed);
+        assert!(SerdeRegistry::new().serde_for::<Order>().is_none());
+    }
+
+    #[test]
+    fn test_registry_rejects_unknown_type_and_mismatched_event() {
+        let registry = registry();
+        let user_registered = UserRegistered {
+            id: EventIdType::new(),
+            value: "alice".to_string(),
+        };
+
+        assert!(!registry.contains("Invoice"));
+        assert!(matches!(
+            registry.deserialize("Invoice", b"{}"),
+            Err(SerdeError::ConversionError(_))
+        ));
+        assert!(matches!(
+            registry.serialize("Order", &user_registered),
+            Err(SerdeError::ConversionError(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod protobuf_tests {
+    use super::*;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct OrderPlacedV1 {
+        #[prost(string, tag = "1")]
+        order_id: String,
+        #[prost(uint64, tag = "2")]
+        amount: u64,
+    }
+
+    /// `OrderPlacedV1` after an optional field was added.
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct OrderPlacedV2 {
+        #[prost(string, tag = "1")]
+        order_id: String,
+        #[prost(uint64, tag = "2")]
+        amount: u64,
+        #[prost(string, optional, tag = "3")]
+        coupon: Option<String>,
+    }
+
+    #[test]
+    fn test_prost_serde_round_trip() {
+        let serde = ProstSerde::<OrderPlacedV2>::default();
+        let event = OrderPlacedV2 {
+            order_id: "ord-1".to_string(),
+            amount: 1200,
+            coupon: Some("WELCOME".to_string()),
+        };
+
+        let bytes = serde.serialize(&event).unwrap();
+
+        assert_eq!(serde.deserialize(&bytes).unwrap(), event);
+    }
+
+    #[test]
+    fn test_prost_serde_decodes_across_added_optional_field() {
+        let old = OrderPlacedV1 {
+            order_id: "ord-1".to_string(),
+            amount: 1200,
+        };
+        let old_bytes = ProstSerde::<OrderPlacedV1>::default().serialize(&old).unwrap();
+
+        let upgraded = ProstSerde::<OrderPlacedV2>::default().deserialize(&old_bytes).unwrap();
+        assert_eq!(upgraded.order_id, "ord-1");
+        assert_eq!(upgraded.amount, 1200);
+        assert_eq!(upgraded.coupon, None);
+
+        let new_bytes = ProstSerde::<OrderPlacedV2>::default()
+            .serialize(&OrderPlacedV2 {
+                coupon: Some("WELCOME".to_string()),
+                ..upgraded
+            })
+            .unwrap();
+        assert_eq!(
+            ProstSerde::<OrderPlacedV1>::default().deserialize(&new_bytes).unwrap(),
+            old
+        );
+    }
+
+    #[test]
+    fn test_prost_serde_rejects_malformed_payload() {
+        assert!(matches!(
+            ProstSerde::<OrderPlacedV1>::default().deserialize(&[0x0a, 0x05, b'o']),
+            Err(SerdeError::ProtobufDeserializationError(_))
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "messagepack"))]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum AccountEvent {
+        Opened { id: String, owner: String },
+        Deposited { id: String, amount: u64, tags: Vec<String> },
+    }
+
+    fn deposited() -> AccountEvent {
+        AccountEvent::Deposited {
+            id: "01J1234567890ABCDEFGHJKMNP".to_string(),
+            amount: 1_250,
+            tags: vec!["salary".to_string(), "monthly".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_message_pack_round_trip() {
+        let serde = MessagePack::<AccountEvent>::default();
+
+        for event in [
+            AccountEvent::Opened {
+                id: "01J1234567890ABCDEFGHJKMNQ".to_string(),
+                owner: "alice".to_string(),
+            },
+            deposited(),
+        ] {
+            let bytes = serde.serialize(&event).unwrap();
+            assert_eq!(serde.deserialize(&bytes).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn test_message_pack_is_smaller_than_json() {
+        let event = deposited();
+
+        let message_pack = MessagePack::<AccountEvent>::default().serialize(&event).unwrap();
+        let json = Json::<AccountEvent>::default().serialize(&event).unwrap();
+
+        assert!(
+            message_pack.len() < json.len(),
+            "MessagePack {} bytes, JSON {} bytes",
+            message_pack.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn test_message_pack_rejects_invalid_payload() {
+        let result = MessagePack::<AccountEvent>::default().deserialize(&[0xc1]);
+
+        assert!(matches!(result, Err(SerdeError::MessagePackDeserializationError(_))));
+    }
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod encryption_tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct EmailChanged {
+        user_id: String,
+        email: String,
+    }
+
+    fn encrypting_serde() -> EncryptingSerde<EmailChanged, Json<EmailChanged>, MemoryKeyProvider> {
+        EncryptingSerde::new(Json::default(), MemoryKeyProvider::new(), |event: &EmailChanged| {
+            event.user_id.clone()
+        })
+    }
+
+    fn email_changed(user_id: &str) -> EmailChanged {
+        EmailChanged {
+            user_id: user_id.to_string(),
+            email: "alice@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_encrypting_serde_round_trip_hides_plaintext() {
+        let serde = encrypting_serde();
+        let event = email_changed("user-1");
+
+        let bytes = serde.serialize(&event).unwrap();
+
+        assert!(!bytes
+            .windows(b"alice@example.com".len())
+            .any(|w| w == b"alice@example.com"));
+        assert_eq!(serde.deserialize(&bytes).unwrap(), event);
+    }
+
+    #[test]
+    fn test_destroyed_key_shreds_only_that_aggregate() {
+        let serde = encrypting_serde();
+        let shredded = serde.serialize(&email_changed("user-1")).unwrap();
+        let kept = serde.serialize(&email_changed("user-2")).unwrap();
+
+        serde.key_provider().destroy_key("user-1");
+
+        assert!(matches!(
+            serde.deserialize(&shredded),
+            Err(SerdeError::KeyShredded(aggregate_id)) if aggregate_id == "user-1"
+        ));
+        assert!(matches!(
+            serde.serialize(&email_changed("user-1")),
+            Err(SerdeError::KeyShredded(_))
+        ));
+        assert_eq!(serde.deserialize(&kept).unwrap(), email_changed("user-2"));
+    }
+
+    #[test]
+    fn test_shredded_key_maps_to_persistence_error() {
+        let serde = encrypting_serde();
+        let bytes = serde.serialize(&email_changed("user-1")).unwrap();
+        serde.key_provider().destroy_key("user-1");
+
+        let err = crate::persist::PersistenceError::from(serde.deserialize(&bytes).unwrap_err());
+
+        assert!(matches!(
+            err,
+            crate::persist::PersistenceError::KeyShredded { aggregate_id } if aggregate_id == "user-1"
+        ));
+    }
+
+    #[test]
+    fn test_tampered_payload_is_rejected() {
+        let serde = encrypting_serde();
+        let mut bytes = serde.serialize(&email_changed("user-1")).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        assert!(matches!(serde.deserialize(&bytes), Err(SerdeError::ConversionError(_))));
+        assert!(matches!(serde.deserialize(&[]), Err(SerdeError::ConversionError(_))));
+    }
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod compression_tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        id: String,
+        lines: Vec<String>,
+    }
+
+    fn large_order() -> Order {
+        Order {
+            id: "ord-1".to_string(),
+            lines: (0..500).map(|i| format!("line {i}: 1 x widget")).collect(),
+        }
+    }
+
+    #[test]
+    fn test_compressing_serde_round_trip() {
+        let order = large_order();
+        for serde in [
+            CompressingSerde::gzip(Json::<Order>::default()),
+            CompressingSerde::zstd(Json::<Order>::default()),
+        ] {
+            let bytes = serde.serialize(&order).unwrap();
+            assert_eq!(serde.deserialize(&bytes).unwrap(), order);
+        }
+    }
+
+    #[test]
+    fn test_compressing_serde_shrinks_large_aggregates() {
+        let order = large_order();
+        let plain = Json::<Order>::default().serialize(&order).unwrap();
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let compressed = CompressingSerde::new(Json::<Order>::default(), compression)
+                .serialize(&order)
+                .unwrap();
+            assert!(
+                compressed.len() * 4 < plain.len(),
+                "{compression:?}: {}",
+                compressed.len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_compressing_serde_reads_payloads_of_other_compressions() {
+        let order = large_order();
+        let gzip = CompressingSerde::gzip(Json::<Order>::default());
+        let zstd = CompressingSerde::zstd(Json::<Order>::default());
+        let plain = Json::<Order>::default().serialize(&order).unwrap();
+
+        assert_eq!(zstd.deserialize(&gzip.serialize(&order).unwrap()).unwrap(), order);
+        assert_eq!(gzip.deserialize(&zstd.serialize(&order).unwrap()).unwrap(), order);
+        assert_eq!(zstd.deserialize(&plain).unwrap(), order);
+    }
+
+    #[test]
+    fn test_corrupt_compressed_payload_is_rejected() {
+        let serde = CompressingSerde::gzip(Json::<Order>::default());
+        let mut bytes = serde.serialize(&large_order()).unwrap();
+        bytes.truncate(bytes.len() / 2);
+
+        assert!(matches!(
+            serde.deserialize(&bytes),
+            Err(SerdeError::CompressionError(_))
+        ));
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{sequence_number::SequenceNumber, version::Version};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PersistedSnapshot {
     pub aggregate_type: String,
     pub aggregate_id: String,
     pub aggregate: Vec<u8>,
+    /// Sequence number replay resumes from. The snapshot state covers every event before it.
     pub seq_nr: SequenceNumber,
     pub version: Version,
 }
@@ -4,6 +4,8 @@
 //! using a Given-When-Then pattern similar to behavior-driven development (BDD).
 
 use crate::aggregate::AggregateRoot;
+use crate::integration_event::{IntegrationEvent, IntoIntegrationEvents};
+use crate::serde::Serde;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
@@ -23,6 +25,46 @@ impl<A: AggregateRoot> TestFramework<A> {
     }
 }
 
+impl<A: AggregateRoot + PartialEq> TestFramework<A> {
+    /// Verify that replaying `events` from any snapshot reproduces the state of a full replay
+    ///
+    /// Builds a reference aggregate by applying every event to `A::init`, then for each position in the
+    /// stream snapshots an aggregate built from the events before it, restores the snapshot through
+    /// `serde` and applies the remaining events, as `load_aggregate` does. Every result must equal the
+    /// reference aggregate.
+    pub fn assert_replay_consistency<S>(self, serde: S, events: Vec<A::DomainEvent>)
+    where
+        S: Serde<A>,
+    {
+        let id = self.aggregate.id().clone();
+        let mut expected = A::init(id.clone());
+        for event in &events {
+            expected.apply(event.clone());
+        }
+
+        for snapshot_at in 0..=events.len() {
+            let (before, after) = events.split_at(snapshot_at);
+            let mut aggregate = A::init(id.clone());
+            for event in before {
+                aggregate.apply(event.clone());
+            }
+            let payload = serde
+                .serialize(&aggregate)
+                .unwrap_or_else(|e| panic!("Failed to serialize aggregate: {e:?}"));
+            let mut restored = serde
+                .deserialize(&payload)
+                .unwrap_or_else(|e| panic!("Failed to deserialize aggregate: {e:?}"));
+            for event in after {
+                restored.apply(event.clone());
+            }
+            assert_eq!(
+                restored, expected,
+                "Replay from a snapshot after {snapshot_at} events differs from a full replay"
+            );
+        }
+    }
+}
+
 /// Given phase - setup initial state
 impl<A: AggregateRoot> TestFramework<A> {
     /// Start with no previous events (clean state)
@@ -61,15 +103,44 @@ pub struct WhenPhase<A: AggregateRoot> {
 impl<A: AggregateRoot> WhenPhase<A> {
     /// Execute a command on the aggregate
     pub fn when(mut self, command: A::Command) -> ThenPhase<A> {
-        let result = self.aggregate.handle(command);
+        let result = self.aggregate.handle_many(command);
 
-        // Convert single event result to Vec for consistent handling
-        let vec_result = result.map(|event| vec![event]);
+        ThenPhase {
+            aggregate: self.aggregate,
+            initial_events: self.initial_events,
+            result,
+            applied_events: 0,
+        }
+    }
 
+    /// Execute commands in sequence, applying the events of each command before handling the next
+    ///
+    /// The events of all commands are collected into the result. Execution stops at the first command
+    /// that fails, whose error becomes the result.
+    pub fn when_many(mut self, commands: Vec<A::Command>) -> ThenPhase<A> {
+        let mut events = Vec::new();
+        let mut result = Ok(());
+        for command in commands {
+            match self.aggregate.handle_many(command) {
+                Ok(command_events) => {
+                    for event in &command_events {
+                        self.aggregate.apply(event.clone());
+                    }
+                    events.extend(command_events);
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        let applied_events = events.len();
         ThenPhase {
             aggregate: self.aggregate,
             initial_events: self.initial_events,
-            result: vec_result,
+            result: result.map(|()| events),
+            applied_events,
         }
     }
 }
@@ -80,6 +151,8 @@ pub struct ThenPhase<A: AggregateRoot> {
     #[allow(dead_code)]
     initial_events: Vec<A::DomainEvent>,
     result: Result<Vec<A::DomainEvent>, A::Error>,
+    /// Number of events in `result` already applied to `aggregate`
+    applied_events: usize,
 }
 
 impl<A: AggregateRoot> ThenPhase<A>
@@ -112,6 +185,43 @@ where
         self.then_expect_events(vec![])
     }
 
+    /// Verify that the produced events map to the expected integration events
+    ///
+    /// Integration events are not required to implement `PartialEq`, so they are compared by
+    /// `event_type()`, in order, and every produced event must have a non-empty `id()`.
+    /// Use [`ThenPhase::then_verify_integration_events`] to assert on their fields.
+    pub fn then_expect_integration_events(self, expected_events: Vec<A::IntegrationEvent>) {
+        let expected_types: Vec<_> = expected_events.iter().map(IntegrationEvent::event_type).collect();
+        self.then_verify_integration_events(|actual_events| {
+            let actual_types: Vec<_> = actual_events.iter().map(IntegrationEvent::event_type).collect();
+            assert_eq!(
+                actual_types, expected_types,
+                "Expected integration events do not match actual integration events.\nExpected: {expected_events:?}\nActual: {actual_events:?}"
+            );
+            for event in &actual_events {
+                assert!(!event.id().is_empty(), "Integration event has an empty id: {event:?}");
+            }
+        });
+    }
+
+    /// Get access to the integration events mapped from the produced events for custom assertions
+    pub fn then_verify_integration_events<F>(self, verification: F)
+    where
+        F: FnOnce(Vec<A::IntegrationEvent>),
+    {
+        match self.result {
+            Ok(events) => verification(
+                events
+                    .into_iter()
+                    .flat_map(IntoIntegrationEvents::into_integration_events)
+                    .collect(),
+            ),
+            Err(e) => {
+                panic!("Expected events but got error: {e:?}");
+            }
+        }
+    }
+
     /// Verify that an error was produced
     pub fn then_expect_error<E>(self) -> E
     where
@@ -146,14 +256,41 @@ where
     where
         F: FnOnce(&A),
     {
-        // Apply resulting events if successful
+        self.apply_result();
+        assertion(&self.aggregate);
+    }
+
+    /// Verify that the aggregate after command execution survives a snapshot round trip through `serde`
+    ///
+    /// Catches aggregates whose serialization drops or alters state, e.g. `#[serde(skip)]` fields,
+    /// before such snapshots are written in production.
+    pub fn then_snapshot_roundtrips<S>(mut self, serde: S)
+    where
+        A: PartialEq,
+        S: Serde<A>,
+    {
+        self.apply_result();
+        let payload = serde
+            .serialize(&self.aggregate)
+            .unwrap_or_else(|e| panic!("Failed to serialize aggregate: {e:?}"));
+        let restored = serde
+            .deserialize(&payload)
+            .unwrap_or_else(|e| panic!("Failed to deserialize aggregate: {e:?}"));
+        assert_eq!(
+            restored, self.aggregate,
+            "Aggregate does not survive a snapshot round trip.\nBefore: {:?}\nAfter: {restored:?}",
+            self.aggregate
+        );
+    }
+
+    /// Apply resulting events not yet applied to the aggregate if successful
+    fn apply_result(&mut self) {
         if let Ok(events) = &self.result {
-            for event in events {
+            for event in events.iter().skip(self.applied_events) {
                 self.aggregate.apply(event.clone());
             }
+            self.applied_events = events.len();
         }
-
-        assertion(&self.aggregate);
     }
 
     /// Get access to the result for custom assertions
@@ -175,8 +312,10 @@ mod tests {
         event_id::EventIdType,
         integration_event::{IntegrationEvent, IntoIntegrationEvents},
         message::Message,
+        serde::Json,
         AggregateRoot,
     };
+    use serde::{Deserialize, Serialize};
 
     // Test ID type
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -187,7 +326,7 @@ mod tests {
     }
 
     // Test aggregate for verifying the test framework
-    #[derive(Debug, Clone, PartialEq)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     struct TestAggregate {
         id: AggregateId<TestId>,
         value: i32,
@@ -428,4 +567,64 @@ mod tests {
             .when(TestCommand::Deactivate)
             .then_expect_error_matches(|e| matches!(e, TestError::NotActive));
     }
+
+    // Aggregate whose snapshot silently drops `is_active`
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct LossyAggregate {
+        id: AggregateId<TestId>,
+        #[serde(skip)]
+        is_active: bool,
+    }
+
+    impl AggregateRoot for LossyAggregate {
+        const TYPE: &'static str = "LossyAggregate";
+        type ID = TestId;
+        type Command = TestCommand;
+        type DomainEvent = TestEvent;
+        type IntegrationEvent = TestIntegrationEvent;
+        type Error = TestError;
+
+        fn init(id: AggregateId<Self::ID>) -> Self {
+            Self { id, is_active: false }
+        }
+
+        fn id(&self) -> &AggregateId<Self::ID> {
+            &self.id
+        }
+
+        fn handle(&mut self, command: Self::Command) -> Result<Self::DomainEvent, Self::Error> {
+            match command {
+                TestCommand::Create { id } => Ok(TestEvent::Created { id }),
+                _ => Err(TestError::NotActive),
+            }
+        }
+
+        fn apply(&mut self, event: Self::DomainEvent) {
+            if let TestEvent::Created { id } = event {
+                self.id = id;
+                self.is_active = true;
+            }
+        }
+    }
+
+    #[test]
+    fn test_snapshot_roundtrips() {
+        let id = AggregateId::<TestId>::new();
+
+        TestFramework::with(TestAggregate::init(id))
+            .given(vec![TestEvent::Created { id }])
+            .when(TestCommand::UpdateValue { value: 7 })
+            .then_snapshot_roundtrips(Json::<TestAggregate>::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Aggregate does not survive a snapshot round trip")]
+    fn test_snapshot_roundtrips_catches_skipped_field() {
+        let id = AggregateId::<TestId>::new();
+
+        TestFramework::with(LossyAggregate::init(id))
+            .given_no_previous_events()
+            .when(TestCommand::Create { id })
+            .then_snapshot_roundtrips(Json::<LossyAggregate>::default());
+    }
 }
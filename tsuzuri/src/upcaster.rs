@@ -0,0 +1,113 @@
+use crate::persist::PersistenceError;
+use std::{borrow::Cow, collections::HashMap, fmt};
+
+/// Rewrites a stored event payload into the layout the current event type deserializes.
+pub trait Upcaster: Send + Sync + 'static {
+    /// Converts `payload`, written with schema `version`, into the current schema.
+    fn upcast(&self, version: u32, payload: &[u8]) -> Result<Vec<u8>, PersistenceError>;
+}
+
+impl<F> Upcaster for F
+where
+    F: Fn(u32, &[u8]) -> Result<Vec<u8>, PersistenceError> + Send + Sync + 'static,
+{
+    fn upcast(&self, version: u32, payload: &[u8]) -> Result<Vec<u8>, PersistenceError> {
+        self(version, payload)
+    }
+}
+
+/// Upcaster that returns the payload unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopUpcaster;
+
+impl Upcaster for NoopUpcaster {
+    fn upcast(&self, _version: u32, payload: &[u8]) -> Result<Vec<u8>, PersistenceError> {
+        Ok(payload.to_vec())
+    }
+}
+
+/// Upcasters keyed by event type. Event types without a registered upcaster are passed through unchanged.
+#[derive(Default)]
+pub struct UpcasterRegistry {
+    upcasters: HashMap<String, Box<dyn Upcaster>>,
+}
+
+impl UpcasterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `upcaster` for `event_type`, replacing any previous registration.
+    pub fn register(&mut self, event_type: impl Into<String>, upcaster: impl Upcaster) {
+        self.upcasters.insert(event_type.into(), Box::new(upcaster));
+    }
+
+    pub fn with(mut self, event_type: impl Into<String>, upcaster: impl Upcaster) -> Self {
+        self.register(event_type, upcaster);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.upcasters.is_empty()
+    }
+
+    /// Returns the payload in the current schema for `event_type`.
+    pub fn upcast<'a>(
+        &self,
+        event_type: &str,
+        version: u32,
+        payload: &'a [u8],
+    ) -> Result<Cow<'a, [u8]>, PersistenceError> {
+        match self.upcasters.get(event_type) {
+            Some(upcaster) => Ok(Cow::Owned(upcaster.upcast(version, payload)?)),
+            None => Ok(Cow::Borrowed(payload)),
+        }
+    }
+}
+
+impl fmt::Debug for UpcasterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpcasterRegistry")
+            .field("event_types", &self.upcasters.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noop_upcaster_returns_payload() {
+        assert_eq!(NoopUpcaster.upcast(1, b"payload").unwrap(), b"payload".to_vec());
+    }
+
+    #[test]
+    fn test_registry_upcasts_registered_event_types_only() {
+        let registry = UpcasterRegistry::new().with("Renamed", |version: u32, payload: &[u8]| {
+            let mut upcast = payload.to_vec();
+            upcast.extend_from_slice(format!("-v{version}").as_bytes());
+            Ok(upcast)
+        });
+
+        let upcast = registry.upcast("Renamed", 1, b"payload").unwrap();
+        assert!(matches!(upcast, Cow::Owned(_)));
+        assert_eq!(upcast.as_ref(), b"payload-v1");
+
+        let untouched = registry.upcast("Other", 1, b"payload").unwrap();
+        assert!(matches!(untouched, Cow::Borrowed(_)));
+        assert_eq!(untouched.as_ref(), b"payload");
+    }
+
+    #[test]
+    fn test_registry_propagates_upcast_errors() {
+        let registry = UpcasterRegistry::new().with("Broken", |_: u32, _: &[u8]| {
+            Err(PersistenceError::DeserializationError("unsupported schema".into()))
+        });
+
+        assert!(matches!(
+            registry.upcast("Broken", 1, b"payload"),
+            Err(PersistenceError::DeserializationError(_))
+        ));
+    }
+}
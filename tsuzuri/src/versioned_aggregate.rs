@@ -40,6 +40,13 @@ impl<T: AggregateRoot> VersionedAggregate<T> {
         self.seq_nr
     }
 
+    /// Returns whether the aggregate was persisted before, i.e. it has events or was loaded from a snapshot.
+    ///
+    /// A freshly initialized aggregate is at version 0 and sequence number 0, like one whose ID was never used.
+    pub fn exists(&self) -> bool {
+        self.seq_nr > 0 || self.version > 0
+    }
+
     pub fn set_seq_nr(&mut self, seq_nr: SequenceNumber) {
         self.seq_nr = seq_nr;
     }
@@ -49,10 +56,18 @@ impl<T: AggregateRoot> VersionedAggregate<T> {
         Ok(event)
     }
 
+    pub fn handle_many(&mut self, cmd: T::Command) -> Result<Vec<T::DomainEvent>, T::Error> {
+        self.aggregate.handle_many(cmd)
+    }
+
     pub fn apply(&mut self, event: T::DomainEvent) {
         self.aggregate.apply(event);
     }
 
+    pub fn try_apply(&mut self, event: T::DomainEvent) -> Result<(), T::Error> {
+        self.aggregate.try_apply(event)
+    }
+
     pub fn snapshot(&self) -> (&T, Version, SequenceNumber) {
         (self.aggregate(), self.version, self.seq_nr)
     }
@@ -245,6 +260,14 @@ mod tests {
         assert_eq!(versioned.seq_nr, 0);
     }
 
+    #[test]
+    fn test_exists() {
+        let id = AggregateId::<TestId>::new();
+        assert!(!VersionedAggregate::new(TestAggregate::init(id), 0, 0).exists());
+        assert!(VersionedAggregate::new(TestAggregate::init(id), 0, 3).exists());
+        assert!(VersionedAggregate::from_snapshot(TestAggregate::init(id), 1, 0).exists());
+    }
+
     #[test]
     fn test_handle_command() {
         let id = AggregateId::<TestId>::new();
@@ -305,6 +328,22 @@ mod tests {
         assert_eq!(versioned.aggregate.state, "initial");
     }
 
+    #[test]
+    fn test_handle_many() {
+        let mut versioned = create_test_versioned_aggregate();
+
+        let events = versioned
+            .handle_many(TestCommand::DoSomething { id: *versioned.id() })
+            .unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], TestEvent::SomethingHappened { .. }));
+        assert_eq!(versioned.aggregate.state, "initial");
+        assert!(versioned
+            .handle_many(TestCommand::CausesError { id: *versioned.id() })
+            .is_err());
+    }
+
     #[test]
     fn test_snapshot() {
         let versioned = create_test_versioned_aggregate();